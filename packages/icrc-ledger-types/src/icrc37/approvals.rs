@@ -0,0 +1,173 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::fmt;
+
+use super::super::icrc1::account::{Account, Subaccount};
+use super::super::icrc1::transfer::Memo;
+use super::super::icrc7::metadata::TokenId;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ApprovalInfo {
+    pub spender: Account,
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    pub created_at_time: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApproveTokenArg {
+    pub token_id: TokenId,
+    pub approval_info: ApprovalInfo,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ApproveTokenError {
+    InvalidSpender,
+    // The caller does not own the token.
+    Unauthorized,
+    NonExistingTokenId,
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    GenericError { error_code: Nat, message: String },
+    GenericBatchError { error_code: Nat, message: String },
+}
+
+pub type ApproveTokenResult = Result<Nat, ApproveTokenError>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApproveCollectionArg {
+    pub approval_info: ApprovalInfo,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ApproveCollectionError {
+    InvalidSpender,
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    GenericError { error_code: Nat, message: String },
+    GenericBatchError { error_code: Nat, message: String },
+}
+
+pub type ApproveCollectionResult = Result<Nat, ApproveCollectionError>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevokeTokenApprovalArg {
+    /// The spender whose approval is revoked; all approvals of the token are
+    /// revoked if not set.
+    #[serde(default)]
+    pub spender: Option<Account>,
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    pub token_id: TokenId,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RevokeTokenApprovalError {
+    ApprovalDoesNotExist,
+    // The caller does not own the token.
+    Unauthorized,
+    NonExistingTokenId,
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    GenericError { error_code: Nat, message: String },
+    GenericBatchError { error_code: Nat, message: String },
+}
+
+pub type RevokeTokenApprovalResult = Result<Nat, RevokeTokenApprovalError>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevokeCollectionApprovalArg {
+    /// The spender whose approval is revoked; all collection approvals of the
+    /// caller are revoked if not set.
+    #[serde(default)]
+    pub spender: Option<Account>,
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RevokeCollectionApprovalError {
+    ApprovalDoesNotExist,
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    GenericError { error_code: Nat, message: String },
+    GenericBatchError { error_code: Nat, message: String },
+}
+
+pub type RevokeCollectionApprovalResult = Result<Nat, RevokeCollectionApprovalError>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct IsApprovedArg {
+    pub spender: Account,
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    pub token_id: TokenId,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenApproval {
+    pub token_id: TokenId,
+    pub approval_info: ApprovalInfo,
+}
+
+pub type CollectionApproval = ApprovalInfo;
+
+macro_rules! impl_display {
+    ($error:ident { $($variant:ident => $message:literal),* $(,)? }) => {
+        impl fmt::Display for $error {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $(Self::$variant => write!(f, $message),)*
+                    Self::TooOld => {
+                        write!(f, "transaction's created_at_time is too far in the past")
+                    }
+                    Self::CreatedInFuture { ledger_time } => write!(
+                        f,
+                        "transaction's created_at_time is in future, current ledger time is {}",
+                        ledger_time
+                    ),
+                    Self::GenericError {
+                        error_code,
+                        message,
+                    }
+                    | Self::GenericBatchError {
+                        error_code,
+                        message,
+                    } => write!(f, "{} {}", error_code, message),
+                }
+            }
+        }
+    };
+}
+
+impl_display!(ApproveTokenError {
+    InvalidSpender => "the spender cannot be the owner of the token",
+    Unauthorized => "the caller does not own the token",
+    NonExistingTokenId => "the token does not exist",
+});
+
+impl_display!(ApproveCollectionError {
+    InvalidSpender => "the spender cannot be the owner of the collection approval",
+});
+
+impl_display!(RevokeTokenApprovalError {
+    ApprovalDoesNotExist => "the approval does not exist",
+    Unauthorized => "the caller does not own the token",
+    NonExistingTokenId => "the token does not exist",
+});
+
+impl_display!(RevokeCollectionApprovalError {
+    ApprovalDoesNotExist => "the approval does not exist",
+});
@@ -0,0 +1,2 @@
+pub mod approvals;
+pub mod transfer_from;
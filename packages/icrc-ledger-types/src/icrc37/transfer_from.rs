@@ -0,0 +1,66 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::fmt;
+
+use super::super::icrc1::account::{Account, Subaccount};
+use super::super::icrc1::transfer::Memo;
+use super::super::icrc7::metadata::TokenId;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransferFromArg {
+    #[serde(default)]
+    pub spender_subaccount: Option<Subaccount>,
+    pub from: Account,
+    pub to: Account,
+    pub token_id: TokenId,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferFromError {
+    InvalidRecipient,
+    // The spender holds neither a token nor a collection approval from [from].
+    Unauthorized,
+    NonExistingTokenId,
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+    GenericError { error_code: Nat, message: String },
+    GenericBatchError { error_code: Nat, message: String },
+}
+
+impl fmt::Display for TransferFromError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRecipient => {
+                write!(f, "the recipient is not allowed to receive the token")
+            }
+            Self::Unauthorized => write!(f, "the spender is not approved to transfer the token"),
+            Self::NonExistingTokenId => write!(f, "the token does not exist"),
+            Self::TooOld => write!(f, "transaction's created_at_time is too far in the past"),
+            Self::CreatedInFuture { ledger_time } => write!(
+                f,
+                "transaction's created_at_time is in future, current ledger time is {}",
+                ledger_time
+            ),
+            Self::Duplicate { duplicate_of } => write!(
+                f,
+                "transaction is a duplicate of another transaction in block {}",
+                duplicate_of
+            ),
+            Self::GenericError {
+                error_code,
+                message,
+            }
+            | Self::GenericBatchError {
+                error_code,
+                message,
+            } => write!(f, "{} {}", error_code, message),
+        }
+    }
+}
+
+pub type TransferFromResult = Result<Nat, TransferFromError>;
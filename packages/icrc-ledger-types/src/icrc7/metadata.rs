@@ -0,0 +1,31 @@
+use candid::Nat;
+
+use crate::icrc::generic_value::ICRC3Value;
+
+/// The identifier of a non-fungible token.
+pub type TokenId = Nat;
+
+/// The metadata of a token or of the whole collection as returned by the
+/// `icrc7_token_metadata` and `icrc7_collection_metadata` endpoints.
+pub type Metadata = Vec<(String, ICRC3Value)>;
+
+/// Well-known keys of the ICRC-7 collection metadata.
+pub mod keys {
+    pub const SYMBOL: &str = "icrc7:symbol";
+    pub const NAME: &str = "icrc7:name";
+    pub const DESCRIPTION: &str = "icrc7:description";
+    pub const LOGO: &str = "icrc7:logo";
+    pub const TOTAL_SUPPLY: &str = "icrc7:total_supply";
+    pub const SUPPLY_CAP: &str = "icrc7:supply_cap";
+    pub const MAX_QUERY_BATCH_SIZE: &str = "icrc7:max_query_batch_size";
+    pub const MAX_UPDATE_BATCH_SIZE: &str = "icrc7:max_update_batch_size";
+    pub const DEFAULT_TAKE_VALUE: &str = "icrc7:default_take_value";
+    pub const MAX_TAKE_VALUE: &str = "icrc7:max_take_value";
+    pub const MAX_MEMO_SIZE: &str = "icrc7:max_memo_size";
+    pub const ATOMIC_BATCH_TRANSFERS: &str = "icrc7:atomic_batch_transfers";
+    pub const TX_WINDOW: &str = "icrc7:tx_window";
+    pub const PERMITTED_DRIFT: &str = "icrc7:permitted_drift";
+    pub const MAX_APPROVALS_PER_TOKEN_OR_COLLECTION: &str =
+        "icrc37:max_approvals_per_token_or_collection";
+    pub const MAX_REVOKE_APPROVALS: &str = "icrc37:max_revoke_approvals";
+}
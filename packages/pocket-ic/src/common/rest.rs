@@ -252,6 +252,152 @@ pub struct RawAddCycles {
     pub amount: u128,
 }
 
+/// A location in the source code of a canister module.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u64,
+}
+
+/// The number of times a function of a canister module has been executed.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct FunctionCoverage {
+    /// The index of the function in the canister module.
+    pub function_index: u32,
+    /// The name of the function in the name section of the canister module (if any).
+    pub name: Option<String>,
+    /// The location of the first instruction of the function in the source code
+    /// according to the DWARF line tables of the canister module (if any).
+    pub source_location: Option<SourceLocation>,
+    pub hits: u64,
+}
+
+/// The Wasm coverage of a canister: the number of times each function
+/// of the canister module has been executed in replicated mode
+/// since the canister module was installed.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub struct CanisterCoverage {
+    pub functions: Vec<FunctionCoverage>,
+}
+
+impl CanisterCoverage {
+    /// Exports the coverage in the lcov tracefile format.
+    ///
+    /// The coverage is function-level: every function is reported as a
+    /// function and as a single line, the line of its first instruction,
+    /// executed as many times as the function.
+    /// Only functions with a source location are reported, so the result
+    /// is empty if the canister module has no DWARF line tables.
+    pub fn to_lcov(&self) -> String {
+        let function_name = |f: &FunctionCoverage| {
+            f.name
+                .clone()
+                .unwrap_or_else(|| format!("func[{}]", f.function_index))
+        };
+        let mut functions_by_file: BTreeMap<&str, Vec<(u64, &FunctionCoverage)>> = BTreeMap::new();
+        for f in &self.functions {
+            if let Some(location) = &f.source_location {
+                functions_by_file
+                    .entry(location.file.as_str())
+                    .or_default()
+                    .push((location.line, f));
+            }
+        }
+        let mut lcov = String::new();
+        for (file, functions) in functions_by_file {
+            lcov.push_str(&format!("TN:\nSF:{}\n", file));
+            for (line, f) in &functions {
+                lcov.push_str(&format!("FN:{},{}\n", line, function_name(f)));
+            }
+            for (_, f) in &functions {
+                lcov.push_str(&format!("FNDA:{},{}\n", f.hits, function_name(f)));
+            }
+            lcov.push_str(&format!("FNF:{}\n", functions.len()));
+            lcov.push_str(&format!(
+                "FNH:{}\n",
+                functions.iter().filter(|(_, f)| f.hits > 0).count()
+            ));
+            let mut lines: BTreeMap<u64, u64> = BTreeMap::new();
+            for (line, f) in &functions {
+                *lines.entry(*line).or_default() += f.hits;
+            }
+            for (line, hits) in &lines {
+                lcov.push_str(&format!("DA:{},{}\n", line, hits));
+            }
+            lcov.push_str(&format!("LF:{}\n", lines.len()));
+            lcov.push_str(&format!(
+                "LH:{}\n",
+                lines.values().filter(|hits| **hits > 0).count()
+            ));
+            lcov.push_str("end_of_record\n");
+        }
+        lcov
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct RawWasmProfile {
+    pub canister_id: RawCanisterId,
+    pub method: String,
+    pub folded_stacks: String,
+}
+
+/// The instructions executed by a single execution of a canister method
+/// (or of a callback) attributed to the call stacks of Wasm functions.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct WasmProfile {
+    pub canister_id: Principal,
+    /// The executed method (e.g., `canister_update write`) or callback
+    /// (e.g., `replicated reply callback`).
+    pub method: String,
+    /// The profile in the folded-stack format read by flamegraph tools:
+    /// one line per call stack consisting of the names of the functions
+    /// from the outermost to the innermost function separated by `;`,
+    /// followed by a space and the number of instructions executed
+    /// by the innermost function. Functions without a name in the name section
+    /// of the canister module are named `func[<function index>]`.
+    pub folded_stacks: String,
+}
+
+impl From<RawWasmProfile> for WasmProfile {
+    fn from(raw_wasm_profile: RawWasmProfile) -> Self {
+        Self {
+            canister_id: Principal::from_slice(&raw_wasm_profile.canister_id.canister_id),
+            method: raw_wasm_profile.method,
+            folded_stacks: raw_wasm_profile.folded_stacks,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct RawWasmProfiles {
+    pub profiles: Vec<RawWasmProfile>,
+    pub num_dropped: u64,
+}
+
+/// The Wasm profiles of the messages executed since the profiles were last taken.
+/// Every subnet only keeps a bounded number of the most recent profiles.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct WasmProfiles {
+    pub profiles: Vec<WasmProfile>,
+    /// The number of older profiles that were dropped to bound the memory
+    /// used for profiles that have not been taken.
+    pub num_dropped: u64,
+}
+
+impl From<RawWasmProfiles> for WasmProfiles {
+    fn from(raw_wasm_profiles: RawWasmProfiles) -> Self {
+        Self {
+            profiles: raw_wasm_profiles
+                .profiles
+                .into_iter()
+                .map(WasmProfile::from)
+                .collect(),
+            num_dropped: raw_wasm_profiles.num_dropped,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
 pub struct RawCycles {
     pub cycles: u128,
@@ -506,6 +652,71 @@ pub struct InstanceConfig {
     pub nonmainnet_features: bool,
     pub log_level: Option<String>,
     pub bitcoind_addr: Option<Vec<SocketAddr>>,
+    /// If set, every state-changing operation on the instance is recorded
+    /// in a journal that can be replayed on a fresh instance.
+    #[serde(default)]
+    pub journal: bool,
+    /// If set, canister modules are instrumented to count the executions
+    /// of their functions for Wasm coverage reports.
+    #[serde(default)]
+    pub wasm_coverage: bool,
+    /// If set, the instructions executed by every message are attributed
+    /// to the call stacks of Wasm functions in which they were executed.
+    #[serde(default)]
+    pub wasm_profiling: bool,
+}
+
+/// The version of the HTTP endpoint through which an update call was submitted.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, JsonSchema, PartialEq, Eq)]
+pub enum RawCallRequestVersion {
+    V2,
+    V3,
+}
+
+/// A state-changing operation recorded in the journal of a PocketIC instance.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub enum RawJournalEntry {
+    SetTime(RawTime),
+    Tick,
+    /// A round executed on every subnet to write a checkpoint
+    /// when the instance was forked.
+    CheckpointedTick,
+    AdvanceTimeAndTick {
+        nanos: u64,
+    },
+    SubmitIngressMessage(RawCanisterCall),
+    AwaitIngressMessage(RawMessageId),
+    ExecuteIngressMessage(RawCanisterCall),
+    AddCycles(RawAddCycles),
+    SetStableMemory {
+        #[serde(deserialize_with = "base64::deserialize")]
+        #[serde(serialize_with = "base64::serialize")]
+        canister_id: Vec<u8>,
+        #[serde(deserialize_with = "base64::deserialize")]
+        #[serde(serialize_with = "base64::serialize")]
+        data: Vec<u8>,
+    },
+    MockCanisterHttp(RawMockCanisterHttpResponse),
+    /// An update call submitted through the HTTP endpoint `/api/v2` or `/api/v3`
+    /// with the CBOR-encoded request envelope in `bytes`.
+    Call {
+        effective_canister_id: RawCanisterId,
+        version: RawCallRequestVersion,
+        #[serde(deserialize_with = "base64::deserialize")]
+        #[serde(serialize_with = "base64::serialize")]
+        bytes: Vec<u8>,
+    },
+}
+
+/// The journal of a PocketIC instance: the configuration with which the instance
+/// was created and all state-changing operations performed on the instance so far.
+/// Replaying the entries on a fresh instance created from the same configuration
+/// yields the same instance state, with the exception of HTTPS outcalls
+/// processed in auto progress mode and instances created from a state directory.
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct RawJournal {
+    pub instance_config: InstanceConfig,
+    pub entries: Vec<RawJournalEntry>,
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
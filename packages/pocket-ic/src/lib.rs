@@ -36,9 +36,9 @@
 //!
 use crate::{
     common::rest::{
-        BlobCompression, BlobId, CanisterHttpRequest, ExtendedSubnetConfigSet, HttpsConfig,
-        InstanceId, MockCanisterHttpResponse, RawEffectivePrincipal, RawMessageId, SubnetId,
-        SubnetKind, SubnetSpec, Topology,
+        BlobCompression, BlobId, CanisterCoverage, CanisterHttpRequest, ExtendedSubnetConfigSet,
+        HttpsConfig, InstanceId, MockCanisterHttpResponse, RawEffectivePrincipal, RawJournal,
+        RawMessageId, SubnetId, SubnetKind, SubnetSpec, Topology, WasmProfiles,
     },
     management_canister::{
         CanisterId, CanisterInstallMode, CanisterLogRecord, CanisterSettings, CanisterStatusResult,
@@ -91,6 +91,9 @@ pub struct PocketIcBuilder {
     nonmainnet_features: bool,
     log_level: Option<Level>,
     bitcoind_addr: Option<Vec<SocketAddr>>,
+    journal: bool,
+    wasm_coverage: bool,
+    wasm_profiling: bool,
 }
 
 #[allow(clippy::new_without_default)]
@@ -104,6 +107,9 @@ impl PocketIcBuilder {
             nonmainnet_features: false,
             log_level: None,
             bitcoind_addr: None,
+            journal: false,
+            wasm_coverage: false,
+            wasm_profiling: false,
         }
     }
 
@@ -123,6 +129,9 @@ impl PocketIcBuilder {
             self.nonmainnet_features,
             self.log_level,
             self.bitcoind_addr,
+            self.journal,
+            self.wasm_coverage,
+            self.wasm_profiling,
         )
     }
 
@@ -136,6 +145,9 @@ impl PocketIcBuilder {
             self.nonmainnet_features,
             self.log_level,
             self.bitcoind_addr,
+            self.journal,
+            self.wasm_coverage,
+            self.wasm_profiling,
         )
         .await
     }
@@ -177,6 +189,28 @@ impl PocketIcBuilder {
         }
     }
 
+    /// Record all state-changing operations on the PocketIC instance in a journal
+    /// that can be retrieved via `PocketIc::journal` and replayed via `PocketIc::replay_from`.
+    pub fn with_journal(mut self) -> Self {
+        self.journal = true;
+        self
+    }
+
+    /// Instrument all canister modules installed on the PocketIC instance
+    /// with coverage counters that can be retrieved via `PocketIc::get_wasm_coverage`.
+    pub fn with_wasm_coverage(mut self) -> Self {
+        self.wasm_coverage = true;
+        self
+    }
+
+    /// Attribute the instructions executed by every message on the PocketIC instance
+    /// to the call stacks of Wasm functions in which they were executed.
+    /// The resulting profiles can be retrieved via `PocketIc::take_wasm_profiles`.
+    pub fn with_wasm_profiling(mut self) -> Self {
+        self.wasm_profiling = true;
+        self
+    }
+
     /// Add an empty NNS subnet
     pub fn with_nns_subnet(mut self) -> Self {
         let mut config = self.config.unwrap_or_default();
@@ -386,7 +420,32 @@ impl PocketIc {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoind_addr: Option<Vec<SocketAddr>>,
+        journal: bool,
+        wasm_coverage: bool,
+        wasm_profiling: bool,
     ) -> Self {
+        Self::from_async(PocketIcAsync::from_components(
+            subnet_config_set,
+            server_url,
+            max_request_time_ms,
+            state_dir,
+            nonmainnet_features,
+            log_level,
+            bitcoind_addr,
+            journal,
+            wasm_coverage,
+            wasm_profiling,
+        ))
+    }
+
+    /// Creates a new PocketIC instance from the instance configuration of the given journal
+    /// and replays all journal entries on the new instance.
+    /// The server is started if it's not already running.
+    pub fn replay_from(journal: RawJournal) -> Self {
+        Self::from_async(PocketIcAsync::replay_from(journal))
+    }
+
+    fn from_async(pocket_ic: impl std::future::Future<Output = PocketIcAsync>) -> Self {
         let (tx, rx) = channel();
         let thread = thread::spawn(move || {
             let rt = tokio::runtime::Builder::new_current_thread()
@@ -397,18 +456,7 @@ impl PocketIc {
         });
         let runtime = rx.recv().unwrap();
 
-        let pocket_ic = runtime.block_on(async {
-            PocketIcAsync::from_components(
-                subnet_config_set,
-                server_url,
-                max_request_time_ms,
-                state_dir,
-                nonmainnet_features,
-                log_level,
-                bitcoind_addr,
-            )
-            .await
-        });
+        let pocket_ic = runtime.block_on(pocket_ic);
 
         Self {
             pocket_ic,
@@ -422,6 +470,17 @@ impl PocketIc {
         self.pocket_ic.get_server_url()
     }
 
+    /// Creates a new PocketIC instance as an independent copy of this instance.
+    /// The subnet states are copied from checkpoints written for this purpose
+    /// so that a costly setup (e.g., deploying the NNS and SNS canisters)
+    /// only needs to be done once. Writing the checkpoints executes a round
+    /// on every subnet of this instance.
+    /// The new instance is deleted once the returned handle is dropped.
+    #[instrument(skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn fork(&self) -> Self {
+        Self::from_async(self.pocket_ic.fork())
+    }
+
     /// Returns the instance ID.
     pub fn instance_id(&self) -> InstanceId {
         self.pocket_ic.instance_id
@@ -494,6 +553,15 @@ impl PocketIc {
         })
     }
 
+    /// Returns the journal of all state-changing operations performed on this instance so far.
+    /// Panics if the instance was not created with journaling enabled
+    /// (see `PocketIcBuilder::with_journal`).
+    #[instrument(skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn journal(&self) -> RawJournal {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.journal().await })
+    }
+
     /// Make the IC produce and progress by one block.
     /// Note that multiple ticks might be necessary to observe
     /// an expected effect, e.g., if the effect depends on
@@ -616,6 +684,28 @@ impl PocketIc {
         runtime.block_on(async { self.pocket_ic.get_controllers(canister_id).await })
     }
 
+    /// Get the Wasm coverage of a canister, i.e., the number of times each function
+    /// of its module has been executed in replicated mode since the module was installed.
+    /// Panics if the instance was not created with Wasm coverage enabled
+    /// (see `PocketIcBuilder::with_wasm_coverage`).
+    #[instrument(skip(self), fields(instance_id=self.pocket_ic.instance_id, canister_id = %canister_id.to_string()))]
+    pub fn get_wasm_coverage(&self, canister_id: CanisterId) -> CanisterCoverage {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.get_wasm_coverage(canister_id).await })
+    }
+
+    /// Take the Wasm instruction profiles of all messages executed since the profiles
+    /// were last taken. Each profile is in the folded stack format consumed by flamegraph tools.
+    /// Only a bounded number of the most recent profiles is kept; the number of dropped
+    /// profiles is returned alongside the profiles.
+    /// Panics if the instance was not created with Wasm profiling enabled
+    /// (see `PocketIcBuilder::with_wasm_profiling`).
+    #[instrument(skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn take_wasm_profiles(&self) -> WasmProfiles {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.take_wasm_profiles().await })
+    }
+
     /// Get the current cycles balance of a canister.
     #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id, canister_id = %canister_id.to_string()))]
     pub fn cycle_balance(&self, canister_id: CanisterId) -> u128 {
@@ -1,11 +1,12 @@
 use crate::common::rest::{
-    ApiResponse, AutoProgressConfig, BlobCompression, BlobId, CanisterHttpRequest,
-    CreateHttpGatewayResponse, CreateInstanceResponse, ExtendedSubnetConfigSet, HttpGatewayBackend,
-    HttpGatewayConfig, HttpGatewayInfo, HttpsConfig, InstanceConfig, InstanceId,
-    MockCanisterHttpResponse, RawAddCycles, RawCanisterCall, RawCanisterHttpRequest, RawCanisterId,
-    RawCanisterResult, RawCycles, RawEffectivePrincipal, RawMessageId, RawMockCanisterHttpResponse,
-    RawPrincipalId, RawSetStableMemory, RawStableMemory, RawSubmitIngressResult, RawSubnetId,
-    RawTime, RawVerifyCanisterSigArg, RawWasmResult, SubnetId, Topology,
+    ApiResponse, AutoProgressConfig, BlobCompression, BlobId, CanisterCoverage,
+    CanisterHttpRequest, CreateHttpGatewayResponse, CreateInstanceResponse,
+    ExtendedSubnetConfigSet, HttpGatewayBackend, HttpGatewayConfig, HttpGatewayInfo, HttpsConfig,
+    InstanceConfig, InstanceId, MockCanisterHttpResponse, RawAddCycles, RawCanisterCall,
+    RawCanisterHttpRequest, RawCanisterId, RawCanisterResult, RawCycles, RawEffectivePrincipal,
+    RawJournal, RawMessageId, RawMockCanisterHttpResponse, RawPrincipalId, RawSetStableMemory,
+    RawStableMemory, RawSubmitIngressResult, RawSubnetId, RawTime, RawVerifyCanisterSigArg,
+    RawWasmProfiles, RawWasmResult, SubnetId, Topology, WasmProfiles,
 };
 use crate::management_canister::{
     CanisterId, CanisterIdRecord, CanisterInstallMode, CanisterInstallModeUpgradeInner,
@@ -129,6 +130,9 @@ impl PocketIc {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoind_addr: Option<Vec<SocketAddr>>,
+        journal: bool,
+        wasm_coverage: bool,
+        wasm_profiling: bool,
     ) -> Self {
         let subnet_config_set = subnet_config_set.into();
         if state_dir.is_none()
@@ -142,8 +146,34 @@ impl PocketIc {
             nonmainnet_features,
             log_level: log_level.map(|l| l.to_string()),
             bitcoind_addr,
+            journal,
+            wasm_coverage,
+            wasm_profiling,
         };
+        Self::from_instance_config(instance_config, server_url, max_request_time_ms).await
+    }
+
+    /// Creates a new PocketIC instance from the instance configuration of the given journal
+    /// and replays all journal entries on the new instance.
+    /// The server is started if it's not already running.
+    pub async fn replay_from(journal: RawJournal) -> Self {
+        let server_url = crate::start_or_reuse_server();
+        let pocket_ic = Self::from_instance_config(
+            journal.instance_config,
+            server_url,
+            Some(crate::DEFAULT_MAX_REQUEST_TIME_MS),
+        )
+        .await;
+        let endpoint = "update/replay";
+        pocket_ic.post::<(), _>(endpoint, journal.entries).await;
+        pocket_ic
+    }
 
+    async fn from_instance_config(
+        instance_config: InstanceConfig,
+        server_url: Url,
+        max_request_time_ms: Option<u64>,
+    ) -> Self {
         let test_driver_pid = std::process::id();
         let log_guard = setup_tracing(test_driver_pid);
 
@@ -179,6 +209,46 @@ impl PocketIc {
         self.server_url.clone()
     }
 
+    /// Creates a new PocketIC instance as an independent copy of this instance.
+    /// The subnet states are copied from checkpoints written for this purpose
+    /// so that a costly setup (e.g., deploying the NNS and SNS canisters)
+    /// only needs to be done once. Writing the checkpoints executes a round
+    /// on every subnet of this instance.
+    /// The new instance is deleted once the returned handle is dropped.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub async fn fork(&self) -> Self {
+        let test_driver_pid = std::process::id();
+        let log_guard = setup_tracing(test_driver_pid);
+
+        let reqwest_client = reqwest::Client::new();
+        let instance_id = match reqwest_client
+            .post(self.instance_url().join("fork").unwrap())
+            .send()
+            .await
+            .expect("Failed to get result")
+            .json::<CreateInstanceResponse>()
+            .await
+            .expect("Could not parse response for fork instance request")
+        {
+            CreateInstanceResponse::Created { instance_id, .. } => instance_id,
+            CreateInstanceResponse::Error { message } => panic!("{}", message),
+        };
+        debug!(
+            "instance_id={} New instance forked from instance_id={}.",
+            instance_id, self.instance_id
+        );
+
+        Self {
+            instance_id,
+            max_request_time_ms: self.max_request_time_ms,
+            http_gateway: None,
+            server_url: self.server_url.clone(),
+            reqwest_client,
+            owns_instance: true,
+            _log_guard: log_guard,
+        }
+    }
+
     /// Returns the topology of the different subnets of this PocketIC instance.
     pub async fn topology(&self) -> Topology {
         let endpoint = "read/topology";
@@ -284,6 +354,15 @@ impl PocketIc {
             .expect("Failed to get json")
     }
 
+    /// Returns the journal of all state-changing operations performed on this instance so far.
+    /// Panics if the instance was not created with journaling enabled
+    /// (see `PocketIcBuilder::with_journal`).
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub async fn journal(&self) -> RawJournal {
+        let endpoint = "read/journal";
+        self.get(endpoint).await
+    }
+
     /// Make the IC produce and progress by one block.
     /// Note that multiple ticks might be necessary to observe
     /// an expected effect, e.g., if the effect depends on
@@ -508,6 +587,35 @@ impl PocketIc {
         result.into_iter().map(|p| p.into()).collect()
     }
 
+    /// Get the Wasm coverage of a canister, i.e., the number of times each function
+    /// of its module has been executed in replicated mode since the module was installed.
+    /// Panics if the instance was not created with Wasm coverage enabled
+    /// (see `PocketIcBuilder::with_wasm_coverage`).
+    #[instrument(skip(self), fields(instance_id=self.instance_id, canister_id = %canister_id.to_string()))]
+    pub async fn get_wasm_coverage(&self, canister_id: CanisterId) -> CanisterCoverage {
+        let endpoint = "read/get_wasm_coverage";
+        self.post(
+            endpoint,
+            RawCanisterId {
+                canister_id: canister_id.as_slice().to_vec(),
+            },
+        )
+        .await
+    }
+
+    /// Take the Wasm instruction profiles of all messages executed since the profiles
+    /// were last taken. Each profile is in the folded stack format consumed by flamegraph tools.
+    /// Only a bounded number of the most recent profiles is kept; the number of dropped
+    /// profiles is returned alongside the profiles.
+    /// Panics if the instance was not created with Wasm profiling enabled
+    /// (see `PocketIcBuilder::with_wasm_profiling`).
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub async fn take_wasm_profiles(&self) -> WasmProfiles {
+        let endpoint = "update/take_wasm_profiles";
+        let res: RawWasmProfiles = self.post(endpoint, "").await;
+        res.into()
+    }
+
     /// Get the current cycles balance of a canister.
     #[instrument(ret, skip(self), fields(instance_id=self.instance_id, canister_id = %canister_id.to_string()))]
     pub async fn cycle_balance(&self, canister_id: CanisterId) -> u128 {
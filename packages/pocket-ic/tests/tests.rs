@@ -6,8 +6,9 @@ use pocket_ic::management_canister::{
 };
 use pocket_ic::{
     common::rest::{
-        BlobCompression, CanisterHttpReply, CanisterHttpResponse, MockCanisterHttpResponse,
-        RawEffectivePrincipal, SubnetKind,
+        BlobCompression, CanisterCoverage, CanisterHttpReply, CanisterHttpResponse,
+        FunctionCoverage, MockCanisterHttpResponse, RawEffectivePrincipal, RawJournalEntry,
+        SourceLocation, SubnetKind,
     },
     query_candid, update_candid, DefaultEffectiveCanisterIdError, ErrorCode, PocketIc,
     PocketIcBuilder, WasmResult,
@@ -87,6 +88,226 @@ fn call_counter_can(ic: &PocketIc, can_id: CanisterId, method: &str) -> WasmResu
     .expect("Failed to call counter canister")
 }
 
+#[test]
+fn test_journal_replay() {
+    let pic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_journal()
+        .build();
+
+    let can_id = pic.create_canister();
+    pic.add_cycles(can_id, INIT_CYCLES);
+    pic.install_canister(can_id, counter_wasm(), vec![], None);
+    call_counter_can(&pic, can_id, "write");
+    pic.advance_time(std::time::Duration::from_secs(60));
+    pic.tick();
+    call_counter_can(&pic, can_id, "write");
+
+    let journal = pic.journal();
+    assert!(journal.instance_config.journal);
+    assert!(!journal.entries.is_empty());
+
+    let replayed = PocketIc::replay_from(journal.clone());
+    assert_eq!(replayed.get_time(), pic.get_time());
+    assert_eq!(replayed.cycle_balance(can_id), pic.cycle_balance(can_id));
+    let reply = call_counter_can(&replayed, can_id, "read");
+    assert_eq!(reply, WasmResult::Reply(vec![2, 0, 0, 0]));
+
+    // The replayed instance records the replayed entries in its own journal
+    // followed by the submission and awaiting of the call to `read` above.
+    let replayed_journal = replayed.journal();
+    assert_eq!(replayed_journal.entries.len(), journal.entries.len() + 2);
+}
+
+#[test]
+fn test_fork() {
+    let pic = PocketIc::new();
+
+    let can_id = pic.create_canister();
+    pic.add_cycles(can_id, INIT_CYCLES);
+    pic.install_canister(can_id, counter_wasm(), vec![], None);
+    call_counter_can(&pic, can_id, "write");
+
+    let fork = pic.fork();
+    assert_eq!(fork.topology(), pic.topology());
+    assert_eq!(fork.get_time(), pic.get_time());
+
+    // The two instances evolve independently.
+    call_counter_can(&fork, can_id, "write");
+    let reply = call_counter_can(&fork, can_id, "write");
+    assert_eq!(reply, WasmResult::Reply(vec![3, 0, 0, 0]));
+    let reply = call_counter_can(&pic, can_id, "read");
+    assert_eq!(reply, WasmResult::Reply(vec![1, 0, 0, 0]));
+
+    // Dropping the fork does not affect the original instance.
+    drop(fork);
+    let reply = call_counter_can(&pic, can_id, "write");
+    assert_eq!(reply, WasmResult::Reply(vec![2, 0, 0, 0]));
+}
+
+#[test]
+fn test_fork_of_instance_restored_from_state_dir() {
+    let state_dir = tempfile::TempDir::new().unwrap();
+
+    let pic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_state_dir(state_dir.path().to_path_buf())
+        .build();
+    let can_id = pic.create_canister();
+    pic.add_cycles(can_id, INIT_CYCLES);
+    pic.install_canister(can_id, counter_wasm(), vec![], None);
+    call_counter_can(&pic, can_id, "write");
+    drop(pic);
+
+    // The canister memory of the restored instance is backed by the files
+    // in its state directory.
+    let pic = PocketIcBuilder::new()
+        .with_state_dir(state_dir.path().to_path_buf())
+        .build();
+    let fork = pic.fork();
+    drop(pic);
+
+    let reply = call_counter_can(&fork, can_id, "write");
+    assert_eq!(reply, WasmResult::Reply(vec![2, 0, 0, 0]));
+
+    // Forking the fork writes a checkpoint of the fork and switches
+    // its states to that checkpoint.
+    let fork_of_fork = fork.fork();
+    let reply = call_counter_can(&fork, can_id, "write");
+    assert_eq!(reply, WasmResult::Reply(vec![3, 0, 0, 0]));
+    let reply = call_counter_can(&fork_of_fork, can_id, "read");
+    assert_eq!(reply, WasmResult::Reply(vec![2, 0, 0, 0]));
+}
+
+#[test]
+fn test_journal_replay_of_forked_instance() {
+    let pic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_journal()
+        .build();
+
+    let can_id = pic.create_canister();
+    pic.add_cycles(can_id, INIT_CYCLES);
+    pic.install_canister(can_id, counter_wasm(), vec![], None);
+    call_counter_can(&pic, can_id, "write");
+
+    // Forking executes a round on the original instance, which is journaled.
+    let fork = pic.fork();
+    drop(fork);
+    call_counter_can(&pic, can_id, "write");
+
+    let journal = pic.journal();
+    assert!(journal
+        .entries
+        .iter()
+        .any(|entry| matches!(entry, RawJournalEntry::CheckpointedTick)));
+
+    let replayed = PocketIc::replay_from(journal);
+    assert_eq!(replayed.get_time(), pic.get_time());
+    assert_eq!(replayed.cycle_balance(can_id), pic.cycle_balance(can_id));
+    let reply = call_counter_can(&replayed, can_id, "read");
+    assert_eq!(reply, WasmResult::Reply(vec![2, 0, 0, 0]));
+}
+
+#[test]
+fn test_wasm_coverage() {
+    let pic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_wasm_coverage()
+        .build();
+
+    let can_id = pic.create_canister();
+    pic.add_cycles(can_id, INIT_CYCLES);
+    pic.install_canister(can_id, counter_wasm(), vec![], None);
+    call_counter_can(&pic, can_id, "write");
+    call_counter_can(&pic, can_id, "write");
+
+    // The functions `write` and `read` follow the two imported functions.
+    let coverage = pic.get_wasm_coverage(can_id);
+    assert_eq!(
+        coverage.functions,
+        vec![
+            FunctionCoverage {
+                function_index: 2,
+                name: Some("write".to_string()),
+                source_location: None,
+                hits: 2,
+            },
+            FunctionCoverage {
+                function_index: 3,
+                name: Some("read".to_string()),
+                source_location: None,
+                hits: 2,
+            },
+        ]
+    );
+
+    // The counter canister has no DWARF line tables.
+    assert!(coverage.to_lcov().is_empty());
+
+    let coverage = CanisterCoverage {
+        functions: vec![
+            FunctionCoverage {
+                function_index: 2,
+                name: Some("write".to_string()),
+                source_location: Some(SourceLocation {
+                    file: "src/lib.rs".to_string(),
+                    line: 7,
+                }),
+                hits: 2,
+            },
+            FunctionCoverage {
+                function_index: 3,
+                name: None,
+                source_location: Some(SourceLocation {
+                    file: "src/lib.rs".to_string(),
+                    line: 3,
+                }),
+                hits: 0,
+            },
+        ],
+    };
+    assert_eq!(
+        coverage.to_lcov(),
+        "TN:\nSF:src/lib.rs\nFN:7,write\nFN:3,func[3]\nFNDA:2,write\nFNDA:0,func[3]\nFNF:2\nFNH:1\nDA:3,0\nDA:7,2\nLF:2\nLH:1\nend_of_record\n"
+    );
+}
+
+#[test]
+fn test_wasm_profiling() {
+    let pic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_wasm_profiling()
+        .build();
+
+    let can_id = pic.create_canister();
+    pic.add_cycles(can_id, INIT_CYCLES);
+    pic.install_canister(can_id, counter_wasm(), vec![], None);
+    pic.take_wasm_profiles();
+
+    call_counter_can(&pic, can_id, "write");
+
+    let profiles = pic.take_wasm_profiles();
+    assert_eq!(profiles.num_dropped, 0);
+    let profile = profiles
+        .profiles
+        .iter()
+        .find(|p| p.method == "canister_update write")
+        .unwrap();
+    assert_eq!(profile.canister_id, can_id);
+    // The function `write` calls the function `read`.
+    let stacks: Vec<_> = profile
+        .folded_stacks
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    assert!(stacks.contains(&"write"));
+    assert!(stacks.contains(&"write;read"));
+
+    // Profiles are only returned once.
+    assert!(pic.take_wasm_profiles().profiles.is_empty());
+}
+
 #[test]
 fn test_create_canister_with_id() {
     let pic = PocketIcBuilder::new()
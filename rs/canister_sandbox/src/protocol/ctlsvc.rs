@@ -104,6 +104,7 @@ mod tests {
                 instance_stats: InstanceStats::default(),
                 system_api_call_counters: SystemApiCallCounters::default(),
                 canister_log: CanisterLog::default(),
+                wasm_profile: Default::default(),
             },
             state: Some(StateModifications {
                 globals: vec![
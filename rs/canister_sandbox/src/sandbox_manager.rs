@@ -135,6 +135,7 @@ impl Execution {
                 instance_stats,
                 system_api_call_counters,
                 canister_log,
+                wasm_profile,
             },
             deltas,
             instance_or_system_api,
@@ -202,6 +203,7 @@ impl Execution {
                     instance_stats,
                     system_api_call_counters,
                     canister_log,
+                    wasm_profile,
                 };
                 self.sandbox_manager.controller.execution_finished(
                     protocol::ctlsvc::ExecutionFinishedRequest {
@@ -229,6 +231,7 @@ impl Execution {
                     instance_stats,
                     system_api_call_counters,
                     canister_log,
+                    wasm_profile,
                 };
 
                 self.sandbox_manager.controller.execution_finished(
@@ -174,6 +174,7 @@ mod tests {
         methods::{FuncRef, WasmMethod},
         time::Time,
         CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
+        DEFAULT_CANISTER_LOG_BUFFER_SIZE,
     };
     use ic_wasm_types::BinaryEncodedWasm;
     use mockall::*;
@@ -233,14 +234,19 @@ mod tests {
             BTreeMap::new(),
             0,
             ic00_aliases,
+            BTreeSet::new(),
+            vec![],
             SMALL_APP_SUBNET_MAX_SIZE,
             SchedulerConfig::application_subnet().dirty_page_overhead,
             CanisterTimer::Inactive,
             0,
             BTreeSet::from([controller]),
+            BTreeMap::new(),
+            0,
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
             caller,
             0,
+            DEFAULT_CANISTER_LOG_BUFFER_SIZE,
             IS_WASM64_EXECUTION,
         )
     }
@@ -3,7 +3,9 @@ use std::time::Duration;
 use ic_base_types::NumBytes;
 use ic_registry_subnet_type::SubnetType;
 use ic_sys::PAGE_SIZE;
-use ic_types::{NumInstructions, NumOsPages, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES};
+use ic_types::{
+    NumInstructions, NumOsPages, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM64_MEMORY_IN_BYTES,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::flag_status::FlagStatus;
@@ -112,6 +114,15 @@ pub struct FeatureFlags {
     pub best_effort_responses: FlagStatus,
     /// Collect a backtrace from the canister when it panics.
     pub canister_backtrace: FlagStatus,
+    /// Count the executions of every function of a canister module
+    /// for Wasm coverage reports (only for testing).
+    pub wasm_coverage: FlagStatus,
+    /// Attribute the instructions executed by every message to the call stacks
+    /// of Wasm functions in which they were executed (only for testing).
+    pub wasm_profiling: FlagStatus,
+    /// Allow canisters to record their most recent system API calls and
+    /// write them to the canister log when they trap (only for testing).
+    pub wasm_tracing: FlagStatus,
 }
 
 impl FeatureFlags {
@@ -123,6 +134,9 @@ impl FeatureFlags {
             wasm64: FlagStatus::Enabled,
             best_effort_responses: FlagStatus::Disabled,
             canister_backtrace: FlagStatus::Enabled,
+            wasm_coverage: FlagStatus::Disabled,
+            wasm_profiling: FlagStatus::Disabled,
+            wasm_tracing: FlagStatus::Disabled,
         }
     }
 }
@@ -242,8 +256,10 @@ pub struct Config {
     /// The maximum allowed size for an uncompressed canister Wasm module.
     pub wasm_max_size: NumBytes,
 
-    /// The maximum size of the wasm heap memory.
-    pub max_wasm_memory_size: NumBytes,
+    /// The maximum size of the wasm heap memory of Wasm64 canisters.
+    /// The heap memory of Wasm32 canisters is limited to 4 GiB by the
+    /// address space.
+    pub max_wasm64_memory_size: NumBytes,
 
     /// The maximum size of the stable memory.
     pub max_stable_memory_size: NumBytes,
@@ -283,7 +299,7 @@ impl Config {
             max_dirty_pages_without_optimization: DEFAULT_MAX_DIRTY_PAGES_WITHOUT_OPTIMIZATION,
             dirty_page_copy_overhead: DIRTY_PAGE_COPY_OVERHEAD,
             wasm_max_size: WASM_MAX_SIZE,
-            max_wasm_memory_size: NumBytes::new(MAX_WASM_MEMORY_IN_BYTES),
+            max_wasm64_memory_size: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
             max_stable_memory_size: NumBytes::new(MAX_STABLE_MEMORY_IN_BYTES),
             wasm64_dirty_page_overhead_multiplier: WASM64_DIRTY_PAGE_OVERHEAD_MULTIPLIER,
         }
@@ -404,6 +404,10 @@ pub struct CyclesAccountManagerConfig {
     /// appropriately charged for.
     pub compute_percent_allocated_per_second_fee: Cycles,
 
+    /// Fee charged to a canister in the burst priority class for every round
+    /// in which its scheduling priority is boosted.
+    pub burst_priority_round_fee: Cycles,
+
     /// How often to charge canisters for memory and compute allocations.
     pub duration_between_allocation_charges: Duration,
 
@@ -445,6 +449,8 @@ impl CyclesAccountManagerConfig {
             reference_subnet_size: DEFAULT_REFERENCE_SUBNET_SIZE,
             canister_creation_fee: Cycles::new(500_000_000_000),
             compute_percent_allocated_per_second_fee: Cycles::new(10_000_000),
+            // Corresponds to 10% of compute allocation for a one-second round.
+            burst_priority_round_fee: Cycles::new(100_000_000),
 
             // The following fields are set based on a thought experiment where
             // we estimated how many resources a representative benchmark on a
@@ -485,6 +491,7 @@ impl CyclesAccountManagerConfig {
             reference_subnet_size: DEFAULT_REFERENCE_SUBNET_SIZE,
             canister_creation_fee: Cycles::new(0),
             compute_percent_allocated_per_second_fee: Cycles::new(0),
+            burst_priority_round_fee: Cycles::new(0),
             update_message_execution_fee: Cycles::new(0),
             ten_update_instructions_execution_fee: Cycles::new(0),
             ten_update_instructions_execution_fee_wasm64: Cycles::new(0),
@@ -241,6 +241,12 @@ impl CyclesAccountManager {
         self.scale_cost(self.config.ingress_message_reception_fee, subnet_size)
     }
 
+    /// Returns the fee for being scheduled with burst priority for one round
+    /// in [`Cycles`].
+    pub fn burst_priority_round_fee(&self, subnet_size: usize) -> Cycles {
+        self.scale_cost(self.config.burst_priority_round_fee, subnet_size)
+    }
+
     /// Returns the fee for storing a GiB of data per second scaled by subnet size.
     pub fn gib_storage_per_second_fee(&self, subnet_size: usize) -> Cycles {
         self.scale_cost(self.config.gib_storage_per_second_fee, subnet_size)
@@ -268,6 +274,16 @@ impl CyclesAccountManager {
         )
     }
 
+    /// Returns the fee for sending a request with a payload of the given size
+    /// and receiving its response, excluding the prepayments for the
+    /// transmission and execution of the response, in [`Cycles`].
+    pub fn xnet_call_total_fee(&self, payload_size: NumBytes, subnet_size: usize) -> Cycles {
+        self.scale_cost(
+            self.config.xnet_call_fee + self.config.xnet_byte_transmission_fee * payload_size.get(),
+            subnet_size,
+        )
+    }
+
     // Returns the total idle resource consumption rate in cycles per day.
     pub fn idle_cycles_burned_rate(
         &self,
@@ -506,6 +522,34 @@ impl CyclesAccountManager {
         )
     }
 
+    /// Withdraws and consumes the fee for scheduling the canister with burst
+    /// priority in the current round.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CanisterOutOfCyclesError` if there are not enough cycles in
+    /// the canister balance above the freezing threshold.
+    pub fn consume_cycles_for_burst_priority(
+        &self,
+        canister: &mut CanisterState,
+        subnet_size: usize,
+    ) -> Result<(), CanisterOutOfCyclesError> {
+        let memory_usage = canister.memory_usage();
+        let message_memory = canister.message_memory_usage();
+        let compute_allocation = canister.compute_allocation();
+        let cycles = self.burst_priority_round_fee(subnet_size);
+        self.consume_cycles(
+            &mut canister.system_state,
+            memory_usage,
+            message_memory,
+            compute_allocation,
+            cycles,
+            subnet_size,
+            CyclesUseCase::SchedulingPriority,
+            false, // caller is system => no need to reveal top up balance
+        )
+    }
+
     /// Prepays the cost of executing a message with the given number of
     /// instructions. See the comment of `execution_cost()` for details
     /// about the execution cost.
@@ -778,11 +822,8 @@ impl CyclesAccountManager {
         //   - the fee to send the request (by size)
         //   - the fee for the largest possible response
         //   - the fee for executing the largest allowed response when it eventually arrives.
-        let transmission_fee = self.scale_cost(
-            self.config.xnet_call_fee
-                + self.config.xnet_byte_transmission_fee * request.payload_size_bytes().get(),
-            subnet_size,
-        ) + prepayment_for_response_transmission;
+        let transmission_fee = self.xnet_call_total_fee(request.payload_size_bytes(), subnet_size)
+            + prepayment_for_response_transmission;
 
         let fee = transmission_fee + prepayment_for_response_execution;
 
@@ -931,6 +972,7 @@ impl CyclesAccountManager {
             | CyclesUseCase::ECDSAOutcalls
             | CyclesUseCase::SchnorrOutcalls
             | CyclesUseCase::VetKd
+            | CyclesUseCase::SchedulingPriority
             | CyclesUseCase::HTTPOutcalls
             | CyclesUseCase::DeletedCanisters
             | CyclesUseCase::NonConsumed
@@ -1,17 +1,18 @@
 //! Standalone interface for testing application canisters.
 
-use crate::message::{msg_stream_from_file, Message};
+use crate::message::{msg_stream_from_file, ExpectedReject, ExpectedReply, Message};
 use hex::encode;
 use ic_config::{subnet_config::SubnetConfig, Config};
 use ic_crypto_test_utils_ni_dkg::dummy_initial_dkg_transcript_with_master_key;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_error_types::{ErrorCode, UserError};
+use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_execution_environment::ExecutionServices;
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
 use ic_interfaces::{
     execution_environment::{IngressHistoryReader, QueryExecutionError},
     messaging::MessageRouting,
 };
+use ic_interfaces_state_manager::StateReader;
 use ic_messaging::MessageRoutingImpl;
 use ic_metrics::MetricsRegistry;
 use ic_protobuf::registry::{
@@ -41,6 +42,7 @@ use ic_types::{
     messages::{MessageId, SignedIngress},
     replica_config::ReplicaConfig,
     time, CanisterId, NodeId, NumInstructions, PrincipalId, Randomness, RegistryVersion, SubnetId,
+    Time,
 };
 use ic_types::{
     batch::{BatchMessages, BlockmakerMetrics},
@@ -75,19 +77,28 @@ pub struct DrunOptions {
 }
 
 /// Deliver a single message to the Message Routing layer
+/// and return its result.
 fn deliver_message(
     msg: SignedIngress,
     message_routing: &dyn MessageRouting,
     ingress_hist_reader: &dyn IngressHistoryReader,
     extra_batches: u64,
-) {
+    batch_time: Option<Time>,
+) -> Result<WasmResult, UserError> {
     let message_id = msg.id();
 
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
+    let result = execute_ingress_message(
+        message_routing,
+        msg,
+        &message_id,
+        ingress_hist_reader,
+        batch_time,
+    );
     // print result after waiting, to not interleave the result
     // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
+    wait_extra_batches(message_routing, extra_batches, batch_time);
     print_ingress_result(&message_id, ingress_hist_reader);
+    result
 }
 
 fn setup_logger(log_file: PathBuf) -> Logger {
@@ -271,15 +282,23 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
         MaliciousFlags::default(),
     );
 
+    // The time of the batches delivered to message routing:
+    // the current system time unless the time has been set explicitly.
+    let mut batch_time: Option<Time> = None;
+    // The result of the last message (to check expectations against).
+    let mut last_result: Option<Result<WasmResult, UserError>> = None;
+
     for parse_result in msg_stream {
-        match parse_result? {
-            Message::Install(msg) => {
-                deliver_message(
+        let (line, msg) = parse_result?;
+        match msg {
+            Message::Install(msg) | Message::Ingress(msg) | Message::Create(msg) => {
+                last_result = Some(deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
-                );
+                    batch_time,
+                ));
             }
 
             Message::Query(q) => {
@@ -296,25 +315,35 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
                         panic!("Certified state unavailable for query call.")
                     }
                 };
-                print_query_result(query_result);
+                print_query_result(query_result.clone());
+                last_result = Some(query_result);
             }
 
-            Message::Ingress(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
+            Message::ExpectReply(expected) => check_expected_reply(&expected, &last_result)
+                .map_err(|e| format!("Line {}: {}", line, e))?,
+
+            Message::ExpectReject(expected) => check_expected_reject(&expected, &last_result)
+                .map_err(|e| format!("Line {}: {}", line, e))?,
+
+            Message::SetTime(time) => {
+                let state_time = state_manager.get_latest_state().get_ref().time();
+                if time < state_time {
+                    return Err(format!(
+                        "Line {}: Cannot set time to {} which is before the current time {}.",
+                        line, time, state_time
+                    ));
+                }
+                batch_time = Some(time);
+                // Deliver a batch so that the new time is observable by subsequent queries.
+                wait_extra_batches(&message_routing, 1, batch_time);
             }
 
-            Message::Create(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
+            Message::AdvanceTime(duration) => {
+                let current_time =
+                    batch_time.unwrap_or_else(|| state_manager.get_latest_state().get_ref().time());
+                batch_time = Some(current_time + duration);
+                // Deliver a batch so that the new time is observable by subsequent queries.
+                wait_extra_batches(&message_routing, 1, batch_time);
             }
         }
     }
@@ -330,6 +359,65 @@ fn disable_dts(subnet_config: &mut SubnetConfig) {
         scheduler_config.max_instructions_per_install_code;
 }
 
+fn check_expected_reply(
+    expected: &ExpectedReply,
+    result: &Option<Result<WasmResult, UserError>>,
+) -> Result<(), String> {
+    match result {
+        Some(Ok(WasmResult::Reply(reply))) if expected.matches(reply) => Ok(()),
+        Some(Ok(WasmResult::Reply(reply))) => Err(format!(
+            "Expected reply {}, but got reply 0x{}.",
+            expected,
+            encode(reply)
+        )),
+        Some(Ok(WasmResult::Reject(msg))) => Err(format!(
+            "Expected reply {}, but got reject: {}",
+            expected, msg
+        )),
+        Some(Err(err)) => Err(format!(
+            "Expected reply {}, but got error: {}",
+            expected, err
+        )),
+        None => Err("Expected a reply, but no message has been executed yet.".to_string()),
+    }
+}
+
+fn check_expected_reject(
+    expected: &ExpectedReject,
+    result: &Option<Result<WasmResult, UserError>>,
+) -> Result<(), String> {
+    let matches = match (expected, result) {
+        (ExpectedReject::RejectCode(code), Some(Ok(WasmResult::Reject(_)))) => {
+            *code == RejectCode::CanisterReject
+        }
+        (ExpectedReject::RejectCode(code), Some(Err(err))) => *code == err.reject_code(),
+        (ExpectedReject::ErrorCode(code), Some(Err(err))) => *code == err.code().to_string(),
+        (_, Some(_)) => false,
+        (_, None) => {
+            return Err("Expected a reject, but no message has been executed yet.".to_string())
+        }
+    };
+    if matches {
+        return Ok(());
+    }
+    match result {
+        Some(Ok(WasmResult::Reply(reply))) => Err(format!(
+            "Expected reject {}, but got reply 0x{}.",
+            expected,
+            encode(reply)
+        )),
+        Some(Ok(WasmResult::Reject(msg))) => Err(format!(
+            "Expected reject {}, but got canister reject: {}",
+            expected, msg
+        )),
+        Some(Err(err)) => Err(format!(
+            "Expected reject {}, but got error: {}",
+            expected, err
+        )),
+        None => unreachable!(),
+    }
+}
+
 fn print_query_result(res: Result<WasmResult, UserError>) {
     match res {
         Ok(payload) => {
@@ -373,7 +461,11 @@ fn get_random_seed() -> [u8; 32] {
     seed.try_into().unwrap()
 }
 
-fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -> Batch {
+fn build_batch(
+    message_routing: &dyn MessageRouting,
+    msgs: Vec<SignedIngress>,
+    batch_time: Option<Time>,
+) -> Batch {
     Batch {
         batch_number: message_routing.expected_batch_height(),
         batch_summary: None,
@@ -386,7 +478,7 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         chain_key_subnet_public_keys: BTreeMap::new(),
         idkg_pre_signature_ids: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
-        time: time::current_time(),
+        time: batch_time.unwrap_or_else(time::current_time),
         consensus_responses: vec![],
         blockmaker_metrics: BlockmakerMetrics::new_for_test(),
         replica_version: ReplicaVersion::default(),
@@ -401,8 +493,9 @@ fn execute_ingress_message(
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
+    batch_time: Option<Time>,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut batch = build_batch(message_routing, vec![msg], batch_time);
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
         // In the first batch we try to send the ingress message itself. If it fails, we
         // repeat with the same batch.
@@ -413,7 +506,7 @@ fn execute_ingress_message(
         // potential inter-canister messages that the ingress message may have
         // triggered.
         if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
+            batch = build_batch(message_routing, vec![], batch_time)
         }
         sleep(WAIT_PER_BATCH);
 
@@ -459,10 +552,14 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(
+    message_routing: &dyn MessageRouting,
+    extra_batches: u64,
+    batch_time: Option<Time>,
+) {
     for _ in 0..extra_batches {
         loop {
-            let batch = build_batch(message_routing, vec![]);
+            let batch = build_batch(message_routing, vec![], batch_time);
             let ok = message_routing.deliver_batch(batch).is_ok();
             sleep(WAIT_PER_BATCH);
             if ok {
@@ -67,9 +67,9 @@ async fn drun_main() -> Result<(), String> {
             .embedders_config
             .feature_flags
             .best_effort_responses = FlagStatus::Enabled;
-        hypervisor_config.embedders_config.max_wasm_memory_size = MAIN_MEMORY_CAPACITY;
+        hypervisor_config.embedders_config.max_wasm64_memory_size = MAIN_MEMORY_CAPACITY;
         hypervisor_config.max_canister_memory_size =
-            hypervisor_config.embedders_config.max_wasm_memory_size
+            hypervisor_config.embedders_config.max_wasm64_memory_size
                 + hypervisor_config.embedders_config.max_stable_memory_size;
 
         let cfg = Config::load_with_default(&source, default_config).unwrap_or_else(|err| {
@@ -1,6 +1,8 @@
 use super::CanisterId;
 
+use candid::IDLArgs;
 use hex::decode;
+use ic_error_types::RejectCode;
 use ic_execution_environment::execution::upgrade::ENHANCED_ORTHOGONAL_PERSISTENCE_SECTION;
 use ic_management_canister_types::{
     self as ic00, CanisterInstallModeV2, CanisterUpgradeOptions, Payload, WasmMemoryPersistence,
@@ -8,7 +10,7 @@ use ic_management_canister_types::{
 use ic_types::{
     messages::{Query, QuerySource, SignedIngress},
     time::expiry_time_from_now,
-    PrincipalId, UserId,
+    PrincipalId, Time, UserId,
 };
 
 use std::{
@@ -17,6 +19,7 @@ use std::{
     io::{self, Read},
     str::Chars,
     string::FromUtf8Error,
+    time::Duration,
 };
 
 #[derive(PartialEq, Debug)]
@@ -25,6 +28,66 @@ pub(crate) enum Message {
     Query(Query),
     Install(SignedIngress),
     Create(SignedIngress),
+    ExpectReply(ExpectedReply),
+    ExpectReject(ExpectedReject),
+    SetTime(Time),
+    AdvanceTime(Duration),
+}
+
+/// The reply expected from the previous message.
+#[derive(PartialEq, Debug)]
+pub(crate) enum ExpectedReply {
+    /// The reply must be equal to the given bytes.
+    Bytes(Vec<u8>),
+    /// The reply must decode to the same Candid values as the given
+    /// (Candid-encoded) arguments.
+    Candid(Vec<u8>),
+}
+
+impl ExpectedReply {
+    pub(crate) fn matches(&self, reply: &[u8]) -> bool {
+        match self {
+            ExpectedReply::Bytes(bytes) => bytes == reply,
+            ExpectedReply::Candid(bytes) => {
+                // Both sides are decoded so that differences in the encoding
+                // (e.g., the order of the type table) do not matter.
+                match (IDLArgs::from_bytes(bytes), IDLArgs::from_bytes(reply)) {
+                    (Ok(expected), Ok(actual)) => expected == actual,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExpectedReply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectedReply::Bytes(bytes) => write!(f, "0x{}", hex::encode(bytes)),
+            ExpectedReply::Candid(bytes) => match IDLArgs::from_bytes(bytes) {
+                Ok(args) => write!(f, "{}", args),
+                Err(_) => write!(f, "0x{}", hex::encode(bytes)),
+            },
+        }
+    }
+}
+
+/// The reject expected from the previous message.
+#[derive(PartialEq, Debug)]
+pub(crate) enum ExpectedReject {
+    /// The reject code (e.g., `4` for `CanisterReject`) must be equal to the given one.
+    RejectCode(RejectCode),
+    /// The error code (e.g., `IC0503`) must be equal to the given one.
+    ErrorCode(String),
+}
+
+impl fmt::Display for ExpectedReject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectedReject::RejectCode(code) => write!(f, "{} ({:?})", *code as u64, code),
+            ExpectedReject::ErrorCode(code) => write!(f, "{}", code),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -124,9 +187,10 @@ impl<R: Read> Iterator for LineIterator<R> {
     }
 }
 
+/// Returns the messages in the given file together with their (1-based) line numbers.
 pub(crate) fn msg_stream_from_file(
     filename: &str,
-) -> Result<impl Iterator<Item = Result<Message, String>>, String> {
+) -> Result<impl Iterator<Item = Result<(usize, Message), String>>, String> {
     let f = File::open(filename).map_err(|e| e.to_string())?;
     let line_iterator = LineIterator::new(f);
 
@@ -138,15 +202,19 @@ pub(crate) fn msg_stream_from_file(
             _ => true,
         })
         .map(|(i, line)| match line {
-            Ok(line) => {
-                parse_message(&line, i as u64).map_err(|e| format!("Line {}: {}", i + 1, e))
-            }
+            Ok(line) => parse_message(&line, i as u64)
+                .map(|msg| (i + 1, msg))
+                .map_err(|e| format!("Line {}: {}", i + 1, e)),
             Err(e) => Err(format!("Error while reading line {}: {}", i, e)),
         }))
 }
 
 fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     let s = s.trim_end();
+    // Expectations are parsed separately since the expected payload might contain whitespace.
+    if let Some(("expect", expectation)) = s.split_once(char::is_whitespace) {
+        return parse_expect(expectation.trim_start());
+    }
     let tokens: Vec<&str> = s.splitn(4, char::is_whitespace).collect();
 
     match &tokens[..] {
@@ -188,6 +256,12 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
         ["upgrade", canister_id, wasm_file, payload] => {
             parse_install(nonce, canister_id, payload, wasm_file, "upgrade")
         }
+        ["set_time", nanos] => Ok(Message::SetTime(Time::from_nanos_since_unix_epoch(
+            parse_nanos(nanos)?,
+        ))),
+        ["advance_time", nanos] => Ok(Message::AdvanceTime(Duration::from_nanos(parse_nanos(
+            nanos,
+        )?))),
         _ => Err(format!(
             "Failed to parse line {}, don't have a pattern to match this with",
             s
@@ -195,6 +269,47 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
     }
 }
 
+fn parse_expect(s: &str) -> Result<Message, String> {
+    match s.split_once(char::is_whitespace) {
+        Some(("reply", payload)) => {
+            let payload = payload.trim_start();
+            let expected = if payload.starts_with('(') {
+                ExpectedReply::Candid(parse_candid(payload)?)
+            } else {
+                ExpectedReply::Bytes(parse_octet_string(payload)?)
+            };
+            Ok(Message::ExpectReply(expected))
+        }
+        Some(("reject", code)) => parse_reject_code(code.trim_start()).map(Message::ExpectReject),
+        _ => Err(format!(
+            "Failed to parse expectation {}, expected `expect reply <payload>` or `expect reject <code>`",
+            s
+        )),
+    }
+}
+
+fn parse_reject_code(code: &str) -> Result<ExpectedReject, String> {
+    if let Some(digits) = code.strip_prefix("IC") {
+        if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(ExpectedReject::ErrorCode(code.to_string()));
+        }
+    } else if let Ok(reject_code) = code.parse::<u64>() {
+        return RejectCode::try_from(reject_code)
+            .map(ExpectedReject::RejectCode)
+            .map_err(|_| format!("Unknown reject code {}.", code));
+    }
+    Err(format!(
+        "Illegal reject code {}, expected a reject code (e.g., 4) or an error code (e.g., IC0503).",
+        code
+    ))
+}
+
+fn parse_nanos(nanos: &str) -> Result<u64, String> {
+    nanos
+        .parse::<u64>()
+        .map_err(|e| format!("Failed to parse {} as nanoseconds: {}", nanos, e))
+}
+
 fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
     use std::str::FromStr;
     match PrincipalId::from_str(canister_id) {
@@ -312,11 +427,20 @@ fn validate_method_name(method_name: &str) -> Result<String, String> {
 fn parse_octet_string(input_str: &str) -> Result<Vec<u8>, String> {
     if input_str.starts_with('"') {
         parse_quoted(input_str)
+    } else if input_str.starts_with('(') {
+        parse_candid(input_str)
     } else {
         parse_hex(input_str)
     }
 }
 
+fn parse_candid(s: &str) -> Result<Vec<u8>, String> {
+    let args = candid_parser::parse_idl_args(s)
+        .map_err(|e| format!("Failed to parse Candid arguments {}: {}", s, e))?;
+    args.to_bytes()
+        .map_err(|e| format!("Failed to encode Candid arguments {}: {}", s, e))
+}
+
 fn parse_quoted(quoted_str: &str) -> Result<Vec<u8>, String> {
     if !quoted_str.is_ascii() {
         return Err(String::from("Only ASCII strings are allowed."));
@@ -501,6 +625,68 @@ mod tests {
         assert!(parse_message(s, 0).is_err());
     }
 
+    #[test]
+    fn test_parse_message_candid_payload_succeeds() {
+        let s = &format!("ingress {} write (42 : nat, \"a b\")", APP_CANISTER_URL);
+        let parsed_message = parse_message(s, 0).unwrap();
+        let method_payload = match &parsed_message {
+            Message::Ingress(signed_ingress) => signed_ingress.content().arg(),
+            _ => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                parsed_message
+            ),
+        };
+        assert_eq!(
+            method_payload,
+            candid::encode_args((candid::Nat::from(42_u64), "a b")).unwrap()
+        );
+
+        let s = &format!("query {} read (42 : nat", APP_CANISTER_URL);
+        assert!(parse_message(s, 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_expectations() {
+        let expected = match parse_message("expect reply (42 : nat, \"a b\")", 0).unwrap() {
+            Message::ExpectReply(expected) => expected,
+            msg => panic!("parse_message() returned an unexpected message: {:?}", msg),
+        };
+        assert!(expected.matches(&candid::encode_args((candid::Nat::from(42_u64), "a b")).unwrap()));
+        assert!(
+            !expected.matches(&candid::encode_args((candid::Nat::from(43_u64), "a b")).unwrap())
+        );
+        assert!(!expected.matches(&[1, 2, 3]));
+
+        assert_eq!(
+            parse_message("expect reply 0x010203", 0).unwrap(),
+            Message::ExpectReply(ExpectedReply::Bytes(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            parse_message("expect reject 4", 0).unwrap(),
+            Message::ExpectReject(ExpectedReject::RejectCode(RejectCode::CanisterReject))
+        );
+        assert_eq!(
+            parse_message("expect reject IC0503", 0).unwrap(),
+            Message::ExpectReject(ExpectedReject::ErrorCode("IC0503".to_string()))
+        );
+        assert!(parse_message("expect reject 42", 0).is_err());
+        assert!(parse_message("expect reject IC05", 0).is_err());
+        assert!(parse_message("expect nothing", 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_directives() {
+        assert_eq!(
+            parse_message("set_time 1620328630000000061", 0).unwrap(),
+            Message::SetTime(Time::from_nanos_since_unix_epoch(1620328630000000061))
+        );
+        assert_eq!(
+            parse_message("advance_time 1000000000", 0).unwrap(),
+            Message::AdvanceTime(Duration::from_secs(1))
+        );
+        assert!(parse_message("advance_time 1s", 0).is_err());
+    }
+
     #[test]
     fn test_line_iterator() {
         let text = Cursor::new(
@@ -469,6 +469,7 @@ pub fn wasm_execution_error(
             instance_stats: InstanceStats::default(),
             system_api_call_counters: SystemApiCallCounters::default(),
             canister_log: Default::default(),
+            wasm_profile: Default::default(),
         },
         None,
     )
@@ -604,6 +605,7 @@ pub fn process(
         subnet_available_memory,
         embedder.config().feature_flags.wasm_native_stable_memory,
         embedder.config().feature_flags.canister_backtrace,
+        embedder.config().feature_flags.wasm_tracing,
         embedder.config().max_sum_exported_function_name_lengths,
         stable_memory.clone(),
         wasm_memory.size,
@@ -637,6 +639,7 @@ pub fn process(
                     instance_stats: InstanceStats::default(),
                     system_api_call_counters: SystemApiCallCounters::default(),
                     canister_log: Default::default(),
+                    wasm_profile: Default::default(),
                 },
                 None,
                 Err(system_api.unwrap()), // should be safe because we've passed Some(api) to new_instance
@@ -659,6 +662,8 @@ pub fn process(
     let system_api = instance.store_data_mut().system_api_mut().unwrap();
     let system_api_call_counters = system_api.call_counters();
     let mut canister_log = system_api.take_canister_log();
+    let wasm_profile = system_api.take_wasm_profile(instruction_counter);
+    let system_api_trace = system_api.take_system_api_trace();
     let slice_instruction_limit = system_api.slice_instruction_limit();
     // Capping at the limit to preserve the existing behaviour. It should be
     // possible to remove capping after ensuring that all callers can handle
@@ -696,6 +701,7 @@ pub fn process(
                         instance_stats,
                         system_api_call_counters,
                         canister_log,
+                        wasm_profile,
                     },
                     None,
                     Ok(instance),
@@ -782,13 +788,24 @@ pub fn process(
             }
         }
         Err(err) => {
+            // The system API calls recorded by Wasm tracing (if any) are
+            // written right after the trap message.
+            let trace = if system_api_trace.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\nLast {} system API calls:\n{}",
+                    system_api_trace.len(),
+                    system_api_trace.join("\n")
+                )
+            };
             if let Some(log_message) = match err {
                 HypervisorError::Trapped {
                     trap_code,
                     backtrace,
                 } => match backtrace {
-                    Some(bt) => Some(format!("[TRAP]: {}\n{}", trap_code, bt)),
-                    None => Some(format!("[TRAP]: {}", trap_code)),
+                    Some(bt) => Some(format!("[TRAP]: {}{}\n{}", trap_code, trace, bt)),
+                    None => Some(format!("[TRAP]: {}{}", trap_code, trace)),
                 },
                 HypervisorError::CalledTrap { message, backtrace } => {
                     let message = if message.is_empty() {
@@ -797,13 +814,13 @@ pub fn process(
                         &message
                     };
                     match backtrace {
-                        Some(bt) => Some(format!("[TRAP]: {}\n{}", message, bt)),
-                        None => Some(format!("[TRAP]: {}", message)),
+                        Some(bt) => Some(format!("[TRAP]: {}{}\n{}", message, trace, bt)),
+                        None => Some(format!("[TRAP]: {}{}", message, trace)),
                     }
                 }
                 _ => None,
             } {
-                canister_log.add_record(timestamp_nanos, log_message.into_bytes());
+                canister_log.add_trap_record(timestamp_nanos, log_message.into_bytes());
             }
             None
         }
@@ -831,6 +848,7 @@ pub fn process(
             instance_stats,
             system_api_call_counters,
             canister_log,
+            wasm_profile,
         },
         wasm_state_changes,
         Ok(instance),
@@ -20,6 +20,7 @@ use crate::{serialized_module::SerializedModule, CompilationResult, WasmtimeEmbe
 use wasmtime::InstancePre;
 
 pub mod decoding;
+pub mod dwarf;
 pub mod instrumentation;
 mod system_api_replacements;
 pub mod validation;
@@ -209,8 +210,10 @@ fn validate_and_instrument(
         config.metering_type,
         config.subnet_type,
         config.dirty_page_overhead,
-        config.max_wasm_memory_size,
+        config.max_wasm64_memory_size,
         config.max_stable_memory_size,
+        config.feature_flags.wasm_coverage,
+        config.feature_flags.wasm_profiling,
     )?;
     Ok((wasm_validation_details, instrumentation_output))
 }
@@ -0,0 +1,693 @@
+//! A minimal reader of the DWARF line tables (`.debug_line` custom section)
+//! of Wasm modules used to map functions to their locations in the source
+//! code.
+//!
+//! Addresses in the DWARF sections of Wasm modules are offsets relative to
+//! the start of the contents of the code section.
+
+use std::ops::Range;
+use wasmparser::{Parser, Payload};
+
+/// A location in the source code of a Wasm module.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u64,
+}
+
+/// Returns the source location of the first instruction of every function
+/// defined by the given module (in the order of the code section) according
+/// to the DWARF line tables of the module.
+///
+/// The location of a function is `None` if the module has no line table or
+/// if the line table contains no row for the function.
+pub fn function_source_locations(wasm: &[u8]) -> Result<Vec<Option<SourceLocation>>, String> {
+    let mut code_section_start = None;
+    let mut function_bodies = vec![];
+    let mut debug_line = None;
+    let mut debug_line_str: &[u8] = &[];
+    let mut debug_str: &[u8] = &[];
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload.map_err(|err| err.to_string())? {
+            Payload::CodeSectionStart { range, .. } => code_section_start = Some(range.start),
+            Payload::CodeSectionEntry(body) => function_bodies.push(body.range()),
+            Payload::CustomSection(reader) => match reader.name() {
+                ".debug_line" => debug_line = Some(reader.data()),
+                ".debug_line_str" => debug_line_str = reader.data(),
+                ".debug_str" => debug_str = reader.data(),
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+    let (Some(debug_line), Some(code_section_start)) = (debug_line, code_section_start) else {
+        return Ok(vec![None; function_bodies.len()]);
+    };
+
+    let strings = StringSections {
+        debug_line_str,
+        debug_str,
+    };
+    let mut rows = read_line_programs(debug_line, &strings)?;
+    rows.sort_by_key(|row| row.address);
+
+    let relative = |range: &Range<usize>| {
+        (
+            (range.start - code_section_start) as u64,
+            (range.end - code_section_start) as u64,
+        )
+    };
+    Ok(function_bodies
+        .iter()
+        .map(|body| {
+            let (start, end) = relative(body);
+            let first = rows.partition_point(|row| row.address < start);
+            rows[first..]
+                .iter()
+                .take_while(|row| row.address < end)
+                .find(|row| row.line > 0)
+                .and_then(|row| {
+                    row.file.clone().map(|file| SourceLocation {
+                        file,
+                        line: row.line,
+                    })
+                })
+        })
+        .collect())
+}
+
+// A row of a line table.
+struct Row {
+    address: u64,
+    file: Option<String>,
+    line: u64,
+}
+
+struct StringSections<'a> {
+    debug_line_str: &'a [u8],
+    debug_str: &'a [u8],
+}
+
+const DW_LNS_COPY: u8 = 1;
+const DW_LNS_ADVANCE_PC: u8 = 2;
+const DW_LNS_ADVANCE_LINE: u8 = 3;
+const DW_LNS_SET_FILE: u8 = 4;
+const DW_LNS_CONST_ADD_PC: u8 = 8;
+const DW_LNS_FIXED_ADVANCE_PC: u8 = 9;
+
+const DW_LNE_END_SEQUENCE: u8 = 1;
+const DW_LNE_SET_ADDRESS: u8 = 2;
+const DW_LNE_DEFINE_FILE: u8 = 3;
+
+const DW_LNCT_PATH: u64 = 1;
+const DW_LNCT_DIRECTORY_INDEX: u64 = 2;
+
+const DW_FORM_BLOCK: u64 = 0x09;
+const DW_FORM_BLOCK1: u64 = 0x0a;
+const DW_FORM_DATA1: u64 = 0x0b;
+const DW_FORM_DATA2: u64 = 0x05;
+const DW_FORM_DATA4: u64 = 0x06;
+const DW_FORM_DATA8: u64 = 0x07;
+const DW_FORM_DATA16: u64 = 0x1e;
+const DW_FORM_STRING: u64 = 0x08;
+const DW_FORM_STRP: u64 = 0x0e;
+const DW_FORM_UDATA: u64 = 0x0f;
+const DW_FORM_LINE_STRP: u64 = 0x1f;
+
+// Reads the rows of all line programs (one per compilation unit) in the
+// given `.debug_line` section.
+fn read_line_programs(debug_line: &[u8], strings: &StringSections) -> Result<Vec<Row>, String> {
+    let mut rows = vec![];
+    let mut reader = Reader::new(debug_line);
+    while reader.pos < debug_line.len() {
+        let (unit_length, is_64) = match reader.u32()? {
+            0xffff_ffff => (reader.u64()?, true),
+            length => (length as u64, false),
+        };
+        let unit_end = reader
+            .pos
+            .checked_add(unit_length as usize)
+            .filter(|end| *end <= debug_line.len())
+            .ok_or("Line program exceeds the .debug_line section")?;
+        let version = reader.u16()?;
+        if !(2..=5).contains(&version) {
+            reader.pos = unit_end;
+            continue;
+        }
+        if version >= 5 {
+            // The address size and the segment selector size.
+            reader.bytes(2)?;
+        }
+        let header_length = reader.offset(is_64)?;
+        let program_start = usize::try_from(header_length)
+            .ok()
+            .and_then(|header_length| reader.pos.checked_add(header_length))
+            .filter(|start| *start <= unit_end)
+            .ok_or("Line program header exceeds the line program")?;
+        let min_instruction_length = reader.u8()? as u64;
+        if version >= 4 {
+            // The maximum number of operations per instruction.
+            reader.u8()?;
+        }
+        // The default value of the `is_stmt` register.
+        reader.u8()?;
+        let line_base = reader.u8()? as i8 as i64;
+        let line_range = reader.u8()? as u64;
+        if line_range == 0 {
+            return Err("Line program has a line range of 0".to_string());
+        }
+        let opcode_base = reader.u8()?;
+        let standard_opcode_lengths = reader.bytes(opcode_base.saturating_sub(1) as usize)?;
+
+        let (mut files, file_index_base) = if version >= 5 {
+            let directories = reader.entries(is_64, strings)?;
+            let files = reader.entries(is_64, strings)?;
+            let files = files
+                .into_iter()
+                .map(|(path, directory)| {
+                    let directory = directories
+                        .get(directory as usize)
+                        .and_then(|(path, _)| path.as_deref());
+                    path.map(|path| join(directory, &path))
+                })
+                .collect::<Vec<_>>();
+            (files, 0)
+        } else {
+            let mut directories = vec![];
+            loop {
+                let directory = reader.cstr()?;
+                if directory.is_empty() {
+                    break;
+                }
+                directories.push(directory);
+            }
+            let mut files = vec![];
+            loop {
+                let path = reader.cstr()?;
+                if path.is_empty() {
+                    break;
+                }
+                let directory = reader.uleb()?;
+                // The modification time and the length of the file.
+                reader.uleb()?;
+                reader.uleb()?;
+                // The directory 0 is the compilation directory, which is not
+                // part of the list of directories.
+                let directory = (directory as usize)
+                    .checked_sub(1)
+                    .and_then(|index| directories.get(index))
+                    .map(|directory| directory.as_str());
+                files.push(Some(join(directory, &path)));
+            }
+            (files, 1)
+        };
+
+        reader.pos = program_start;
+        let mut address = 0;
+        let mut file = 1;
+        let mut line: u64 = 1;
+        while reader.pos < unit_end {
+            let opcode = reader.u8()?;
+            let mut emit_row = false;
+            if opcode >= opcode_base {
+                let adjusted_opcode = (opcode - opcode_base) as u64;
+                address = advance_address(
+                    address,
+                    adjusted_opcode / line_range,
+                    min_instruction_length,
+                )?;
+                line = advance_line(line, line_base + (adjusted_opcode % line_range) as i64)?;
+                emit_row = true;
+            } else {
+                match opcode {
+                    0 => {
+                        // The length includes the extended opcode itself.
+                        let end = usize::try_from(reader.uleb()?)
+                            .ok()
+                            .filter(|length| *length > 0)
+                            .and_then(|length| reader.pos.checked_add(length))
+                            .filter(|end| *end <= unit_end)
+                            .ok_or("Extended opcode exceeds the line program")?;
+                        let length = end - reader.pos;
+                        match reader.u8()? {
+                            DW_LNE_END_SEQUENCE => {
+                                address = 0;
+                                file = 1;
+                                line = 1;
+                            }
+                            DW_LNE_SET_ADDRESS => {
+                                address = reader.uint(length - 1)?;
+                            }
+                            DW_LNE_DEFINE_FILE => {
+                                files.push(Some(reader.cstr()?));
+                            }
+                            _ => (),
+                        }
+                        reader.pos = end;
+                    }
+                    DW_LNS_COPY => emit_row = true,
+                    DW_LNS_ADVANCE_PC => {
+                        address = advance_address(address, reader.uleb()?, min_instruction_length)?
+                    }
+                    DW_LNS_ADVANCE_LINE => line = advance_line(line, reader.sleb()?)?,
+                    DW_LNS_SET_FILE => file = reader.uleb()?,
+                    DW_LNS_CONST_ADD_PC => {
+                        address = advance_address(
+                            address,
+                            (255 - opcode_base) as u64 / line_range,
+                            min_instruction_length,
+                        )?
+                    }
+                    DW_LNS_FIXED_ADVANCE_PC => {
+                        address = advance_address(address, reader.u16()? as u64, 1)?
+                    }
+                    _ => {
+                        // Skip the arguments of other standard opcodes.
+                        for _ in 0..standard_opcode_lengths[opcode as usize - 1] {
+                            reader.uleb()?;
+                        }
+                    }
+                }
+            }
+            if emit_row {
+                rows.push(Row {
+                    address,
+                    file: (file as usize)
+                        .checked_sub(file_index_base)
+                        .and_then(|index| files.get(index))
+                        .cloned()
+                        .flatten(),
+                    line,
+                });
+            }
+        }
+        reader.pos = unit_end;
+    }
+    Ok(rows)
+}
+
+// Advances the address register by `operation_advance` instructions.
+fn advance_address(
+    address: u64,
+    operation_advance: u64,
+    min_instruction_length: u64,
+) -> Result<u64, String> {
+    operation_advance
+        .checked_mul(min_instruction_length)
+        .and_then(|advance| address.checked_add(advance))
+        .ok_or_else(|| "Address overflow in line program".to_string())
+}
+
+// Advances the line register by `advance` lines.
+fn advance_line(line: u64, advance: i64) -> Result<u64, String> {
+    line.checked_add_signed(advance)
+        .ok_or_else(|| "Line number out of range in line program".to_string())
+}
+
+fn join(directory: Option<&str>, path: &str) -> String {
+    match directory {
+        Some(directory) if !directory.is_empty() && !path.starts_with('/') => {
+            format!("{}/{}", directory.trim_end_matches('/'), path)
+        }
+        _ => path.to_string(),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or("Unexpected end of DWARF section")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    // Reads an unsigned little-endian integer of `len` bytes.
+    fn uint(&mut self, len: usize) -> Result<u64, String> {
+        if len > 8 {
+            return Err(format!("Unsupported integer size {}", len));
+        }
+        Ok(self
+            .bytes(len)?
+            .iter()
+            .rev()
+            .fold(0, |value, byte| (value << 8) | *byte as u64))
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(self.uint(2)? as u16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(self.uint(4)? as u32)
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        self.uint(8)
+    }
+
+    fn offset(&mut self, is_64: bool) -> Result<u64, String> {
+        if is_64 {
+            self.u64()
+        } else {
+            Ok(self.u32()? as u64)
+        }
+    }
+
+    fn uleb(&mut self) -> Result<u64, String> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as u64) << shift;
+            }
+            shift = shift.saturating_add(7);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn sleb(&mut self) -> Result<i64, String> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as i64) << shift;
+            }
+            shift = shift.saturating_add(7);
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    }
+
+    // Reads a null-terminated string.
+    fn cstr(&mut self) -> Result<String, String> {
+        let len = self.data[self.pos.min(self.data.len())..]
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or("Unterminated string in DWARF section")?;
+        let string = String::from_utf8_lossy(self.bytes(len)?).to_string();
+        self.pos += 1;
+        Ok(string)
+    }
+
+    // Reads the directory or file name entries of a DWARF 5 line program
+    // header as pairs of a path and a directory index.
+    fn entries(
+        &mut self,
+        is_64: bool,
+        strings: &StringSections,
+    ) -> Result<Vec<(Option<String>, u64)>, String> {
+        let num_formats = self.u8()?;
+        let mut formats = vec![];
+        for _ in 0..num_formats {
+            formats.push((self.uleb()?, self.uleb()?));
+        }
+        let num_entries = self.uleb()?;
+        // Every entry takes at least one byte per format.
+        if (formats.is_empty() && num_entries > 0)
+            || num_entries > (self.data.len() - self.pos.min(self.data.len())) as u64
+        {
+            return Err("Too many entries in line program header".to_string());
+        }
+        let mut entries = vec![];
+        for _ in 0..num_entries {
+            let mut path = None;
+            let mut directory = 0;
+            for (content_type, form) in formats.iter() {
+                let string = |offset: u64, section: &[u8]| {
+                    let mut reader = Reader::new(section);
+                    reader.pos = offset as usize;
+                    reader.cstr()
+                };
+                let (string, number) = match *form {
+                    DW_FORM_STRING => (Some(self.cstr()?), 0),
+                    DW_FORM_LINE_STRP => {
+                        let offset = self.offset(is_64)?;
+                        (Some(string(offset, strings.debug_line_str)?), 0)
+                    }
+                    DW_FORM_STRP => {
+                        let offset = self.offset(is_64)?;
+                        (Some(string(offset, strings.debug_str)?), 0)
+                    }
+                    DW_FORM_UDATA => (None, self.uleb()?),
+                    DW_FORM_DATA1 => (None, self.uint(1)?),
+                    DW_FORM_DATA2 => (None, self.uint(2)?),
+                    DW_FORM_DATA4 => (None, self.uint(4)?),
+                    DW_FORM_DATA8 => (None, self.uint(8)?),
+                    DW_FORM_DATA16 => {
+                        self.bytes(16)?;
+                        (None, 0)
+                    }
+                    DW_FORM_BLOCK => {
+                        let len = self.uleb()? as usize;
+                        self.bytes(len)?;
+                        (None, 0)
+                    }
+                    DW_FORM_BLOCK1 => {
+                        let len = self.u8()? as usize;
+                        self.bytes(len)?;
+                        (None, 0)
+                    }
+                    form => return Err(format!("Unsupported DWARF form {:#x}", form)),
+                };
+                match *content_type {
+                    DW_LNCT_PATH => path = string,
+                    DW_LNCT_DIRECTORY_INDEX => directory = number,
+                    _ => (),
+                }
+            }
+            entries.push((path, directory));
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Appends a custom section to the given module.
+    fn with_custom_section(mut wasm: Vec<u8>, name: &str, data: &[u8]) -> Vec<u8> {
+        let mut contents = vec![name.len() as u8];
+        contents.extend_from_slice(name.as_bytes());
+        contents.extend_from_slice(data);
+        wasm.push(0);
+        wasm.push(contents.len() as u8);
+        wasm.extend(contents);
+        wasm
+    }
+
+    // The header of a DWARF 4 line program with the file `src/lib.rs`.
+    fn header(min_instruction_length: u8, line_range: u8) -> Vec<u8> {
+        let mut header = vec![
+            min_instruction_length,
+            1,   // maximum operations per instruction
+            1,   // default is_stmt
+            251, // line base (-5)
+            line_range,
+            13, // opcode base
+        ];
+        header.extend_from_slice(&[0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1]);
+        header.extend_from_slice(b"src\0\0");
+        header.extend_from_slice(b"lib.rs\0\x01\0\0\0");
+        header
+    }
+
+    // A `.debug_line` section with a single DWARF 4 line program whose
+    // header length field is `header_length`.
+    fn section(header: Vec<u8>, header_length: u32, program: &[u8]) -> Vec<u8> {
+        let mut unit = 4_u16.to_le_bytes().to_vec();
+        unit.extend_from_slice(&header_length.to_le_bytes());
+        unit.extend(header);
+        unit.extend_from_slice(program);
+
+        let mut section = (unit.len() as u32).to_le_bytes().to_vec();
+        section.extend(unit);
+        section
+    }
+
+    // A DWARF 4 line program with a single sequence starting at `address`
+    // on line 42 of `src/lib.rs`.
+    fn debug_line(address: u32) -> Vec<u8> {
+        let header = header(1, 14);
+        let mut program = vec![0, 5, DW_LNE_SET_ADDRESS];
+        program.extend_from_slice(&address.to_le_bytes());
+        program.extend_from_slice(&[DW_LNS_ADVANCE_LINE, 41, DW_LNS_COPY]);
+        program.extend_from_slice(&[DW_LNS_ADVANCE_PC, 2, DW_LNS_COPY]);
+        program.extend_from_slice(&[0, 1, DW_LNE_END_SEQUENCE]);
+        let header_length = header.len() as u32;
+        section(header, header_length, &program)
+    }
+
+    fn read(debug_line: &[u8]) -> Result<Vec<Row>, String> {
+        read_line_programs(
+            debug_line,
+            &StringSections {
+                debug_line_str: &[],
+                debug_str: &[],
+            },
+        )
+    }
+
+    fn module() -> Vec<u8> {
+        wat::parse_str(
+            r#"(module
+                (func $a (drop (i32.const 1)))
+                (func $b (drop (i32.const 2)))
+            )"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn locations_without_dwarf() {
+        assert_eq!(
+            function_source_locations(&module()).unwrap(),
+            vec![None, None]
+        );
+    }
+
+    #[test]
+    fn locations_from_line_table() {
+        let wasm = module();
+        let mut code_section_start = 0;
+        let mut second_body_start = 0;
+        for payload in Parser::new(0).parse_all(&wasm) {
+            match payload.unwrap() {
+                Payload::CodeSectionStart { range, .. } => code_section_start = range.start,
+                Payload::CodeSectionEntry(body) => second_body_start = body.range().start,
+                _ => (),
+            }
+        }
+        // The line table only covers the second function.
+        let address = (second_body_start - code_section_start + 1) as u32;
+        let wasm = with_custom_section(wasm, ".debug_line", &debug_line(address));
+        assert_eq!(
+            function_source_locations(&wasm).unwrap(),
+            vec![
+                None,
+                Some(SourceLocation {
+                    file: "src/lib.rs".to_string(),
+                    line: 42,
+                })
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_line_programs_are_rejected() {
+        let valid_header_length = header(1, 14).len() as u32;
+
+        // A line range of 0.
+        let err = read(&section(header(1, 0), valid_header_length, &[DW_LNS_COPY]));
+        assert!(err.unwrap_err().contains("line range of 0"));
+
+        // A header length beyond the end of the line program.
+        for header_length in [valid_header_length + 100, u32::MAX] {
+            let err = read(&section(header(1, 14), header_length, &[DW_LNS_COPY]));
+            assert!(err.unwrap_err().contains("header exceeds"));
+        }
+
+        // An extended opcode that is longer than the line program or empty.
+        let mut program = vec![0];
+        program.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+        program.push(DW_LNE_END_SEQUENCE);
+        let err = read(&section(header(1, 14), valid_header_length, &program));
+        assert!(err.unwrap_err().contains("Extended opcode exceeds"));
+        let err = read(&section(
+            header(1, 14),
+            valid_header_length,
+            &[0, 0, DW_LNE_END_SEQUENCE],
+        ));
+        assert!(err.unwrap_err().contains("Extended opcode exceeds"));
+
+        // An address advance that overflows.
+        let mut program = vec![0, 9, DW_LNE_SET_ADDRESS];
+        program.extend_from_slice(&u64::MAX.to_le_bytes());
+        program.extend_from_slice(&[DW_LNS_ADVANCE_PC, 1]);
+        let err = read(&section(header(1, 14), valid_header_length, &program));
+        assert!(err.unwrap_err().contains("Address overflow"));
+        let program = [
+            DW_LNS_ADVANCE_PC,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0x01,
+        ];
+        let err = read(&section(header(255, 14), valid_header_length, &program));
+        assert!(err.unwrap_err().contains("Address overflow"));
+
+        // Line advances below line 0 and beyond `u64::MAX`.
+        let err = read(&section(
+            header(1, 14),
+            valid_header_length,
+            &[DW_LNS_ADVANCE_LINE, 0x7e],
+        ));
+        assert!(err.unwrap_err().contains("Line number out of range"));
+        // `i64::MAX`
+        let max_advance = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+        let mut program = vec![];
+        for _ in 0..3 {
+            program.push(DW_LNS_ADVANCE_LINE);
+            program.extend_from_slice(&max_advance);
+        }
+        let err = read(&section(header(1, 14), valid_header_length, &program));
+        assert!(err.unwrap_err().contains("Line number out of range"));
+
+        // Malformed line programs make the whole module fail instead of
+        // panicking.
+        let wasm = with_custom_section(
+            module(),
+            ".debug_line",
+            &section(header(1, 0), valid_header_length, &[DW_LNS_COPY]),
+        );
+        assert!(function_source_locations(&wasm).is_err());
+    }
+
+    #[test]
+    fn dwarf_5_header_with_too_many_entries_is_rejected() {
+        let mut unit = 5_u16.to_le_bytes().to_vec();
+        // The address size and the segment selector size.
+        unit.extend_from_slice(&[4, 0]);
+        let mut header = vec![1, 1, 1, 251, 14, 13];
+        header.extend_from_slice(&[0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1]);
+        // No directory entry formats, but 2^63 directories.
+        header.push(0);
+        header.extend_from_slice(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]);
+        unit.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        unit.extend(header);
+        let mut section = (unit.len() as u32).to_le_bytes().to_vec();
+        section.extend(unit);
+        let err = read(&section);
+        assert!(err.unwrap_err().contains("Too many entries"));
+    }
+}
@@ -98,6 +98,42 @@
 //! bound by the length of the longest execution path consisting of
 //! non-reentrant basic blocks.
 //!
+//! # Wasm coverage
+//!
+//! If Wasm coverage is enabled, then a mutable global counter is inserted
+//! (and exported) for every function defined by the original module and
+//! incremented at the beginning of that function:
+//! ```wasm
+//! (global (;N;) (mut i64) (i64.const 0))
+//! (export "canister counter_coverage_0" (global N)))
+//! ```
+//! The counters are persisted together with the other exported globals
+//! and can be mapped back to the functions of the original module
+//! (and to their source locations if the original module contains DWARF
+//! line tables) using [`wasm_coverage`].
+//!
+//! # Wasm profiling
+//!
+//! If Wasm profiling is enabled, then two functions are imported (after all
+//! other imports) and called on entry to and on exit from every function
+//! defined by the original module:
+//! ```wasm
+//! (import "__" "profile_enter" (func $profile_enter (param i32)))
+//! (import "__" "profile_exit" (func $profile_exit))
+//! ...
+//! (func (;N;)
+//!   i32.const <index of the function in the original module>
+//!   call $profile_enter
+//!   block
+//!     <original function body>
+//!   end
+//!   call $profile_exit)
+//! ```
+//! The function `profile_exit` is also called before every `return`
+//! (and tail call) in the original function body. The embedder uses
+//! the instruction counter at each of these calls to attribute
+//! the executed instructions to call stacks of the original module.
+//!
 //! # Wasm-native stable memory
 //!
 //! Two additional memories are inserted for stable memory. One is the actual
@@ -110,6 +146,7 @@
 //! ```
 //!
 
+use super::dwarf::{function_source_locations, SourceLocation};
 use super::system_api_replacements::replacement_functions;
 use super::validation::API_VERSION_IC0;
 use super::{InstrumentationOutput, Segments, SystemApiFunc};
@@ -119,8 +156,8 @@ use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::PAGE_SIZE;
 use ic_types::methods::WasmMethod;
-use ic_types::NumBytes;
 use ic_types::NumInstructions;
+use ic_types::{NumBytes, MAX_WASM_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, WasmError, WasmInstrumentationError};
 
 use crate::wasmtime_embedder::{
@@ -721,6 +758,9 @@ const TABLE_STR: &str = "table";
 pub(crate) const INSTRUCTIONS_COUNTER_GLOBAL_NAME: &str = "canister counter_instructions";
 pub(crate) const DIRTY_PAGES_COUNTER_GLOBAL_NAME: &str = "canister counter_dirty_pages";
 pub(crate) const ACCESSED_PAGES_COUNTER_GLOBAL_NAME: &str = "canister counter_accessed_pages";
+const COVERAGE_COUNTER_GLOBAL_NAME_PREFIX: &str = "canister counter_coverage_";
+const PROFILE_ENTER_FUN_NAME: &str = "profile_enter";
+const PROFILE_EXIT_FUN_NAME: &str = "profile_exit";
 const CANISTER_START_STR: &str = "canister_start";
 
 /// There is one byte for each OS page in the memory.
@@ -948,10 +988,18 @@ pub(super) fn instrument(
     metering_type: MeteringType,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
-    max_wasm_memory_size: NumBytes,
+    max_wasm64_memory_size: NumBytes,
     max_stable_memory_size: NumBytes,
+    wasm_coverage: FlagStatus,
+    wasm_profiling: FlagStatus,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
     let main_memory_type = main_memory_type(&module);
+    let num_local_functions = module.code_sections.len();
+    let num_original_imported_functions = module
+        .imports
+        .iter()
+        .filter(|imp| matches!(imp.ty, TypeRef::Func(_)))
+        .count() as u32;
     let stable_memory_index;
     let mut module = inject_helper_functions(module, wasm_native_stable_memory, main_memory_type);
     module = export_table(module);
@@ -959,7 +1007,7 @@ pub(super) fn instrument(
         module,
         write_barrier,
         wasm_native_stable_memory,
-        max_wasm_memory_size,
+        max_wasm64_memory_size,
         max_stable_memory_size,
     );
 
@@ -1064,10 +1112,24 @@ pub(super) fn instrument(
             subnet_type,
             dirty_page_overhead,
             main_memory_type,
-            max_wasm_memory_size,
+            max_wasm64_memory_size,
         )
     }
 
+    let mut coverage_strs: Vec<String> = Vec::new();
+    if wasm_coverage == FlagStatus::Enabled {
+        module = inject_coverage_counters(
+            module,
+            num_local_functions,
+            num_imported_globals as usize,
+            &mut coverage_strs,
+        );
+    }
+
+    if wasm_profiling == FlagStatus::Enabled {
+        module = inject_profiling(module, num_local_functions, num_original_imported_functions);
+    }
+
     let exported_functions = module
         .exports
         .iter()
@@ -1149,7 +1211,7 @@ fn replace_system_api_functions(
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
     main_memory_type: WasmMemoryType,
-    max_wasm_memory_size: NumBytes,
+    max_wasm64_memory_size: NumBytes,
 ) {
     let api_indexes = calculate_api_indexes(module);
     let number_of_func_imports = module
@@ -1166,7 +1228,7 @@ fn replace_system_api_functions(
         subnet_type,
         dirty_page_overhead,
         main_memory_type,
-        max_wasm_memory_size,
+        max_wasm64_memory_size,
     ) {
         if let Some(old_index) = api_indexes.get(&api) {
             let type_idx = add_func_type(module, ty);
@@ -1990,24 +2052,24 @@ fn update_memories(
     mut module: Module,
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
-    max_wasm_memory_size: NumBytes,
+    max_wasm64_memory_size: NumBytes,
     max_stable_memory_size: NumBytes,
 ) -> (Module, u32) {
     let mut stable_index = 0;
 
     if let Some(mem) = module.memories.first_mut() {
         if mem.memory64 {
-            let max_wasm_memory_size_in_wasm_pages =
-                max_memory_size_in_wasm_pages(max_wasm_memory_size);
+            let max_wasm64_memory_size_in_wasm_pages =
+                max_memory_size_in_wasm_pages(max_wasm64_memory_size);
             match mem.maximum {
                 Some(max) => {
                     // In case the maximum memory size is larger than the maximum allowed, cap it.
-                    if max > max_wasm_memory_size_in_wasm_pages {
-                        mem.maximum = Some(max_wasm_memory_size_in_wasm_pages);
+                    if max > max_wasm64_memory_size_in_wasm_pages {
+                        mem.maximum = Some(max_wasm64_memory_size_in_wasm_pages);
                     }
                 }
                 None => {
-                    mem.maximum = Some(max_wasm_memory_size_in_wasm_pages);
+                    mem.maximum = Some(max_wasm64_memory_size_in_wasm_pages);
                 }
             }
         }
@@ -2030,7 +2092,13 @@ fn update_memories(
         module.exports.push(memory_export);
     }
 
-    let wasm_bytemap_size_in_wasm_pages = bytemap_size_in_wasm_pages(max_wasm_memory_size);
+    // The bytemap must cover the largest possible heap, which is bounded by
+    // the address space for Wasm32 and by the configured maximum for Wasm64.
+    let max_heap_size = match module.memories.first() {
+        Some(mem) if mem.memory64 => max_wasm64_memory_size,
+        _ => NumBytes::new(MAX_WASM_MEMORY_IN_BYTES),
+    };
+    let wasm_bytemap_size_in_wasm_pages = bytemap_size_in_wasm_pages(max_heap_size);
     if write_barrier == FlagStatus::Enabled && !module.memories.is_empty() {
         module.memories.push(MemoryType {
             memory64: false,
@@ -2083,6 +2151,227 @@ fn update_memories(
     (module, stable_index)
 }
 
+// Injects an exported counter incremented at the beginning of each of the first
+// `num_local_functions` functions, i.e., the functions defined by the original module.
+fn inject_coverage_counters<'a>(
+    mut module: Module<'a>,
+    num_local_functions: usize,
+    num_imported_globals: usize,
+    extra_data: &'a mut Vec<String>,
+) -> Module<'a> {
+    let first_counter_ix = (module.globals.len() + num_imported_globals) as u32;
+    for func_ix in 0..num_local_functions {
+        extra_data.push(format!(
+            "{}{}",
+            COVERAGE_COUNTER_GLOBAL_NAME_PREFIX, func_ix
+        ));
+    }
+    for (func_ix, func_body) in module
+        .code_sections
+        .iter_mut()
+        .take(num_local_functions)
+        .enumerate()
+    {
+        let global_index = first_counter_ix + func_ix as u32;
+        module.globals.push(Global {
+            ty: GlobalType {
+                content_type: ValType::I64,
+                mutable: true,
+                shared: false,
+            },
+            init_expr: Operator::I64Const { value: 0 },
+        });
+        module.exports.push(Export {
+            name: extra_data[func_ix].as_str(),
+            kind: ExternalKind::Global,
+            index: global_index,
+        });
+        func_body.instructions.splice(
+            0..0,
+            [
+                Operator::GlobalGet { global_index },
+                Operator::I64Const { value: 1 },
+                Operator::I64Add,
+                Operator::GlobalSet { global_index },
+            ],
+        );
+    }
+    module
+}
+
+// Injects calls to the `profile_enter` and `profile_exit` imports on entry to and
+// on exit from each of the first `num_local_functions` functions, i.e., the functions
+// defined by the original module (see the module documentation for details).
+fn inject_profiling(
+    mut module: Module<'_>,
+    num_local_functions: usize,
+    num_original_imported_functions: u32,
+) -> Module<'_> {
+    let enter_type_idx = add_func_type(&mut module, FuncType::new([ValType::I32], []));
+    let exit_type_idx = add_func_type(&mut module, FuncType::new([], []));
+    let num_imported_functions = module
+        .imports
+        .iter()
+        .filter(|imp| matches!(imp.ty, TypeRef::Func(_)))
+        .count() as u32;
+    module.imports.push(Import {
+        module: INSTRUMENTED_FUN_MODULE,
+        name: PROFILE_ENTER_FUN_NAME,
+        ty: TypeRef::Func(enter_type_idx),
+    });
+    module.imports.push(Import {
+        module: INSTRUMENTED_FUN_MODULE,
+        name: PROFILE_EXIT_FUN_NAME,
+        ty: TypeRef::Func(exit_type_idx),
+    });
+    let enter_fn = num_imported_functions;
+    let exit_fn = num_imported_functions + 1;
+    mutate_function_indices(&mut module, |i| {
+        if i >= num_imported_functions {
+            i + 2
+        } else {
+            i
+        }
+    });
+
+    for func_ix in 0..num_local_functions {
+        let type_idx = module.functions[func_ix];
+        let results = match &module.types[type_idx as usize].composite_type.inner {
+            CompositeInnerType::Func(func_type) => func_type.results().to_vec(),
+            _ => vec![],
+        };
+        let blockty = match results.as_slice() {
+            [] => BlockType::Empty,
+            [result] => BlockType::Type(*result),
+            _ => BlockType::FuncType(add_func_type(&mut module, FuncType::new([], results))),
+        };
+        let func_body = &mut module.code_sections[func_ix];
+        let original_instructions = std::mem::take(&mut func_body.instructions);
+        let mut instructions = Vec::with_capacity(original_instructions.len() + 8);
+        instructions.push(Operator::I32Const {
+            value: (num_original_imported_functions + func_ix as u32) as i32,
+        });
+        instructions.push(Operator::Call {
+            function_index: enter_fn,
+        });
+        instructions.push(Operator::Block { blockty });
+        for op in original_instructions {
+            if let Operator::Return
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. } = op
+            {
+                instructions.push(Operator::Call {
+                    function_index: exit_fn,
+                });
+            }
+            instructions.push(op);
+        }
+        // The final `End` of the original function body now ends the block.
+        instructions.push(Operator::Call {
+            function_index: exit_fn,
+        });
+        instructions.push(Operator::End);
+        func_body.instructions = instructions;
+    }
+    module
+}
+
+/// The number of times a function of a canister module has been executed.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct FunctionCoverage {
+    /// The index of the function in the original (uninstrumented) module.
+    pub function_index: u32,
+    /// The name of the function in the name section of the original module (if any).
+    pub name: Option<String>,
+    /// The location of the first instruction of the function in the source code
+    /// according to the DWARF line tables of the original module (if any).
+    pub source_location: Option<SourceLocation>,
+    pub hits: u64,
+}
+
+/// Returns the hit counts of all functions defined by the given original
+/// (uninstrumented) module from the exported globals of a canister whose
+/// module was instrumented with Wasm coverage enabled.
+///
+/// The coverage counters are the last exported globals of an instrumented
+/// module and follow the order of the functions in the original module.
+pub fn wasm_coverage(
+    wasm: &BinaryEncodedWasm,
+    exported_globals: &[ic_replicated_state::Global],
+) -> Result<Vec<FunctionCoverage>, WasmInstrumentationError> {
+    let module = Module::parse(wasm.as_slice(), false).map_err(|err| {
+        WasmInstrumentationError::WasmDeserializeError(WasmError::new(err.to_string()))
+    })?;
+    let num_imported_functions = module
+        .imports
+        .iter()
+        .filter(|i| matches!(i.ty, TypeRef::Func(_)))
+        .count();
+    let num_local_functions = module.code_sections.len();
+    if exported_globals.len() < num_local_functions {
+        return Err(WasmInstrumentationError::WasmDeserializeError(
+            WasmError::new(format!(
+                "Expected at least {} exported globals, but got {}",
+                num_local_functions,
+                exported_globals.len()
+            )),
+        ));
+    }
+    let function_names = module_function_names(&module);
+    // Coverage does not depend on debug info, so malformed debug info
+    // only results in missing source locations.
+    let source_locations = function_source_locations(wasm.as_slice())
+        .unwrap_or_else(|_| vec![None; num_local_functions]);
+    let counters = &exported_globals[exported_globals.len() - num_local_functions..];
+    counters
+        .iter()
+        .zip(source_locations)
+        .enumerate()
+        .map(|(func_ix, (counter, source_location))| {
+            let function_index = (num_imported_functions + func_ix) as u32;
+            match counter {
+                ic_replicated_state::Global::I64(hits) => Ok(FunctionCoverage {
+                    function_index,
+                    name: function_names.get(&function_index).cloned(),
+                    source_location,
+                    hits: *hits as u64,
+                }),
+                _ => Err(WasmInstrumentationError::WasmDeserializeError(
+                    WasmError::new(format!(
+                        "Coverage counter of function {} is not an i64 global",
+                        function_index
+                    )),
+                )),
+            }
+        })
+        .collect()
+}
+
+fn module_function_names(module: &Module<'_>) -> BTreeMap<u32, String> {
+    module
+        .name_section
+        .as_ref()
+        .map(|names| {
+            names
+                .function_names
+                .iter()
+                .map(|(index, name)| (*index, name.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the names of functions (by function index) in the name section
+/// of the given module (if any).
+pub fn wasm_function_names(
+    wasm: &BinaryEncodedWasm,
+) -> Result<BTreeMap<u32, String>, WasmInstrumentationError> {
+    let module = Module::parse(wasm.as_slice(), false).map_err(|err| {
+        WasmInstrumentationError::WasmDeserializeError(WasmError::new(err.to_string()))
+    })?;
+    Ok(module_function_names(&module))
+}
+
 // Mutable globals must be exported to be persisted.
 fn export_mutable_globals<'a>(
     mut module: Module<'a>,
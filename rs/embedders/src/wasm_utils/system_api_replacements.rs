@@ -35,7 +35,7 @@ pub(super) fn replacement_functions(
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
     main_memory_type: WasmMemoryType,
-    max_wasm_memory_size: NumBytes,
+    max_wasm64_memory_size: NumBytes,
 ) -> Vec<(SystemApiFunc, (FuncType, Body<'static>))> {
     let count_clean_pages_fn_index = special_indices.count_clean_pages_fn.unwrap();
     let dirty_pages_counter_index = special_indices.dirty_pages_counter_ix.unwrap();
@@ -59,7 +59,7 @@ pub(super) fn replacement_functions(
         // We need to therefore allow the heap addresses to be larger than u32::MAX in Wasm64 mode
         // for stable_read and stable_write.
         WasmMemoryType::Wasm32 => u32::MAX as u64,
-        WasmMemoryType::Wasm64 => max_wasm_memory_size.get(),
+        WasmMemoryType::Wasm64 => max_wasm64_memory_size.get(),
     };
 
     vec![
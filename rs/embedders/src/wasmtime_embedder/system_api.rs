@@ -610,6 +610,69 @@ pub fn syscalls<
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "subnet_self_size", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead::SUBNET_SELF_SIZE)?;
+                with_system_api(&mut caller, |s| s.ic0_subnet_self_size()).and_then(|s| {
+                    I::try_from(s).map_err(|e| {
+                        anyhow::Error::msg(format!("ic0_subnet_self_size failed: {}", e))
+                    })
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "subnet_self_copy", {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                let offset: usize = offset.try_into().expect("Failed to convert I to usize");
+                let size: usize = size.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(&mut caller, overhead::SUBNET_SELF_COPY, size)?;
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.ic0_subnet_self_copy(dst, offset, size, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, size)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "root_key_size", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead::ROOT_KEY_SIZE)?;
+                with_system_api(&mut caller, |s| s.ic0_root_key_size()).and_then(|s| {
+                    I::try_from(s)
+                        .map_err(|e| anyhow::Error::msg(format!("ic0_root_key_size failed: {}", e)))
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "root_key_copy", {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                let offset: usize = offset.try_into().expect("Failed to convert I to usize");
+                let size: usize = size.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(&mut caller, overhead::ROOT_KEY_COPY, size)?;
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.ic0_root_key_copy(dst, offset, size, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, size)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "debug_print", {
             move |mut caller: Caller<'_, StoreData>, offset: I, length: I| {
@@ -635,6 +698,48 @@ pub fn syscalls<
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "debug_print_structured", {
+            move |mut caller: Caller<'_, StoreData>,
+                  level: i32,
+                  tags_offset: I,
+                  tags_length: I,
+                  offset: I,
+                  length: I| {
+                let tags_length: u64 = tags_length.try_into().expect("Failed to convert I to u64");
+                let length: u64 = length.try_into().expect("Failed to convert I to u64");
+                let mut num_bytes = 0;
+                num_bytes += logging_charge_bytes(&mut caller, tags_length.saturating_add(length))?;
+                let debug_print_is_enabled = debug_print_is_enabled(&mut caller, feature_flags)?;
+                if debug_print_is_enabled {
+                    num_bytes += length;
+                }
+                charge_for_cpu_and_mem(&mut caller, overhead::DEBUG_PRINT, num_bytes as usize)?;
+                let tags_offset: usize = tags_offset
+                    .try_into()
+                    .expect("Failed to convert I to usize");
+                let tags_length = tags_length as usize;
+                let offset: usize = offset.try_into().expect("Failed to convert I to usize");
+                let length = length as usize;
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.save_structured_log_message(
+                        level,
+                        tags_offset,
+                        tags_length,
+                        offset,
+                        length,
+                        memory,
+                    )?;
+                    if debug_print_is_enabled {
+                        system_api.ic0_debug_print(offset, length, memory)
+                    } else {
+                        Ok(())
+                    }
+                })
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "trap", {
             move |mut caller: Caller<'_, StoreData>, offset: I, length: I| -> Result<(), _> {
@@ -1037,6 +1142,36 @@ pub fn syscalls<
         })
         .unwrap();
 
+    linker
+        .func_wrap("__", "profile_enter", {
+            move |mut caller: Caller<'_, StoreData>, func_index: i32| -> Result<(), _> {
+                with_error_handling(&mut caller, |c| {
+                    let global = get_num_instructions_global(c)?;
+                    let instruction_counter = load_value(&global, c)?;
+                    c.data_mut()
+                        .system_api_mut()?
+                        .profile_enter(func_index as u32, instruction_counter);
+                    Ok(())
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("__", "profile_exit", {
+            move |mut caller: Caller<'_, StoreData>| -> Result<(), _> {
+                with_error_handling(&mut caller, |c| {
+                    let global = get_num_instructions_global(c)?;
+                    let instruction_counter = load_value(&global, c)?;
+                    c.data_mut()
+                        .system_api_mut()?
+                        .profile_exit(instruction_counter);
+                    Ok(())
+                })
+            }
+        })
+        .unwrap();
+
     match main_memory_type {
         WasmMemoryType::Wasm32 => {
             linker
@@ -1214,6 +1349,228 @@ pub fn syscalls<
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "cost_call", {
+            move |mut caller: Caller<'_, StoreData>,
+                  method_name_size: u64,
+                  payload_size: u64,
+                  dst: I| {
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu(&mut caller, overhead::COST_CALL)?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_call(method_name_size, payload_size, dst, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_create_canister", {
+            move |mut caller: Caller<'_, StoreData>, dst: I| {
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu(&mut caller, overhead::COST_CREATE_CANISTER)?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_create_canister(dst, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_http_request", {
+            move |mut caller: Caller<'_, StoreData>,
+                  request_size: u64,
+                  max_res_bytes: u64,
+                  dst: I| {
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu(&mut caller, overhead::COST_HTTP_REQUEST)?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_http_request(request_size, max_res_bytes, dst, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_sign_with_ecdsa", {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I, ecdsa_curve: u32, dst: I| {
+                let src: usize = src.try_into().expect("Failed to convert I to usize");
+                let size: usize = size.try_into().expect("Failed to convert I to usize");
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(&mut caller, overhead::COST_SIGN_WITH_ECDSA, size)?;
+                let result = with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_sign_with_ecdsa(src, size, ecdsa_curve, dst, memory)
+                })?;
+                // The cost is only written to `dst` on success.
+                if result == 0 && feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)?;
+                }
+                Ok(result)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_sign_with_schnorr", {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I, algorithm: u32, dst: I| {
+                let src: usize = src.try_into().expect("Failed to convert I to usize");
+                let size: usize = size.try_into().expect("Failed to convert I to usize");
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(&mut caller, overhead::COST_SIGN_WITH_SCHNORR, size)?;
+                let result = with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_sign_with_schnorr(src, size, algorithm, dst, memory)
+                })?;
+                // The cost is only written to `dst` on success.
+                if result == 0 && feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)?;
+                }
+                Ok(result)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "cost_vetkd_derive_key", {
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I, vetkd_curve: u32, dst: I| {
+                let src: usize = src.try_into().expect("Failed to convert I to usize");
+                let size: usize = size.try_into().expect("Failed to convert I to usize");
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(&mut caller, overhead::COST_VETKD_DERIVE_KEY, size)?;
+                let result = with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_cost_vetkd_derive_key(src, size, vetkd_curve, dst, memory)
+                })?;
+                // The cost is only written to `dst` on success.
+                if result == 0 && feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, 16)?;
+                }
+                Ok(result)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "env_var_count", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead::ENV_VAR_COUNT)?;
+                with_system_api(&mut caller, |s| s.ic0_env_var_count()).and_then(|s| {
+                    I::try_from(s).map_err(|e| {
+                        anyhow::Error::msg(format!("ic0::env_var_count failed: {}", e))
+                    })
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "env_var_name_size", {
+            move |mut caller: Caller<'_, StoreData>, index: I| {
+                let index: usize = index.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu(&mut caller, overhead::ENV_VAR_NAME_SIZE)?;
+                with_system_api(&mut caller, |s| s.ic0_env_var_name_size(index)).and_then(|s| {
+                    I::try_from(s).map_err(|e| {
+                        anyhow::Error::msg(format!("ic0::env_var_name_size failed: {}", e))
+                    })
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "env_var_name_copy", {
+            move |mut caller: Caller<'_, StoreData>, index: I, dst: I, offset: I, size: I| {
+                let index: usize = index.try_into().expect("Failed to convert I to usize");
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                let offset: usize = offset.try_into().expect("Failed to convert I to usize");
+                let size: usize = size.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(&mut caller, overhead::ENV_VAR_NAME_COPY, size)?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_env_var_name_copy(index, dst, offset, size, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, size)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "env_var_name_exists", {
+            move |mut caller: Caller<'_, StoreData>, name_src: I, name_size: I| {
+                let name_src: usize = name_src.try_into().expect("Failed to convert I to usize");
+                let name_size: usize = name_size.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(&mut caller, overhead::ENV_VAR_NAME_EXISTS, name_size)?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_env_var_name_exists(name_src, name_size, memory)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "env_var_value_size", {
+            move |mut caller: Caller<'_, StoreData>, name_src: I, name_size: I| {
+                let name_src: usize = name_src.try_into().expect("Failed to convert I to usize");
+                let name_size: usize = name_size.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(&mut caller, overhead::ENV_VAR_VALUE_SIZE, name_size)?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_env_var_value_size(name_src, name_size, memory)
+                })
+                .and_then(|s| {
+                    I::try_from(s).map_err(|e| {
+                        anyhow::Error::msg(format!("ic0::env_var_value_size failed: {}", e))
+                    })
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "env_var_value_copy", {
+            move |mut caller: Caller<'_, StoreData>,
+                  name_src: I,
+                  name_size: I,
+                  dst: I,
+                  offset: I,
+                  size: I| {
+                let name_src: usize = name_src.try_into().expect("Failed to convert I to usize");
+                let name_size: usize = name_size.try_into().expect("Failed to convert I to usize");
+                let dst: usize = dst.try_into().expect("Failed to convert I to usize");
+                let offset: usize = offset.try_into().expect("Failed to convert I to usize");
+                let size: usize = size.try_into().expect("Failed to convert I to usize");
+                charge_for_cpu_and_mem(
+                    &mut caller,
+                    overhead::ENV_VAR_VALUE_COPY,
+                    name_size.saturating_add(size),
+                )?;
+                with_memory_and_system_api(&mut caller, |s, memory| {
+                    s.ic0_env_var_value_copy(name_src, name_size, dst, offset, size, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, size)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "call_with_best_effort_response", {
             move |mut caller: Caller<'_, StoreData>, timeout_seconds: u32| {
@@ -113,6 +113,7 @@ fn test_api_for_update(
             .feature_flags
             .wasm_native_stable_memory,
         EmbeddersConfig::default().feature_flags.canister_backtrace,
+        EmbeddersConfig::default().feature_flags.wasm_tracing,
         EmbeddersConfig::default().max_sum_exported_function_name_lengths,
         Memory::new_for_testing(),
         NumWasmPages::from(0),
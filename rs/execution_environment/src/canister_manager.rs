@@ -25,33 +25,40 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_management_canister_types::{
-    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterSnapshotResponse,
-    CanisterStatusResultV2, CanisterStatusType, ChunkHash, InstallChunkedCodeArgs,
-    InstallCodeArgsV2, Method as Ic00Method, StoredChunksReply, UploadChunkReply,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterSnapshotDataKind,
+    CanisterSnapshotDataOffset, CanisterSnapshotResponse, CanisterStatusResultV2,
+    CanisterStatusType, ChunkHash, EnvironmentVariable, InstallChunkedCodeArgs, InstallCodeArgsV2,
+    Method as Ic00Method, ReadCanisterSnapshotDataResponse, ReadCanisterSnapshotMetadataResponse,
+    SnapshotGlobal, StoredChunksReply, UploadCanisterSnapshotDataArgs,
+    UploadCanisterSnapshotMetadataArgs, UploadCanisterSnapshotMetadataResponse, UploadChunkReply,
+    MAX_SNAPSHOT_DATA_SLICE_SIZE,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_snapshots::{CanisterSnapshot, CanisterSnapshotError},
+    canister_snapshots::{CanisterSnapshot, CanisterSnapshotError, PageMemory},
     canister_state::{
         execution_state::Memory,
         system_state::{
             wasm_chunk_store::{self, WasmChunkStore},
             CyclesUseCase, ReservationError,
         },
-        NextExecution,
+        NextExecution, WASM_PAGE_SIZE_IN_BYTES,
     },
     metadata_state::subnet_call_context_manager::InstallCodeCallId,
+    num_bytes_try_from,
     page_map::PageAllocatorFileDescriptor,
-    CallOrigin, CanisterState, NetworkTopology, ReplicatedState, SchedulerState, SystemState,
+    CallOrigin, CanisterState, Global, NetworkTopology, NumWasmPages, ReplicatedState,
+    SchedulerState, SystemState,
 };
-use ic_system_api::ExecutionParameters;
+use ic_system_api::{ExecutionParameters, CERTIFIED_DATA_MAX_LENGTH};
 use ic_types::{
     ingress::{IngressState, IngressStatus},
     messages::{
         CanisterCall, MessageId, Payload, RejectContext, Response as CanisterResponse,
         SignedIngressContent, StopCanisterContext,
     },
+    methods::SystemMethod,
     nominal_cycles::NominalCycles,
     CanisterId, CanisterTimer, ComputeAllocation, Cycles, InvalidComputeAllocationError,
     InvalidMemoryAllocationError, MemoryAllocation, NumBytes, NumInstructions, PrincipalId,
@@ -64,7 +71,12 @@ use num_traits::SaturatingAdd;
 use prometheus::IntCounter;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::{collections::BTreeSet, convert::TryFrom, str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    str::FromStr,
+    sync::Arc,
+};
 
 #[derive(Eq, PartialEq, Debug)]
 pub(crate) struct InstallCodeResult {
@@ -88,6 +100,10 @@ pub(crate) enum DtsInstallCodeResult {
         message: CanisterCall,
         call_id: InstallCodeCallId,
         instructions_used: NumInstructions,
+        /// The instructions executed by each system method (e.g.
+        /// `canister_pre_upgrade`) across all slices. System methods that
+        /// were not executed are missing.
+        system_method_instructions: BTreeMap<SystemMethod, NumInstructions>,
         result: Result<InstallCodeResult, CanisterManagerError>,
     },
     Paused {
@@ -129,6 +145,7 @@ pub(crate) struct CanisterMgrConfig {
     wasm_chunk_store_max_size: NumBytes,
     canister_snapshot_baseline_instructions: NumInstructions,
     default_wasm_memory_limit: NumBytes,
+    pub(crate) wasm_tracing: FlagStatus,
 }
 
 impl CanisterMgrConfig {
@@ -150,6 +167,7 @@ impl CanisterMgrConfig {
         wasm_chunk_store_max_size: NumBytes,
         canister_snapshot_baseline_instructions: NumInstructions,
         default_wasm_memory_limit: NumBytes,
+        wasm_tracing: FlagStatus,
     ) -> Self {
         Self {
             subnet_memory_capacity,
@@ -168,6 +186,7 @@ impl CanisterMgrConfig {
             wasm_chunk_store_max_size,
             canister_snapshot_baseline_instructions,
             default_wasm_memory_limit,
+            wasm_tracing,
         }
     }
 }
@@ -406,6 +425,16 @@ pub enum AddCanisterChangeToHistory {
     No,
 }
 
+/// How a new canister snapshot came to be, which determines how it gets
+/// persisted by the state manager.
+enum SnapshotSource {
+    /// Taken from the current state of the canister.
+    TakenFromCanister,
+    /// Created from metadata uploaded by a controller, with the contents to
+    /// be uploaded separately.
+    MetadataUpload,
+}
+
 /// The entity responsible for managing canisters (creation, installing, etc.)
 pub(crate) struct CanisterManager {
     hypervisor: Arc<Hypervisor>,
@@ -506,7 +535,11 @@ impl CanisterManager {
             | Ok(Ic00Method::TakeCanisterSnapshot)
             | Ok(Ic00Method::LoadCanisterSnapshot)
             | Ok(Ic00Method::ListCanisterSnapshots)
-            | Ok(Ic00Method::DeleteCanisterSnapshot) => {
+            | Ok(Ic00Method::DeleteCanisterSnapshot)
+            | Ok(Ic00Method::ReadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::ReadCanisterSnapshotData)
+            | Ok(Ic00Method::UploadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::UploadCanisterSnapshotData) => {
                 match effective_canister_id {
                     Some(canister_id) => {
                         let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
@@ -567,6 +600,7 @@ impl CanisterManager {
             settings,
             NumBytes::new(0),
             NumBytes::new(0),
+            NumBytes::new(0),
             MemoryAllocation::BestEffort,
             subnet_available_memory,
             subnet_memory_saturation,
@@ -574,6 +608,7 @@ impl CanisterManager {
             subnet_compute_allocation_usage,
             self.config.compute_capacity,
             self.config.max_controllers,
+            self.config.wasm_tracing,
             self.config.default_freeze_threshold,
             canister_cycles_balance,
             &self.cycles_account_manager,
@@ -640,6 +675,21 @@ impl CanisterManager {
         if let Some(wasm_memory_limit) = settings.wasm_memory_limit() {
             canister.system_state.wasm_memory_limit = Some(wasm_memory_limit);
         }
+        if let Some(log_buffer_size) = settings.log_buffer_size() {
+            canister
+                .system_state
+                .canister_log
+                .set_capacity(log_buffer_size.get() as usize);
+        }
+        if let Some(environment_variables) = settings.environment_variables() {
+            canister.system_state.environment_variables = environment_variables.clone();
+        }
+        if let Some(wasm_trace_length) = settings.wasm_trace_length() {
+            canister.system_state.wasm_trace_length = wasm_trace_length;
+        }
+        if let Some(priority_class) = settings.priority_class() {
+            canister.scheduler_state.priority_class = priority_class;
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -661,6 +711,7 @@ impl CanisterManager {
         let validated_settings = validate_canister_settings(
             settings,
             canister.memory_usage(),
+            canister.canister_log_memory_usage(),
             canister.message_memory_usage(),
             canister.memory_allocation(),
             &round_limits.subnet_available_memory,
@@ -669,6 +720,7 @@ impl CanisterManager {
             round_limits.compute_allocation_used,
             self.config.compute_capacity,
             self.config.max_controllers,
+            self.config.wasm_tracing,
             canister.system_state.freeze_threshold,
             canister.system_state.balance(),
             &self.cycles_account_manager,
@@ -696,7 +748,7 @@ impl CanisterManager {
                 .saturating_sub(old_compute_allocation - new_compute_allocation);
         }
 
-        let new_usage = old_usage;
+        let new_usage = canister.memory_usage();
         let new_mem = canister.memory_allocation().allocated_bytes(new_usage);
         if new_mem >= old_mem {
             // Settings were validated before so this should always succeed.
@@ -868,6 +920,7 @@ impl CanisterManager {
                 call_id: _,
                 message: _,
                 instructions_used,
+                system_method_instructions: _,
                 result,
             } => (result, instructions_used, Some(canister)),
             DtsInstallCodeResult::Paused {
@@ -959,6 +1012,7 @@ impl CanisterManager {
                 message,
                 call_id,
                 instructions_used: NumInstructions::from(0),
+                system_method_instructions: BTreeMap::new(),
                 result: Err(err),
             };
         }
@@ -989,6 +1043,7 @@ impl CanisterManager {
                             message,
                             call_id,
                             instructions_used: NumInstructions::from(0),
+                            system_method_instructions: BTreeMap::new(),
                             result: Err(CanisterManagerError::InstallCodeNotEnoughCycles(err)),
                         };
                     }
@@ -1175,6 +1230,13 @@ impl CanisterManager {
         let log_visibility = canister.system_state.log_visibility.clone();
         let wasm_memory_limit = canister.system_state.wasm_memory_limit;
         let wasm_memory_threshold = canister.system_state.wasm_memory_threshold;
+        let log_buffer_size = canister.system_state.canister_log.capacity();
+        let environment_variables = canister
+            .system_state
+            .environment_variables
+            .iter()
+            .map(|(name, value)| EnvironmentVariable::new(name, value))
+            .collect();
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -1211,8 +1273,13 @@ impl CanisterManager {
                 .scheduler_state
                 .total_query_stats
                 .egress_payload_size,
+            canister.scheduler_state.total_query_stats.num_cache_hits,
             wasm_memory_limit.map(|x| x.get()),
             wasm_memory_threshold.get(),
+            log_buffer_size as u64,
+            environment_variables,
+            canister.system_state.wasm_trace_length,
+            canister.scheduler_state.priority_class,
         ))
     }
 
@@ -1806,54 +1873,11 @@ impl CanisterManager {
             return (Err(err), NumInstructions::new(0));
         };
 
-        let replace_snapshot_size = match replace_snapshot {
-            // Check that replace snapshot ID exists if provided.
-            Some(replace_snapshot) => {
-                match state.canister_snapshots.get(replace_snapshot) {
-                    None => {
-                        // If not found, the operation fails due to invalid parameters.
-                        return (
-                            Err(CanisterManagerError::CanisterSnapshotNotFound {
-                                canister_id: canister.canister_id(),
-                                snapshot_id: replace_snapshot,
-                            }),
-                            NumInstructions::new(0),
-                        );
-                    }
-                    Some(snapshot) => {
-                        // Verify the provided replacement snapshot belongs to this canister.
-                        if snapshot.canister_id() != canister.canister_id() {
-                            return (
-                                Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
-                                    canister_id: canister.canister_id(),
-                                    snapshot_id: replace_snapshot,
-                                }),
-                                NumInstructions::new(0),
-                            );
-                        }
-                        snapshot.size()
-                    }
-                }
-            }
-            // No replace snapshot ID provided, check whether the maximum number of snapshots
-            // has been reached.
-            None => {
-                if state
-                    .canister_snapshots
-                    .count_by_canister(&canister.canister_id())
-                    >= MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER
-                {
-                    return (
-                        Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
-                            canister_id: canister.canister_id(),
-                            limit: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
-                        }),
-                        NumInstructions::new(0),
-                    );
-                }
-                0.into()
-            }
-        };
+        let replace_snapshot_size =
+            match self.validate_replace_snapshot(canister, replace_snapshot, state) {
+                Ok(size) => size,
+                Err(err) => return (Err(err), NumInstructions::new(0)),
+            };
 
         if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled
             && canister.scheduler_state.heap_delta_debit >= self.config.heap_delta_rate_limit
@@ -1882,85 +1906,15 @@ impl CanisterManager {
                 .saturating_sub(replace_snapshot_size.get()),
         );
 
-        {
-            // Run the following checks on memory usage and return an error
-            // if any fails:
-            // 1. Check new usage will not freeze canister
-            // 2. Check subnet has available memory
-            // 3. Reserve cycles on canister
-            // 4. Actually deduct memory from subnet (asserting it won't fail)
-
-            // Calculate if any cycles will need to be reserved.
-            let reservation_cycles = self.cycles_account_manager.storage_reservation_cycles(
-                new_snapshot_increase,
-                resource_saturation,
-                subnet_size,
-            );
-
-            // Memory usage will increase by the snapshot size.
-            // Check that it doesn't bump the canister over the freezing threshold.
-            let threshold = self.cycles_account_manager.freeze_threshold_cycles(
-                canister.system_state.freeze_threshold,
-                canister.memory_allocation(),
-                new_memory_usage,
-                canister.message_memory_usage(),
-                canister.compute_allocation(),
-                subnet_size,
-                canister.system_state.reserved_balance(),
-            );
-
-            if canister.system_state.balance() < threshold + reservation_cycles {
-                return (
-                    Err(CanisterManagerError::InsufficientCyclesInMemoryGrow {
-                        bytes: new_snapshot_increase,
-                        available: canister.system_state.balance(),
-                        threshold,
-                    }),
-                    NumInstructions::new(0),
-                );
-            }
-            // Verify that the subnet has enough memory for a new snapshot.
-            if let Err(err) = round_limits
-                .subnet_available_memory
-                .check_available_memory(new_snapshot_increase, NumBytes::from(0), NumBytes::from(0))
-                .map_err(
-                    |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
-                        requested: new_snapshot_increase,
-                        available: NumBytes::from(
-                            round_limits
-                                .subnet_available_memory
-                                .get_execution_memory()
-                                .max(0) as u64,
-                        ),
-                    },
-                )
-            {
-                return (Err(err), NumInstructions::new(0));
-            };
-            // Reserve needed cycles if the subnet is becoming saturated.
-            if let Err(err) = canister
-                .system_state
-                .reserve_cycles(reservation_cycles)
-                .map_err(|err| match err {
-                    ReservationError::InsufficientCycles {
-                        requested,
-                        available,
-                    } => CanisterManagerError::InsufficientCyclesInMemoryGrow {
-                        bytes: new_snapshot_increase,
-                        available,
-                        threshold: requested,
-                    },
-                    ReservationError::ReservedLimitExceed { requested, limit } => {
-                        CanisterManagerError::ReservedCyclesLimitExceededInMemoryGrow {
-                            bytes: new_snapshot_increase,
-                            requested,
-                            limit,
-                        }
-                    }
-                })
-            {
-                return (Err(err), NumInstructions::new(0));
-            };
+        if let Err(err) = self.reserve_snapshot_memory(
+            canister,
+            new_memory_usage,
+            new_snapshot_increase,
+            round_limits,
+            resource_saturation,
+            subnet_size,
+        ) {
+            return (Err(err), NumInstructions::new(0));
         }
 
         // Charge for taking a snapshot of the canister.
@@ -1994,55 +1948,14 @@ impl CanisterManager {
             Err(err) => return (Err(err), instructions),
         };
 
-        // Delete old snapshot identified by `replace_snapshot` ID.
-        if let Some(replace_snapshot) = replace_snapshot {
-            state.canister_snapshots.remove(replace_snapshot);
-            canister.system_state.snapshots_memory_usage = canister
-                .system_state
-                .snapshots_memory_usage
-                .get()
-                .saturating_sub(replace_snapshot_size.get())
-                .into();
-            // Confirm that `snapshots_memory_usage` is updated correctly.
-            debug_assert_eq!(
-                canister.system_state.snapshots_memory_usage,
-                state
-                    .canister_snapshots
-                    .compute_memory_usage_by_canister(canister.canister_id()),
-            );
-            round_limits.subnet_available_memory.increment(
-                replace_snapshot_size,
-                NumBytes::from(0),
-                NumBytes::from(0),
-            );
-        }
-
-        // Actually deduct memory from the subnet. It's safe to unwrap
-        // here because we already checked the available memory above.
-        round_limits.subnet_available_memory
-            .try_decrement(new_snapshot_size, NumBytes::from(0), NumBytes::from(0))
-            .expect("Error: Cannot fail to decrement SubnetAvailableMemory after checking for availability");
-
-        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled {
-            canister.scheduler_state.heap_delta_debit = canister
-                .scheduler_state
-                .heap_delta_debit
-                .saturating_add(&new_snapshot.heap_delta());
-        }
-        state.metadata.heap_delta_estimate = state
-            .metadata
-            .heap_delta_estimate
-            .saturating_add(&new_snapshot.heap_delta());
-
-        let snapshot_id =
-            SnapshotId::from((canister.canister_id(), canister.new_local_snapshot_id()));
-        state
-            .canister_snapshots
-            .push(snapshot_id, Arc::new(new_snapshot));
-        canister.system_state.snapshots_memory_usage = canister
-            .system_state
-            .snapshots_memory_usage
-            .saturating_add(&new_snapshot_size);
+        let snapshot_id = self.insert_snapshot(
+            canister,
+            new_snapshot,
+            replace_snapshot.map(|id| (id, replace_snapshot_size)),
+            state,
+            round_limits,
+            SnapshotSource::TakenFromCanister,
+        );
         (
             Ok(CanisterSnapshotResponse::new(
                 &snapshot_id,
@@ -2296,25 +2209,7 @@ impl CanisterManager {
     ) -> Result<(), CanisterManagerError> {
         // Check sender is a controller.
         validate_controller(canister, &sender)?;
-
-        match state.canister_snapshots.get(delete_snapshot_id) {
-            None => {
-                // If not found, the operation fails due to invalid parameters.
-                return Err(CanisterManagerError::CanisterSnapshotNotFound {
-                    canister_id: canister.canister_id(),
-                    snapshot_id: delete_snapshot_id,
-                });
-            }
-            Some(delete_snapshot) => {
-                // Verify the provided `delete_snapshot_id` belongs to this canister.
-                if delete_snapshot.canister_id() != canister.canister_id() {
-                    return Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
-                        canister_id: canister.canister_id(),
-                        snapshot_id: delete_snapshot_id,
-                    });
-                }
-            }
-        }
+        get_snapshot_of_canister(canister.canister_id(), delete_snapshot_id, state)?;
         let old_snapshot = state.canister_snapshots.remove(delete_snapshot_id);
         // Already confirmed that `old_snapshot` exists.
         let old_snapshot_size = old_snapshot.unwrap().size();
@@ -2338,6 +2233,635 @@ impl CanisterManager {
         );
         Ok(())
     }
+
+    /// Returns the metadata of the specified canister snapshot, or
+    /// an error if it failed to retrieve the information.
+    ///
+    /// Reading the snapshot metadata can only be initiated by the controllers.
+    pub(crate) fn read_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        snapshot_id: SnapshotId,
+        canister: &CanisterState,
+        state: &ReplicatedState,
+    ) -> Result<ReadCanisterSnapshotMetadataResponse, CanisterManagerError> {
+        // Check sender is a controller.
+        validate_controller(canister, &sender)?;
+        let snapshot = get_snapshot_of_canister(canister.canister_id(), snapshot_id, state)?;
+
+        let wasm_memory_size = num_bytes_try_from(snapshot.wasm_memory().size)
+            .expect("could not convert from wasm memory number of pages to bytes");
+        let stable_memory_size = num_bytes_try_from(snapshot.stable_memory().size)
+            .expect("could not convert from stable memory number of pages to bytes");
+        Ok(ReadCanisterSnapshotMetadataResponse {
+            taken_at_timestamp: snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            canister_version: snapshot.canister_version(),
+            wasm_module_size: snapshot.canister_module().len() as u64,
+            exported_globals: snapshot
+                .exported_globals()
+                .iter()
+                .map(SnapshotGlobal::from)
+                .collect(),
+            wasm_memory_size: wasm_memory_size.get(),
+            stable_memory_size: stable_memory_size.get(),
+            wasm_chunk_store: snapshot
+                .chunk_store()
+                .keys()
+                .map(|hash| ChunkHash {
+                    hash: hash.to_vec(),
+                })
+                .collect(),
+            certified_data: snapshot.certified_data().clone(),
+        })
+    }
+
+    /// Returns a piece of the data of the specified canister snapshot, or
+    /// an error if it failed to retrieve it.
+    ///
+    /// Reading the snapshot data can only be initiated by the controllers.
+    /// Every read is charged like uploading a Wasm chunk.
+    pub(crate) fn read_snapshot_data(
+        &self,
+        sender: PrincipalId,
+        snapshot_id: SnapshotId,
+        kind: CanisterSnapshotDataKind,
+        canister: &mut CanisterState,
+        state: &ReplicatedState,
+        subnet_size: usize,
+    ) -> (
+        Result<ReadCanisterSnapshotDataResponse, CanisterManagerError>,
+        NumInstructions,
+    ) {
+        // Check sender is a controller.
+        if let Err(err) = validate_controller(canister, &sender) {
+            return (Err(err), NumInstructions::new(0));
+        }
+        let snapshot = match get_snapshot_of_canister(canister.canister_id(), snapshot_id, state) {
+            Ok(snapshot) => snapshot,
+            Err(err) => return (Err(err), NumInstructions::new(0)),
+        };
+
+        let instructions = self.config.upload_wasm_chunk_instructions;
+        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
+            &sender,
+            canister,
+            instructions,
+            subnet_size,
+            // Reading snapshot data is a constant set fee and does not depend
+            // on whether the snapshot belongs to a Wasm64 or Wasm32 module.
+            WasmExecutionMode::Wasm32,
+        ) {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
+                NumInstructions::new(0),
+            );
+        }
+
+        let chunk = match kind {
+            CanisterSnapshotDataKind::WasmModule { offset, size } => {
+                let module = snapshot.canister_module().as_slice();
+                validate_snapshot_data_range(offset, size, module.len() as u64).map(|()| {
+                    let start = offset as usize;
+                    module[start..start + size as usize].to_vec()
+                })
+            }
+            CanisterSnapshotDataKind::MainMemory { offset, size } => {
+                read_snapshot_memory(snapshot.wasm_memory(), offset, size)
+            }
+            CanisterSnapshotDataKind::StableMemory { offset, size } => {
+                read_snapshot_memory(snapshot.stable_memory(), offset, size)
+            }
+            CanisterSnapshotDataKind::WasmChunk { hash } => {
+                let chunk = <[u8; 32]>::try_from(hash.as_slice())
+                    .ok()
+                    .and_then(|hash| snapshot.chunk_store().get_chunk_data(&hash))
+                    .map(|pages| pages.flatten().copied().collect::<Vec<u8>>());
+                chunk.ok_or_else(|| CanisterManagerError::WasmChunkStoreError {
+                    message: format!(
+                        "Chunk with hash {} not found in snapshot {}",
+                        hex::encode(&hash),
+                        snapshot_id
+                    ),
+                })
+            }
+        };
+
+        (
+            chunk.map(ReadCanisterSnapshotDataResponse::new),
+            instructions,
+        )
+    }
+
+    /// Creates a new, empty canister snapshot from the given metadata. Its
+    /// contents are subsequently filled in through `upload_snapshot_data`.
+    ///
+    /// Uploading a canister snapshot can only be initiated by the controllers.
+    /// The same limits as for taking a snapshot apply, and if `replace_snapshot`
+    /// is `Some`, the identified snapshot is deleted in favor of the new one.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn upload_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        args: UploadCanisterSnapshotMetadataArgs,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        resource_saturation: &ResourceSaturation,
+        subnet_size: usize,
+    ) -> (
+        Result<UploadCanisterSnapshotMetadataResponse, CanisterManagerError>,
+        NumInstructions,
+    ) {
+        // Check sender is a controller.
+        if let Err(err) = validate_controller(canister, &sender) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        let replace_snapshot = args.replace_snapshot();
+        let replace_snapshot_size =
+            match self.validate_replace_snapshot(canister, replace_snapshot, state) {
+                Ok(size) => size,
+                Err(err) => return (Err(err), NumInstructions::new(0)),
+            };
+
+        let (wasm_memory_size, stable_memory_size) = match self.validate_snapshot_metadata(&args) {
+            Ok(sizes) => sizes,
+            Err(err) => return (Err(err), NumInstructions::new(0)),
+        };
+
+        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled
+            && canister.scheduler_state.heap_delta_debit >= self.config.heap_delta_rate_limit
+        {
+            return (
+                Err(CanisterManagerError::CanisterHeapDeltaRateLimited {
+                    canister_id: canister.canister_id(),
+                    value: canister.scheduler_state.heap_delta_debit,
+                    limit: self.config.heap_delta_rate_limit,
+                }),
+                NumInstructions::new(0),
+            );
+        }
+
+        let new_snapshot = CanisterSnapshot::from_metadata(
+            canister.canister_id(),
+            state.time(),
+            canister.system_state.canister_version,
+            args.certified_data,
+            args.exported_globals.iter().map(Global::from).collect(),
+            args.wasm_module_size,
+            wasm_memory_size,
+            stable_memory_size,
+            Arc::clone(&self.fd_factory),
+        );
+        let new_snapshot_size = new_snapshot.size();
+        let new_snapshot_increase = NumBytes::from(
+            new_snapshot_size
+                .get()
+                .saturating_sub(replace_snapshot_size.get()),
+        );
+        let new_memory_usage = NumBytes::from(
+            canister
+                .memory_usage()
+                .get()
+                .saturating_add(new_snapshot_size.get())
+                .saturating_sub(replace_snapshot_size.get()),
+        );
+
+        // Charge for creating the snapshot before reserving cycles for its
+        // memory, so that a failed charge leaves no reservation behind. The
+        // data itself is charged for as it gets uploaded.
+        let instructions = self.config.canister_snapshot_baseline_instructions;
+        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
+            &sender,
+            canister,
+            instructions,
+            subnet_size,
+            // The number of instructions charged is a constant set fee, so it does
+            // not matter whether the snapshot is for a Wasm64 or Wasm32 module.
+            WasmExecutionMode::Wasm32,
+        ) {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
+                NumInstructions::new(0),
+            );
+        };
+
+        if let Err(err) = self.reserve_snapshot_memory(
+            canister,
+            new_memory_usage,
+            new_snapshot_increase,
+            round_limits,
+            resource_saturation,
+            subnet_size,
+        ) {
+            return (Err(err), instructions);
+        }
+
+        let snapshot_id = self.insert_snapshot(
+            canister,
+            new_snapshot,
+            replace_snapshot.map(|id| (id, replace_snapshot_size)),
+            state,
+            round_limits,
+            SnapshotSource::MetadataUpload,
+        );
+        (
+            Ok(UploadCanisterSnapshotMetadataResponse::new(&snapshot_id)),
+            instructions,
+        )
+    }
+
+    /// Writes a piece of data into the specified canister snapshot, or returns
+    /// an error if it failed.
+    ///
+    /// Uploading snapshot data can only be initiated by the controllers.
+    /// Every upload is charged like uploading a Wasm chunk.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn upload_snapshot_data(
+        &self,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        args: &UploadCanisterSnapshotDataArgs,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        resource_saturation: &ResourceSaturation,
+        subnet_size: usize,
+    ) -> (Result<(), CanisterManagerError>, NumInstructions) {
+        // Check sender is a controller.
+        if let Err(err) = validate_controller(canister, &sender) {
+            return (Err(err), NumInstructions::new(0));
+        }
+        let snapshot_id = args.get_snapshot_id();
+        let snapshot = match get_snapshot_of_canister(canister.canister_id(), snapshot_id, state) {
+            Ok(snapshot) => snapshot,
+            Err(err) => return (Err(err), NumInstructions::new(0)),
+        };
+
+        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled
+            && canister.scheduler_state.heap_delta_debit >= self.config.heap_delta_rate_limit
+        {
+            return (
+                Err(CanisterManagerError::CanisterHeapDeltaRateLimited {
+                    canister_id: canister.canister_id(),
+                    value: canister.scheduler_state.heap_delta_debit,
+                    limit: self.config.heap_delta_rate_limit,
+                }),
+                NumInstructions::new(0),
+            );
+        }
+
+        let chunk_len = args.chunk.len() as u64;
+        // Validate the target range before charging so that a malformed
+        // request does not cost anything.
+        let validation = match args.kind {
+            CanisterSnapshotDataOffset::WasmModule { offset } => validate_snapshot_data_range(
+                offset,
+                chunk_len,
+                snapshot.canister_module().len() as u64,
+            ),
+            CanisterSnapshotDataOffset::MainMemory { offset } => validate_snapshot_data_range(
+                offset,
+                chunk_len,
+                num_bytes_try_from(snapshot.wasm_memory().size).map_or(0, |bytes| bytes.get()),
+            ),
+            CanisterSnapshotDataOffset::StableMemory { offset } => validate_snapshot_data_range(
+                offset,
+                chunk_len,
+                num_bytes_try_from(snapshot.stable_memory().size).map_or(0, |bytes| bytes.get()),
+            ),
+            CanisterSnapshotDataOffset::WasmChunk => snapshot
+                .chunk_store()
+                .can_insert_chunk(self.config.wasm_chunk_store_max_size, &args.chunk)
+                .map_err(|err| CanisterManagerError::WasmChunkStoreError { message: err }),
+        };
+        if let Err(err) = validation {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        // Charge for the upload before reserving any cycles for memory growth,
+        // like `upload_chunk` does, so that a failed charge leaves no
+        // reservation behind.
+        let instructions = self.config.upload_wasm_chunk_instructions;
+        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
+            &sender,
+            canister,
+            instructions,
+            subnet_size,
+            // Uploading snapshot data is a constant set fee and does not depend
+            // on whether the snapshot belongs to a Wasm64 or Wasm32 module.
+            WasmExecutionMode::Wasm32,
+        ) {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
+                NumInstructions::new(0),
+            );
+        }
+
+        // A new chunk grows the snapshot, which needs to be paid for like
+        // any other memory growth.
+        let is_new_chunk = args.kind == CanisterSnapshotDataOffset::WasmChunk
+            && snapshot
+                .chunk_store()
+                .get_chunk_data(&ic_crypto_sha2::Sha256::hash(&args.chunk))
+                .is_none();
+        if is_new_chunk {
+            let chunk_bytes = wasm_chunk_store::chunk_size();
+            let new_memory_usage = canister.memory_usage() + chunk_bytes;
+            if let Err(err) = self.reserve_snapshot_memory(
+                canister,
+                new_memory_usage,
+                chunk_bytes,
+                round_limits,
+                resource_saturation,
+                subnet_size,
+            ) {
+                return (Err(err), instructions);
+            }
+        }
+
+        let heap_delta = match args.kind {
+            CanisterSnapshotDataOffset::WasmChunk => {
+                // We checked above that the chunk can be inserted.
+                let increase = state
+                    .canister_snapshots
+                    .insert_chunk(
+                        snapshot_id,
+                        self.config.wasm_chunk_store_max_size,
+                        &args.chunk,
+                    )
+                    .expect("Error: Insert chunk cannot fail after checking `can_insert_chunk`");
+                round_limits.subnet_available_memory
+                    .try_decrement(increase, NumBytes::from(0), NumBytes::from(0))
+                    .expect("Error: Cannot fail to decrement SubnetAvailableMemory after checking for availability");
+                canister.system_state.snapshots_memory_usage = canister
+                    .system_state
+                    .snapshots_memory_usage
+                    .saturating_add(&increase);
+                increase
+            }
+            CanisterSnapshotDataOffset::WasmModule { offset }
+            | CanisterSnapshotDataOffset::MainMemory { offset }
+            | CanisterSnapshotDataOffset::StableMemory { offset } => {
+                // The snapshot exists, we looked it up above.
+                let snapshot =
+                    Arc::make_mut(state.canister_snapshots.get_mut(snapshot_id).unwrap());
+                match args.kind {
+                    CanisterSnapshotDataOffset::WasmModule { .. } => {
+                        snapshot.write_wasm_module(offset as usize, &args.chunk)
+                    }
+                    CanisterSnapshotDataOffset::MainMemory { .. } => {
+                        snapshot.write_wasm_memory(offset as usize, &args.chunk)
+                    }
+                    _ => snapshot.write_stable_memory(offset as usize, &args.chunk),
+                }
+                NumBytes::from(chunk_len)
+            }
+        };
+
+        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled {
+            canister.scheduler_state.heap_delta_debit = canister
+                .scheduler_state
+                .heap_delta_debit
+                .saturating_add(&heap_delta);
+        }
+        state.metadata.heap_delta_estimate = state
+            .metadata
+            .heap_delta_estimate
+            .saturating_add(&heap_delta);
+
+        (Ok(()), instructions)
+    }
+
+    /// Checks the snapshot to be replaced by a new snapshot of the canister,
+    /// if any, and returns its size. Without a snapshot to be replaced, checks
+    /// that the canister has not reached the maximum number of snapshots.
+    fn validate_replace_snapshot(
+        &self,
+        canister: &CanisterState,
+        replace_snapshot: Option<SnapshotId>,
+        state: &ReplicatedState,
+    ) -> Result<NumBytes, CanisterManagerError> {
+        match replace_snapshot {
+            // Check that replace snapshot ID exists if provided.
+            Some(replace_snapshot) => {
+                get_snapshot_of_canister(canister.canister_id(), replace_snapshot, state)
+                    .map(|snapshot| snapshot.size())
+            }
+            // No replace snapshot ID provided, check whether the maximum number of snapshots
+            // has been reached.
+            None => {
+                if state
+                    .canister_snapshots
+                    .count_by_canister(&canister.canister_id())
+                    >= MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER
+                {
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id: canister.canister_id(),
+                        limit: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
+                    });
+                }
+                Ok(0.into())
+            }
+        }
+    }
+
+    /// Validates the uploaded snapshot metadata and returns the sizes of the
+    /// Wasm and stable memories in Wasm pages.
+    fn validate_snapshot_metadata(
+        &self,
+        args: &UploadCanisterSnapshotMetadataArgs,
+    ) -> Result<(NumWasmPages, NumWasmPages), CanisterManagerError> {
+        let invalid =
+            |message: String| CanisterManagerError::CanisterSnapshotInvalidMetadata { message };
+        // A chunked Wasm module can be as large as the Wasm chunk store.
+        if args.wasm_module_size > self.config.wasm_chunk_store_max_size.get() {
+            return Err(invalid(format!(
+                "Wasm module size {} exceeds the maximum of {} bytes",
+                args.wasm_module_size, self.config.wasm_chunk_store_max_size
+            )));
+        }
+        if args.certified_data.len() > CERTIFIED_DATA_MAX_LENGTH {
+            return Err(invalid(format!(
+                "Certified data of {} bytes exceeds the maximum of {} bytes",
+                args.certified_data.len(),
+                CERTIFIED_DATA_MAX_LENGTH
+            )));
+        }
+        let to_pages = |name: &str, size: u64| {
+            if size % WASM_PAGE_SIZE_IN_BYTES as u64 != 0 {
+                return Err(invalid(format!(
+                    "{} size {} is not a multiple of the Wasm page size {}",
+                    name, size, WASM_PAGE_SIZE_IN_BYTES
+                )));
+            }
+            if size > self.config.max_canister_memory_size.get() {
+                return Err(invalid(format!(
+                    "{} size {} exceeds the maximum canister memory size of {} bytes",
+                    name, size, self.config.max_canister_memory_size
+                )));
+            }
+            Ok(NumWasmPages::new(
+                (size / WASM_PAGE_SIZE_IN_BYTES as u64) as usize,
+            ))
+        };
+        Ok((
+            to_pages("Wasm memory", args.wasm_memory_size)?,
+            to_pages("Stable memory", args.stable_memory_size)?,
+        ))
+    }
+
+    /// Checks that the canister's memory usage can grow by `memory_increase`
+    /// bytes to `new_memory_usage` because of a new or growing snapshot:
+    /// the canister must not become frozen and the subnet must have enough
+    /// available memory. Reserves cycles if the subnet is becoming saturated.
+    ///
+    /// The caller is responsible for actually deducting the memory from the
+    /// subnet.
+    fn reserve_snapshot_memory(
+        &self,
+        canister: &mut CanisterState,
+        new_memory_usage: NumBytes,
+        memory_increase: NumBytes,
+        round_limits: &RoundLimits,
+        resource_saturation: &ResourceSaturation,
+        subnet_size: usize,
+    ) -> Result<(), CanisterManagerError> {
+        // Calculate if any cycles will need to be reserved.
+        let reservation_cycles = self.cycles_account_manager.storage_reservation_cycles(
+            memory_increase,
+            resource_saturation,
+            subnet_size,
+        );
+
+        // Memory usage will increase by the snapshot size.
+        // Check that it doesn't bump the canister over the freezing threshold.
+        let threshold = self.cycles_account_manager.freeze_threshold_cycles(
+            canister.system_state.freeze_threshold,
+            canister.memory_allocation(),
+            new_memory_usage,
+            canister.message_memory_usage(),
+            canister.compute_allocation(),
+            subnet_size,
+            canister.system_state.reserved_balance(),
+        );
+
+        if canister.system_state.balance() < threshold + reservation_cycles {
+            return Err(CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                bytes: memory_increase,
+                available: canister.system_state.balance(),
+                threshold,
+            });
+        }
+        // Verify that the subnet has enough memory for a new snapshot.
+        round_limits
+            .subnet_available_memory
+            .check_available_memory(memory_increase, NumBytes::from(0), NumBytes::from(0))
+            .map_err(
+                |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                    requested: memory_increase,
+                    available: NumBytes::from(
+                        round_limits
+                            .subnet_available_memory
+                            .get_execution_memory()
+                            .max(0) as u64,
+                    ),
+                },
+            )?;
+        // Reserve needed cycles if the subnet is becoming saturated.
+        canister
+            .system_state
+            .reserve_cycles(reservation_cycles)
+            .map_err(|err| match err {
+                ReservationError::InsufficientCycles {
+                    requested,
+                    available,
+                } => CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                    bytes: memory_increase,
+                    available,
+                    threshold: requested,
+                },
+                ReservationError::ReservedLimitExceed { requested, limit } => {
+                    CanisterManagerError::ReservedCyclesLimitExceededInMemoryGrow {
+                        bytes: memory_increase,
+                        requested,
+                        limit,
+                    }
+                }
+            })
+    }
+
+    /// Adds a new snapshot of the canister to the replicated state, deleting
+    /// the snapshot it replaces (given with its size), if any, and returns the
+    /// ID of the new snapshot.
+    ///
+    /// The memory for the new snapshot must have been checked to be available
+    /// through `reserve_snapshot_memory`.
+    fn insert_snapshot(
+        &self,
+        canister: &mut CanisterState,
+        new_snapshot: CanisterSnapshot,
+        replace_snapshot: Option<(SnapshotId, NumBytes)>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        source: SnapshotSource,
+    ) -> SnapshotId {
+        // Delete old snapshot identified by `replace_snapshot` ID.
+        if let Some((replace_snapshot, replace_snapshot_size)) = replace_snapshot {
+            state.canister_snapshots.remove(replace_snapshot);
+            canister.system_state.snapshots_memory_usage = canister
+                .system_state
+                .snapshots_memory_usage
+                .get()
+                .saturating_sub(replace_snapshot_size.get())
+                .into();
+            // Confirm that `snapshots_memory_usage` is updated correctly.
+            debug_assert_eq!(
+                canister.system_state.snapshots_memory_usage,
+                state
+                    .canister_snapshots
+                    .compute_memory_usage_by_canister(canister.canister_id()),
+            );
+            round_limits.subnet_available_memory.increment(
+                replace_snapshot_size,
+                NumBytes::from(0),
+                NumBytes::from(0),
+            );
+        }
+
+        let new_snapshot_size = new_snapshot.size();
+        // Actually deduct memory from the subnet. It's safe to unwrap
+        // here because we already checked the available memory above.
+        round_limits.subnet_available_memory
+            .try_decrement(new_snapshot_size, NumBytes::from(0), NumBytes::from(0))
+            .expect("Error: Cannot fail to decrement SubnetAvailableMemory after checking for availability");
+
+        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled {
+            canister.scheduler_state.heap_delta_debit = canister
+                .scheduler_state
+                .heap_delta_debit
+                .saturating_add(&new_snapshot.heap_delta());
+        }
+        state.metadata.heap_delta_estimate = state
+            .metadata
+            .heap_delta_estimate
+            .saturating_add(&new_snapshot.heap_delta());
+
+        let snapshot_id =
+            SnapshotId::from((canister.canister_id(), canister.new_local_snapshot_id()));
+        match source {
+            SnapshotSource::TakenFromCanister => state
+                .canister_snapshots
+                .push(snapshot_id, Arc::new(new_snapshot)),
+            SnapshotSource::MetadataUpload => state
+                .canister_snapshots
+                .push_uploaded(snapshot_id, Arc::new(new_snapshot)),
+        };
+        canister.system_state.snapshots_memory_usage = canister
+            .system_state
+            .snapshots_memory_usage
+            .saturating_add(&new_snapshot_size);
+        snapshot_id
+    }
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -2443,6 +2967,13 @@ pub(crate) enum CanisterManagerError {
         limit: usize,
     },
     CanisterSnapshotNotEnoughCycles(CanisterOutOfCyclesError),
+    CanisterSnapshotInvalidSubslice {
+        offset: u64,
+        size: u64,
+    },
+    CanisterSnapshotInvalidMetadata {
+        message: String,
+    },
     LongExecutionAlreadyInProgress {
         canister_id: CanisterId,
     },
@@ -2613,6 +3144,16 @@ impl AsErrorHelp for CanisterManagerError {
                 suggestion: "".to_string(),
                 doc_link: "".to_string(),
             },
+            CanisterManagerError::CanisterSnapshotInvalidSubslice { .. } => ErrorHelp::UserError {
+                suggestion: "Use the `read_canister_snapshot_metadata` API to check the sizes \
+                of the snapshot contents and request at most 2MB at a time."
+                    .to_string(),
+                doc_link: "".to_string(),
+            },
+            CanisterManagerError::CanisterSnapshotInvalidMetadata { .. } => ErrorHelp::UserError {
+                suggestion: "".to_string(),
+                doc_link: "".to_string(),
+            },
             CanisterManagerError::LongExecutionAlreadyInProgress { .. } => ErrorHelp::UserError {
                 suggestion: "Try waiting for the long execution to complete.".to_string(),
                 doc_link: doc_ref("long-execution-already-in-progress"),
@@ -2927,6 +3468,22 @@ impl From<CanisterManagerError> for UserError {
                     format!("Canister snapshotting failed with `{}`{additional_help}", err),
                 )
             }
+            CanisterSnapshotInvalidSubslice { offset, size } => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!(
+                        "Invalid subslice into snapshot data: offset {} and size {} are out of bounds or exceed the maximum slice size of {} bytes.{additional_help}", offset, size, MAX_SNAPSHOT_DATA_SLICE_SIZE,
+                    )
+                )
+            }
+            CanisterSnapshotInvalidMetadata { message } => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!(
+                        "Invalid snapshot metadata: {}{additional_help}", message
+                    )
+                )
+            }
             LongExecutionAlreadyInProgress { canister_id } => {
                 Self::new(
                     ErrorCode::CanisterRejectedMessage,
@@ -2978,6 +3535,55 @@ impl From<CanisterManagerError> for RejectContext {
     }
 }
 
+/// Returns the snapshot with the given ID if it exists and belongs to the
+/// given canister.
+fn get_snapshot_of_canister(
+    canister_id: CanisterId,
+    snapshot_id: SnapshotId,
+    state: &ReplicatedState,
+) -> Result<&Arc<CanisterSnapshot>, CanisterManagerError> {
+    match state.canister_snapshots.get(snapshot_id) {
+        // If not found, the operation fails due to invalid parameters.
+        None => Err(CanisterManagerError::CanisterSnapshotNotFound {
+            canister_id,
+            snapshot_id,
+        }),
+        Some(snapshot) => {
+            // Verify the provided `snapshot_id` belongs to this canister.
+            if snapshot.canister_id() != canister_id {
+                return Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                    canister_id,
+                    snapshot_id,
+                });
+            }
+            Ok(snapshot)
+        }
+    }
+}
+
+/// Checks that `[offset, offset + size)` lies within snapshot data of length
+/// `len` and that `size` does not exceed `MAX_SNAPSHOT_DATA_SLICE_SIZE`.
+fn validate_snapshot_data_range(
+    offset: u64,
+    size: u64,
+    len: u64,
+) -> Result<(), CanisterManagerError> {
+    match offset.checked_add(size) {
+        Some(end) if end <= len && size <= MAX_SNAPSHOT_DATA_SLICE_SIZE => Ok(()),
+        _ => Err(CanisterManagerError::CanisterSnapshotInvalidSubslice { offset, size }),
+    }
+}
+
+fn read_snapshot_memory(
+    memory: &PageMemory,
+    offset: u64,
+    size: u64,
+) -> Result<Vec<u8>, CanisterManagerError> {
+    let len = num_bytes_try_from(memory.size).map_or(0, |bytes| bytes.get());
+    validate_snapshot_data_range(offset, size, len)?;
+    Ok(memory.read(offset as usize, size as usize))
+}
+
 /// Uninstalls a canister.
 ///
 /// See https://internetcomputer.org/docs/current/references/ic-interface-spec#ic-uninstall_code
@@ -30,12 +30,12 @@ use ic_limits::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_logger::replica_logger::no_op_logger;
 use ic_management_canister_types::{
     CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterIdRecord,
-    CanisterInstallMode, CanisterInstallModeV2, CanisterSettingsArgsBuilder,
+    CanisterInstallMode, CanisterInstallModeV2, CanisterPriorityClass, CanisterSettingsArgsBuilder,
     CanisterStatusResultV2, CanisterStatusType, CanisterUpgradeOptions, ChunkHash,
-    ClearChunkStoreArgs, CreateCanisterArgs, EmptyBlob, InstallCodeArgsV2, Method,
-    NodeMetricsHistoryArgs, NodeMetricsHistoryResponse, Payload, StoredChunksArgs,
+    ClearChunkStoreArgs, CreateCanisterArgs, EmptyBlob, EnvironmentVariable, InstallCodeArgsV2,
+    Method, NodeMetricsHistoryArgs, NodeMetricsHistoryResponse, Payload, StoredChunksArgs,
     StoredChunksReply, SubnetInfoArgs, SubnetInfoResponse, UpdateSettingsArgs, UploadChunkArgs,
-    UploadChunkReply, WasmMemoryPersistence,
+    UploadChunkReply, WasmMemoryPersistence, MAX_WASM_TRACE_LENGTH,
 };
 use ic_metrics::MetricsRegistry;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
@@ -314,6 +314,7 @@ fn canister_manager_config(
         ic_config::embedders::Config::default().wasm_max_size,
         SchedulerConfig::application_subnet().canister_snapshot_baseline_instructions,
         DEFAULT_WASM_MEMORY_LIMIT,
+        FlagStatus::Disabled,
     )
 }
 
@@ -6988,6 +6989,148 @@ fn update_settings_can_set_reserved_cycles_limit() {
     );
 }
 
+#[test]
+fn update_settings_can_set_environment_variables() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let update_settings =
+        |test: &mut ExecutionTest, environment_variables: Vec<EnvironmentVariable>| {
+            let payload = UpdateSettingsArgs {
+                canister_id: canister_id.into(),
+                settings: CanisterSettingsArgsBuilder::new()
+                    .with_environment_variables(environment_variables)
+                    .build(),
+                sender_canister_version: None,
+            }
+            .encode();
+            test.subnet_message(Method::UpdateSettings, payload)
+        };
+
+    update_settings(
+        &mut test,
+        vec![
+            EnvironmentVariable::new("NETWORK", "mainnet"),
+            EnvironmentVariable::new("LEDGER", "ryjl3-tyaaa-aaaaa-aaaba-cai"),
+        ],
+    )
+    .unwrap();
+    let status =
+        CanisterStatusResultV2::decode(&get_reply(test.canister_status(canister_id))).unwrap();
+    // Variables are returned ordered by name.
+    assert_eq!(
+        status.settings().environment_variables(),
+        &[
+            EnvironmentVariable::new("LEDGER", "ryjl3-tyaaa-aaaaa-aaaba-cai"),
+            EnvironmentVariable::new("NETWORK", "mainnet"),
+        ]
+    );
+
+    // Duplicate names are rejected and leave the variables unchanged.
+    let err = update_settings(
+        &mut test,
+        vec![
+            EnvironmentVariable::new("NETWORK", "mainnet"),
+            EnvironmentVariable::new("NETWORK", "testnet"),
+        ],
+    )
+    .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .environment_variables
+            .len(),
+        2
+    );
+
+    // Setting an empty list removes all variables.
+    update_settings(&mut test, vec![]).unwrap();
+    assert!(test
+        .canister_state(canister_id)
+        .system_state
+        .environment_variables
+        .is_empty());
+}
+
+#[test]
+fn update_settings_wasm_trace_length_requires_wasm_tracing() {
+    let update_settings = |test: &mut ExecutionTest, canister_id: CanisterId, length: u64| {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_wasm_trace_length(length)
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode();
+        test.subnet_message(Method::UpdateSettings, payload)
+    };
+
+    // Wasm tracing is disabled by default, so only zero is accepted.
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let err = update_settings(&mut test, canister_id, 10).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    update_settings(&mut test, canister_id, 0).unwrap();
+
+    let mut test = ExecutionTestBuilder::new().with_wasm_tracing().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    update_settings(&mut test, canister_id, MAX_WASM_TRACE_LENGTH).unwrap();
+    let status =
+        CanisterStatusResultV2::decode(&get_reply(test.canister_status(canister_id))).unwrap();
+    assert_eq!(
+        status.settings().wasm_trace_length(),
+        candid::Nat::from(MAX_WASM_TRACE_LENGTH)
+    );
+
+    // Lengths above the maximum are rejected and leave the setting unchanged.
+    let err = update_settings(&mut test, canister_id, MAX_WASM_TRACE_LENGTH + 1).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_trace_length,
+        MAX_WASM_TRACE_LENGTH
+    );
+}
+
+#[test]
+fn update_settings_sets_priority_class() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let status =
+        CanisterStatusResultV2::decode(&get_reply(test.canister_status(canister_id))).unwrap();
+    assert_eq!(
+        status.settings().priority_class(),
+        CanisterPriorityClass::Standard
+    );
+
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.into(),
+        settings: CanisterSettingsArgsBuilder::new()
+            .with_priority_class(CanisterPriorityClass::Burst)
+            .build(),
+        sender_canister_version: None,
+    }
+    .encode();
+    test.subnet_message(Method::UpdateSettings, payload)
+        .unwrap();
+
+    assert_eq!(
+        test.canister_state(canister_id)
+            .scheduler_state
+            .priority_class,
+        CanisterPriorityClass::Burst
+    );
+    let status =
+        CanisterStatusResultV2::decode(&get_reply(test.canister_status(canister_id))).unwrap();
+    assert_eq!(
+        status.settings().priority_class(),
+        CanisterPriorityClass::Burst
+    );
+}
+
 #[test]
 fn canister_status_contains_reserved_cycles() {
     const CYCLES: Cycles = Cycles::new(1_000_000_000_000_000);
@@ -1,14 +1,19 @@
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, UserError};
 use ic_interfaces::execution_environment::SubnetAvailableMemory;
-use ic_management_canister_types::{CanisterSettingsArgs, LogVisibilityV2};
+use ic_management_canister_types::{
+    CanisterPriorityClass, CanisterSettingsArgs, LogVisibilityV2,
+    MAX_ENVIRONMENT_VARIABLE_NAME_SIZE, MAX_ENVIRONMENT_VARIABLE_VALUE_SIZE, MAX_WASM_TRACE_LENGTH,
+};
 use ic_types::{
-    ComputeAllocation, Cycles, InvalidComputeAllocationError, InvalidMemoryAllocationError,
-    MemoryAllocation, PrincipalId,
+    canister_log::CanisterLog, ComputeAllocation, Cycles, InvalidComputeAllocationError,
+    InvalidMemoryAllocationError, MemoryAllocation, PrincipalId, MAX_CANISTER_LOG_BUFFER_SIZE,
+    MIN_CANISTER_LOG_BUFFER_SIZE,
 };
 use num_traits::cast::ToPrimitive;
-use std::convert::TryFrom;
+use std::{collections::BTreeMap, convert::TryFrom};
 
 use crate::canister_manager::CanisterManagerError;
 
@@ -27,6 +32,14 @@ pub(crate) struct CanisterSettings {
     pub(crate) reserved_cycles_limit: Option<Cycles>,
     pub(crate) log_visibility: Option<LogVisibilityV2>,
     pub(crate) wasm_memory_limit: Option<NumBytes>,
+    /// Size of the canister log buffer.
+    pub(crate) log_buffer_size: Option<NumBytes>,
+    /// Environment variables replacing the existing ones, keyed by name.
+    pub(crate) environment_variables: Option<BTreeMap<String, String>>,
+    /// Number of most recent system API calls recorded for debugging traps.
+    pub(crate) wasm_trace_length: Option<u64>,
+    /// Scheduling priority class.
+    pub(crate) priority_class: Option<CanisterPriorityClass>,
 }
 
 impl CanisterSettings {
@@ -39,6 +52,10 @@ impl CanisterSettings {
         reserved_cycles_limit: Option<Cycles>,
         log_visibility: Option<LogVisibilityV2>,
         wasm_memory_limit: Option<NumBytes>,
+        log_buffer_size: Option<NumBytes>,
+        environment_variables: Option<BTreeMap<String, String>>,
+        wasm_trace_length: Option<u64>,
+        priority_class: Option<CanisterPriorityClass>,
     ) -> Self {
         Self {
             controllers,
@@ -49,6 +66,10 @@ impl CanisterSettings {
             reserved_cycles_limit,
             log_visibility,
             wasm_memory_limit,
+            log_buffer_size,
+            environment_variables,
+            wasm_trace_length,
+            priority_class,
         }
     }
 
@@ -83,6 +104,22 @@ impl CanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn log_buffer_size(&self) -> Option<NumBytes> {
+        self.log_buffer_size
+    }
+
+    pub fn environment_variables(&self) -> Option<&BTreeMap<String, String>> {
+        self.environment_variables.as_ref()
+    }
+
+    pub fn wasm_trace_length(&self) -> Option<u64> {
+        self.wasm_trace_length
+    }
+
+    pub fn priority_class(&self) -> Option<CanisterPriorityClass> {
+        self.priority_class
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -146,6 +183,61 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             None => None,
         };
 
+        let log_buffer_size = match input.log_buffer_size {
+            Some(size) => {
+                let size = size
+                    .0
+                    .to_u64()
+                    .filter(|size| {
+                        (MIN_CANISTER_LOG_BUFFER_SIZE as u64..=MAX_CANISTER_LOG_BUFFER_SIZE as u64)
+                            .contains(size)
+                    })
+                    .ok_or(UpdateSettingsError::LogBufferSizeOutOfRange { provided: size })?;
+                Some(NumBytes::new(size))
+            }
+            None => None,
+        };
+
+        let environment_variables = match input.environment_variables {
+            Some(variables) => {
+                let mut environment_variables = BTreeMap::new();
+                for variable in variables.get() {
+                    if variable.name.len() > MAX_ENVIRONMENT_VARIABLE_NAME_SIZE {
+                        return Err(UpdateSettingsError::EnvironmentVariableNameTooLarge {
+                            size: variable.name.len(),
+                        });
+                    }
+                    if variable.value.len() > MAX_ENVIRONMENT_VARIABLE_VALUE_SIZE {
+                        return Err(UpdateSettingsError::EnvironmentVariableValueTooLarge {
+                            name: variable.name.clone(),
+                            size: variable.value.len(),
+                        });
+                    }
+                    if environment_variables
+                        .insert(variable.name.clone(), variable.value.clone())
+                        .is_some()
+                    {
+                        return Err(UpdateSettingsError::DuplicateEnvironmentVariable {
+                            name: variable.name.clone(),
+                        });
+                    }
+                }
+                Some(environment_variables)
+            }
+            None => None,
+        };
+
+        let wasm_trace_length = match input.wasm_trace_length {
+            Some(length) => Some(
+                length
+                    .0
+                    .to_u64()
+                    .filter(|length| *length <= MAX_WASM_TRACE_LENGTH)
+                    .ok_or(UpdateSettingsError::WasmTraceLengthOutOfRange { provided: length })?,
+            ),
+            None => None,
+        };
+
         Ok(CanisterSettings::new(
             input
                 .controllers
@@ -157,6 +249,10 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             reserved_cycles_limit,
             input.log_visibility,
             wasm_memory_limit,
+            log_buffer_size,
+            environment_variables,
+            wasm_trace_length,
+            input.priority_class,
         ))
     }
 }
@@ -181,6 +277,10 @@ pub(crate) struct CanisterSettingsBuilder {
     reserved_cycles_limit: Option<Cycles>,
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<NumBytes>,
+    log_buffer_size: Option<NumBytes>,
+    environment_variables: Option<BTreeMap<String, String>>,
+    wasm_trace_length: Option<u64>,
+    priority_class: Option<CanisterPriorityClass>,
 }
 
 #[allow(dead_code)]
@@ -195,6 +295,10 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: None,
             log_visibility: None,
             wasm_memory_limit: None,
+            log_buffer_size: None,
+            environment_variables: None,
+            wasm_trace_length: None,
+            priority_class: None,
         }
     }
 
@@ -208,6 +312,10 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
+            log_buffer_size: self.log_buffer_size,
+            environment_variables: self.environment_variables,
+            wasm_trace_length: self.wasm_trace_length,
+            priority_class: self.priority_class,
         }
     }
 
@@ -266,6 +374,37 @@ impl CanisterSettingsBuilder {
             ..self
         }
     }
+
+    pub fn with_log_buffer_size(self, log_buffer_size: NumBytes) -> Self {
+        Self {
+            log_buffer_size: Some(log_buffer_size),
+            ..self
+        }
+    }
+
+    pub fn with_environment_variables(
+        self,
+        environment_variables: BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            environment_variables: Some(environment_variables),
+            ..self
+        }
+    }
+
+    pub fn with_wasm_trace_length(self, wasm_trace_length: u64) -> Self {
+        Self {
+            wasm_trace_length: Some(wasm_trace_length),
+            ..self
+        }
+    }
+
+    pub fn with_priority_class(self, priority_class: CanisterPriorityClass) -> Self {
+        Self {
+            priority_class: Some(priority_class),
+            ..self
+        }
+    }
 }
 
 pub enum UpdateSettingsError {
@@ -275,6 +414,11 @@ pub enum UpdateSettingsError {
     ReservedCyclesLimitOutOfRange { provided: candid::Nat },
     WasmMemoryLimitOutOfRange { provided: candid::Nat },
     WasmMemoryThresholdOutOfRange { provided: candid::Nat },
+    LogBufferSizeOutOfRange { provided: candid::Nat },
+    EnvironmentVariableNameTooLarge { size: usize },
+    EnvironmentVariableValueTooLarge { name: String, size: usize },
+    DuplicateEnvironmentVariable { name: String },
+    WasmTraceLengthOutOfRange { provided: candid::Nat },
 }
 
 impl From<UpdateSettingsError> for UserError {
@@ -324,6 +468,38 @@ impl From<UpdateSettingsError> for UserError {
                     provided
                 ),
             ),
+            UpdateSettingsError::LogBufferSizeOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Log buffer size expected to be in the range of [{}..{}], got {}",
+                    MIN_CANISTER_LOG_BUFFER_SIZE, MAX_CANISTER_LOG_BUFFER_SIZE, provided
+                ),
+            ),
+            UpdateSettingsError::EnvironmentVariableNameTooLarge { size } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Environment variable name expected to be at most {} bytes, got {}",
+                    MAX_ENVIRONMENT_VARIABLE_NAME_SIZE, size
+                ),
+            ),
+            UpdateSettingsError::EnvironmentVariableValueTooLarge { name, size } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Value of environment variable {} expected to be at most {} bytes, got {}",
+                    name, MAX_ENVIRONMENT_VARIABLE_VALUE_SIZE, size
+                ),
+            ),
+            UpdateSettingsError::DuplicateEnvironmentVariable { name } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!("Environment variable {} is set more than once", name),
+            ),
+            UpdateSettingsError::WasmTraceLengthOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Wasm trace length expected to be in the range of [0..{}], got {}",
+                    MAX_WASM_TRACE_LENGTH, provided
+                ),
+            ),
         }
     }
 }
@@ -350,6 +526,10 @@ pub(crate) struct ValidatedCanisterSettings {
     reservation_cycles: Cycles,
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<NumBytes>,
+    log_buffer_size: Option<NumBytes>,
+    environment_variables: Option<BTreeMap<String, String>>,
+    wasm_trace_length: Option<u64>,
+    priority_class: Option<CanisterPriorityClass>,
 }
 
 impl ValidatedCanisterSettings {
@@ -388,11 +568,30 @@ impl ValidatedCanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn log_buffer_size(&self) -> Option<NumBytes> {
+        self.log_buffer_size
+    }
+
+    pub fn environment_variables(&self) -> Option<&BTreeMap<String, String>> {
+        self.environment_variables.as_ref()
+    }
+
+    pub fn wasm_trace_length(&self) -> Option<u64> {
+        self.wasm_trace_length
+    }
+
+    pub fn priority_class(&self) -> Option<CanisterPriorityClass> {
+        self.priority_class
+    }
 }
 
 /// Validates the new canisters settings:
 /// - memory allocation:
 ///     - it cannot be lower than the current canister memory usage.
+///     - it cannot be lower than the canister memory usage after resizing
+///       the log buffer, whose part exceeding the default size is charged
+///       to the canister.
 ///     - there must be enough available subnet capacity for the change.
 ///     - there must be enough cycles for storage reservation.
 ///     - there must be enough cycles to avoid freezing the canister.
@@ -401,12 +600,15 @@ impl ValidatedCanisterSettings {
 ///     - there must be enough cycles to avoid freezing the canister.
 /// - controllers:
 ///     - the number of controllers cannot exceed the given maximum.
+/// - Wasm tracing:
+///     - it can only be enabled if the subnet allows Wasm tracing.
 ///
 /// Keep this function in sync with `do_update_settings()`.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn validate_canister_settings(
     settings: CanisterSettings,
     canister_memory_usage: NumBytes,
+    canister_log_memory_usage: NumBytes,
     canister_message_memory_usage: NumBytes,
     canister_memory_allocation: MemoryAllocation,
     subnet_available_memory: &SubnetAvailableMemory,
@@ -415,6 +617,7 @@ pub(crate) fn validate_canister_settings(
     subnet_compute_allocation_usage: u64,
     subnet_compute_allocation_capacity: u64,
     max_controllers: usize,
+    wasm_tracing: FlagStatus,
     canister_freezing_threshold: NumSeconds,
     canister_cycles_balance: Cycles,
     cycles_account_manager: &CyclesAccountManager,
@@ -423,21 +626,33 @@ pub(crate) fn validate_canister_settings(
     canister_reserved_balance_limit: Option<Cycles>,
 ) -> Result<ValidatedCanisterSettings, CanisterManagerError> {
     let old_memory_bytes = canister_memory_allocation.allocated_bytes(canister_memory_usage);
-    let new_memory_bytes = match settings.memory_allocation {
+    let new_memory_usage = match settings.log_buffer_size {
         None => canister_memory_usage,
-        Some(new_memory_allocation) => {
-            // The new memory allocation cannot be lower than the current canister
-            // memory usage.
-            if let MemoryAllocation::Reserved(reserved_bytes) = new_memory_allocation {
-                if reserved_bytes < canister_memory_usage {
-                    return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
-                        memory_allocation_given: new_memory_allocation,
-                        memory_usage_needed: canister_memory_usage,
-                    });
-                }
+        Some(log_buffer_size) => {
+            canister_memory_usage - canister_log_memory_usage
+                + CanisterLog::memory_usage_for_capacity(log_buffer_size.get() as usize)
+        }
+    };
+    let new_memory_allocation = settings
+        .memory_allocation
+        .unwrap_or(canister_memory_allocation);
+
+    // The new memory allocation cannot be lower than the new canister memory
+    // usage. The check also applies to the current memory allocation if the
+    // memory usage grows because of a larger log buffer.
+    if settings.memory_allocation.is_some() || new_memory_usage > canister_memory_usage {
+        if let MemoryAllocation::Reserved(reserved_bytes) = new_memory_allocation {
+            if reserved_bytes < new_memory_usage {
+                return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                    memory_allocation_given: new_memory_allocation,
+                    memory_usage_needed: new_memory_usage,
+                });
             }
-            new_memory_allocation.allocated_bytes(canister_memory_usage)
         }
+    }
+    let new_memory_bytes = match settings.memory_allocation {
+        None => new_memory_usage,
+        Some(new_memory_allocation) => new_memory_allocation.allocated_bytes(new_memory_usage),
     };
 
     // If the available memory in the subnet is negative, then we must cap
@@ -484,6 +699,16 @@ pub(crate) fn validate_canister_settings(
         }
     }
 
+    if let Some(wasm_trace_length) = settings.wasm_trace_length() {
+        if wasm_trace_length > 0 && wasm_tracing == FlagStatus::Disabled {
+            return Err(CanisterManagerError::InvalidSettings {
+                message: "Invalid settings: 'wasm_trace_length' cannot be set because \
+                    Wasm tracing is not enabled on this subnet."
+                    .to_string(),
+            });
+        }
+    }
+
     if let Some(wasm_memory_limit) = settings.wasm_memory_limit() {
         if let Some(wasm_memory_threshold) = settings.wasm_memory_threshold() {
             if wasm_memory_threshold > wasm_memory_limit {
@@ -494,10 +719,6 @@ pub(crate) fn validate_canister_settings(
         }
     }
 
-    let new_memory_allocation = settings
-        .memory_allocation
-        .unwrap_or(canister_memory_allocation);
-
     let new_compute_allocation = settings
         .compute_allocation()
         .unwrap_or(canister_compute_allocation);
@@ -509,7 +730,7 @@ pub(crate) fn validate_canister_settings(
     let threshold = cycles_account_manager.freeze_threshold_cycles(
         freezing_threshold,
         new_memory_allocation,
-        canister_memory_usage,
+        new_memory_usage,
         canister_message_memory_usage,
         new_compute_allocation,
         subnet_size,
@@ -595,5 +816,9 @@ pub(crate) fn validate_canister_settings(
         reservation_cycles,
         log_visibility: settings.log_visibility().cloned(),
         wasm_memory_limit: settings.wasm_memory_limit(),
+        log_buffer_size: settings.log_buffer_size(),
+        environment_variables: settings.environment_variables,
+        wasm_trace_length: settings.wasm_trace_length,
+        priority_class: settings.priority_class,
     })
 }
@@ -23,6 +23,7 @@ use ic_system_api::ApiType;
 use ic_types::funds::Cycles;
 use ic_types::messages::{CanisterCall, RequestMetadata};
 use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
+use std::collections::BTreeMap;
 
 /// Installs a new code in canister. The algorithm consists of five stages:
 /// - Stage 0: validate input.
@@ -89,6 +90,7 @@ pub(crate) fn execute_install(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
 
@@ -109,6 +111,7 @@ pub(crate) fn execute_install(
                 round,
                 err,
                 helper.take_canister_log(),
+                helper.take_system_method_instructions(),
             );
         }
     };
@@ -137,6 +140,7 @@ pub(crate) fn execute_install(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
     helper.clear_certified_data();
@@ -229,8 +233,13 @@ fn install_stage_2a_process_start_result(
     round: RoundContext,
     round_limits: &mut RoundLimits,
 ) -> DtsInstallCodeResult {
-    let (instructions_consumed, result) =
-        helper.handle_wasm_execution(canister_state_changes, output, &original, &round);
+    let (instructions_consumed, result) = helper.handle_wasm_execution(
+        SystemMethod::CanisterStart,
+        canister_state_changes,
+        output,
+        &original,
+        &round,
+    );
 
     info!(
         round.log,
@@ -249,6 +258,7 @@ fn install_stage_2a_process_start_result(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
 
@@ -342,8 +352,13 @@ fn install_stage_3_process_init_result(
     round: RoundContext,
     round_limits: &mut RoundLimits,
 ) -> DtsInstallCodeResult {
-    let (instructions_consumed, result) =
-        helper.handle_wasm_execution(canister_state_changes, output, &original, &round);
+    let (instructions_consumed, result) = helper.handle_wasm_execution(
+        SystemMethod::CanisterInit,
+        canister_state_changes,
+        output,
+        &original,
+        &round,
+    );
     info!(
         round.log,
         "Executing (canister_init) on canister {} consumed {} instructions.  {} instructions are left.",
@@ -360,6 +375,7 @@ fn install_stage_3_process_init_result(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
     helper.finish(clean_canister, original, round, round_limits)
@@ -409,6 +425,7 @@ impl PausedInstallCodeExecution for PausedInitExecution {
                     round,
                     err,
                     new_canister_log,
+                    BTreeMap::new(),
                 );
             }
         };
@@ -512,6 +529,7 @@ impl PausedInstallCodeExecution for PausedStartExecutionDuringInstall {
                     round,
                     err,
                     new_canister_log,
+                    BTreeMap::new(),
                 );
             }
         };
@@ -1,6 +1,7 @@
 // This module defines types and functions common between canister installation
 // and upgrades.
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use ic_base_types::{CanisterId, NumBytes, PrincipalId};
@@ -20,8 +21,8 @@ use ic_state_layout::{CanisterLayout, CheckpointLayout, ReadOnly};
 use ic_sys::PAGE_SIZE;
 use ic_system_api::ExecutionParameters;
 use ic_types::{
-    funds::Cycles, messages::CanisterCall, CanisterLog, CanisterTimer, ComputeAllocation, Height,
-    MemoryAllocation, NumInstructions, Time,
+    funds::Cycles, messages::CanisterCall, methods::SystemMethod, CanisterLog, CanisterTimer,
+    ComputeAllocation, Height, MemoryAllocation, NumInstructions, Time,
 };
 use ic_wasm_types::WasmHash;
 
@@ -83,6 +84,7 @@ pub(crate) enum InstallCodeStep {
         module_hash: WasmHash,
     },
     HandleWasmExecution {
+        method: SystemMethod,
         canister_state_changes: Option<CanisterStateChanges>,
         output: WasmExecutionOutput,
     },
@@ -118,6 +120,8 @@ pub(crate) struct InstallCodeHelper {
     deallocated_wasm_custom_sections_bytes: NumBytes,
     // The total heap delta of all steps.
     total_heap_delta: NumBytes,
+    // The instructions executed by each system method.
+    system_method_instructions: BTreeMap<SystemMethod, NumInstructions>,
 }
 
 impl InstallCodeHelper {
@@ -133,6 +137,7 @@ impl InstallCodeHelper {
             deallocated_bytes: NumBytes::from(0),
             deallocated_wasm_custom_sections_bytes: NumBytes::from(0),
             total_heap_delta: NumBytes::from(0),
+            system_method_instructions: BTreeMap::new(),
         }
     }
 
@@ -305,6 +310,7 @@ impl InstallCodeHelper {
                     round,
                     CanisterManagerError::Hypervisor(self.canister.canister_id(), err),
                     self.take_canister_log(),
+                    self.take_system_method_instructions(),
                 );
             }
         }
@@ -349,6 +355,7 @@ impl InstallCodeHelper {
                         round,
                         err,
                         self.take_canister_log(),
+                        self.take_system_method_instructions(),
                     );
                 }
             }
@@ -375,6 +382,7 @@ impl InstallCodeHelper {
                     round,
                     err,
                     self.take_canister_log(),
+                    self.take_system_method_instructions(),
                 );
             }
         }
@@ -419,6 +427,7 @@ impl InstallCodeHelper {
                         round,
                         err,
                         self.take_canister_log(),
+                        self.take_system_method_instructions(),
                     );
                 }
             }
@@ -442,6 +451,7 @@ impl InstallCodeHelper {
                         available: available.max(old_compute_allocation.as_percent()),
                     },
                     self.take_canister_log(),
+                    self.take_system_method_instructions(),
                 );
             }
             round_limits.compute_allocation_used = others + new_compute_allocation.as_percent();
@@ -483,6 +493,7 @@ impl InstallCodeHelper {
             message: original.message,
             call_id: original.call_id,
             instructions_used,
+            system_method_instructions: self.system_method_instructions,
             result: Ok(InstallCodeResult {
                 heap_delta: self.total_heap_delta,
                 old_wasm_hash,
@@ -515,8 +526,13 @@ impl InstallCodeHelper {
                 reserved_cycles_limit: None,
                 log_visibility: None,
                 wasm_memory_limit: None,
+                log_buffer_size: None,
+                environment_variables: None,
+                wasm_trace_length: None,
+                priority_class: None,
             },
             self.canister.memory_usage(),
+            self.canister.canister_log_memory_usage(),
             self.canister.message_memory_usage(),
             self.canister.memory_allocation(),
             &round_limits.subnet_available_memory,
@@ -525,6 +541,7 @@ impl InstallCodeHelper {
             round_limits.compute_allocation_used,
             original.config.compute_capacity,
             original.config.max_controllers,
+            original.config.wasm_tracing,
             self.canister.system_state.freeze_threshold,
             self.canister.system_state.balance(),
             round.cycles_account_manager,
@@ -677,12 +694,20 @@ impl InstallCodeHelper {
         std::mem::take(&mut self.canister.system_state.canister_log)
     }
 
+    /// Takes the instructions executed by each system method so far.
+    pub(crate) fn take_system_method_instructions(
+        &mut self,
+    ) -> BTreeMap<SystemMethod, NumInstructions> {
+        std::mem::take(&mut self.system_method_instructions)
+    }
+
     /// Checks the result of Wasm execution and applies the state changes.
     ///
     /// Returns the amount of instructions consumed along with the result of
     /// applying the state changes.
     pub fn handle_wasm_execution(
         &mut self,
+        method: SystemMethod,
         canister_state_changes: Option<CanisterStateChanges>,
         mut output: WasmExecutionOutput,
         original: &OriginalContext,
@@ -693,6 +718,7 @@ impl InstallCodeHelper {
             .canister_log
             .append(&mut output.canister_log);
         self.steps.push(InstallCodeStep::HandleWasmExecution {
+            method: method.clone(),
             canister_state_changes: canister_state_changes.clone(),
             output: output.clone(),
         });
@@ -704,6 +730,10 @@ impl InstallCodeHelper {
                 .get()
                 .saturating_sub(output.num_instructions_left.get()),
         );
+        *self
+            .system_method_instructions
+            .entry(method)
+            .or_insert(NumInstructions::new(0)) += instructions_consumed;
 
         self.execution_parameters
             .instruction_limits
@@ -842,11 +872,17 @@ impl InstallCodeHelper {
                 Ok(())
             }
             InstallCodeStep::HandleWasmExecution {
+                method,
                 canister_state_changes,
                 output,
             } => {
-                let (_, result) =
-                    self.handle_wasm_execution(canister_state_changes, output, original, round);
+                let (_, result) = self.handle_wasm_execution(
+                    method,
+                    canister_state_changes,
+                    output,
+                    original,
+                    round,
+                );
                 result
             }
             InstallCodeStep::ChargeForLargeWasmAssembly { instructions } => {
@@ -917,6 +953,9 @@ pub(crate) fn canister_layout(
 /// The only state changes applied to the clean canister state:
 ///  - saving the new canister log
 ///  - refunding the prepaid execution cycles
+///
+/// The instructions executed by the system methods before the error are
+/// reported in the result.
 pub(crate) fn finish_err(
     clean_canister: CanisterState,
     instructions_left: NumInstructions,
@@ -924,6 +963,7 @@ pub(crate) fn finish_err(
     round: RoundContext,
     err: CanisterManagerError,
     new_canister_log: CanisterLog,
+    system_method_instructions: BTreeMap<SystemMethod, NumInstructions>,
 ) -> DtsInstallCodeResult {
     let mut new_canister = clean_canister;
 
@@ -964,6 +1004,7 @@ pub(crate) fn finish_err(
         message: original.message,
         call_id: original.call_id,
         instructions_used,
+        system_method_instructions,
         result: Err(err),
     }
 }
@@ -31,6 +31,7 @@ use ic_types::{
     funds::Cycles,
     messages::{CanisterCall, RequestMetadata},
 };
+use std::collections::BTreeMap;
 
 use super::install_code::MemoryHandling;
 
@@ -113,6 +114,7 @@ pub(crate) fn execute_upgrade(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
 
@@ -130,6 +132,7 @@ pub(crate) fn execute_upgrade(
                 round,
                 (canister_id, HypervisorError::WasmModuleNotFound).into(),
                 helper.take_canister_log(),
+                helper.take_system_method_instructions(),
             );
         }
     };
@@ -218,8 +221,13 @@ fn upgrade_stage_1_process_pre_upgrade_result(
     round: RoundContext,
     round_limits: &mut RoundLimits,
 ) -> DtsInstallCodeResult {
-    let (instructions_consumed, result) =
-        helper.handle_wasm_execution(canister_state_changes, output, &original, &round);
+    let (instructions_consumed, result) = helper.handle_wasm_execution(
+        SystemMethod::CanisterPreUpgrade,
+        canister_state_changes,
+        output,
+        &original,
+        &round,
+    );
 
     info!(
         round.log,
@@ -238,6 +246,7 @@ fn upgrade_stage_1_process_pre_upgrade_result(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
 
@@ -276,6 +285,7 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
                 round,
                 err,
                 helper.take_canister_log(),
+                helper.take_system_method_instructions(),
             );
         }
     };
@@ -308,6 +318,7 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
                 round,
                 err,
                 helper.take_canister_log(),
+                helper.take_system_method_instructions(),
             );
         }
     };
@@ -331,6 +342,7 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
 
@@ -423,8 +435,13 @@ fn upgrade_stage_3b_process_start_result(
     round: RoundContext,
     round_limits: &mut RoundLimits,
 ) -> DtsInstallCodeResult {
-    let (instructions_consumed, result) =
-        helper.handle_wasm_execution(canister_state_changes, output, &original, &round);
+    let (instructions_consumed, result) = helper.handle_wasm_execution(
+        SystemMethod::CanisterStart,
+        canister_state_changes,
+        output,
+        &original,
+        &round,
+    );
 
     info!(
         round.log,
@@ -443,6 +460,7 @@ fn upgrade_stage_3b_process_start_result(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
 
@@ -539,8 +557,13 @@ fn upgrade_stage_4b_process_post_upgrade_result(
     round: RoundContext,
     round_limits: &mut RoundLimits,
 ) -> DtsInstallCodeResult {
-    let (instructions_consumed, result) =
-        helper.handle_wasm_execution(canister_state_changes, output, &original, &round);
+    let (instructions_consumed, result) = helper.handle_wasm_execution(
+        SystemMethod::CanisterPostUpgrade,
+        canister_state_changes,
+        output,
+        &original,
+        &round,
+    );
     info!(
         round.log,
         "Executing (canister_post_upgrade) on canister {} consumed {} instructions.  {} instructions are left.",
@@ -557,6 +580,7 @@ fn upgrade_stage_4b_process_post_upgrade_result(
             round,
             err,
             helper.take_canister_log(),
+            helper.take_system_method_instructions(),
         );
     }
     helper.finish(clean_canister, original, round, round_limits)
@@ -608,6 +632,7 @@ impl PausedInstallCodeExecution for PausedPreUpgradeExecution {
                     round,
                     err,
                     new_canister_log,
+                    BTreeMap::new(),
                 );
             }
         };
@@ -713,6 +738,7 @@ impl PausedInstallCodeExecution for PausedStartExecutionDuringUpgrade {
                     round,
                     err,
                     new_canister_log,
+                    BTreeMap::new(),
                 );
             }
         };
@@ -817,6 +843,7 @@ impl PausedInstallCodeExecution for PausedPostUpgradeExecution {
                     round,
                     err,
                     new_canister_log,
+                    BTreeMap::new(),
                 );
             }
         };
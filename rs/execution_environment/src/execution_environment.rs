@@ -40,9 +40,11 @@ use ic_management_canister_types::{
     EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgsV2, ListCanisterSnapshotArgs,
     LoadCanisterSnapshotArgs, MasterPublicKeyId, Method as Ic00Method, NodeMetricsHistoryArgs,
     Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
-    SchnorrPublicKeyArgs, SchnorrPublicKeyResponse, SetupInitialDKGArgs, SignWithECDSAArgs,
-    SignWithSchnorrArgs, SignWithSchnorrAux, StoredChunksArgs, SubnetInfoArgs, SubnetInfoResponse,
-    TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs,
+    ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotMetadataArgs, SchnorrPublicKeyArgs,
+    SchnorrPublicKeyResponse, SetupInitialDKGArgs, SignWithECDSAArgs, SignWithSchnorrArgs,
+    SignWithSchnorrAux, StoredChunksArgs, SubnetInfoArgs, SubnetInfoResponse,
+    TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs,
+    UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs, UploadChunkArgs,
     VetKdPublicKeyArgs, VetKdPublicKeyResult, IC_00,
 };
 use ic_metrics::MetricsRegistry;
@@ -316,6 +318,17 @@ struct PausedExecutionRegistry {
     paused_install_code: HashMap<PausedExecutionId, Box<dyn PausedInstallCodeExecution>>,
 }
 
+/// The outcome of [`ExecutionEnvironment::dry_run_install_code`].
+#[derive(Debug)]
+pub struct InstallCodeDryRunResult {
+    /// The instructions executed by each system method (e.g.
+    /// `canister_pre_upgrade`). System methods that were not executed are
+    /// missing.
+    pub system_method_instructions: BTreeMap<SystemMethod, NumInstructions>,
+    /// The result of the `install_code` message.
+    pub result: Result<(), UserError>,
+}
+
 // The replies that can be returned for a `stop_canister` request.
 #[derive(Eq, PartialEq, Debug)]
 enum StopCanisterReply {
@@ -408,6 +421,7 @@ impl ExecutionEnvironment {
             config.embedders_config.wasm_max_size,
             canister_snapshot_baseline_instructions,
             config.default_wasm_memory_limit,
+            config.embedders_config.feature_flags.wasm_tracing,
         );
         let metrics = ExecutionEnvironmentMetrics::new(metrics_registry);
         let canister_manager = CanisterManager::new(
@@ -1548,6 +1562,92 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::ReadCanisterSnapshotMetadata) => {
+                let res = ReadCanisterSnapshotMetadataArgs::decode(payload)
+                    .and_then(|args| self.read_snapshot_metadata(*msg.sender(), &state, args));
+                ExecuteSubnetMessageResult::Finished {
+                    response: res,
+                    refund: msg.take_cycles(),
+                }
+            }
+
+            Ok(Ic00Method::ReadCanisterSnapshotData) => {
+                match ReadCanisterSnapshotDataArgs::decode(payload) {
+                    Err(err) => ExecuteSubnetMessageResult::Finished {
+                        response: Err(err),
+                        refund: msg.take_cycles(),
+                    },
+                    Ok(args) => {
+                        let (result, instructions_used) = self.read_snapshot_data(
+                            *msg.sender(),
+                            &mut state,
+                            args,
+                            registry_settings.subnet_size,
+                        );
+                        let msg_result = ExecuteSubnetMessageResult::Finished {
+                            response: result,
+                            refund: msg.take_cycles(),
+                        };
+
+                        let state =
+                            self.finish_subnet_message_execution(state, msg, msg_result, since);
+                        return (state, Some(instructions_used));
+                    }
+                }
+            }
+
+            Ok(Ic00Method::UploadCanisterSnapshotMetadata) => {
+                match UploadCanisterSnapshotMetadataArgs::decode(payload) {
+                    Err(err) => ExecuteSubnetMessageResult::Finished {
+                        response: Err(err),
+                        refund: msg.take_cycles(),
+                    },
+                    Ok(args) => {
+                        let (result, instructions_used) = self.upload_snapshot_metadata(
+                            *msg.sender(),
+                            &mut state,
+                            args,
+                            registry_settings.subnet_size,
+                            round_limits,
+                        );
+                        let msg_result = ExecuteSubnetMessageResult::Finished {
+                            response: result,
+                            refund: msg.take_cycles(),
+                        };
+
+                        let state =
+                            self.finish_subnet_message_execution(state, msg, msg_result, since);
+                        return (state, Some(instructions_used));
+                    }
+                }
+            }
+
+            Ok(Ic00Method::UploadCanisterSnapshotData) => {
+                match UploadCanisterSnapshotDataArgs::decode(payload) {
+                    Err(err) => ExecuteSubnetMessageResult::Finished {
+                        response: Err(err),
+                        refund: msg.take_cycles(),
+                    },
+                    Ok(args) => {
+                        let (result, instructions_used) = self.upload_snapshot_data(
+                            *msg.sender(),
+                            &mut state,
+                            args,
+                            registry_settings.subnet_size,
+                            round_limits,
+                        );
+                        let msg_result = ExecuteSubnetMessageResult::Finished {
+                            response: result,
+                            refund: msg.take_cycles(),
+                        };
+
+                        let state =
+                            self.finish_subnet_message_execution(state, msg, msg_result, since);
+                        return (state, Some(instructions_used));
+                    }
+                }
+            }
+
             Err(ParseError::VariantNotFound) => {
                 let res = Err(UserError::new(
                     ErrorCode::CanisterMethodNotFound,
@@ -1565,6 +1665,9 @@ impl ExecutionEnvironment {
         //   - `InstallChunkedCode`
         //   - `TakeCanisterSnapshot`
         //   - `LoadCanisterSnapshot`
+        //   - `ReadCanisterSnapshotData`
+        //   - `UploadCanisterSnapshotMetadata`
+        //   - `UploadCanisterSnapshotData`
         //   - `SignWithECDSA`
         // If you modify code below, please also update
         // these cases.
@@ -2244,6 +2347,150 @@ impl ExecutionEnvironment {
         result
     }
 
+    /// Returns the metadata of the specified canister snapshot.
+    fn read_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        state: &ReplicatedState,
+        args: ReadCanisterSnapshotMetadataArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = get_canister(args.get_canister_id(), state)?;
+
+        self.canister_manager
+            .read_snapshot_metadata(sender, args.get_snapshot_id(), canister, state)
+            .map(|response| response.encode())
+            .map_err(UserError::from)
+    }
+
+    /// Returns a piece of the data of the specified canister snapshot.
+    fn read_snapshot_data(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: ReadCanisterSnapshotDataArgs,
+        subnet_size: usize,
+    ) -> (Result<Vec<u8>, UserError>, NumInstructions) {
+        let canister_id = args.get_canister_id();
+        // Take canister out.
+        let mut canister = match state.take_canister_state(&canister_id) {
+            None => {
+                return (
+                    Err(UserError::new(
+                        ErrorCode::CanisterNotFound,
+                        format!("Canister {} not found.", &canister_id),
+                    )),
+                    NumInstructions::new(0),
+                )
+            }
+            Some(canister) => canister,
+        };
+
+        let snapshot_id = args.get_snapshot_id();
+        let (result, instructions_used) = self.canister_manager.read_snapshot_data(
+            sender,
+            snapshot_id,
+            args.kind,
+            &mut canister,
+            state,
+            subnet_size,
+        );
+        // Put canister back.
+        state.put_canister_state(canister);
+
+        match result {
+            Ok(response) => (Ok(response.encode()), instructions_used),
+            Err(err) => (Err(err.into()), instructions_used),
+        }
+    }
+
+    /// Creates a new canister snapshot from uploaded metadata.
+    fn upload_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: UploadCanisterSnapshotMetadataArgs,
+        subnet_size: usize,
+        round_limits: &mut RoundLimits,
+    ) -> (Result<Vec<u8>, UserError>, NumInstructions) {
+        let canister_id = args.get_canister_id();
+        // Take canister out.
+        let mut canister = match state.take_canister_state(&canister_id) {
+            None => {
+                return (
+                    Err(UserError::new(
+                        ErrorCode::CanisterNotFound,
+                        format!("Canister {} not found.", &canister_id),
+                    )),
+                    NumInstructions::new(0),
+                )
+            }
+            Some(canister) => canister,
+        };
+
+        let resource_saturation =
+            self.subnet_memory_saturation(&round_limits.subnet_available_memory);
+        let (result, instructions_used) = self.canister_manager.upload_snapshot_metadata(
+            sender,
+            &mut canister,
+            args,
+            state,
+            round_limits,
+            &resource_saturation,
+            subnet_size,
+        );
+        // Put canister back.
+        state.put_canister_state(canister);
+
+        match result {
+            Ok(response) => (Ok(response.encode()), instructions_used),
+            Err(err) => (Err(err.into()), instructions_used),
+        }
+    }
+
+    /// Writes a piece of data into an uploaded canister snapshot.
+    fn upload_snapshot_data(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: UploadCanisterSnapshotDataArgs,
+        subnet_size: usize,
+        round_limits: &mut RoundLimits,
+    ) -> (Result<Vec<u8>, UserError>, NumInstructions) {
+        let canister_id = args.get_canister_id();
+        // Take canister out.
+        let mut canister = match state.take_canister_state(&canister_id) {
+            None => {
+                return (
+                    Err(UserError::new(
+                        ErrorCode::CanisterNotFound,
+                        format!("Canister {} not found.", &canister_id),
+                    )),
+                    NumInstructions::new(0),
+                )
+            }
+            Some(canister) => canister,
+        };
+
+        let resource_saturation =
+            self.subnet_memory_saturation(&round_limits.subnet_available_memory);
+        let (result, instructions_used) = self.canister_manager.upload_snapshot_data(
+            sender,
+            &mut canister,
+            &args,
+            state,
+            round_limits,
+            &resource_saturation,
+            subnet_size,
+        );
+        // Put canister back.
+        state.put_canister_state(canister);
+
+        match result {
+            Ok(()) => (Ok(EmptyBlob.encode()), instructions_used),
+            Err(err) => (Err(err.into()), instructions_used),
+        }
+    }
+
     fn node_metrics_history(
         &self,
         state: &ReplicatedState,
@@ -2916,7 +3163,7 @@ impl ExecutionEnvironment {
     ///   added to the task queue of the canister.
     pub fn execute_install_code(
         &self,
-        mut msg: CanisterCall,
+        msg: CanisterCall,
         call_id: Option<InstallCodeCallId>,
         prepaid_execution_cycles: Option<Cycles>,
         dts_status: DtsInstallCodeStatus,
@@ -2928,22 +3175,116 @@ impl ExecutionEnvironment {
         // Start logging execution time for `install_code`.
         let since = Instant::now();
 
+        match self.start_install_code(
+            msg,
+            call_id,
+            prepaid_execution_cycles,
+            dts_status,
+            &mut state,
+            instruction_limits,
+            round_limits,
+            subnet_size,
+        ) {
+            Ok(dts_result) => {
+                self.process_install_code_result(state, dts_result, dts_status, since)
+            }
+            Err((mut msg, err)) => {
+                let refund = msg.take_cycles();
+                let state = self.finish_subnet_message_execution(
+                    state,
+                    msg,
+                    ExecuteSubnetMessageResult::Finished {
+                        response: Err(err),
+                        refund,
+                    },
+                    since,
+                );
+                (state, Some(NumInstructions::from(0)))
+            }
+        }
+    }
+
+    /// Executes the given `install_code` message on the given state without
+    /// deterministic time slicing and returns the resulting state together
+    /// with the instructions executed by each system method.
+    ///
+    /// Unlike [`Self::execute_install_code`], no subnet response is produced,
+    /// so the resulting state is only meant to be inspected, e.g., to check
+    /// whether an upgrade fits into the instruction limit before performing
+    /// it. It must never be committed.
+    pub fn dry_run_install_code(
+        &self,
+        msg: CanisterCall,
+        mut state: ReplicatedState,
+        instruction_limit: NumInstructions,
+        subnet_size: usize,
+    ) -> (ReplicatedState, InstallCodeDryRunResult) {
+        let mut round_limits = RoundLimits {
+            instructions: as_round_instructions(instruction_limit),
+            subnet_available_memory: self.subnet_available_memory(&state),
+            subnet_available_callbacks: self.subnet_available_callbacks(&state),
+            compute_allocation_used: state.total_compute_allocation(),
+        };
+        let dts_result = self.start_install_code(
+            msg,
+            None,
+            None,
+            DtsInstallCodeStatus::StartingFirstExecution,
+            &mut state,
+            InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit),
+            &mut round_limits,
+            subnet_size,
+        );
+        let dry_run_result = match dts_result {
+            Ok(DtsInstallCodeResult::Finished {
+                canister,
+                message: _,
+                call_id,
+                instructions_used: _,
+                system_method_instructions,
+                result,
+            }) => {
+                state.put_canister_state(canister);
+                state
+                    .metadata
+                    .subnet_call_context_manager
+                    .remove_install_code_call(call_id);
+                InstallCodeDryRunResult {
+                    system_method_instructions,
+                    result: result.map(|_| ()).map_err(UserError::from),
+                }
+            }
+            Ok(DtsInstallCodeResult::Paused { .. }) => {
+                unreachable!("install_code cannot be paused without deterministic time slicing")
+            }
+            Err((_, err)) => InstallCodeDryRunResult {
+                system_method_instructions: BTreeMap::new(),
+                result: Err(err),
+            },
+        };
+        (state, dry_run_result)
+    }
+
+    /// Decodes the given `install_code` message and starts its execution on
+    /// the target canister, which is taken out of the given state.
+    ///
+    /// Returns the message with the error if the message could not be decoded
+    /// or the canister does not exist.
+    fn start_install_code(
+        &self,
+        msg: CanisterCall,
+        call_id: Option<InstallCodeCallId>,
+        prepaid_execution_cycles: Option<Cycles>,
+        dts_status: DtsInstallCodeStatus,
+        state: &mut ReplicatedState,
+        instruction_limits: InstructionLimits,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<DtsInstallCodeResult, (CanisterCall, UserError)> {
         let (install_context, old_canister) =
-            match Self::decode_input_and_take_canister(&msg, &mut state) {
+            match Self::decode_input_and_take_canister(&msg, state) {
                 Ok(result) => result,
-                Err(err) => {
-                    let refund = msg.take_cycles();
-                    let state = self.finish_subnet_message_execution(
-                        state,
-                        msg,
-                        ExecuteSubnetMessageResult::Finished {
-                            response: Err(err),
-                            refund,
-                        },
-                        since,
-                    );
-                    return (state, Some(NumInstructions::from(0)));
-                }
+                Err(err) => return Err((msg, err)),
             };
 
         // Track whether the deprecated fields in install_code were used.
@@ -3019,7 +3360,7 @@ impl ExecutionEnvironment {
             ingress_with_cycles_error: &self.metrics.ingress_with_cycles_error,
         };
 
-        let dts_result = self.canister_manager.install_code_dts(
+        Ok(self.canister_manager.install_code_dts(
             install_context,
             msg,
             call_id,
@@ -3034,8 +3375,7 @@ impl ExecutionEnvironment {
             round_counters,
             subnet_size,
             self.config.dirty_page_logging,
-        );
-        self.process_install_code_result(state, dts_result, dts_status, since)
+        ))
     }
 
     /// Processes the result of install code message that was executed using
@@ -3060,6 +3400,7 @@ impl ExecutionEnvironment {
                 mut message,
                 call_id,
                 instructions_used,
+                system_method_instructions: _,
                 result,
             } => {
                 let canister_id = canister.canister_id();
@@ -6,10 +6,13 @@ use ic_cycles_account_manager::ResourceSaturation;
 use ic_cycles_account_manager::WasmExecutionMode;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_management_canister_types::{
-    self as ic00, CanisterChange, CanisterChangeDetails, CanisterSnapshotResponse,
-    ClearChunkStoreArgs, DeleteCanisterSnapshotArgs, ListCanisterSnapshotArgs,
-    LoadCanisterSnapshotArgs, Method, Payload as Ic00Payload, TakeCanisterSnapshotArgs,
-    UploadChunkArgs,
+    self as ic00, CanisterChange, CanisterChangeDetails, CanisterSnapshotDataKind,
+    CanisterSnapshotDataOffset, CanisterSnapshotResponse, ClearChunkStoreArgs,
+    DeleteCanisterSnapshotArgs, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, Method,
+    Payload as Ic00Payload, ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotDataResponse,
+    ReadCanisterSnapshotMetadataArgs, ReadCanisterSnapshotMetadataResponse, SnapshotGlobal,
+    TakeCanisterSnapshotArgs, UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs,
+    UploadCanisterSnapshotMetadataResponse, UploadChunkArgs, MAX_SNAPSHOT_DATA_SLICE_SIZE,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
@@ -1836,6 +1839,401 @@ fn snapshot_must_include_globals() {
     assert_eq!(result, WasmResult::Reply(vec![1, 0, 0, 0]));
 }
 
+#[test]
+fn read_and_upload_canister_snapshot_decode_round_trip() {
+    let canister_id = canister_test_id(4);
+    let snapshot_id = SnapshotId::from((canister_id, 6));
+
+    let args = ReadCanisterSnapshotMetadataArgs::new(canister_id, snapshot_id);
+    assert_eq!(
+        args,
+        ReadCanisterSnapshotMetadataArgs::decode(args.encode().as_slice()).unwrap()
+    );
+
+    let args = ReadCanisterSnapshotDataArgs::new(
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataKind::StableMemory {
+            offset: 10,
+            size: 20,
+        },
+    );
+    assert_eq!(
+        args,
+        ReadCanisterSnapshotDataArgs::decode(args.encode().as_slice()).unwrap()
+    );
+
+    let args = UploadCanisterSnapshotMetadataArgs::new(
+        canister_id,
+        Some(snapshot_id),
+        42,
+        vec![SnapshotGlobal::I32(1), SnapshotGlobal::V128(2)],
+        65536,
+        0,
+        vec![3; 32],
+    );
+    assert_eq!(
+        args,
+        UploadCanisterSnapshotMetadataArgs::decode(args.encode().as_slice()).unwrap()
+    );
+
+    let args = UploadCanisterSnapshotDataArgs::new(
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataOffset::MainMemory { offset: 5 },
+        vec![1, 2, 3],
+    );
+    assert_eq!(
+        args,
+        UploadCanisterSnapshotDataArgs::decode(args.encode().as_slice()).unwrap()
+    );
+}
+
+#[test]
+fn read_canister_snapshot_data_fails_invalid_subslice() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+
+    let args = TakeCanisterSnapshotArgs::new(canister_id, None);
+    let result = test.subnet_message("take_canister_snapshot", args.encode());
+    let snapshot_id = CanisterSnapshotResponse::decode(&result.unwrap().bytes())
+        .unwrap()
+        .snapshot_id();
+
+    // Reading past the end of the module fails.
+    let args = ReadCanisterSnapshotDataArgs::new(
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataKind::WasmModule {
+            offset: UNIVERSAL_CANISTER_WASM.len() as u64,
+            size: 1,
+        },
+    );
+    let error = test
+        .subnet_message("read_canister_snapshot_data", args.encode())
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::InvalidManagementPayload);
+
+    // Reading more than the maximum slice size fails.
+    let args = ReadCanisterSnapshotDataArgs::new(
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataKind::StableMemory {
+            offset: 0,
+            size: MAX_SNAPSHOT_DATA_SLICE_SIZE + 1,
+        },
+    );
+    let error = test
+        .subnet_message("read_canister_snapshot_data", args.encode())
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::InvalidManagementPayload);
+}
+
+#[test]
+fn upload_canister_snapshot_metadata_fails_invalid_memory_size() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+
+    // The Wasm memory size is not a multiple of the Wasm page size.
+    let args =
+        UploadCanisterSnapshotMetadataArgs::new(canister_id, None, 10, vec![], 1000, 0, vec![]);
+    let error = test
+        .subnet_message("upload_canister_snapshot_metadata", args.encode())
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::InvalidManagementPayload);
+    assert_eq!(test.state().canister_snapshots.count(), 0);
+}
+
+/// Downloads `size` bytes of snapshot data in slices of at most
+/// `MAX_SNAPSHOT_DATA_SLICE_SIZE` bytes.
+fn read_snapshot_data(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    snapshot_id: SnapshotId,
+    size: u64,
+    kind: impl Fn(u64, u64) -> CanisterSnapshotDataKind,
+) -> Vec<u8> {
+    let mut data = vec![];
+    let mut offset = 0;
+    while offset < size {
+        let slice_size = MAX_SNAPSHOT_DATA_SLICE_SIZE.min(size - offset);
+        let args =
+            ReadCanisterSnapshotDataArgs::new(canister_id, snapshot_id, kind(offset, slice_size));
+        let result = test.subnet_message("read_canister_snapshot_data", args.encode());
+        let response = ReadCanisterSnapshotDataResponse::decode(&result.unwrap().bytes()).unwrap();
+        data.extend(response.chunk);
+        offset += slice_size;
+    }
+    data
+}
+
+/// Uploads snapshot data in slices of at most `MAX_SNAPSHOT_DATA_SLICE_SIZE` bytes.
+fn upload_snapshot_data(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    snapshot_id: SnapshotId,
+    data: &[u8],
+    kind: impl Fn(u64) -> CanisterSnapshotDataOffset,
+) {
+    for (i, slice) in data
+        .chunks(MAX_SNAPSHOT_DATA_SLICE_SIZE as usize)
+        .enumerate()
+    {
+        let offset = i as u64 * MAX_SNAPSHOT_DATA_SLICE_SIZE;
+        let args = UploadCanisterSnapshotDataArgs::new(
+            canister_id,
+            snapshot_id,
+            kind(offset),
+            slice.to_vec(),
+        );
+        test.subnet_message("upload_canister_snapshot_data", args.encode())
+            .unwrap();
+    }
+}
+
+#[test]
+fn download_and_upload_canister_snapshot_succeeds() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+
+    // Put some data into the stable memory and the chunk store.
+    test.ingress(
+        canister_id,
+        "update",
+        wasm()
+            .stable_grow(1)
+            .stable_write(0, b"snapshot")
+            .reply()
+            .build(),
+    )
+    .unwrap();
+    let chunk = vec![1, 2, 3, 4, 5];
+    let upload_args = UploadChunkArgs {
+        canister_id: canister_id.into(),
+        chunk: chunk.clone(),
+    };
+    test.subnet_message("upload_chunk", upload_args.encode())
+        .unwrap();
+
+    // Take a snapshot and download it.
+    let args = TakeCanisterSnapshotArgs::new(canister_id, None);
+    let result = test.subnet_message("take_canister_snapshot", args.encode());
+    let snapshot_id = CanisterSnapshotResponse::decode(&result.unwrap().bytes())
+        .unwrap()
+        .snapshot_id();
+
+    let args = ReadCanisterSnapshotMetadataArgs::new(canister_id, snapshot_id);
+    let result = test.subnet_message("read_canister_snapshot_metadata", args.encode());
+    let metadata = ReadCanisterSnapshotMetadataResponse::decode(&result.unwrap().bytes()).unwrap();
+    assert_eq!(
+        metadata.wasm_module_size,
+        UNIVERSAL_CANISTER_WASM.len() as u64
+    );
+    assert_eq!(metadata.stable_memory_size, 65536);
+    assert_eq!(metadata.wasm_chunk_store.len(), 1);
+
+    let module = read_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        metadata.wasm_module_size,
+        |offset, size| CanisterSnapshotDataKind::WasmModule { offset, size },
+    );
+    assert_eq!(module, UNIVERSAL_CANISTER_WASM.to_vec());
+    let main_memory = read_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        metadata.wasm_memory_size,
+        |offset, size| CanisterSnapshotDataKind::MainMemory { offset, size },
+    );
+    let stable_memory = read_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        metadata.stable_memory_size,
+        |offset, size| CanisterSnapshotDataKind::StableMemory { offset, size },
+    );
+    assert_eq!(&stable_memory[..8], b"snapshot");
+    let args = ReadCanisterSnapshotDataArgs::new(
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataKind::WasmChunk {
+            hash: metadata.wasm_chunk_store[0].hash.clone(),
+        },
+    );
+    let result = test.subnet_message("read_canister_snapshot_data", args.encode());
+    let downloaded_chunk = ReadCanisterSnapshotDataResponse::decode(&result.unwrap().bytes())
+        .unwrap()
+        .chunk;
+    assert_eq!(downloaded_chunk, chunk);
+
+    // Upload the snapshot as a new one, replacing the original.
+    let args = UploadCanisterSnapshotMetadataArgs::new(
+        canister_id,
+        Some(snapshot_id),
+        metadata.wasm_module_size,
+        metadata.exported_globals.clone(),
+        metadata.wasm_memory_size,
+        metadata.stable_memory_size,
+        metadata.certified_data.clone(),
+    );
+    let result = test.subnet_message("upload_canister_snapshot_metadata", args.encode());
+    let uploaded_snapshot_id =
+        UploadCanisterSnapshotMetadataResponse::decode(&result.unwrap().bytes())
+            .unwrap()
+            .get_snapshot_id();
+    assert_ne!(uploaded_snapshot_id, snapshot_id);
+    assert!(test.state().canister_snapshots.get(snapshot_id).is_none());
+
+    upload_snapshot_data(
+        &mut test,
+        canister_id,
+        uploaded_snapshot_id,
+        &module,
+        |offset| CanisterSnapshotDataOffset::WasmModule { offset },
+    );
+    upload_snapshot_data(
+        &mut test,
+        canister_id,
+        uploaded_snapshot_id,
+        &main_memory,
+        |offset| CanisterSnapshotDataOffset::MainMemory { offset },
+    );
+    upload_snapshot_data(
+        &mut test,
+        canister_id,
+        uploaded_snapshot_id,
+        &stable_memory,
+        |offset| CanisterSnapshotDataOffset::StableMemory { offset },
+    );
+    upload_snapshot_data(&mut test, canister_id, uploaded_snapshot_id, &chunk, |_| {
+        CanisterSnapshotDataOffset::WasmChunk
+    });
+
+    // The uploaded snapshot has the same contents as the downloaded one.
+    let args = ReadCanisterSnapshotMetadataArgs::new(canister_id, uploaded_snapshot_id);
+    let result = test.subnet_message("read_canister_snapshot_metadata", args.encode());
+    let uploaded_metadata =
+        ReadCanisterSnapshotMetadataResponse::decode(&result.unwrap().bytes()).unwrap();
+    assert_eq!(
+        uploaded_metadata.wasm_chunk_store,
+        metadata.wasm_chunk_store
+    );
+    assert_eq!(
+        uploaded_metadata.exported_globals,
+        metadata.exported_globals
+    );
+
+    let unflushed_changes = test.state_mut().canister_snapshots.take_unflushed_changes();
+    assert_eq!(
+        unflushed_changes,
+        vec![
+            SnapshotOperation::Backup(canister_id, snapshot_id),
+            SnapshotOperation::Delete(snapshot_id),
+            SnapshotOperation::Upload(canister_id, uploaded_snapshot_id),
+        ]
+    );
+
+    // Overwrite the stable memory and restore it from the uploaded snapshot.
+    test.ingress(
+        canister_id,
+        "update",
+        wasm().stable_write(0, b"modified").reply().build(),
+    )
+    .unwrap();
+    let args = LoadCanisterSnapshotArgs::new(canister_id, uploaded_snapshot_id, None);
+    test.subnet_message("load_canister_snapshot", args.encode())
+        .unwrap();
+    let result = test
+        .ingress(
+            canister_id,
+            "update",
+            wasm().stable_read(0, 8).append_and_reply().build(),
+        )
+        .unwrap();
+    assert_eq!(result, WasmResult::Reply(b"snapshot".to_vec()));
+}
+
+#[test]
+fn upload_canister_snapshot_data_fails_without_reserving_cycles_when_out_of_cycles() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000);
+    const CAPACITY: u64 = 1_000_000_000;
+    // Every memory increase beyond the threshold reserves cycles.
+    const THRESHOLD: u64 = 1;
+
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_execution_memory(CAPACITY as i64)
+        .with_subnet_memory_reservation(0)
+        .with_subnet_memory_threshold(THRESHOLD as i64)
+        .build();
+    let canister_id = test
+        .canister_from_cycles_and_binary(CYCLES, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    test.canister_update_reserved_cycles_limit(canister_id, CYCLES)
+        .unwrap();
+
+    let args = UploadCanisterSnapshotMetadataArgs::new(canister_id, None, 10, vec![], 0, 0, vec![]);
+    let result = test.subnet_message("upload_canister_snapshot_metadata", args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&result.unwrap().bytes())
+        .unwrap()
+        .get_snapshot_id();
+
+    // Leave the canister with fewer cycles above its freezing threshold than
+    // the upload costs.
+    let threshold = test.freezing_threshold(canister_id);
+    let new_balance = threshold + Cycles::from(1_000_u128);
+    let to_remove = test.canister_state(canister_id).system_state.balance() - new_balance;
+    test.canister_state_mut(canister_id)
+        .system_state
+        .remove_cycles(to_remove, CyclesUseCase::BurnedCycles);
+
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    let reserved_cycles_before = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+    let subnet_available_memory_before = test.subnet_available_memory();
+
+    let args = UploadCanisterSnapshotDataArgs::new(
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataOffset::WasmChunk,
+        vec![1, 2, 3],
+    );
+    let error = test
+        .subnet_message("upload_canister_snapshot_data", args.encode())
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::CanisterOutOfCycles);
+
+    // Neither the fee nor a reservation for the new chunk was taken.
+    let system_state = &test.canister_state(canister_id).system_state;
+    assert_eq!(system_state.balance(), balance_before);
+    assert_eq!(system_state.reserved_balance(), reserved_cycles_before);
+    assert_eq!(
+        test.subnet_available_memory(),
+        subnet_available_memory_before
+    );
+    assert!(test
+        .state()
+        .canister_snapshots
+        .get(snapshot_id)
+        .unwrap()
+        .chunk_store()
+        .keys()
+        .next()
+        .is_none());
+}
+
 /// Early warning system / stumbling block forcing the authors of changes adding
 /// or removing canister state fields to think about and/or ask the Execution
 /// team to think about any repercussions to the canister snapshot logic.
@@ -1896,6 +2294,7 @@ fn canister_snapshot_change_guard_do_not_modify_without_reading_doc_comment() {
         accumulated_priority: _,
         priority_credit: _,
         long_execution_mode: _,
+        priority_class: _,
         heap_delta_debit: _,
         install_code_debit: _,
         time_of_last_allocation_charge: _,
@@ -3,12 +3,12 @@ use ic_config::execution_environment::{Config, MAX_COMPILATION_CACHE_SIZE};
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::{
-    wasm_executor::{WasmExecutionResult, WasmExecutor, WasmExecutorImpl},
+    wasm_executor::{PausedWasmExecution, WasmExecutionResult, WasmExecutor, WasmExecutorImpl},
     wasm_utils::decoding::decoded_wasm_size,
     CompilationCache, CompilationResult, WasmExecutionInput, WasmtimeEmbedder,
 };
 use ic_interfaces::execution_environment::{
-    HypervisorError, HypervisorResult, WasmExecutionOutput,
+    HypervisorError, HypervisorResult, WasmExecutionOutput, WasmProfile,
 };
 use ic_interfaces_state_manager::StateReader;
 use ic_logger::ReplicaLogger;
@@ -26,7 +26,11 @@ use ic_types::{
 };
 use ic_wasm_types::CanisterModule;
 use prometheus::{Histogram, HistogramVec, IntCounter, IntGauge};
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use crate::execution::common::{apply_canister_state_changes, update_round_limits};
 use crate::execution_environment::{as_round_instructions, CompilationCostHandling, RoundLimits};
@@ -207,8 +211,54 @@ pub struct Hypervisor {
     cost_to_compile_wasm_instruction: NumInstructions,
     dirty_page_overhead: NumInstructions,
     canister_guaranteed_callback_quota: usize,
+    wasm_profiling: FlagStatus,
+    wasm_profiles: WasmProfiles,
+}
+
+/// The Wasm profile of a message execution (see `FeatureFlags::wasm_profiling`).
+#[derive(Clone, Debug)]
+pub struct WasmProfileRecord {
+    pub canister_id: CanisterId,
+    /// The exported method (e.g., `canister_update <name>`) or the API type
+    /// (e.g., `reply callback`) of the execution.
+    pub method: String,
+    /// The executed canister module (to map function indices to names).
+    pub canister_module: CanisterModule,
+    pub profile: WasmProfile,
+}
+
+/// The maximum number of Wasm profiles kept by a hypervisor until they are
+/// taken. Older profiles are dropped to make room for new ones.
+pub const MAX_WASM_PROFILES: usize = 1_000;
+
+/// The Wasm profiles of message executions recorded by a hypervisor that have
+/// not been taken yet: a ring buffer of at most `MAX_WASM_PROFILES` profiles.
+#[derive(Debug, Default)]
+pub struct WasmProfileBuffer {
+    records: VecDeque<WasmProfileRecord>,
+    num_dropped: u64,
+}
+
+impl WasmProfileBuffer {
+    fn push(&mut self, record: WasmProfileRecord) {
+        if self.records.len() == MAX_WASM_PROFILES {
+            self.records.pop_front();
+            self.num_dropped += 1;
+        }
+        self.records.push_back(record);
+    }
+
+    /// Takes the recorded profiles (oldest first) and the number of profiles
+    /// dropped since the profiles were last taken.
+    pub fn take(&mut self) -> (Vec<WasmProfileRecord>, u64) {
+        let records = std::mem::take(&mut self.records).into();
+        (records, std::mem::take(&mut self.num_dropped))
+    }
 }
 
+/// The Wasm profiles recorded by a hypervisor.
+pub type WasmProfiles = Arc<Mutex<WasmProfileBuffer>>;
+
 impl Hypervisor {
     pub(crate) fn subnet_id(&self) -> SubnetId {
         self.own_subnet_id
@@ -319,6 +369,8 @@ impl Hypervisor {
                 .cost_to_compile_wasm_instruction,
             dirty_page_overhead,
             canister_guaranteed_callback_quota: config.canister_guaranteed_callback_quota,
+            wasm_profiling: config.embedders_config.feature_flags.wasm_profiling,
+            wasm_profiles: Default::default(),
         }
     }
 
@@ -347,6 +399,8 @@ impl Hypervisor {
             cost_to_compile_wasm_instruction,
             dirty_page_overhead,
             canister_guaranteed_callback_quota,
+            wasm_profiling: FlagStatus::Disabled,
+            wasm_profiles: Default::default(),
         }
     }
 
@@ -468,6 +522,20 @@ impl Hypervisor {
             execution_state.is_wasm64,
         );
         let api_type_str = api_type.as_str();
+        let wasm_profile_context = match self.wasm_profiling {
+            FlagStatus::Enabled => Some(WasmProfileContext {
+                canister_id: system_state.canister_id(),
+                method: match &func_ref {
+                    FuncRef::Method(method) => method.to_string(),
+                    FuncRef::UpdateClosure(_) | FuncRef::QueryClosure(_) => {
+                        api_type_str.to_string()
+                    }
+                },
+                canister_module: execution_state.wasm_binary.binary.clone(),
+                wasm_profiles: Arc::clone(&self.wasm_profiles),
+            }),
+            FlagStatus::Disabled => None,
+        };
         let (compilation_result, mut execution_result) = Arc::clone(&self.wasm_executor).execute(
             WasmExecutionInput {
                 api_type,
@@ -486,6 +554,9 @@ impl Hypervisor {
                 .observe_compilation_metrics(&compilation_result);
         }
         self.metrics.observe(&execution_result, api_type_str);
+        if let Some(wasm_profile_context) = wasm_profile_context {
+            execution_result = wasm_profile_context.record(execution_result);
+        }
 
         // If the caller does not have permission to view this canister's logs,
         // then it shouldn't get a backtrace either. So in that case we remove
@@ -524,6 +595,11 @@ impl Hypervisor {
         execution_result
     }
 
+    /// Returns the Wasm profiles recorded by this hypervisor.
+    pub fn wasm_profiles(&self) -> WasmProfiles {
+        Arc::clone(&self.wasm_profiles)
+    }
+
     #[doc(hidden)]
     pub fn clear_compilation_cache_for_testing(&self) {
         self.compilation_cache.clear_for_testing()
@@ -542,3 +618,90 @@ impl Hypervisor {
             .insert_ok(&canister_module, compiled_module);
     }
 }
+
+/// The context of a Wasm execution whose profile is recorded once it finishes.
+#[derive(Debug)]
+struct WasmProfileContext {
+    canister_id: CanisterId,
+    method: String,
+    canister_module: CanisterModule,
+    wasm_profiles: WasmProfiles,
+}
+
+impl WasmProfileContext {
+    /// Records the profile of the given execution if it has finished.
+    /// Otherwise, the profile is recorded once the paused execution finishes.
+    fn record(self, execution_result: WasmExecutionResult) -> WasmExecutionResult {
+        match execution_result {
+            WasmExecutionResult::Finished(slice, mut output, canister_state_changes) => {
+                let profile = std::mem::take(&mut output.wasm_profile);
+                self.wasm_profiles.lock().unwrap().push(WasmProfileRecord {
+                    canister_id: self.canister_id,
+                    method: self.method,
+                    canister_module: self.canister_module,
+                    profile,
+                });
+                WasmExecutionResult::Finished(slice, output, canister_state_changes)
+            }
+            WasmExecutionResult::Paused(slice, paused) => WasmExecutionResult::Paused(
+                slice,
+                Box::new(ProfiledPausedWasmExecution {
+                    paused,
+                    context: self,
+                }),
+            ),
+        }
+    }
+}
+
+/// A paused Wasm execution whose profile is recorded once it finishes.
+#[derive(Debug)]
+struct ProfiledPausedWasmExecution {
+    paused: Box<dyn PausedWasmExecution>,
+    context: WasmProfileContext,
+}
+
+impl PausedWasmExecution for ProfiledPausedWasmExecution {
+    fn resume(self: Box<Self>, execution_state: &ExecutionState) -> WasmExecutionResult {
+        let execution_result = self.paused.resume(execution_state);
+        self.context.record(execution_result)
+    }
+
+    fn abort(self: Box<Self>) {
+        self.paused.abort()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_types::ids::canister_test_id;
+
+    fn record(instructions: u64) -> WasmProfileRecord {
+        WasmProfileRecord {
+            canister_id: canister_test_id(0),
+            method: "canister_update write".to_string(),
+            canister_module: CanisterModule::new(vec![]),
+            profile: vec![(vec![0], instructions)],
+        }
+    }
+
+    #[test]
+    fn wasm_profile_buffer_drops_oldest_profiles() {
+        let mut buffer = WasmProfileBuffer::default();
+        for i in 0..MAX_WASM_PROFILES as u64 + 10 {
+            buffer.push(record(i));
+        }
+
+        let (records, num_dropped) = buffer.take();
+        assert_eq!(num_dropped, 10);
+        assert_eq!(records.len(), MAX_WASM_PROFILES);
+        assert_eq!(records[0].profile, vec![(vec![0], 10)]);
+
+        // The drop count is reset once the profiles are taken.
+        buffer.push(record(0));
+        let (records, num_dropped) = buffer.take();
+        assert_eq!(records.len(), 1);
+        assert_eq!(num_dropped, 0);
+    }
+}
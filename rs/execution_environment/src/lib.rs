@@ -17,10 +17,13 @@ pub mod util;
 use crate::ingress_filter::IngressFilterServiceImpl;
 pub use execution_environment::{
     as_num_instructions, as_round_instructions, execute_canister, CompilationCostHandling,
-    ExecuteMessageResult, ExecutionEnvironment, ExecutionResponse, RoundInstructions, RoundLimits,
+    ExecuteMessageResult, ExecutionEnvironment, ExecutionResponse, InstallCodeDryRunResult,
+    RoundInstructions, RoundLimits,
 };
 pub use history::{IngressHistoryReaderImpl, IngressHistoryWriterImpl};
-pub use hypervisor::{Hypervisor, HypervisorMetrics};
+pub use hypervisor::{
+    Hypervisor, HypervisorMetrics, WasmProfileBuffer, WasmProfileRecord, WasmProfiles,
+};
 use ic_base_types::PrincipalId;
 use ic_config::{execution_environment::Config, subnet_config::SchedulerConfig};
 use ic_cycles_account_manager::CyclesAccountManager;
@@ -86,6 +89,11 @@ pub struct ExecutionServices {
     pub query_execution_service: QueryExecutionService,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
     pub query_stats_payload_builder: QueryStatsPayloadBuilderParams,
+    /// The Wasm profiles of message executions (only recorded if Wasm profiling is enabled).
+    pub wasm_profiles: WasmProfiles,
+    /// The execution environment of the scheduler, e.g., to dry-run
+    /// `install_code` messages on a copy of the replicated state.
+    pub execution_environment: Arc<ExecutionEnvironment>,
 }
 
 impl ExecutionServices {
@@ -115,6 +123,7 @@ impl ExecutionServices {
             Arc::clone(&fd_factory),
             Arc::clone(&state_reader),
         ));
+        let wasm_profiles = hypervisor.wasm_profiles();
 
         let ingress_history_writer = Arc::new(IngressHistoryWriterImpl::new(
             config.clone(),
@@ -203,6 +212,8 @@ impl ExecutionServices {
             query_execution_service,
             scheduler,
             query_stats_payload_builder,
+            wasm_profiles,
+            execution_environment: exec_env,
         }
     }
 
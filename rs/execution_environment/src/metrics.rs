@@ -7,7 +7,7 @@ use ic_metrics::{
 use ic_system_api::sandbox_safe_system_state::RequestMetadataStats;
 use ic_types::{
     NumInstructions, NumMessages, NumSlices, Time, MAX_STABLE_MEMORY_IN_BYTES,
-    MAX_WASM_MEMORY_IN_BYTES,
+    MAX_WASM64_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec};
 use std::{cell::RefCell, rc::Rc, time::Instant};
@@ -507,6 +507,7 @@ fn memory_buckets() -> Vec<f64> {
         8 * GIB,
         MAX_STABLE_MEMORY_IN_BYTES,
         MAX_WASM_MEMORY_IN_BYTES,
+        MAX_WASM64_MEMORY_IN_BYTES,
     ])
 }
 
@@ -839,5 +840,6 @@ mod tests {
         assert!(buckets.contains(&0));
         assert!(buckets.contains(&MAX_STABLE_MEMORY_IN_BYTES));
         assert!(buckets.contains(&MAX_WASM_MEMORY_IN_BYTES));
+        assert!(buckets.contains(&MAX_WASM64_MEMORY_IN_BYTES));
     }
 }
@@ -340,14 +340,14 @@ fn fetch_canister_logs(
         )),
     }?;
 
+    let (canister_log_records, next_page_idx) = canister.system_state.canister_log.fetch(
+        &args.filter.unwrap_or_default(),
+        args.max_records
+            .map(|max_records| usize::try_from(max_records).unwrap_or(usize::MAX)),
+    );
     let response = FetchCanisterLogsResponse {
-        canister_log_records: canister
-            .system_state
-            .canister_log
-            .records()
-            .iter()
-            .cloned()
-            .collect(),
+        canister_log_records,
+        next_page_idx,
     };
     Ok(WasmResult::Reply(Encode!(&response).unwrap()))
 }
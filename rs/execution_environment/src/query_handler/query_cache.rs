@@ -19,12 +19,41 @@ use crate::metrics::duration_histogram;
 #[cfg(test)]
 mod tests;
 
+/// The name of the Wasm custom section, in which a canister declares
+/// its cacheable queries as a whitespace-separated list of method names.
+///
+/// The results of the declared queries are reused across rounds, i.e.
+/// changes in `batch_time` or canister balance are ignored, and the entries
+/// do not expire after the max expiry time. The entries are still invalidated
+/// when any of the evaluated canisters changes its version.
+pub(crate) const CACHEABLE_QUERIES_SECTION: &str = "cacheable-queries";
+
+/// Returns true if the `receiver` canister declares the `method_name` query
+/// as cacheable in its `CACHEABLE_QUERIES_SECTION` custom section.
+fn is_declared_cacheable(
+    state: &ReplicatedState,
+    receiver: &CanisterId,
+    method_name: &str,
+) -> bool {
+    state
+        .canister_state(receiver)
+        .and_then(|canister| canister.execution_state.as_ref())
+        .and_then(|execution_state| {
+            execution_state
+                .metadata
+                .get_custom_section(CACHEABLE_QUERIES_SECTION)
+        })
+        .and_then(|section| std::str::from_utf8(section.content()).ok())
+        .is_some_and(|methods| methods.split_whitespace().any(|m| m == method_name))
+}
+
 ////////////////////////////////////////////////////////////////////////
 /// Query Cache metrics.
 pub(crate) struct QueryCacheMetrics {
     pub hits: IntCounter,
     pub hits_with_ignored_time: IntCounter,
     pub hits_with_ignored_canister_balance: IntCounter,
+    pub hits_of_declared_cacheable_queries: IntCounter,
     pub misses: IntCounter,
     pub evicted_entries: IntCounter,
     pub evicted_entries_duration: Histogram,
@@ -57,6 +86,10 @@ impl QueryCacheMetrics {
                 "execution_query_cache_hits_with_ignored_canister_balance_total",
                 "The total number of cache hits into entries with ignored canister balance",
             ),
+            hits_of_declared_cacheable_queries: metrics_registry.int_counter(
+                "execution_query_cache_hits_of_declared_cacheable_queries_total",
+                "The total number of cache hits into entries of queries declared cacheable by canisters",
+            ),
             misses: metrics_registry.int_counter(
                 "execution_query_cache_misses_total",
                 "The total number of replica side query cache misses",
@@ -209,6 +242,9 @@ pub(crate) struct EntryValue {
     ignore_batch_time: bool,
     /// If set, the canister balance changes might be ignored.
     ignore_canister_balances: bool,
+    /// If set, the receiving canister declared the query as cacheable,
+    /// so the entry is valid until any of the canister versions changes.
+    is_declared_cacheable: bool,
 }
 
 impl CountBytes for EntryValue {
@@ -222,20 +258,24 @@ impl EntryValue {
         env: EntryEnv,
         result: Result<WasmResult, UserError>,
         system_api_call_counters: &SystemApiCallCounters,
+        is_declared_cacheable: bool,
     ) -> EntryValue {
         // The cached entry should be expired after `data_certificate_expiry_time`.
         let includes_data_certificate = system_api_call_counters.data_certificate_copy > 0;
-        // It's safe to ignore `batch_time` changes if the query never calls `ic0.time()`.
-        let ignore_batch_time = system_api_call_counters.time == 0;
+        // It's safe to ignore `batch_time` changes if the query never calls `ic0.time()`,
+        // or if the canister explicitly opted in for reusing the result across rounds.
+        let ignore_batch_time = system_api_call_counters.time == 0 || is_declared_cacheable;
         // It's safe to ignore `canister_balance` changes if the query never checks the balance.
-        let ignore_canister_balances = system_api_call_counters.canister_cycle_balance == 0
-            && system_api_call_counters.canister_cycle_balance128 == 0;
+        let ignore_canister_balances = (system_api_call_counters.canister_cycle_balance == 0
+            && system_api_call_counters.canister_cycle_balance128 == 0)
+            || is_declared_cacheable;
         EntryValue {
             env,
             result,
             includes_data_certificate,
             ignore_batch_time,
             ignore_canister_balances,
+            is_declared_cacheable,
         }
     }
 
@@ -282,11 +322,18 @@ impl EntryValue {
         {
             // The value is still valid.
             metrics.hits.inc();
+            if self.is_declared_cacheable {
+                metrics.hits_of_declared_cacheable_queries.inc();
+            }
             // Apply query stats.
             for (id, stats) in canisters_stats {
                 // Add query statistics to the query aggregator.
                 if let Some(query_stats_collector) = query_stats_collector {
-                    query_stats_collector.register_query_statistics(*id, stats);
+                    let stats = QueryStats {
+                        num_cache_hits: 1,
+                        ..stats.clone()
+                    };
+                    query_stats_collector.register_query_statistics(*id, &stats);
                 }
             }
             // Several factors might cause ignoring behavior simultaneously.
@@ -328,9 +375,19 @@ impl EntryValue {
     }
 
     /// Check cache entry max expiration time.
+    ///
+    /// The entries of queries declared cacheable by the canister never expire.
     fn is_expired(&self, now: Time, max_expiry_time: Duration) -> bool {
+        if self.is_declared_cacheable {
+            return false;
+        }
+        self.is_older_than(now, max_expiry_time)
+    }
+
+    /// Check whether the cache entry is older than the `max_age`.
+    fn is_older_than(&self, now: Time, max_age: Duration) -> bool {
         if let Some(duration) = now.checked_duration_since(self.env.batch_time) {
-            duration > max_expiry_time
+            duration > max_age
         } else {
             false
         }
@@ -352,7 +409,7 @@ impl EntryValue {
         data_certificate_expiry_time: Duration,
     ) -> bool {
         if self.includes_data_certificate {
-            return self.is_expired(now, data_certificate_expiry_time);
+            return self.is_older_than(now, data_certificate_expiry_time);
         }
         false
     }
@@ -458,7 +515,13 @@ impl QueryCache {
             return;
         };
 
-        let value = EntryValue::new(env, result.clone(), system_api_counters);
+        let is_declared_cacheable = is_declared_cacheable(state, &key.receiver, &key.method_name);
+        let value = EntryValue::new(
+            env,
+            result.clone(),
+            system_api_counters,
+            is_declared_cacheable,
+        );
         let mut cache = self.cache.lock().unwrap();
         let evicted_entries = cache.push(key, value);
 
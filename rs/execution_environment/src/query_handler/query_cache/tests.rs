@@ -58,6 +58,27 @@ const QUERY_CACHE_WAT: &str = r#"
     (export "canister_query f2" (func $f))
 )"#;
 
+const CACHEABLE_QUERIES_WAT: &str = r#"
+(module
+    (import "ic0" "msg_reply" (func $msg_reply))
+    (import "ic0" "msg_reply_data_append"
+        (func $msg_reply_data_append (param i32 i32)))
+    (import "ic0" "time" (func $time (result i64)))
+
+    (memory 1)
+
+    (func $time_reply
+        ;; Reply with the current time, so the result depends on `batch_time`.
+        (i64.store (i32.const 0) (call $time))
+        (call $msg_reply_data_append (i32.const 0) (i32.const 8))
+        (call $msg_reply)
+    )
+
+    (export "canister_query declared" (func $time_reply))
+    (export "canister_query undeclared" (func $time_reply))
+    (@custom "icp:public cacheable-queries" "declared other")
+)"#;
+
 fn downcast_query_handler(query_handler: &dyn std::any::Any) -> &InternalHttpQueryHandler {
     // SAFETY:
     //
@@ -138,6 +159,7 @@ fn query_cache_entry_value_counts_elapsed_seconds() {
         entry_env,
         Result::Ok(WasmResult::Reply(vec![])),
         &SystemApiCallCounters::default(),
+        false,
     );
     let forward_time = current_time + Duration::from_secs(2);
     assert_eq!(2.0, entry_value.elapsed_seconds(forward_time));
@@ -1461,6 +1483,72 @@ fn query_cache_supports_query_stats() {
     });
 }
 
+#[test]
+fn query_cache_reuses_declared_cacheable_query_results_across_batch_times() {
+    let mut test = builder_with_query_cache_expiry_times().build();
+    let id = test.canister_from_wat(CACHEABLE_QUERIES_WAT).unwrap();
+
+    let res_1 = test.non_replicated_query(id, "declared", vec![]);
+    assert_eq!(query_cache_metrics(&test).misses.get(), 1);
+
+    // The declared query reads the time, but its result is reused
+    // even after the max expiry time.
+    test.state_mut().metadata.batch_time += MORE_THAN_MAX_EXPIRY_TIME;
+
+    let res_2 = test.non_replicated_query(id, "declared", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(1, m.misses.get());
+    assert_eq!(1, m.hits.get());
+    assert_eq!(1, m.hits_with_ignored_time.get());
+    assert_eq!(1, m.hits_of_declared_cacheable_queries.get());
+    assert_eq!(res_1, res_2);
+
+    // Bumping up the canister version still invalidates the entry.
+    test.canister_state_mut(id).system_state.canister_version += 1;
+
+    let res_3 = test.non_replicated_query(id, "declared", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(2, m.misses.get());
+    assert_eq!(1, m.invalidated_entries_by_canister_version.get());
+    assert_eq!(0, m.invalidated_entries_by_time.get());
+    assert_eq!(0, m.invalidated_entries_by_max_expiry_time.get());
+    assert_ne!(res_1, res_3);
+}
+
+#[test]
+fn query_cache_does_not_reuse_undeclared_query_results_across_batch_times() {
+    let mut test = builder_with_query_cache_expiry_times().build();
+    let id = test.canister_from_wat(CACHEABLE_QUERIES_WAT).unwrap();
+
+    let res_1 = test.non_replicated_query(id, "undeclared", vec![]);
+    assert_eq!(query_cache_metrics(&test).misses.get(), 1);
+
+    test.state_mut().metadata.batch_time += Duration::from_secs(1);
+
+    let res_2 = test.non_replicated_query(id, "undeclared", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(2, m.misses.get());
+    assert_eq!(0, m.hits_of_declared_cacheable_queries.get());
+    assert_eq!(1, m.invalidated_entries_by_time.get());
+    assert_ne!(res_1, res_2);
+}
+
+#[test]
+fn query_cache_reports_cache_hits_in_query_stats() {
+    let mut test = builder_with_query_caching().build();
+    let id = test.canister_from_wat(CACHEABLE_QUERIES_WAT).unwrap();
+
+    test.non_replicated_query(id, "declared", vec![]).unwrap();
+    let stats = test.query_stats_for_testing(&id).unwrap();
+    assert_eq!(stats.num_calls, 1);
+    assert_eq!(stats.num_cache_hits, 0);
+
+    test.non_replicated_query(id, "declared", vec![]).unwrap();
+    let stats = test.query_stats_for_testing(&id).unwrap();
+    assert_eq!(stats.num_calls, 2);
+    assert_eq!(stats.num_cache_hits, 1);
+}
+
 #[test]
 fn query_cache_future_proof_test() {
     match SystemApiCallId::AcceptMessage {
@@ -1479,11 +1567,23 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::CanisterStatus
         | SystemApiCallId::CanisterVersion
         | SystemApiCallId::CertifiedDataSet
+        | SystemApiCallId::CostCall
+        | SystemApiCallId::CostCreateCanister
+        | SystemApiCallId::CostHttpRequest
+        | SystemApiCallId::CostSignWithEcdsa
+        | SystemApiCallId::CostSignWithSchnorr
+        | SystemApiCallId::CostVetkdDeriveKey
         | SystemApiCallId::CyclesBurn128
         | SystemApiCallId::DataCertificateCopy
         | SystemApiCallId::DataCertificatePresent
         | SystemApiCallId::DataCertificateSize
         | SystemApiCallId::DebugPrint
+        | SystemApiCallId::EnvVarCount
+        | SystemApiCallId::EnvVarNameCopy
+        | SystemApiCallId::EnvVarNameExists
+        | SystemApiCallId::EnvVarNameSize
+        | SystemApiCallId::EnvVarValueCopy
+        | SystemApiCallId::EnvVarValueSize
         | SystemApiCallId::GlobalTimerSet
         | SystemApiCallId::InReplicatedExecution
         | SystemApiCallId::IsController
@@ -1510,6 +1610,8 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::MsgReplyDataAppend
         | SystemApiCallId::OutOfInstructions
         | SystemApiCallId::PerformanceCounter
+        | SystemApiCallId::RootKeyCopy
+        | SystemApiCallId::RootKeySize
         | SystemApiCallId::Stable64Grow
         | SystemApiCallId::Stable64Read
         | SystemApiCallId::Stable64Size
@@ -1518,6 +1620,8 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::StableRead
         | SystemApiCallId::StableSize
         | SystemApiCallId::StableWrite
+        | SystemApiCallId::SubnetSelfCopy
+        | SystemApiCallId::SubnetSelfSize
         | SystemApiCallId::Time
         | SystemApiCallId::Trap
         | SystemApiCallId::TryGrowWasmMemory => {
@@ -1537,6 +1641,9 @@ fn query_cache_future_proof_test() {
             // * Changes in `canister_version` always invalidate cache entries.
             //   This includes update calls, configuration changes, upgrades...
             //
+            // Queries declared in the `cacheable-queries` custom section opt
+            // out of the first two assumptions, but never of the third one.
+            //
             // If you introduce a new System API call that depends on
             // time or balance or a new Canister property that should
             // invalidate cache entries, please check with the Runtime and/or
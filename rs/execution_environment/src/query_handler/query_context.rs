@@ -466,6 +466,7 @@ impl<'a> QueryContext<'a> {
             num_instructions: instructions_executed.get(),
             ingress_payload_size: ingress_payload_size as u64,
             egress_payload_size: egress_payload_size as u64,
+            num_cache_hits: 0,
         };
         self.add_evaluated_canister_stats(canister.canister_id(), &stats);
         if let Some(query_stats) = self.local_query_execution_stats {
@@ -824,6 +825,9 @@ impl<'a> QueryContext<'a> {
         // Add the canister to the set of evaluated canisters early, i.e. before any errors.
         self.add_evaluated_canister_stats(canister_id, &QueryStats::default());
 
+        // Composite queries can only call canisters on this subnet: there is no
+        // authenticated replica-to-replica query transport to forward calls to
+        // canisters on other subnets, so such calls are rejected here.
         let canister = match self.state.get_ref().get_active_canister(&canister_id) {
             Ok(canister) => canister,
             Err(err) => {
@@ -19,7 +19,9 @@ use ic_interfaces::execution_environment::{
     IngressHistoryWriter, Scheduler, SubnetAvailableMemory,
 };
 use ic_logger::{debug, error, fatal, info, new_logger, warn, ReplicaLogger};
-use ic_management_canister_types::{CanisterStatusType, MasterPublicKeyId, Method as Ic00Method};
+use ic_management_canister_types::{
+    CanisterPriorityClass, CanisterStatusType, MasterPublicKeyId, Method as Ic00Method,
+};
 use ic_metrics::MetricsRegistry;
 use ic_replicated_state::{
     canister_state::{
@@ -38,7 +40,7 @@ use ic_types::{
     messages::{CanisterMessage, Ingress, MessageId, Response, NO_DEADLINE},
     CanisterId, ComputeAllocation, Cycles, ExecutionRound, MemoryAllocation, NumBytes,
     NumInstructions, NumSlices, PrincipalId, Randomness, ReplicaVersion, SubnetId, Time,
-    MAX_WASM_MEMORY_IN_BYTES,
+    MAX_WASM64_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use ic_types::{nominal_cycles::NominalCycles, NumMessages};
 use more_asserts::{debug_assert_ge, debug_assert_le};
@@ -962,6 +964,37 @@ impl SchedulerImpl {
         }
     }
 
+    /// Charges the burst priority fee to every canister with the burst priority
+    /// class that is about to start a new execution in this round and returns
+    /// the IDs of the canisters that paid.
+    ///
+    /// Canisters that cannot afford the fee are scheduled as standard canisters
+    /// in this round.
+    fn charge_canisters_for_burst_priority(
+        &self,
+        state: &mut ReplicatedState,
+        subnet_size: usize,
+    ) -> BTreeSet<CanisterId> {
+        let mut burst_canister_ids = BTreeSet::new();
+        for canister in state.canisters_iter_mut() {
+            if canister.scheduler_state.priority_class != CanisterPriorityClass::Burst
+                || canister.next_execution() != NextExecution::StartNew
+                || canister.has_aborted_execution()
+                || canister.has_paused_execution()
+            {
+                continue;
+            }
+            if self
+                .cycles_account_manager
+                .consume_cycles_for_burst_priority(canister, subnet_size)
+                .is_ok()
+            {
+                burst_canister_ids.insert(canister.canister_id());
+            }
+        }
+        burst_canister_ids
+    }
+
     /// Iterates over all canisters on the subnet, checking if a source canister
     /// has output messages for a destination canister on the same subnet and
     /// moving them from the source to the destination canister if the
@@ -1102,6 +1135,8 @@ impl SchedulerImpl {
                         compute_allocation: Default::default(), // not used
                         long_execution_mode: canister.scheduler_state.long_execution_mode,
                         has_aborted_or_paused_execution: true,
+                        // Long executions are not boosted by burst priority.
+                        burst_priority_boost: Default::default(),
                     })
                 } else {
                     None
@@ -1146,13 +1181,20 @@ impl SchedulerImpl {
     }
 
     fn initialize_wasm_memory_limit(&self, state: &mut ReplicatedState) {
-        fn compute_default_wasm_memory_limit(default: NumBytes, usage: NumBytes) -> NumBytes {
+        fn compute_default_wasm_memory_limit(
+            default: NumBytes,
+            usage: NumBytes,
+            is_wasm64: bool,
+        ) -> NumBytes {
+            let hard_limit = if is_wasm64 {
+                MAX_WASM64_MEMORY_IN_BYTES
+            } else {
+                MAX_WASM_MEMORY_IN_BYTES
+            };
             // Returns the larger of the two:
             // - the default value
             // - the average between the current usage and the hard limit.
-            default.max(NumBytes::new(
-                MAX_WASM_MEMORY_IN_BYTES.saturating_add(usage.get()) / 2,
-            ))
+            default.max(NumBytes::new(hard_limit.saturating_add(usage.get()) / 2))
         }
 
         let default_wasm_memory_limit = self.exec_env.default_wasm_memory_limit();
@@ -1162,11 +1204,16 @@ impl SchedulerImpl {
                     .execution_state
                     .as_ref()
                     .map_or_else(|| NumWasmPages::new(0), |es| es.wasm_memory.size);
+                let is_wasm64 = canister
+                    .execution_state
+                    .as_ref()
+                    .map_or(false, |es| es.is_wasm64);
                 if let Ok(wasm_memory_usage) = num_bytes_try_from(num_wasm_pages) {
                     canister.system_state.wasm_memory_limit =
                         Some(compute_default_wasm_memory_limit(
                             default_wasm_memory_limit,
                             wasm_memory_usage,
+                            is_wasm64,
                         ));
                 }
             }
@@ -1461,6 +1508,11 @@ impl Scheduler for SchedulerImpl {
             scheduler_round_limits.update_subnet_round_limits(&subnet_round_limits);
         };
 
+        // Charge the burst priority fee before scheduling so that only the
+        // canisters that paid for this round are boosted.
+        let burst_canister_ids =
+            self.charge_canisters_for_burst_priority(&mut state, registry_settings.subnet_size);
+
         // Scheduling.
         let round_schedule = {
             let _timer = self.metrics.round_scheduling_duration.start_timer();
@@ -1471,6 +1523,7 @@ impl Scheduler for SchedulerImpl {
                 current_round,
                 self.config.accumulated_priority_reset_interval,
                 &mut state.canister_states,
+                &burst_canister_ids,
                 &self.metrics,
             )
         };
@@ -2225,7 +2278,11 @@ fn can_execute_subnet_msg(
         | Ic00Method::ClearChunkStore
         | Ic00Method::TakeCanisterSnapshot
         | Ic00Method::ListCanisterSnapshots
-        | Ic00Method::DeleteCanisterSnapshot => true,
+        | Ic00Method::DeleteCanisterSnapshot
+        | Ic00Method::ReadCanisterSnapshotMetadata
+        | Ic00Method::ReadCanisterSnapshotData
+        | Ic00Method::UploadCanisterSnapshotMetadata
+        | Ic00Method::UploadCanisterSnapshotData => true,
     }
 }
 
@@ -2293,7 +2350,11 @@ fn get_instructions_limits_for_subnet_message(
             | TakeCanisterSnapshot
             | LoadCanisterSnapshot
             | ListCanisterSnapshots
-            | DeleteCanisterSnapshot => default_limits,
+            | DeleteCanisterSnapshot
+            | ReadCanisterSnapshotMetadata
+            | ReadCanisterSnapshotData
+            | UploadCanisterSnapshotMetadata
+            | UploadCanisterSnapshotData => default_limits,
             InstallCode | InstallChunkedCode => InstructionLimits::new(
                 dts,
                 config.max_instructions_per_install_code,
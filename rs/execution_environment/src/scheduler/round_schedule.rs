@@ -13,6 +13,12 @@ use crate::{
 
 use super::SchedulerMetrics;
 
+/// The priority boost, in percent of a full round, that a canister in the
+/// burst priority class gets in a round for which it paid the burst priority
+/// fee. The boost is bounded, so a canister with a compute allocation whose
+/// accumulated priority exceeds the boost is still scheduled first.
+const BURST_PRIORITY_BOOST_PERCENT: i64 = 10;
+
 /// Round metrics required to prioritize a canister.
 #[derive(Clone, Debug)]
 pub(super) struct CanisterRoundState {
@@ -27,6 +33,10 @@ pub(super) struct CanisterRoundState {
     /// True when there is an aborted or paused long update execution.
     /// Note: this doesn't include paused or aborted install codes.
     pub(super) has_aborted_or_paused_execution: bool,
+    /// Priority boost of a canister that paid the burst priority fee for this
+    /// round. It only affects the order in this round and is never added to
+    /// the accumulated priority.
+    pub(super) burst_priority_boost: AccumulatedPriority,
 }
 
 /// Represents three ordered active Canister ID groups to schedule.
@@ -362,7 +372,7 @@ impl RoundSchedule {
             (
                 std::cmp::Reverse(rs.long_execution_mode),
                 std::cmp::Reverse(rs.has_aborted_or_paused_execution),
-                std::cmp::Reverse(rs.accumulated_priority),
+                std::cmp::Reverse(rs.accumulated_priority + rs.burst_priority_boost),
                 rs.canister_id,
             )
         });
@@ -373,12 +383,16 @@ impl RoundSchedule {
     ///
     /// A shorter description of the scheduling strategy is available in the note
     /// section about [Scheduler and AccumulatedPriority] in types/src/lib.rs
+    ///
+    /// Canisters in `burst_canister_ids` have paid for burst priority in this
+    /// round, so their new executions are ordered with a bounded priority boost.
     pub(super) fn apply_scheduling_strategy(
         logger: &ReplicaLogger,
         scheduler_cores: usize,
         current_round: ExecutionRound,
         accumulated_priority_reset_interval: ExecutionRound,
         canister_states: &mut BTreeMap<CanisterId, CanisterState>,
+        burst_canister_ids: &BTreeSet<CanisterId>,
         metrics: &SchedulerMetrics,
     ) -> RoundSchedule {
         let number_of_canisters = canister_states.len();
@@ -429,12 +443,19 @@ impl RoundSchedule {
 
             let compute_allocation = canister.scheduler_state.compute_allocation;
             let accumulated_priority = canister.scheduler_state.accumulated_priority;
+            let burst_priority_boost =
+                if !has_aborted_or_paused_execution && burst_canister_ids.contains(&canister_id) {
+                    (BURST_PRIORITY_BOOST_PERCENT * multiplier).into()
+                } else {
+                    AccumulatedPriority::default()
+                };
             round_states.push(CanisterRoundState {
                 canister_id,
                 accumulated_priority,
                 compute_allocation,
                 long_execution_mode: canister.scheduler_state.long_execution_mode,
                 has_aborted_or_paused_execution,
+                burst_priority_boost,
             });
 
             total_compute_allocation_percent += compute_allocation.as_percent() as i64;
@@ -1201,6 +1201,7 @@ impl TestWasmExecutorCore {
                 instance_stats: InstanceStats::default(),
                 system_api_call_counters: SystemApiCallCounters::default(),
                 canister_log: Default::default(),
+                wasm_profile: Default::default(),
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -1243,6 +1244,7 @@ impl TestWasmExecutorCore {
             instance_stats,
             system_api_call_counters: SystemApiCallCounters::default(),
             canister_log: Default::default(),
+            wasm_profile: Default::default(),
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
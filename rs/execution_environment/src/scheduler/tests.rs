@@ -19,8 +19,8 @@ use ic_interfaces::execution_environment::SubnetAvailableMemory;
 use ic_logger::replica_logger::no_op_logger;
 use ic_management_canister_types::{
     self as ic00, BoundedHttpHeaders, CanisterHttpResponsePayload, CanisterIdRecord,
-    CanisterStatusType, DerivationPath, EcdsaKeyId, EmptyBlob, Method, Payload as _, SchnorrKeyId,
-    SignWithSchnorrArgs, TakeCanisterSnapshotArgs, UninstallCodeArgs,
+    CanisterPriorityClass, CanisterStatusType, DerivationPath, EcdsaKeyId, EmptyBlob, Method,
+    Payload as _, SchnorrKeyId, SignWithSchnorrArgs, TakeCanisterSnapshotArgs, UninstallCodeArgs,
 };
 use ic_registry_routing_table::CanisterIdRange;
 use ic_registry_subnet_type::SubnetType;
@@ -3725,6 +3725,138 @@ fn scheduler_maintains_canister_order() {
     }
 }
 
+#[test]
+fn burst_priority_canister_is_scheduled_first_and_pays_the_fee() {
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            max_instructions_per_round: NumInstructions::from(100),
+            max_instructions_per_message: NumInstructions::from(100),
+            max_instructions_per_message_without_dts: NumInstructions::from(100),
+            max_instructions_per_slice: NumInstructions::from(100),
+            instruction_overhead_per_execution: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(0),
+            ..SchedulerConfig::application_subnet()
+        })
+        .build();
+
+    // All canisters have the same accumulated priority, so without burst
+    // priority they would be ordered by canister ID.
+    let standard_a = test.create_canister();
+    let standard_b = test.create_canister();
+    let burst = test.create_canister();
+    test.canister_state_mut(burst)
+        .scheduler_state
+        .priority_class = CanisterPriorityClass::Burst;
+    for canister_id in [standard_a, standard_b, burst] {
+        test.send_ingress(canister_id, ingress(100));
+    }
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    // Each core executes only one message, so only the first canister on each
+    // core makes progress.
+    let executed: BTreeSet<_> = test
+        .executed_schedule()
+        .into_iter()
+        .map(|(_round, canister_id, _num_instructions)| canister_id)
+        .collect();
+    assert_eq!(executed, BTreeSet::from([burst, standard_a]));
+
+    let fee = test
+        .scheduler()
+        .cycles_account_manager
+        .burst_priority_round_fee(test.subnet_size());
+    assert_eq!(
+        test.canister_state(burst)
+            .system_state
+            .canister_metrics
+            .get_consumed_cycles_by_use_cases()
+            .get(&CyclesUseCase::SchedulingPriority),
+        Some(&NominalCycles::from(fee))
+    );
+    assert_eq!(
+        test.canister_state(standard_a)
+            .system_state
+            .canister_metrics
+            .get_consumed_cycles_by_use_cases()
+            .get(&CyclesUseCase::SchedulingPriority),
+        None
+    );
+}
+
+#[test]
+fn burst_priority_does_not_override_compute_allocation() {
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            max_instructions_per_round: NumInstructions::from(100),
+            max_instructions_per_message: NumInstructions::from(100),
+            max_instructions_per_message_without_dts: NumInstructions::from(100),
+            max_instructions_per_slice: NumInstructions::from(100),
+            instruction_overhead_per_execution: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(0),
+            ..SchedulerConfig::application_subnet()
+        })
+        .build();
+
+    // There are more burst canisters than scheduler cores, so if burst
+    // priority took precedence, the canister with a compute allocation would
+    // never be executed.
+    let num_rounds = 10;
+    let allocated = test.create_canister_with(
+        Cycles::new(1_000_000_000_000_000),
+        ComputeAllocation::try_from(50).unwrap(),
+        MemoryAllocation::BestEffort,
+        None,
+        None,
+        None,
+    );
+    let burst_a = test.create_canister();
+    let burst_b = test.create_canister();
+    for canister_id in [burst_a, burst_b] {
+        test.canister_state_mut(canister_id)
+            .scheduler_state
+            .priority_class = CanisterPriorityClass::Burst;
+    }
+    for canister_id in [allocated, burst_a, burst_b] {
+        for _ in 0..num_rounds {
+            test.send_ingress(canister_id, ingress(100));
+        }
+    }
+
+    for _ in 0..num_rounds {
+        test.execute_round(ExecutionRoundType::OrdinaryRound);
+    }
+
+    // Each core executes only one message per round, so a 50% compute
+    // allocation guarantees an execution in at least every other round.
+    let allocated_rounds = test
+        .executed_schedule()
+        .into_iter()
+        .filter(|(_round, canister_id, _num_instructions)| *canister_id == allocated)
+        .count();
+    assert!(
+        allocated_rounds >= num_rounds / 2,
+        "Canister with compute allocation executed in {} of {} rounds",
+        allocated_rounds,
+        num_rounds
+    );
+    // Burst canisters still make progress and pay the fee.
+    for canister_id in [burst_a, burst_b] {
+        assert!(test
+            .executed_schedule()
+            .iter()
+            .any(|(_round, id, _num_instructions)| *id == canister_id));
+        assert!(test
+            .canister_state(canister_id)
+            .system_state
+            .canister_metrics
+            .get_consumed_cycles_by_use_cases()
+            .contains_key(&CyclesUseCase::SchedulingPriority));
+    }
+}
+
 #[test]
 fn threshold_signature_agreements_metric_is_updated() {
     let ecdsa_key_id = make_ecdsa_key_id(0);
@@ -1,10 +1,12 @@
 use ic_base_types::PrincipalId;
 use ic_config::execution_environment::Config as ExecutionConfig;
+use ic_config::flag_status::FlagStatus;
 use ic_config::subnet_config::SubnetConfig;
 use ic_management_canister_types::{
-    self as ic00, BoundedAllowedViewers, CanisterIdRecord, CanisterInstallMode, CanisterLogRecord,
-    CanisterSettingsArgs, CanisterSettingsArgsBuilder, DataSize, EmptyBlob,
-    FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibilityV2, Payload,
+    self as ic00, BoundedAllowedViewers, CanisterIdRecord, CanisterInstallMode, CanisterLogFilter,
+    CanisterLogLevel, CanisterLogRange, CanisterLogRecord, CanisterSettingsArgs,
+    CanisterSettingsArgsBuilder, DataSize, EmptyBlob, FetchCanisterLogsRequest,
+    FetchCanisterLogsResponse, LogVisibilityV2, Payload,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
@@ -14,7 +16,8 @@ use ic_test_utilities::universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_
 use ic_test_utilities_execution_environment::{get_reply, wat_canister, wat_fn};
 use ic_test_utilities_metrics::{fetch_histogram_stats, fetch_histogram_vec_stats, labels};
 use ic_types::{
-    ingress::WasmResult, CanisterId, Cycles, NumInstructions, MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE,
+    ingress::WasmResult, CanisterId, Cycles, NumInstructions, DEFAULT_CANISTER_LOG_BUFFER_SIZE,
+    MAX_CANISTER_LOG_BUFFER_SIZE,
 };
 use more_asserts::{assert_le, assert_lt};
 use proptest::{prelude::ProptestConfig, prop_assume};
@@ -41,8 +44,10 @@ fn canister_log_response(data: Vec<(u64, u64, Vec<u8>)>) -> FetchCanisterLogsRes
                 idx,
                 timestamp_nanos,
                 content,
+                ..Default::default()
             })
             .collect(),
+        next_page_idx: None,
     }
 }
 
@@ -198,6 +203,7 @@ fn test_fetch_canister_logs_via_query_call() {
         Ok(WasmResult::Reply(
             FetchCanisterLogsResponse {
                 canister_log_records: vec![],
+                next_page_idx: None,
             }
             .encode(),
         ))
@@ -282,6 +288,7 @@ fn test_log_visibility_of_fetch_canister_logs() {
     let ok = Ok(WasmResult::Reply(
         FetchCanisterLogsResponse {
             canister_log_records: vec![],
+            next_page_idx: None,
         }
         .encode(),
     ));
@@ -555,6 +562,57 @@ fn test_logging_explicit_canister_trap_with_message() {
     );
 }
 
+#[test]
+fn test_logging_trap_with_wasm_trace() {
+    let mut execution_config = ExecutionConfig::default();
+    execution_config.embedders_config.feature_flags.wasm_tracing = FlagStatus::Enabled;
+    let env = StateMachineBuilder::new()
+        .with_config(Some(StateMachineConfig::new(
+            SubnetConfig::new(SubnetType::Application),
+            execution_config,
+        )))
+        .with_checkpoints_enabled(false)
+        .build();
+    let controller = PrincipalId::new_user_test_id(42);
+    let canister_id = env.create_canister_with_cycles(
+        None,
+        Cycles::from(301_000_000_000_u128),
+        Some(
+            CanisterSettingsArgsBuilder::new()
+                .with_controllers(vec![controller])
+                .with_wasm_trace_length(2)
+                .build(),
+        ),
+    );
+    env.install_wasm_in_mode(
+        canister_id,
+        CanisterInstallMode::Install,
+        wat_canister()
+            .update(
+                "test",
+                wat_fn()
+                    .api_global_timer_set(1)
+                    .api_global_timer_set(2)
+                    .trap_with_blob(b"some text"),
+            )
+            .build_wasm(),
+        vec![],
+    )
+    .unwrap();
+
+    let _ = env.execute_ingress(canister_id, "test", vec![]);
+    let logs = readable_logs_without_backtraces(fetch_canister_logs(&env, controller, canister_id));
+
+    // Only the last two system API calls are kept, oldest first.
+    assert_eq!(logs.len(), 1);
+    let lines: Vec<_> = logs[0].2.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "[TRAP]: some text");
+    assert_eq!(lines[1], "Last 2 system API calls:");
+    assert!(lines[2].starts_with("GlobalTimerSet: "));
+    assert!(lines[3].starts_with("Trap: "));
+}
+
 #[test]
 fn test_canister_log_stays_within_limit() {
     // Test that the total size of canister log records stays within the limit
@@ -564,7 +622,7 @@ fn test_canister_log_stays_within_limit() {
         wat_canister()
             .update(
                 "test",
-                wat_fn().debug_print(&[42; MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE]),
+                wat_fn().debug_print(&[42; DEFAULT_CANISTER_LOG_BUFFER_SIZE]),
             )
             .build_wasm(),
     );
@@ -581,7 +639,7 @@ fn test_canister_log_stays_within_limit() {
             .iter()
             .map(|r| r.data_size())
             .sum::<usize>(),
-        MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE
+        DEFAULT_CANISTER_LOG_BUFFER_SIZE
     );
 }
 
@@ -595,8 +653,8 @@ fn test_canister_log_in_state_stays_within_limit() {
             .update(
                 "test",
                 wat_fn()
-                    .debug_print(&[b'd'; MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE])
-                    .trap_with_blob(&[b't'; MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE]),
+                    .debug_print(&[b'd'; DEFAULT_CANISTER_LOG_BUFFER_SIZE])
+                    .trap_with_blob(&[b't'; DEFAULT_CANISTER_LOG_BUFFER_SIZE]),
             )
             .build_wasm(),
     );
@@ -606,7 +664,276 @@ fn test_canister_log_in_state_stays_within_limit() {
     // Expect that the total size of the log in canister state is not zero and less than the limit.
     let log_size = env.canister_log(canister_id).used_space();
     assert_lt!(0, log_size);
-    assert_le!(log_size, MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+    assert_le!(log_size, DEFAULT_CANISTER_LOG_BUFFER_SIZE);
+}
+
+#[test]
+fn test_fetch_canister_logs_with_filter_and_pagination() {
+    let (env, canister_id, controller) = setup_with_controller(
+        wat_canister()
+            .update(
+                "test",
+                wat_fn()
+                    .debug_print_structured(0, b"", b"message 0")
+                    .debug_print_structured(1, b"init", b"message 1")
+                    // Content cannot forge a level.
+                    .debug_print(b"[error] message 2")
+                    .debug_print_structured(3, b"", b"message 3")
+                    .debug_print_structured(2, b"db,retry", b"message 4"),
+            )
+            .build_wasm(),
+    );
+    let _ = env.execute_ingress(canister_id, "test", vec![]);
+    let fetch = |request: FetchCanisterLogsRequest| -> (Vec<u64>, Option<u64>) {
+        let result = env.query_as(
+            controller,
+            CanisterId::ic_00(),
+            "fetch_canister_logs",
+            request.encode(),
+        );
+        let response = FetchCanisterLogsResponse::decode(&get_reply(result)).unwrap();
+        (
+            response
+                .canister_log_records
+                .iter()
+                .map(|r| r.idx)
+                .collect(),
+            response.next_page_idx,
+        )
+    };
+
+    // By level, fetching two records at a time.
+    let mut filter = CanisterLogFilter {
+        min_level: Some(CanisterLogLevel::Info),
+        ..Default::default()
+    };
+    let request = FetchCanisterLogsRequest::new(canister_id).with_max_records(2);
+    assert_eq!(
+        fetch(request.clone().with_filter(filter.clone())),
+        (vec![1, 3], Some(4))
+    );
+    filter.idx_range = Some(CanisterLogRange::new(4, u64::MAX));
+    assert_eq!(fetch(request.clone().with_filter(filter)), (vec![4], None));
+
+    // By tag.
+    let filter = CanisterLogFilter {
+        tag: Some("db".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        fetch(FetchCanisterLogsRequest::new(canister_id).with_filter(filter)),
+        (vec![4], None)
+    );
+
+    // By index range.
+    let filter = CanisterLogFilter {
+        idx_range: Some(CanisterLogRange::new(1, 3)),
+        ..Default::default()
+    };
+    assert_eq!(
+        fetch(FetchCanisterLogsRequest::new(canister_id).with_filter(filter)),
+        (vec![1, 2], None)
+    );
+
+    // By time range, which excludes all records.
+    let now = system_time_to_nanos(env.time());
+    let filter = CanisterLogFilter {
+        timestamp_nanos_range: Some(CanisterLogRange::new(now + 1, u64::MAX)),
+        ..Default::default()
+    };
+    assert_eq!(
+        fetch(FetchCanisterLogsRequest::new(canister_id).with_filter(filter)),
+        (vec![], None)
+    );
+}
+
+#[test]
+fn test_canister_log_buffer_size_setting() {
+    const MESSAGES_NUMBER: usize = 40;
+    const MESSAGE_SIZE: usize = 1024;
+    let (env, canister_id, controller) = setup_with_controller(
+        wat_canister()
+            .update("test", wat_fn().debug_print(&[42; MESSAGE_SIZE]))
+            .build_wasm(),
+    );
+    let log_buffer_size = |env: &StateMachine| {
+        env.canister_status_as(controller, canister_id)
+            .unwrap()
+            .unwrap()
+            .settings()
+            .log_buffer_size()
+    };
+    assert_eq!(
+        log_buffer_size(&env),
+        candid::Nat::from(DEFAULT_CANISTER_LOG_BUFFER_SIZE)
+    );
+
+    // Values outside of the allowed range are rejected.
+    for invalid_size in [
+        DEFAULT_CANISTER_LOG_BUFFER_SIZE - 1,
+        MAX_CANISTER_LOG_BUFFER_SIZE + 1,
+    ] {
+        let err = env
+            .update_settings(
+                &canister_id,
+                CanisterSettingsArgsBuilder::new()
+                    .with_log_buffer_size(invalid_size as u64)
+                    .build(),
+            )
+            .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    }
+
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_log_buffer_size(MAX_CANISTER_LOG_BUFFER_SIZE as u64)
+            .build(),
+    )
+    .unwrap();
+    assert_eq!(
+        log_buffer_size(&env),
+        candid::Nat::from(MAX_CANISTER_LOG_BUFFER_SIZE)
+    );
+
+    for _ in 0..MESSAGES_NUMBER {
+        let _ = env.execute_ingress(canister_id, "test", vec![]);
+    }
+    // All records fit into the larger buffer.
+    let log = env.canister_log(canister_id);
+    assert_eq!(log.records().len(), MESSAGES_NUMBER);
+    assert_le!(log.used_space(), MAX_CANISTER_LOG_BUFFER_SIZE);
+
+    // Shrinking the buffer drops the oldest records.
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_log_buffer_size(DEFAULT_CANISTER_LOG_BUFFER_SIZE as u64)
+            .build(),
+    )
+    .unwrap();
+    let log = env.canister_log(canister_id);
+    assert_lt!(log.records().len(), MESSAGES_NUMBER);
+    assert_le!(log.used_space(), DEFAULT_CANISTER_LOG_BUFFER_SIZE);
+    assert_eq!(
+        log.records().back().unwrap().idx,
+        MESSAGES_NUMBER as u64 - 1
+    );
+}
+
+#[test]
+fn test_structured_log_records() {
+    let (env, canister_id, controller) = setup_with_controller(
+        wat_canister()
+            .update(
+                "test",
+                wat_fn()
+                    .debug_print(b"plain")
+                    .debug_print_structured(2, b"db,retry", b"structured")
+                    .trap_with_blob(b"boom"),
+            )
+            .build_wasm(),
+    );
+    let _ = env.execute_ingress(canister_id, "test", vec![]);
+    let result = fetch_canister_logs(&env, controller, canister_id);
+    let records = FetchCanisterLogsResponse::decode(&get_reply(result))
+        .unwrap()
+        .canister_log_records;
+    let structure: Vec<_> = records
+        .iter()
+        .map(|r| (r.idx, r.level, r.tags.clone()))
+        .collect();
+    assert_eq!(
+        structure,
+        vec![
+            (0, None, None),
+            (
+                1,
+                Some(CanisterLogLevel::Warning),
+                Some(vec!["db".to_string(), "retry".to_string()])
+            ),
+            (2, Some(CanisterLogLevel::Error), None),
+        ]
+    );
+}
+
+#[test]
+fn test_structured_log_with_invalid_arguments_traps() {
+    for (level, tags) in [
+        (4, b"db".as_slice()),
+        (-1, b"db".as_slice()),
+        (1, b"db,".as_slice()),
+        (1, b"has space".as_slice()),
+        (1, b"\xff".as_slice()),
+    ] {
+        let (env, canister_id) = setup_and_install_wasm(
+            CanisterSettingsArgsBuilder::new().build(),
+            wat_canister()
+                .update(
+                    "test",
+                    wat_fn().debug_print_structured(level, tags, b"message"),
+                )
+                .build_wasm(),
+        );
+        let err = env
+            .execute_ingress(canister_id, "test", vec![])
+            .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    }
+}
+
+#[test]
+fn test_log_buffer_is_charged_to_canister_memory() {
+    let (env, canister_id, _controller) =
+        setup_with_controller(wat_canister().update("test", wat_fn()).build_wasm());
+    let memory_usage = |env: &StateMachine| {
+        env.get_latest_state()
+            .canister_state(&canister_id)
+            .unwrap()
+            .memory_usage()
+            .get()
+    };
+    let initial_usage = memory_usage(&env);
+
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_log_buffer_size(MAX_CANISTER_LOG_BUFFER_SIZE as u64)
+            .build(),
+    )
+    .unwrap();
+    assert_eq!(
+        memory_usage(&env),
+        initial_usage + (MAX_CANISTER_LOG_BUFFER_SIZE - DEFAULT_CANISTER_LOG_BUFFER_SIZE) as u64
+    );
+
+    // Shrinking the buffer releases the memory.
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_log_buffer_size(DEFAULT_CANISTER_LOG_BUFFER_SIZE as u64)
+            .build(),
+    )
+    .unwrap();
+    assert_eq!(memory_usage(&env), initial_usage);
+
+    // A larger buffer must fit into the reserved memory allocation.
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_memory_allocation(initial_usage)
+            .build(),
+    )
+    .unwrap();
+    let err = env
+        .update_settings(
+            &canister_id,
+            CanisterSettingsArgsBuilder::new()
+                .with_log_buffer_size(MAX_CANISTER_LOG_BUFFER_SIZE as u64)
+                .build(),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InsufficientMemoryAllocation);
 }
 
 #[test]
@@ -11,10 +11,12 @@ use ic_config::{
 };
 use ic_management_canister_types::{
     CanisterIdRecord, CanisterInfoRequest, CanisterInstallMode, CanisterInstallModeV2,
-    CanisterSettingsArgsBuilder, ClearChunkStoreArgs, DeleteCanisterSnapshotArgs, EmptyBlob,
-    InstallChunkedCodeArgs, InstallCodeArgs, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs,
-    Method, Payload, StoredChunksArgs, TakeCanisterSnapshotArgs, UninstallCodeArgs,
-    UpdateSettingsArgs, UploadChunkArgs, IC_00,
+    CanisterSettingsArgsBuilder, CanisterSnapshotDataKind, CanisterSnapshotDataOffset,
+    ClearChunkStoreArgs, DeleteCanisterSnapshotArgs, EmptyBlob, InstallChunkedCodeArgs,
+    InstallCodeArgs, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, Method, Payload,
+    ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotMetadataArgs, StoredChunksArgs,
+    TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs,
+    UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs, UploadChunkArgs, IC_00,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::{execution_state::NextScheduledMethod, NextExecution};
@@ -23,7 +25,7 @@ use ic_types::ingress::{IngressState, IngressStatus};
 use ic_types::messages::MessageId;
 use ic_types::{ingress::WasmResult, CryptoHashOfState, Cycles, NumInstructions};
 use ic_universal_canister::{call_args, wasm, CallArgs, UNIVERSAL_CANISTER_WASM};
-use more_asserts::assert_ge;
+use more_asserts::{assert_ge, assert_gt};
 use strum::IntoEnumIterator;
 
 const INITIAL_CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
@@ -1270,6 +1272,46 @@ fn dts_aborted_execution_does_not_block_subnet_messages() {
                 .encode();
                 (method, call_args().other_side(args))
             }),
+            Method::ReadCanisterSnapshotMetadata => test_supported(|aborted_canister_id| {
+                let args = ReadCanisterSnapshotMetadataArgs::new(
+                    aborted_canister_id,
+                    (aborted_canister_id, 0).into(),
+                )
+                .encode();
+                (method, call_args().other_side(args))
+            }),
+            Method::ReadCanisterSnapshotData => test_supported(|aborted_canister_id| {
+                let args = ReadCanisterSnapshotDataArgs::new(
+                    aborted_canister_id,
+                    (aborted_canister_id, 0).into(),
+                    CanisterSnapshotDataKind::WasmModule { offset: 0, size: 0 },
+                )
+                .encode();
+                (method, call_args().other_side(args))
+            }),
+            Method::UploadCanisterSnapshotMetadata => test_supported(|aborted_canister_id| {
+                let args = UploadCanisterSnapshotMetadataArgs::new(
+                    aborted_canister_id,
+                    None,
+                    0,
+                    vec![],
+                    0,
+                    0,
+                    vec![],
+                )
+                .encode();
+                (method, call_args().other_side(args))
+            }),
+            Method::UploadCanisterSnapshotData => test_supported(|aborted_canister_id| {
+                let args = UploadCanisterSnapshotDataArgs::new(
+                    aborted_canister_id,
+                    (aborted_canister_id, 0).into(),
+                    CanisterSnapshotDataOffset::WasmModule { offset: 0 },
+                    vec![],
+                )
+                .encode();
+                (method, call_args().other_side(args))
+            }),
         }
     }
 }
@@ -1884,6 +1926,78 @@ fn dts_ingress_status_of_install_is_correct() {
     env.await_ingress(install, 30).unwrap();
 }
 
+#[test]
+fn dts_upgrade_dry_run_reports_phases_and_discards_changes() {
+    if should_skip_test_due_to_disabled_dts() {
+        // Skip this test if DTS is not supported.
+        return;
+    }
+
+    let slice_instruction_limit = NumInstructions::from(10_000);
+    let env = dts_env(
+        NumInstructions::from(1_000_000_000),
+        slice_instruction_limit,
+    );
+    let binary = wat2wasm(DTS_WAT);
+    let canister = env
+        .install_canister_with_cycles(binary.clone(), vec![], None, INITIAL_CYCLES_BALANCE)
+        .unwrap();
+    let canister_version = |env: &StateMachine, canister: CanisterId| {
+        env.get_latest_state()
+            .canister_state(&canister)
+            .unwrap()
+            .system_state
+            .canister_version
+    };
+    let version_before = canister_version(&env, canister);
+    // A message of another canister is pending during the dry run.
+    let other = env
+        .install_canister_with_cycles(binary.clone(), vec![], None, INITIAL_CYCLES_BALANCE)
+        .unwrap();
+    let other_update = env.send_ingress(PrincipalId::new_anonymous(), other, "update", vec![]);
+    let height_before = env.get_latest_state().metadata.batch_processed_height;
+
+    let dry_run = env.upgrade_canister_dry_run(canister, binary.clone(), vec![]);
+    assert_eq!(dry_run.result, Ok(()));
+    // Every phase calls `$work`, which exceeds the slice limit, but the dry
+    // run is only bounded by the message limit.
+    assert_gt!(dry_run.pre_upgrade_instructions, slice_instruction_limit);
+    assert_gt!(dry_run.start_instructions, slice_instruction_limit);
+    assert_gt!(dry_run.post_upgrade_instructions, slice_instruction_limit);
+    assert_gt!(dry_run.memory_usage_before.get(), 0);
+    assert_gt!(dry_run.memory_usage_after.get(), 0);
+    // The dry run ran on a copy of the state and did not execute any round.
+    assert_eq!(canister_version(&env, canister), version_before);
+    assert_eq!(
+        env.get_latest_state().metadata.batch_processed_height,
+        height_before
+    );
+    assert_eq!(
+        env.await_ingress(other_update, 100),
+        Ok(WasmResult::Reply(vec![]))
+    );
+
+    // With an instruction limit that is too low for `canister_post_upgrade`,
+    // the dry run reports the failure instead of the upgrade failing later.
+    let message_instruction_limit = dry_run.pre_upgrade_instructions
+        + dry_run.start_instructions
+        + NumInstructions::from(dry_run.post_upgrade_instructions.get() / 2);
+    let env = dts_env(message_instruction_limit, slice_instruction_limit);
+    let canister = env
+        .install_canister_with_cycles(binary.clone(), vec![], None, INITIAL_CYCLES_BALANCE)
+        .unwrap();
+    let version_before = canister_version(&env, canister);
+
+    let dry_run = env.upgrade_canister_dry_run(canister, binary, vec![]);
+    assert_eq!(
+        dry_run.result.unwrap_err().code(),
+        ErrorCode::CanisterInstructionLimitExceeded
+    );
+    assert_gt!(dry_run.post_upgrade_instructions, NumInstructions::from(0));
+    assert_eq!(dry_run.memory_usage_after, dry_run.memory_usage_before);
+    assert_eq!(canister_version(&env, canister), version_before);
+}
+
 #[test]
 fn dts_ingress_status_of_upgrade_is_correct() {
     if should_skip_test_due_to_disabled_dts() {
@@ -2645,6 +2645,68 @@ fn set_wasm_memory_limit_from_another_canister() {
     assert_eq!(result, WasmResult::Reply(vec![1, 2, 3]));
 }
 
+#[test]
+fn wasm64_canister_grows_wasm_memory_beyond_4_gib() {
+    const GIB: u64 = 1024 * MIB;
+    let env = StateMachineBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+
+    // `grow_to_5_gib` grows the memory from 1 to 81920 Wasm pages (5 GiB),
+    // stores a value in the last 8 bytes and replies with it.
+    let wat = r#"(module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "msg_reply_data_append"
+            (func $msg_reply_data_append (param i64 i64)))
+        (func (export "canister_update grow_to_5_gib")
+            (drop (memory.grow (i64.const 81919)))
+            (i64.store (i64.const 5368709112) (i64.const 42))
+            (call $msg_reply_data_append (i64.const 5368709112) (i64.const 8))
+            (call $msg_reply)
+        )
+        (func (export "canister_update grow_by_1_gib")
+            (drop (memory.grow (i64.const 16384)))
+            (call $msg_reply)
+        )
+        (memory $memory i64 1)
+    )"#;
+
+    let canister_id = env
+        .install_canister_with_cycles(
+            wat::parse_str(wat).unwrap(),
+            vec![],
+            Some(
+                CanisterSettingsArgsBuilder::new()
+                    .with_wasm_memory_limit(5 * GIB + GIB / 2)
+                    .build(),
+            ),
+            Cycles::new(1_000_000 * B),
+        )
+        .unwrap();
+
+    let result = env
+        .execute_ingress(canister_id, "grow_to_5_gib", vec![])
+        .unwrap();
+    assert_eq!(result, WasmResult::Reply(42_u64.to_le_bytes().to_vec()));
+    assert_eq!(
+        env.get_latest_state()
+            .canister_state(&canister_id)
+            .unwrap()
+            .execution_state
+            .as_ref()
+            .unwrap()
+            .wasm_memory
+            .size,
+        NumWasmPages::new(81920)
+    );
+
+    // Growing beyond the Wasm memory limit fails.
+    let err = env
+        .execute_ingress(canister_id, "grow_by_1_gib", vec![])
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmMemoryLimitExceeded);
+}
+
 #[test]
 fn canister_create_with_default_wasm_memory_limit() {
     let env = StateMachineBuilder::new()
@@ -410,6 +410,103 @@ fn test_sign_with_threshold_key_fee_charged() {
     }
 }
 
+/// A canister replying with the cost of signing with the key `some_key`, as
+/// reported by `ic0.cost_sign_with_ecdsa` and `ic0.cost_sign_with_schnorr`.
+const COST_SIGN_WITH_KEY_WAT: &str = r#"
+(module
+  (import "ic0" "cost_sign_with_ecdsa"
+    (func $cost_sign_with_ecdsa (param i32 i32 i32 i32) (result i32)))
+  (import "ic0" "cost_sign_with_schnorr"
+    (func $cost_sign_with_schnorr (param i32 i32 i32 i32) (result i32)))
+  (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+  (import "ic0" "msg_reply" (func $msg_reply))
+  (memory 1)
+  (data (i32.const 0) "some_key")
+  (func $reply_with_cost (param $result i32)
+    (if (i32.ne (local.get $result) (i32.const 0)) (then unreachable))
+    (call $msg_reply_data_append (i32.const 16) (i32.const 16))
+    (call $msg_reply))
+  ;; Secp256k1 is curve 0.
+  (func (export "canister_update cost_sign_with_ecdsa")
+    (call $reply_with_cost
+      (call $cost_sign_with_ecdsa (i32.const 0) (i32.const 8) (i32.const 0) (i32.const 16))))
+  ;; Ed25519 is algorithm 1.
+  (func (export "canister_update cost_sign_with_schnorr")
+    (call $reply_with_cost
+      (call $cost_sign_with_schnorr (i32.const 0) (i32.const 8) (i32.const 1) (i32.const 16))))
+)"#;
+
+#[test]
+fn test_cost_sign_with_threshold_key_matches_fee_charged() {
+    let test_cases = vec![
+        (
+            Method::SignWithECDSA,
+            make_ecdsa_key("some_key"),
+            "cost_sign_with_ecdsa",
+        ),
+        (
+            Method::SignWithSchnorr,
+            make_schnorr_key("some_key"),
+            "cost_sign_with_schnorr",
+        ),
+    ];
+    for (method, key_id, cost_method) in test_cases {
+        let own_subnet = subnet_test_id(1);
+        let nns_subnet = subnet_test_id(2);
+        // Use a subnet size different from the reference subnet size, so that
+        // the fees are scaled.
+        let mut env = StateMachineBuilder::new()
+            .with_checkpoints_enabled(false)
+            .with_subnet_id(own_subnet)
+            .with_nns_subnet_id(nns_subnet)
+            .with_subnet_size(34)
+            .with_ecdsa_signature_fee(1_300_000)
+            .with_schnorr_signature_fee(1_300_000)
+            .with_chain_key(key_id.clone())
+            .build();
+
+        let cost_canister_id = env.install_canister_wat(COST_SIGN_WITH_KEY_WAT, vec![], None);
+        let cost = match env
+            .execute_ingress(cost_canister_id, cost_method, vec![])
+            .unwrap()
+        {
+            WasmResult::Reply(bytes) => Cycles::new(u128::from_le_bytes(bytes.try_into().unwrap())),
+            WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+        };
+        assert!(cost > Cycles::zero());
+
+        let canister_id = create_universal_canister(&env);
+        let payment = cost + Cycles::new(1_000_000);
+        env.send_ingress(
+            PrincipalId::new_anonymous(),
+            canister_id,
+            "update",
+            wasm()
+                .call_with_cycles(
+                    ic00::IC_00,
+                    method,
+                    call_args().other_side(sign_with_threshold_key_payload(method, key_id)),
+                    payment,
+                )
+                .build(),
+        );
+
+        // Disable automatic signing to be able to read the request payment value.
+        env.set_ecdsa_signing_enabled(false);
+        env.set_schnorr_signing_enabled(false);
+        env.tick();
+
+        // Assert that the fee charged is the cost reported to the canister.
+        let contexts = match method {
+            Method::SignWithECDSA => env.sign_with_ecdsa_contexts(),
+            Method::SignWithSchnorr => env.sign_with_schnorr_contexts(),
+            _ => panic!("Unexpected method"),
+        };
+        let (_, context) = contexts.iter().next().unwrap();
+        assert_eq!(payment - context.request.payment, cost);
+    }
+}
+
 #[test]
 fn test_sign_with_threshold_key_rejected_without_fee() {
     let test_cases = vec![
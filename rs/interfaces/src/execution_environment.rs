@@ -157,6 +157,18 @@ pub enum SystemApiCallId {
     CanisterVersion,
     /// Tracker for `ic0.certified_data_set()`
     CertifiedDataSet,
+    /// Tracker for `ic0.cost_call()`
+    CostCall,
+    /// Tracker for `ic0.cost_create_canister()`
+    CostCreateCanister,
+    /// Tracker for `ic0.cost_http_request()`
+    CostHttpRequest,
+    /// Tracker for `ic0.cost_sign_with_ecdsa()`
+    CostSignWithEcdsa,
+    /// Tracker for `ic0.cost_sign_with_schnorr()`
+    CostSignWithSchnorr,
+    /// Tracker for `ic0.cost_vetkd_derive_key()`
+    CostVetkdDeriveKey,
     /// Tracker for `ic0.cycles_burn128()`
     CyclesBurn128,
     /// Tracker for `ic0.data_certificate_copy()`
@@ -167,6 +179,18 @@ pub enum SystemApiCallId {
     DataCertificateSize,
     /// Tracker for `ic0.debug_print()`
     DebugPrint,
+    /// Tracker for `ic0.env_var_count()`
+    EnvVarCount,
+    /// Tracker for `ic0.env_var_name_copy()`
+    EnvVarNameCopy,
+    /// Tracker for `ic0.env_var_name_exists()`
+    EnvVarNameExists,
+    /// Tracker for `ic0.env_var_name_size()`
+    EnvVarNameSize,
+    /// Tracker for `ic0.env_var_value_copy()`
+    EnvVarValueCopy,
+    /// Tracker for `ic0.env_var_value_size()`
+    EnvVarValueSize,
     /// Tracker for `ic0.global_timer_set()`
     GlobalTimerSet,
     /// Tracker for `ic0.in_replicated_execution()`
@@ -219,6 +243,10 @@ pub enum SystemApiCallId {
     OutOfInstructions,
     /// Tracker for `ic0.performance_counter()`
     PerformanceCounter,
+    /// Tracker for `ic0.root_key_copy()`
+    RootKeyCopy,
+    /// Tracker for `ic0.root_key_size()`
+    RootKeySize,
     /// Tracker for `ic0.stable64_grow()`
     Stable64Grow,
     /// Tracker for `ic0.stable64_read()`
@@ -235,6 +263,10 @@ pub enum SystemApiCallId {
     StableSize,
     /// Tracker for `ic0.stable_write())`
     StableWrite,
+    /// Tracker for `ic0.subnet_self_copy()`
+    SubnetSelfCopy,
+    /// Tracker for `ic0.subnet_self_size()`
+    SubnetSelfSize,
     /// Tracker for `ic0.time()`
     Time,
     /// Tracker for `ic0.trap()`
@@ -1172,6 +1204,158 @@ pub trait SystemApi {
         dst: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
+
+    /// Writes the number of cycles that an inter-canister call with a method
+    /// name of `method_name_size` bytes and an argument of `payload_size` bytes
+    /// would cost to the canister memory at `dst` as a 128-bit value.
+    ///
+    /// The cost includes the prepayments for the transmission and execution
+    /// of the largest possible response.
+    fn ic0_cost_call(
+        &self,
+        method_name_size: u64,
+        payload_size: u64,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Writes the number of cycles that creating a canister would cost to the
+    /// canister memory at `dst` as a 128-bit value.
+    fn ic0_cost_create_canister(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()>;
+
+    /// Writes the number of cycles that an HTTPS outcall of `request_size`
+    /// bytes with a response limit of `max_res_bytes` bytes would cost to the
+    /// canister memory at `dst` as a 128-bit value.
+    fn ic0_cost_http_request(
+        &self,
+        request_size: u64,
+        max_res_bytes: u64,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Writes the number of cycles that signing with the ECDSA key named by
+    /// `src`+`size` on the given curve would cost to the canister memory at
+    /// `dst` as a 128-bit value.
+    ///
+    /// Returns 0 on success, 1 if the curve is unknown and 2 if no such key
+    /// is enabled, in which case nothing is written.
+    fn ic0_cost_sign_with_ecdsa(
+        &self,
+        src: usize,
+        size: usize,
+        ecdsa_curve: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32>;
+
+    /// Same as `ic0_cost_sign_with_ecdsa`, but for Schnorr keys of the given
+    /// algorithm.
+    fn ic0_cost_sign_with_schnorr(
+        &self,
+        src: usize,
+        size: usize,
+        algorithm: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32>;
+
+    /// Same as `ic0_cost_sign_with_ecdsa`, but for deriving a key with the
+    /// vetKD key of the given curve.
+    fn ic0_cost_vetkd_derive_key(
+        &self,
+        src: usize,
+        size: usize,
+        vetkd_curve: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32>;
+
+    /// Returns the number of environment variables of the canister.
+    fn ic0_env_var_count(&self) -> HypervisorResult<usize>;
+
+    /// Returns the size of the name of the environment variable at `index`,
+    /// where variables are ordered by name.
+    ///
+    /// This system call traps if `index` is out of range.
+    fn ic0_env_var_name_size(&self, index: usize) -> HypervisorResult<usize>;
+
+    /// Copies `size` bytes starting from `offset` in the name of the
+    /// environment variable at `index` to heap[dst..dst+size].
+    ///
+    /// This system call traps if `index` is out of range.
+    fn ic0_env_var_name_copy(
+        &self,
+        index: usize,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Checks whether an environment variable with the name stored in
+    /// heap[name_src..name_src+name_size] exists. If yes, then a value of 1
+    /// is returned, otherwise a 0 is returned.
+    fn ic0_env_var_name_exists(
+        &self,
+        name_src: usize,
+        name_size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<u32>;
+
+    /// Returns the size of the value of the environment variable with the
+    /// name stored in heap[name_src..name_src+name_size].
+    ///
+    /// This system call traps if there is no such variable.
+    fn ic0_env_var_value_size(
+        &self,
+        name_src: usize,
+        name_size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<usize>;
+
+    /// Copies `size` bytes starting from `offset` in the value of the
+    /// environment variable with the name stored in
+    /// heap[name_src..name_src+name_size] to heap[dst..dst+size].
+    ///
+    /// This system call traps if there is no such variable.
+    fn ic0_env_var_value_copy(
+        &self,
+        name_src: usize,
+        name_size: usize,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Returns the size of the blob corresponding to the id of the subnet
+    /// the canister is running on.
+    fn ic0_subnet_self_size(&self) -> HypervisorResult<usize>;
+
+    /// Copies `size` bytes starting from `offset` in the id blob of the
+    /// subnet the canister is running on to heap[dst..dst+size].
+    fn ic0_subnet_self_copy(
+        &self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Returns the size of the DER-encoded public key of the IC, which can
+    /// be used to verify certificates.
+    fn ic0_root_key_size(&self) -> HypervisorResult<usize>;
+
+    /// Copies `size` bytes starting from `offset` in the DER-encoded public
+    /// key of the IC to heap[dst..dst+size].
+    fn ic0_root_key_copy(
+        &self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -1278,6 +1462,13 @@ pub trait Scheduler: Send {
     ) -> Self::State;
 }
 
+/// The instructions executed by a Wasm execution attributed to the call stacks
+/// in which they were executed. Every call stack is given by the indices
+/// of the functions in the canister module from the outermost to the innermost
+/// function and the instructions are those executed by the innermost function.
+/// The profile is only collected if Wasm profiling is enabled.
+pub type WasmProfile = Vec<(Vec<u32>, u64)>;
+
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct WasmExecutionOutput {
     pub wasm_result: Result<Option<WasmResult>, HypervisorError>,
@@ -1288,6 +1479,7 @@ pub struct WasmExecutionOutput {
     /// How many times each tracked System API call was invoked.
     pub system_api_call_counters: SystemApiCallCounters,
     pub canister_log: CanisterLog,
+    pub wasm_profile: WasmProfile,
 }
 
 impl fmt::Display for WasmExecutionOutput {
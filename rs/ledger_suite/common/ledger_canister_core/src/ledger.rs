@@ -82,14 +82,15 @@ pub trait LedgerTransaction: Sized {
     type Tokens: TokensType;
 
     /// Constructs a new "burn" transaction that removes the specified `amount` of tokens from the
-    /// `from` account.
+    /// `from` account. Returns `None` if the ledger cannot burn tokens by amount (e.g., because
+    /// its tokens are non-fungible), in which case the ledger never trims accounts.
     fn burn(
         from: Self::AccountId,
         spender: Option<Self::AccountId>,
         amount: Self::Tokens,
         at: Option<TimeStamp>,
         memo: Option<u64>,
-    ) -> Self;
+    ) -> Option<Self>;
 
     fn approve(
         from: Self::AccountId,
@@ -311,7 +312,11 @@ where
     };
 
     for (balance, account) in to_trim {
-        let burn_tx = L::Transaction::burn(account, None, balance, Some(now), Some(TRIMMED_MEMO));
+        let Some(burn_tx) =
+            L::Transaction::burn(account, None, balance, Some(now), Some(TRIMMED_MEMO))
+        else {
+            break;
+        };
 
         burn_tx
             .apply(ledger, now, L::Tokens::zero())
@@ -246,8 +246,8 @@ impl LedgerTransaction for Transaction {
         amount: Tokens,
         created_at_time: Option<TimeStamp>,
         memo: Option<u64>,
-    ) -> Self {
-        Self {
+    ) -> Option<Self> {
+        Some(Self {
             operation: Operation::Burn {
                 from,
                 amount,
@@ -256,7 +256,7 @@ impl LedgerTransaction for Transaction {
             memo: memo.map(Memo).unwrap_or_default(),
             icrc1_memo: None,
             created_at_time,
-        }
+        })
     }
 
     fn approve(
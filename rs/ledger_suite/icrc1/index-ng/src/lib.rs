@@ -1,6 +1,6 @@
 use candid::{CandidType, Deserialize, Nat, Principal};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
-use icrc_ledger_types::icrc1::transfer::BlockIndex;
+use icrc_ledger_types::icrc1::transfer::{BlockIndex, Memo};
 use icrc_ledger_types::icrc3::blocks::GenericBlock;
 use icrc_ledger_types::icrc3::transactions::Transaction;
 
@@ -68,6 +68,96 @@ pub struct GetAccountTransactionsError {
 pub type GetAccountTransactionsResult =
     Result<GetAccountTransactionsResponse, GetAccountTransactionsError>;
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum TransactionKind {
+    Mint,
+    Burn,
+    Approve,
+    // A transfer without a spender.
+    Transfer,
+    // A transfer executed by a spender on behalf of the sender.
+    TransferFrom,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum TransactionDirection {
+    // Transactions that credit the account, i.e., mints and
+    // transfers to the account.
+    Incoming,
+    // Transactions that debit the account, i.e., burns, approves
+    // and transfers from the account.
+    Outgoing,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct TransactionFilter {
+    // If set then only transactions of one of these kinds are returned.
+    pub kinds: Option<Vec<TransactionKind>>,
+    // If set then only transactions moving tokens in this direction
+    // relative to the account are returned.
+    pub direction: Option<TransactionDirection>,
+    // If set then only transactions involving this account as the other
+    // party (sender, receiver or spender) are returned.
+    pub counterparty: Option<Account>,
+    // If set then only transactions with exactly this memo are returned.
+    pub memo: Option<Memo>,
+    // If set then only transactions in blocks with timestamp greater than
+    // or equal to this value (in nanoseconds since the epoch) are returned.
+    pub start_timestamp: Option<u64>,
+    // If set then only transactions in blocks with timestamp strictly
+    // less than this value (in nanoseconds since the epoch) are returned.
+    pub end_timestamp: Option<u64>,
+}
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetFilteredAccountTransactionsArgs {
+    pub account: Account,
+    // Same as in [GetAccountTransactionsArgs].
+    pub start: Option<BlockIndex>,
+    // Maximum number of matching transactions to fetch.
+    pub max_results: Nat,
+    pub filter: TransactionFilter,
+}
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetFilteredAccountTransactionsResponse {
+    pub balance: Nat,
+    pub transactions: Vec<TransactionWithId>,
+    // The txid of the oldest transaction the account has
+    pub oldest_tx_id: Option<BlockIndex>,
+    // If set then the index stopped scanning the transactions of the
+    // account before reaching the end of the requested range, either
+    // because max_results transactions were found or because the scan
+    // limit of a single query was reached. More matching transactions
+    // may exist and can be fetched by using this value as start.
+    pub next_start: Option<BlockIndex>,
+}
+
+pub type GetFilteredAccountTransactionsResult =
+    Result<GetFilteredAccountTransactionsResponse, GetAccountTransactionsError>;
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetAccountBalanceAtArgs {
+    pub account: Account,
+    // The index of the block after which the balance is requested.
+    pub block_index: BlockIndex,
+}
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum GetAccountBalanceAtError {
+    // The block has not been indexed yet.
+    BlockNotIndexed {
+        num_blocks_synced: BlockIndex,
+    },
+    // The index started recording balance history after the block,
+    // e.g., because it was upgraded from a version that did not record it.
+    HistoryUnavailable {
+        first_block_with_history: BlockIndex,
+    },
+}
+
+pub type GetAccountBalanceAtResult = Result<Nat, GetAccountBalanceAtError>;
+
 #[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct ListSubaccountsArgs {
     pub owner: Principal,
@@ -10,9 +10,12 @@ use ic_icrc1::blocks::{encoded_block_to_generic_block, generic_block_to_encoded_
 use ic_icrc1::endpoints::StandardRecord;
 use ic_icrc1::{Block, Operation};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, GetBlocksMethod, IndexArg, InitArg, ListSubaccountsArgs, Log,
-    LogEntry, Status, TransactionWithId, UpgradeArg, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    FeeCollectorRanges, GetAccountBalanceAtArgs, GetAccountBalanceAtError,
+    GetAccountBalanceAtResult, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
+    GetAccountTransactionsResult, GetBlocksMethod, GetFilteredAccountTransactionsArgs,
+    GetFilteredAccountTransactionsResponse, GetFilteredAccountTransactionsResult, IndexArg,
+    InitArg, ListSubaccountsArgs, Log, LogEntry, Status, TransactionDirection, TransactionFilter,
+    TransactionKind, TransactionWithId, UpgradeArg, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_ledger_canister_core::runtime::heap_memory_size_bytes;
 use ic_ledger_core::block::{BlockIndex as BlockIndex64, BlockType, EncodedBlock};
@@ -55,9 +58,14 @@ const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
 const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
 const ACCOUNT_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
 const ACCOUNT_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+const ACCOUNT_BALANCE_HISTORY_MEMORY_ID: MemoryId = MemoryId::new(5);
 
 const DEFAULT_RETRIEVE_BLOCKS_FROM_LEDGER_INTERVAL: Duration = Duration::from_secs(1);
 
+/// The maximum number of account blocks inspected by a single
+/// [get_filtered_account_transactions] call.
+const MAX_BLOCKS_SCANNED_PER_FILTERED_QUERY: usize = 10_000;
+
 #[cfg(not(feature = "u256-tokens"))]
 type Tokens = ic_icrc1_tokens_u64::U64;
 
@@ -77,6 +85,11 @@ type AccountBlockIdsMap = StableBTreeMap<AccountBlockIdsMapKey, (), VM>;
 type AccountDataMapKey = (AccountDataType, (Blob<29>, [u8; 32]));
 type AccountDataMap = StableBTreeMap<AccountDataMapKey, Tokens, VM>;
 
+// The balance of an account after each block that changed it. The keys are
+// the same as in [AccountBlockIdsMap] so that the most recent balance at or
+// before a given block is the first entry of a range query.
+type AccountBalanceHistoryMap = StableBTreeMap<AccountBlockIdsMapKey, Tokens, VM>;
+
 thread_local! {
     /// Static memory manager to manage the memory available for stable structures.
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
@@ -104,6 +117,12 @@ thread_local! {
         RefCell::new(AccountDataMap::init(memory_manager.get(ACCOUNT_DATA_MEMORY_ID)))
     });
 
+    /// Map that contains the balance history of the accounts.
+    /// The account is hashed to save space.
+    static ACCOUNT_BALANCE_HISTORY: RefCell<AccountBalanceHistoryMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AccountBalanceHistoryMap::init(memory_manager.get(ACCOUNT_BALANCE_HISTORY_MEMORY_ID)))
+    });
+
     /// Profiling data to understand cycles usage
     static PROFILING_DATA: RefCell<SpanStats> = RefCell::new(SpanStats::default());
 
@@ -136,6 +155,11 @@ struct State {
     /// index. Lower values will result in a more responsive UI, but higher costs due to increased
     /// cycle burn for the index, ledger and archive(s).
     retrieve_blocks_from_ledger_interval: Option<Duration>,
+
+    /// The index of the first block for which the balance history
+    /// is recorded. This is `None` only before the first init or
+    /// upgrade to a version that records the balance history.
+    balance_history_start: Option<BlockIndex64>,
 }
 
 impl State {
@@ -157,6 +181,7 @@ impl Default for State {
             fee_collectors: Default::default(),
             last_fee: None,
             retrieve_blocks_from_ledger_interval: None,
+            balance_history_start: None,
         }
     }
 }
@@ -259,6 +284,11 @@ fn with_account_data<R>(f: impl FnOnce(&mut AccountDataMap) -> R) -> R {
     ACCOUNT_DATA.with(|cell| f(&mut cell.borrow_mut()))
 }
 
+/// A helper function to access the account balance history.
+fn with_account_balance_history<R>(f: impl FnOnce(&mut AccountBalanceHistoryMap) -> R) -> R {
+    ACCOUNT_BALANCE_HISTORY.with(|cell| f(&mut cell.borrow_mut()))
+}
+
 /// A helper function that returns a decoded block stored in the
 /// block log at the given index or None if there is no block at that index.
 /// This function can trap if the index at the given block cannot be decoded
@@ -279,11 +309,15 @@ fn get_balance(account: Account) -> Tokens {
     })
 }
 
-/// A helper function to change the balance of an account.
-fn change_balance(account: Account, f: impl FnOnce(Tokens) -> Tokens) {
+/// A helper function to change the balance of an account
+/// and record the new balance in the account balance history.
+fn change_balance(block_index: BlockIndex64, account: Account, f: impl FnOnce(Tokens) -> Tokens) {
     let key = balance_key(account);
     let new_balance = f(get_balance(account));
     with_account_data(|account_data| account_data.insert(key, new_balance));
+    with_account_balance_history(|history| {
+        history.insert(account_block_ids_key(account, block_index), new_balance)
+    });
 }
 
 fn balance_key(account: Account) -> (AccountDataType, (Blob<29>, [u8; 32])) {
@@ -310,6 +344,7 @@ fn init(index_arg: Option<IndexArg>) {
         state.ledger_id = ledger_id;
         state.retrieve_blocks_from_ledger_interval =
             retrieve_blocks_from_ledger_interval_seconds.map(Duration::from_secs);
+        state.balance_history_start = Some(0);
     });
 
     // set the first build_index to be called after init
@@ -381,6 +416,17 @@ fn post_upgrade(index_arg: Option<IndexArg>) {
         let _maybe_first_key_value = account_data.first_key_value();
     });
 
+    // The balance history is recorded only for blocks indexed after
+    // the upgrade to a version that supports it.
+    if with_state(|state| state.balance_history_start.is_none()) {
+        let num_blocks = with_blocks(|blocks| blocks.len());
+        log!(
+            P1,
+            "Recording the balance history starting at block {num_blocks}"
+        );
+        mutate_state(|state| state.balance_history_start = Some(num_blocks));
+    }
+
     // set the first build_index to be called after init
     set_build_index_timer(with_state(|state| {
         state.retrieve_blocks_from_ledger_interval()
@@ -837,7 +883,7 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
                 // Until a transfer_from transaction occurs such account would not show up in a `list_subaccounts` query as the spender is not involved in any credit or debit calls at this point.
                 // To ensure that the account still shows up in the `list_subaccount` query we can simply call `change_balance` without actually changing the balance.
                 // If the account is new, this will add it to the AccountDataMap with balance 0 and thus show up in a `list_subaccount` query.
-                change_balance(spender, |balance| balance);
+                change_balance(block_index, spender, |balance| balance);
 
                 debit(block_index, from, fee);
             }
@@ -846,7 +892,7 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
 }
 
 fn debit(block_index: BlockIndex64, account: Account, amount: Tokens) {
-    change_balance(account, |balance| {
+    change_balance(block_index, account, |balance| {
         balance.checked_sub(&amount).unwrap_or_else(|| {
             ic_cdk::trap(&format!("Block {} caused an underflow for account {} when calculating balance {} - amount {}",
                 block_index, account, balance, amount));
@@ -855,7 +901,7 @@ fn debit(block_index: BlockIndex64, account: Account, amount: Tokens) {
 }
 
 fn credit(block_index: BlockIndex64, account: Account, amount: Tokens) {
-    change_balance(account, |balance| {
+    change_balance(block_index, account, |balance| {
         balance.checked_add(&amount).unwrap_or_else(|| {
             ic_cdk::trap(&format!("Block {} caused an overflow for account {} when calculating balance {} + amount {}",
                 block_index, account, balance, amount))
@@ -1038,6 +1084,185 @@ fn get_oldest_tx_id(account: Account) -> Option<BlockIndex64> {
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn get_filtered_account_transactions(
+    arg: GetFilteredAccountTransactionsArgs,
+) -> GetFilteredAccountTransactionsResult {
+    let length = arg
+        .max_results
+        .0
+        .to_u64()
+        .expect("The length must be a u64!")
+        .min(with_state(|opts| opts.max_blocks_per_response))
+        .min(usize::MAX as u64) as usize;
+    let filter = arg.filter;
+    // Block timestamps are monotonic so the timestamp range of the filter
+    // can be turned into a range of block indices.
+    let mut end = arg
+        .start
+        .map_or(u64::MAX, |n| n.0.to_u64().expect("start must be a u64!"));
+    if let Some(end_timestamp) = filter.end_timestamp {
+        end = end.min(first_block_with_timestamp_at_least(end_timestamp));
+    }
+    let first = filter
+        .start_timestamp
+        .map_or(0, first_block_with_timestamp_at_least);
+    let key = account_block_ids_key(arg.account, end);
+    let indices = with_account_block_ids(|account_block_ids| {
+        account_block_ids
+            .range(key..)
+            .take_while(|(k, _)| k.0 == key.0 && k.1 .0 >= first)
+            .filter(|(k, _)| k.1 .0 < end)
+            // one more than the limit to know whether the scan is complete
+            .take(MAX_BLOCKS_SCANNED_PER_FILTERED_QUERY + 1)
+            .map(|(k, _)| k.1 .0)
+            .collect::<Vec<BlockIndex64>>()
+    });
+    let mut transactions = vec![];
+    let mut next_start = None;
+    let mut last_scanned = None;
+    for (scanned, id) in indices.into_iter().enumerate() {
+        if transactions.len() >= length || scanned == MAX_BLOCKS_SCANNED_PER_FILTERED_QUERY {
+            next_start = last_scanned.map(Nat::from);
+            break;
+        }
+        last_scanned = Some(id);
+        let block = get_decoded_block(id).unwrap_or_else(|| {
+            trap(&format!(
+                "Block {} not found in the block log, account blocks map is corrupted!",
+                id
+            ))
+        });
+        if transaction_matches_filter(arg.account, &block, &filter) {
+            transactions.push(TransactionWithId {
+                id: id.into(),
+                transaction: block.into(),
+            });
+        }
+    }
+    let oldest_tx_id = get_oldest_tx_id(arg.account).map(|tx_id| tx_id.into());
+    let balance = get_balance(arg.account).into();
+    Ok(GetFilteredAccountTransactionsResponse {
+        balance,
+        transactions,
+        oldest_tx_id,
+        next_start,
+    })
+}
+
+/// Returns the index of the first block with timestamp greater than or
+/// equal to the given one, or the number of blocks if there is none.
+fn first_block_with_timestamp_at_least(timestamp: u64) -> BlockIndex64 {
+    let mut low = 0;
+    let mut high = with_blocks(|blocks| blocks.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let block = get_decoded_block(mid)
+            .unwrap_or_else(|| trap(&format!("Block {} not found in the block log", mid)));
+        if block.timestamp < timestamp {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+fn transaction_matches_filter(
+    account: Account,
+    block: &Block<Tokens>,
+    filter: &TransactionFilter,
+) -> bool {
+    let (kind, from, to, spender) = match block.transaction.operation {
+        Operation::Mint { to, .. } => (TransactionKind::Mint, None, Some(to), None),
+        Operation::Burn { from, spender, .. } => (TransactionKind::Burn, Some(from), None, spender),
+        Operation::Transfer {
+            from, to, spender, ..
+        } => {
+            let kind = if spender.is_some() {
+                TransactionKind::TransferFrom
+            } else {
+                TransactionKind::Transfer
+            };
+            (kind, Some(from), Some(to), spender)
+        }
+        Operation::Approve { from, spender, .. } => {
+            (TransactionKind::Approve, Some(from), None, Some(spender))
+        }
+    };
+    if let Some(kinds) = &filter.kinds {
+        if !kinds.contains(&kind) {
+            return false;
+        }
+    }
+    match filter.direction {
+        Some(TransactionDirection::Incoming) if to != Some(account) => return false,
+        Some(TransactionDirection::Outgoing) if from != Some(account) => return false,
+        _ => (),
+    }
+    if let Some(counterparty) = filter.counterparty {
+        if ![from, to, spender]
+            .into_iter()
+            .flatten()
+            .any(|party| party != account && party == counterparty)
+        {
+            return false;
+        }
+    }
+    if let Some(memo) = &filter.memo {
+        if block.transaction.memo.as_ref() != Some(memo) {
+            return false;
+        }
+    }
+    true
+}
+
+#[query]
+#[candid_method(query)]
+fn get_account_balance_at(arg: GetAccountBalanceAtArgs) -> GetAccountBalanceAtResult {
+    let block_index = arg
+        .block_index
+        .0
+        .to_u64()
+        .expect("block_index must be a u64!");
+    let num_blocks_synced = with_blocks(|blocks| blocks.len());
+    if block_index >= num_blocks_synced {
+        return Err(GetAccountBalanceAtError::BlockNotIndexed {
+            num_blocks_synced: num_blocks_synced.into(),
+        });
+    }
+    let account_hash = account_sha256(arg.account);
+    let (balance_at, has_history) = with_account_balance_history(|history| {
+        let balance_at = history
+            .range(account_block_ids_key(arg.account, block_index)..)
+            .take_while(|(k, _)| k.0 == account_hash)
+            .next()
+            .map(|(_, balance)| balance);
+        let has_history = history
+            .range(account_block_ids_key(arg.account, u64::MAX)..)
+            .take_while(|(k, _)| k.0 == account_hash)
+            .next()
+            .is_some();
+        (balance_at, has_history)
+    });
+    if let Some(balance) = balance_at {
+        return Ok(balance.into());
+    }
+    match with_state(|state| state.balance_history_start) {
+        // The balance did not change since the history started, so it
+        // is the current balance for any block covered by the history.
+        Some(start) if start <= block_index && !has_history => Ok(get_balance(arg.account).into()),
+        // The balance changed after the block but not between the start
+        // of the history and the block, so it is only known if the
+        // history covers the whole chain.
+        Some(0) => Ok(Nat::from(0u8)),
+        start => Err(GetAccountBalanceAtError::HistoryUnavailable {
+            first_block_with_history: start.unwrap_or(num_blocks_synced).into(),
+        }),
+    }
+}
+
 #[query]
 #[candid_method(query)]
 fn icrc1_balance_of(account: Account) -> Nat {
@@ -7,9 +7,12 @@ use candid::{Decode, Encode, Nat, Principal};
 use ic_agent::identity::Identity;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, GetBlocksResponse, IndexArg, InitArg as IndexInitArg,
-    ListSubaccountsArgs, TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    FeeCollectorRanges, GetAccountBalanceAtArgs, GetAccountBalanceAtError,
+    GetAccountBalanceAtResult, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
+    GetAccountTransactionsResult, GetBlocksResponse, GetFilteredAccountTransactionsArgs,
+    GetFilteredAccountTransactionsResponse, GetFilteredAccountTransactionsResult, IndexArg,
+    InitArg as IndexInitArg, ListSubaccountsArgs, TransactionDirection, TransactionFilter,
+    TransactionKind, TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_icrc1_ledger::{ChangeFeeCollector, LedgerArgument, UpgradeArgs as LedgerUpgradeArgs};
 use ic_icrc1_test_utils::{
@@ -18,7 +21,7 @@ use ic_icrc1_test_utils::{
 use ic_rosetta_test_utils::test_http_request_decoding_quota;
 use ic_state_machine_tests::StateMachine;
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
-use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError};
+use icrc_ledger_types::icrc1::transfer::{BlockIndex, Memo, TransferArg, TransferError};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
 use icrc_ledger_types::icrc3::transactions::{Mint, Transaction, Transfer};
@@ -288,6 +291,48 @@ fn get_account_transactions(
         .expect("Failed to perform GetAccountTransactionsArgs")
 }
 
+fn get_filtered_account_transactions(
+    env: &StateMachine,
+    index_id: CanisterId,
+    account: Account,
+    start: Option<u64>,
+    max_results: u64,
+    filter: TransactionFilter,
+) -> GetFilteredAccountTransactionsResponse {
+    let req = GetFilteredAccountTransactionsArgs {
+        account,
+        start: start.map(|n| n.into()),
+        max_results: max_results.into(),
+        filter,
+    };
+    let req = Encode!(&req).expect("Failed to encode GetFilteredAccountTransactionsArgs");
+    let res = env
+        .execute_ingress(index_id, "get_filtered_account_transactions", req)
+        .expect("Failed to get_filtered_account_transactions")
+        .bytes();
+    Decode!(&res, GetFilteredAccountTransactionsResult)
+        .expect("Failed to decode GetFilteredAccountTransactionsResult")
+        .expect("Failed to perform GetFilteredAccountTransactionsArgs")
+}
+
+fn get_account_balance_at(
+    env: &StateMachine,
+    index_id: CanisterId,
+    account: Account,
+    block_index: u64,
+) -> GetAccountBalanceAtResult {
+    let req = GetAccountBalanceAtArgs {
+        account,
+        block_index: block_index.into(),
+    };
+    let req = Encode!(&req).expect("Failed to encode GetAccountBalanceAtArgs");
+    let res = env
+        .execute_ingress(index_id, "get_account_balance_at", req)
+        .expect("Failed to get_account_balance_at")
+        .bytes();
+    Decode!(&res, GetAccountBalanceAtResult).expect("Failed to decode GetAccountBalanceAtResult")
+}
+
 fn list_subaccounts(
     env: &StateMachine,
     index: CanisterId,
@@ -775,6 +820,177 @@ fn test_get_account_transactions_pagination() {
     }
 }
 
+#[test]
+fn test_get_filtered_account_transactions() {
+    let initial_balances: Vec<_> = vec![(account(1, 0), 1_000_000_000_000)];
+    let env = &StateMachine::new();
+    let minter = minter_identity().sender().unwrap();
+    let ledger_id = install_ledger(
+        env,
+        initial_balances,
+        default_archive_options(),
+        None,
+        minter,
+    );
+    let index_id = install_index_ng(env, index_init_arg_without_interval(ledger_id));
+
+    // Block 0 is the mint to (1, 0).
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 1_000_000); // block 1
+    env.advance_time(Duration::from_secs(3600));
+    let memo = Memo::from(vec![1, 2, 3]);
+    icrc1_transfer(
+        env,
+        ledger_id,
+        PrincipalId(account(2, 0).owner),
+        TransferArg {
+            from_subaccount: None,
+            to: account(1, 0),
+            amount: 100_000u32.into(),
+            created_at_time: None,
+            fee: None,
+            memo: Some(memo.clone()),
+        },
+    ); // block 2
+    approve(env, ledger_id, account(1, 0), account(3, 0), 1_000); // block 3
+    transfer(env, ledger_id, account(2, 0), account(1, 0), 200_000); // block 4
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let block_2_timestamp = get_account_transactions(env, index_id, account(1, 0), Some(3), 1)
+        .transactions[0]
+        .transaction
+        .timestamp;
+    let ids = |filter: TransactionFilter| -> Vec<u64> {
+        get_filtered_account_transactions(env, index_id, account(1, 0), None, u64::MAX, filter)
+            .transactions
+            .into_iter()
+            .map(|tx| tx.id.0.to_u64().unwrap())
+            .collect()
+    };
+
+    assert_eq!(ids(TransactionFilter::default()), vec![4, 3, 2, 1, 0]);
+    assert_eq!(
+        ids(TransactionFilter {
+            kinds: Some(vec![TransactionKind::Mint, TransactionKind::Approve]),
+            ..Default::default()
+        }),
+        vec![3, 0]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            direction: Some(TransactionDirection::Incoming),
+            ..Default::default()
+        }),
+        vec![4, 2, 0]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            direction: Some(TransactionDirection::Outgoing),
+            ..Default::default()
+        }),
+        vec![3, 1]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            counterparty: Some(account(3, 0)),
+            ..Default::default()
+        }),
+        vec![3]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            memo: Some(memo),
+            ..Default::default()
+        }),
+        vec![2]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            end_timestamp: Some(block_2_timestamp),
+            ..Default::default()
+        }),
+        vec![1, 0]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            direction: Some(TransactionDirection::Incoming),
+            start_timestamp: Some(block_2_timestamp),
+            ..Default::default()
+        }),
+        vec![4, 2]
+    );
+
+    // Paginate over the incoming transactions one at a time.
+    let filter = TransactionFilter {
+        direction: Some(TransactionDirection::Incoming),
+        ..Default::default()
+    };
+    let mut start = None;
+    let mut actual_ids = vec![];
+    loop {
+        let res = get_filtered_account_transactions(
+            env,
+            index_id,
+            account(1, 0),
+            start,
+            1,
+            filter.clone(),
+        );
+        actual_ids.extend(
+            res.transactions
+                .into_iter()
+                .map(|tx| tx.id.0.to_u64().unwrap()),
+        );
+        match res.next_start {
+            Some(next_start) => start = Some(next_start.0.to_u64().unwrap()),
+            None => break,
+        }
+    }
+    assert_eq!(actual_ids, vec![4, 2, 0]);
+}
+
+#[test]
+fn test_get_account_balance_at() {
+    let initial_balances: Vec<_> = vec![(account(1, 0), 1_000_000)];
+    let env = &StateMachine::new();
+    let minter = minter_identity().sender().unwrap();
+    let ledger_id = install_ledger(
+        env,
+        initial_balances,
+        default_archive_options(),
+        None,
+        minter,
+    );
+    let index_id = install_index_ng(env, index_init_arg_without_interval(ledger_id));
+
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 100_000); // block 1
+    transfer(env, ledger_id, account(2, 0), account(1, 0), 10_000); // block 2
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let expected_balances = [
+        (account(1, 0), [1_000_000, 900_000 - FEE, 910_000 - FEE]),
+        (account(2, 0), [0, 100_000, 90_000 - FEE]),
+        (account(3, 0), [0, 0, 0]),
+    ];
+    for (account, balances) in expected_balances {
+        for (block_index, balance) in balances.into_iter().enumerate() {
+            assert_eq!(
+                get_account_balance_at(env, index_id, account, block_index as u64),
+                Ok(Nat::from(balance)),
+                "balance of {} at block {}",
+                account,
+                block_index
+            );
+        }
+    }
+
+    assert_eq!(
+        get_account_balance_at(env, index_id, account(1, 0), 3),
+        Err(GetAccountBalanceAtError::BlockNotIndexed {
+            num_blocks_synced: 3u8.into()
+        })
+    );
+}
+
 #[test]
 fn test_icrc1_balance_of() {
     // 1 case only because the test is expensive to run.
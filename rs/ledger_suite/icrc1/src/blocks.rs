@@ -73,9 +73,17 @@ pub fn generic_block_to_encoded_block(generic_block: GenericBlock) -> Result<Enc
 }
 
 pub fn encoded_block_to_generic_block(encoded_block: &EncodedBlock) -> GenericBlock {
-    let value: CiboriumValue =
-        ciborium::de::from_reader(encoded_block.as_slice()).expect("failed to decode block");
-    icrc1_block_from_value(value, 0).expect("failed to decode encoded block")
+    try_encoded_block_to_generic_block(encoded_block).expect("failed to decode encoded block")
+}
+
+/// Like [encoded_block_to_generic_block], but returns an error instead of
+/// panicking if the block is not a valid CBOR value.
+pub fn try_encoded_block_to_generic_block(
+    encoded_block: &EncodedBlock,
+) -> Result<GenericBlock, String> {
+    let value: CiboriumValue = ciborium::de::from_reader(encoded_block.as_slice())
+        .map_err(|e| format!("failed to decode block: {}", e))?;
+    icrc1_block_from_value(value, 0).map_err(|e| e.to_string())
 }
 
 #[derive(Debug, Error)]
@@ -247,8 +247,8 @@ impl<Tokens: TokensType> LedgerTransaction for Transaction<Tokens> {
         amount: Tokens,
         created_at_time: Option<TimeStamp>,
         memo: Option<u64>,
-    ) -> Self {
-        Self {
+    ) -> Option<Self> {
+        Some(Self {
             operation: Operation::Burn {
                 from,
                 spender,
@@ -256,7 +256,7 @@ impl<Tokens: TokensType> LedgerTransaction for Transaction<Tokens> {
             },
             created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
             memo: memo.map(Memo::from),
-        }
+        })
     }
 
     fn approve(
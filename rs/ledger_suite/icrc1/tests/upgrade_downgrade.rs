@@ -1,7 +1,10 @@
 use crate::common::{index_ng_wasm, ledger_wasm, load_wasm_using_env_var};
-use candid::{Encode, Principal};
+use candid::{Decode, Encode, Nat, Principal};
 use ic_base_types::{CanisterId, PrincipalId};
-use ic_icrc1_index_ng::{IndexArg, InitArg as IndexInitArg, UpgradeArg as IndexUpgradeArg};
+use ic_icrc1_index_ng::{
+    GetAccountBalanceAtArgs, GetAccountBalanceAtError, GetAccountBalanceAtResult, IndexArg,
+    InitArg as IndexInitArg, Status, UpgradeArg as IndexUpgradeArg,
+};
 use ic_icrc1_ledger::{FeatureFlags, InitArgsBuilder, LedgerArgument};
 use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_ledger_suite_state_machine_tests::{
@@ -11,6 +14,7 @@ use ic_ledger_suite_state_machine_tests::{
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{StateMachine, StateMachineBuilder};
 use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
 use std::time::{Duration, SystemTime};
 
 mod common;
@@ -93,6 +97,122 @@ fn should_upgrade_and_downgrade_ledger_canister_suite() {
     };
 }
 
+#[test]
+fn should_not_report_balances_before_the_history_of_an_upgraded_index() {
+    let env = &StateMachine::new();
+    let account_1 = Account::from(PrincipalId::new_user_test_id(1).0);
+    let account_2 = Account::from(PrincipalId::new_user_test_id(2).0);
+    let account_3 = Account::from(PrincipalId::new_user_test_id(3).0);
+
+    // Blocks 0 and 1 are indexed by a version without balance history.
+    let ledger_id = install_ledger(
+        env,
+        vec![(account_1, 1_000_000), (account_2, 500)],
+        default_archive_options(),
+        None,
+        MINTER_PRINCIPAL,
+    );
+    let index_id = install_index_ng(
+        env,
+        IndexInitArg {
+            ledger_id: Principal::from(ledger_id),
+            retrieve_blocks_from_ledger_interval_seconds: None,
+        },
+    );
+    wait_until_index_synced(env, index_id, 2);
+
+    // The upgrade starts the balance history at block 2.
+    let index_upgrade_arg = IndexArg::Upgrade(IndexUpgradeArg {
+        ledger_id: None,
+        retrieve_blocks_from_ledger_interval_seconds: None,
+    });
+    env.upgrade_canister(
+        index_id,
+        index_ng_wasm(),
+        Encode!(&index_upgrade_arg).unwrap(),
+    )
+    .unwrap();
+    transfer(env, ledger_id, account_1, account_3, 100_000); // block 2
+    wait_until_index_synced(env, index_id, 3);
+
+    let history_unavailable = Err(GetAccountBalanceAtError::HistoryUnavailable {
+        first_block_with_history: Nat::from(2u64),
+    });
+    // The balance of account 2 did not change since block 1, but it was
+    // different before the history started.
+    assert_eq!(
+        get_account_balance_at(env, index_id, account_2, 0),
+        history_unavailable
+    );
+    assert_eq!(
+        get_account_balance_at(env, index_id, account_2, 2),
+        Ok(Nat::from(500u64))
+    );
+    assert_eq!(
+        get_account_balance_at(env, index_id, account_1, 1),
+        history_unavailable
+    );
+    assert_eq!(
+        get_account_balance_at(env, index_id, account_1, 2),
+        Ok(Nat::from(1_000_000 - 100_000 - FEE))
+    );
+}
+
+fn transfer(env: &StateMachine, ledger_id: CanisterId, from: Account, to: Account, amount: u64) {
+    let arg = TransferArg {
+        from_subaccount: from.subaccount,
+        to,
+        fee: None,
+        created_at_time: None,
+        memo: None,
+        amount: Nat::from(amount),
+    };
+    let res = env
+        .execute_ingress_as(
+            PrincipalId(from.owner),
+            ledger_id,
+            "icrc1_transfer",
+            Encode!(&arg).unwrap(),
+        )
+        .expect("failed to transfer")
+        .bytes();
+    Decode!(&res, Result<Nat, TransferError>)
+        .unwrap()
+        .expect("failed to transfer");
+}
+
+fn wait_until_index_synced(env: &StateMachine, index_id: CanisterId, num_blocks: u64) {
+    for _ in 0..100 {
+        env.advance_time(Duration::from_secs(60));
+        env.tick();
+        let res = env
+            .query(index_id, "status", Encode!(&()).unwrap())
+            .expect("failed to query the index status")
+            .bytes();
+        if Decode!(&res, Status).unwrap().num_blocks_synced == Nat::from(num_blocks) {
+            return;
+        }
+    }
+    panic!("the index did not sync {} blocks", num_blocks);
+}
+
+fn get_account_balance_at(
+    env: &StateMachine,
+    index_id: CanisterId,
+    account: Account,
+    block_index: u64,
+) -> GetAccountBalanceAtResult {
+    let arg = GetAccountBalanceAtArgs {
+        account,
+        block_index: Nat::from(block_index),
+    };
+    let res = env
+        .query(index_id, "get_account_balance_at", Encode!(&arg).unwrap())
+        .expect("failed to query get_account_balance_at")
+        .bytes();
+    Decode!(&res, GetAccountBalanceAtResult).unwrap()
+}
+
 fn default_archive_options() -> ArchiveOptions {
     ArchiveOptions {
         trigger_threshold: ARCHIVE_TRIGGER_THRESHOLD as usize,
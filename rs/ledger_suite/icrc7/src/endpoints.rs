@@ -0,0 +1,75 @@
+use candid::{CandidType, Deserialize, Nat};
+use ic_ledger_canister_core::archive::ArchiveOptions;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::Memo;
+use icrc_ledger_types::icrc7::metadata::TokenId;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct InitArgs {
+    /// The account allowed to mint new tokens. Tokens sent to this account
+    /// are burned.
+    pub minting_account: Account,
+    pub token_symbol: String,
+    pub token_name: String,
+    pub description: Option<String>,
+    pub logo: Option<String>,
+    pub supply_cap: Option<Nat>,
+    pub archive_options: ArchiveOptions,
+    pub max_memo_length: Option<u16>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MintArg {
+    pub to: Account,
+    pub token_id: TokenId,
+    #[serde(default)]
+    pub metadata: Vec<(String, ICRC3Value)>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MintError {
+    // The caller is not the minting account.
+    Unauthorized,
+    TokenIdAlreadyExists,
+    SupplyCapReached,
+    InvalidRecipient,
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+    GenericError { error_code: Nat, message: String },
+}
+
+impl fmt::Display for MintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized => write!(f, "only the minting account can mint tokens"),
+            Self::TokenIdAlreadyExists => write!(f, "a token with the same ID already exists"),
+            Self::SupplyCapReached => write!(f, "the supply cap of the collection is reached"),
+            Self::InvalidRecipient => write!(f, "the minting account cannot own tokens"),
+            Self::TooOld => write!(f, "transaction's created_at_time is too far in the past"),
+            Self::CreatedInFuture { ledger_time } => write!(
+                f,
+                "transaction's created_at_time is in future, current ledger time is {}",
+                ledger_time
+            ),
+            Self::Duplicate { duplicate_of } => write!(
+                f,
+                "transaction is a duplicate of another transaction in block {}",
+                duplicate_of
+            ),
+            Self::GenericError {
+                error_code,
+                message,
+            } => write!(f, "{} {}", error_code, message),
+        }
+    }
+}
+
+pub type MintResult = Result<Nat, MintError>;
@@ -0,0 +1,913 @@
+use crate::endpoints::{InitArgs, MintArg, MintError, MintResult};
+use crate::{stored, Block, LedgerAllowances, LedgerBalances, Operation, Tokens, Transaction};
+use candid::{Nat, Principal};
+use ic_ledger_canister_core::{
+    archive::ArchiveCanisterWasm,
+    blockchain::Blockchain,
+    ledger::{
+        apply_transaction, LedgerContext, LedgerData, TransactionInfo,
+        TransferError as CoreTransferError,
+    },
+    runtime::Runtime,
+};
+use ic_ledger_core::{
+    approvals::AllowanceTable,
+    balances::Balances,
+    block::{BlockIndex, FeeCollector},
+    timestamp::TimeStamp,
+    tokens::Zero,
+};
+use ic_ledger_hash_of::HashOf;
+use icrc_ledger_types::icrc::generic_value::{ICRC3Value, Value};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::Memo;
+use icrc_ledger_types::icrc37::approvals::{
+    ApprovalInfo, ApproveCollectionArg, ApproveCollectionError, ApproveCollectionResult,
+    ApproveTokenArg, ApproveTokenError, ApproveTokenResult, CollectionApproval,
+    RevokeCollectionApprovalArg, RevokeCollectionApprovalError, RevokeCollectionApprovalResult,
+    RevokeTokenApprovalArg, RevokeTokenApprovalError, RevokeTokenApprovalResult, TokenApproval,
+};
+use icrc_ledger_types::icrc37::transfer_from::{
+    TransferFromArg, TransferFromError, TransferFromResult,
+};
+use icrc_ledger_types::icrc7::metadata::{keys, Metadata, TokenId};
+use icrc_ledger_types::icrc7::transfer::{TransferArg, TransferError, TransferResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Bound;
+use std::time::Duration;
+
+const TRANSACTION_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+const MAX_TRANSACTIONS_IN_WINDOW: usize = 3_000_000;
+const MAX_TRANSACTIONS_TO_PURGE: usize = 100_000;
+const DEFAULT_MAX_MEMO_LENGTH: u16 = 32;
+/// The maximum number of live approvals of a single token or of the
+/// collection approvals of a single account.
+pub const MAX_APPROVALS_PER_TOKEN_OR_COLLECTION: usize = 100;
+/// The number of items returned by paginated queries if the caller does not
+/// specify `take`.
+pub const DEFAULT_TAKE_VALUE: usize = 100;
+/// The maximum number of items returned by paginated queries.
+pub const MAX_TAKE_VALUE: usize = 1_000;
+
+/// Error codes of the `GenericError` variants returned by the ledger.
+pub mod error_codes {
+    pub const THROTTLED: u64 = 1;
+    pub const MEMO_TOO_LONG: u64 = 2;
+    pub const TOO_MANY_APPROVALS: u64 = 3;
+    pub const DUPLICATE: u64 = 4;
+    pub const INCONSISTENT_BALANCES: u64 = 5;
+}
+
+/// Errors common to all operations that add a block to the ledger.
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum CommonError {
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: BlockIndex },
+    GenericError { error_code: u64, message: String },
+}
+
+macro_rules! impl_from_common_error {
+    (@common $error:ident, $value:expr) => {
+        match $value {
+            CommonError::TooOld => $error::TooOld,
+            CommonError::CreatedInFuture { ledger_time } => $error::CreatedInFuture { ledger_time },
+            CommonError::GenericError {
+                error_code,
+                message,
+            } => $error::GenericError {
+                error_code: Nat::from(error_code),
+                message,
+            },
+            CommonError::Duplicate { .. } => unreachable!(),
+        }
+    };
+    ($error:ident) => {
+        impl From<CommonError> for $error {
+            fn from(error: CommonError) -> Self {
+                match error {
+                    CommonError::Duplicate { duplicate_of } => Self::Duplicate {
+                        duplicate_of: Nat::from(duplicate_of),
+                    },
+                    other => impl_from_common_error!(@common $error, other),
+                }
+            }
+        }
+    };
+    // ICRC-37 approval errors have no `Duplicate` variant.
+    ($error:ident, no_duplicate) => {
+        impl From<CommonError> for $error {
+            fn from(error: CommonError) -> Self {
+                match error {
+                    CommonError::Duplicate { duplicate_of } => Self::GenericError {
+                        error_code: Nat::from(error_codes::DUPLICATE),
+                        message: format!(
+                            "transaction is a duplicate of another transaction in block {}",
+                            duplicate_of
+                        ),
+                    },
+                    other => impl_from_common_error!(@common $error, other),
+                }
+            }
+        }
+    };
+}
+
+impl_from_common_error!(MintError);
+impl_from_common_error!(TransferError);
+impl_from_common_error!(TransferFromError);
+impl_from_common_error!(ApproveTokenError, no_duplicate);
+impl_from_common_error!(ApproveCollectionError, no_duplicate);
+
+/// The ledger does not track the token of an operation.
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct NonExistingTokenId;
+
+impl From<NonExistingTokenId> for TransferError {
+    fn from(_: NonExistingTokenId) -> Self {
+        Self::NonExistingTokenId
+    }
+}
+
+impl From<NonExistingTokenId> for TransferFromError {
+    fn from(_: NonExistingTokenId) -> Self {
+        Self::NonExistingTokenId
+    }
+}
+impl_from_common_error!(RevokeTokenApprovalError, no_duplicate);
+impl_from_common_error!(RevokeCollectionApprovalError, no_duplicate);
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+struct TokenRecord {
+    owner: Account,
+    /// The token metadata as a [Value::Map].
+    #[serde(with = "stored::value")]
+    metadata: Value,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct Ledger<Rt: Runtime, Wasm: ArchiveCanisterWasm> {
+    balances: LedgerBalances,
+    /// Fungible allowances required by the ledger framework; always empty.
+    #[serde(default)]
+    approvals: LedgerAllowances,
+    blockchain: Blockchain<Rt, Wasm>,
+
+    minting_account: Account,
+
+    transactions_by_hash: BTreeMap<HashOf<Transaction>, BlockIndex>,
+    transactions_by_height: VecDeque<TransactionInfo<Transaction>>,
+
+    token_symbol: String,
+    token_name: String,
+    description: Option<String>,
+    logo: Option<String>,
+    supply_cap: Option<u64>,
+    max_memo_length: u16,
+
+    #[serde(with = "stored::token_id_map")]
+    tokens: BTreeMap<TokenId, TokenRecord>,
+    /// Token approvals indexed by token ID and spender.
+    #[serde(with = "stored::token_id_map")]
+    token_approvals: BTreeMap<TokenId, BTreeMap<Account, ApprovalInfo>>,
+    /// Collection approvals indexed by owner and spender.
+    collection_approvals: BTreeMap<Account, BTreeMap<Account, ApprovalInfo>>,
+}
+
+impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> Ledger<Rt, Wasm> {
+    pub fn from_init_args(
+        InitArgs {
+            minting_account,
+            token_symbol,
+            token_name,
+            description,
+            logo,
+            supply_cap,
+            archive_options,
+            max_memo_length,
+        }: InitArgs,
+    ) -> Self {
+        Self {
+            balances: LedgerBalances::default(),
+            approvals: Default::default(),
+            blockchain: Blockchain::new_with_archive(archive_options),
+            minting_account,
+            transactions_by_hash: BTreeMap::new(),
+            transactions_by_height: VecDeque::new(),
+            token_symbol,
+            token_name,
+            description,
+            logo,
+            supply_cap: supply_cap.map(|cap| {
+                u64::try_from(cap.0.clone()).unwrap_or_else(|e| {
+                    panic!("failed to convert supply cap {} to u64: {}", cap, e)
+                })
+            }),
+            max_memo_length: max_memo_length.unwrap_or(DEFAULT_MAX_MEMO_LENGTH),
+            tokens: BTreeMap::new(),
+            token_approvals: BTreeMap::new(),
+            collection_approvals: BTreeMap::new(),
+        }
+    }
+
+    pub fn minting_account(&self) -> &Account {
+        &self.minting_account
+    }
+
+    pub fn max_memo_length(&self) -> u16 {
+        self.max_memo_length
+    }
+
+    pub fn total_supply(&self) -> u64 {
+        self.tokens.len() as u64
+    }
+
+    pub fn supply_cap(&self) -> Option<u64> {
+        self.supply_cap
+    }
+
+    pub fn collection_metadata(&self) -> Metadata {
+        let text = |s: &str| ICRC3Value::Text(s.to_string());
+        let nat = |n: u64| ICRC3Value::Nat(Nat::from(n));
+        let mut metadata = vec![
+            (keys::SYMBOL.to_string(), text(&self.token_symbol)),
+            (keys::NAME.to_string(), text(&self.token_name)),
+        ];
+        if let Some(description) = &self.description {
+            metadata.push((keys::DESCRIPTION.to_string(), text(description)));
+        }
+        if let Some(logo) = &self.logo {
+            metadata.push((keys::LOGO.to_string(), text(logo)));
+        }
+        metadata.push((keys::TOTAL_SUPPLY.to_string(), nat(self.total_supply())));
+        if let Some(supply_cap) = self.supply_cap {
+            metadata.push((keys::SUPPLY_CAP.to_string(), nat(supply_cap)));
+        }
+        metadata.extend([
+            (
+                keys::DEFAULT_TAKE_VALUE.to_string(),
+                nat(DEFAULT_TAKE_VALUE as u64),
+            ),
+            (keys::MAX_TAKE_VALUE.to_string(), nat(MAX_TAKE_VALUE as u64)),
+            (
+                keys::MAX_MEMO_SIZE.to_string(),
+                nat(self.max_memo_length as u64),
+            ),
+            (
+                keys::TX_WINDOW.to_string(),
+                nat(TRANSACTION_WINDOW.as_nanos() as u64),
+            ),
+            (
+                keys::PERMITTED_DRIFT.to_string(),
+                nat(ic_limits::PERMITTED_DRIFT.as_nanos() as u64),
+            ),
+            (
+                keys::MAX_APPROVALS_PER_TOKEN_OR_COLLECTION.to_string(),
+                nat(MAX_APPROVALS_PER_TOKEN_OR_COLLECTION as u64),
+            ),
+        ]);
+        metadata
+    }
+
+    pub fn owner_of(&self, token_id: &TokenId) -> Option<Account> {
+        self.tokens.get(token_id).map(|token| token.owner)
+    }
+
+    pub fn balance_of(&self, account: &Account) -> Nat {
+        self.balances.account_balance(account).into()
+    }
+
+    pub fn token_metadata(&self, token_id: &TokenId) -> Option<Metadata> {
+        let token = self.tokens.get(token_id)?;
+        let metadata = token
+            .metadata
+            .clone()
+            .as_map()
+            .expect("bug: token metadata must be a map");
+        Some(
+            metadata
+                .into_iter()
+                .map(|(key, value)| (key, ICRC3Value::from(value)))
+                .collect(),
+        )
+    }
+
+    /// Returns the IDs of up to `take` tokens that follow `prev` in ascending order.
+    pub fn tokens(&self, prev: Option<&TokenId>, take: Option<usize>) -> Vec<TokenId> {
+        self.tokens
+            .range::<TokenId, _>(lower_bound(prev))
+            .take(effective_take(take))
+            .map(|(token_id, _)| token_id.clone())
+            .collect()
+    }
+
+    /// Returns the IDs of up to `take` tokens owned by `account` that follow
+    /// `prev` in ascending order.
+    pub fn tokens_of(
+        &self,
+        account: &Account,
+        prev: Option<&TokenId>,
+        take: Option<usize>,
+    ) -> Vec<TokenId> {
+        self.tokens
+            .range::<TokenId, _>(lower_bound(prev))
+            .filter(|(_, token)| &token.owner == account)
+            .take(effective_take(take))
+            .map(|(token_id, _)| token_id.clone())
+            .collect()
+    }
+
+    /// Returns true if `spender` may transfer the token on behalf of the owner
+    /// account `Account { owner: caller, subaccount: from_subaccount }`.
+    pub fn is_approved(
+        &self,
+        caller: Principal,
+        from_subaccount: Option<Subaccount>,
+        spender: &Account,
+        token_id: &TokenId,
+        now: TimeStamp,
+    ) -> bool {
+        let from = Account {
+            owner: caller,
+            subaccount: from_subaccount,
+        };
+        self.owner_of(token_id) == Some(from) && self.has_approval(&from, spender, token_id, now)
+    }
+
+    /// Returns up to `take` live approvals of the token ordered by spender.
+    pub fn token_approvals(
+        &self,
+        token_id: &TokenId,
+        prev: Option<&TokenApproval>,
+        take: Option<usize>,
+        now: TimeStamp,
+    ) -> Vec<TokenApproval> {
+        let Some(approvals) = self.token_approvals.get(token_id) else {
+            return vec![];
+        };
+        approvals
+            .range::<Account, _>(lower_bound(prev.map(|p| &p.approval_info.spender)))
+            .filter(|(_, approval)| is_live(approval, now))
+            .take(effective_take(take))
+            .map(|(_, approval)| TokenApproval {
+                token_id: token_id.clone(),
+                approval_info: approval.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns up to `take` live collection approvals of `owner` ordered by spender.
+    pub fn collection_approvals(
+        &self,
+        owner: &Account,
+        prev: Option<&CollectionApproval>,
+        take: Option<usize>,
+        now: TimeStamp,
+    ) -> Vec<CollectionApproval> {
+        let Some(approvals) = self.collection_approvals.get(owner) else {
+            return vec![];
+        };
+        approvals
+            .range::<Account, _>(lower_bound(prev.map(|p| &p.spender)))
+            .filter(|(_, approval)| is_live(approval, now))
+            .take(effective_take(take))
+            .map(|(_, approval)| approval.clone())
+            .collect()
+    }
+
+    /// Mints a new token. Only the minting account can mint tokens.
+    pub fn mint(&mut self, caller: Principal, arg: MintArg, now: TimeStamp) -> MintResult {
+        if caller != self.minting_account.owner {
+            return Err(MintError::Unauthorized);
+        }
+        if arg.to == self.minting_account {
+            return Err(MintError::InvalidRecipient);
+        }
+        if self.tokens.contains_key(&arg.token_id) {
+            return Err(MintError::TokenIdAlreadyExists);
+        }
+        if self
+            .supply_cap
+            .is_some_and(|cap| self.total_supply() >= cap)
+        {
+            return Err(MintError::SupplyCapReached);
+        }
+        self.check_memo(arg.memo.as_ref())?;
+
+        let metadata: BTreeMap<String, Value> = arg
+            .metadata
+            .into_iter()
+            .map(|(key, value)| (key, Value::from(value)))
+            .collect();
+        let tx = Transaction::new(
+            Operation::Mint {
+                to: arg.to,
+                tid: arg.token_id.clone(),
+                metadata: metadata.clone(),
+            },
+            arg.created_at_time,
+            arg.memo,
+        );
+        let block_index = self.apply(tx, now)?;
+        self.tokens.insert(
+            arg.token_id,
+            TokenRecord {
+                owner: arg.to,
+                metadata: Value::Map(metadata),
+            },
+        );
+        Ok(Nat::from(block_index))
+    }
+
+    /// Transfers a token owned by the caller. Transferring a token to the
+    /// minting account burns it.
+    pub fn transfer(
+        &mut self,
+        caller: Principal,
+        arg: TransferArg,
+        now: TimeStamp,
+    ) -> TransferResult {
+        let from = Account {
+            owner: caller,
+            subaccount: arg.from_subaccount,
+        };
+        match self.owner_of(&arg.token_id) {
+            None => return Err(TransferError::NonExistingTokenId),
+            Some(owner) if owner != from => return Err(TransferError::Unauthorized),
+            Some(_) => {}
+        }
+        if arg.to == from {
+            return Err(TransferError::InvalidRecipient);
+        }
+        self.check_memo(arg.memo.as_ref())?;
+
+        let tid = arg.token_id.clone();
+        let operation = if arg.to == self.minting_account {
+            Operation::Burn { from, tid }
+        } else {
+            Operation::Transfer {
+                from,
+                to: arg.to,
+                tid,
+            }
+        };
+        let block_index = self.apply(
+            Transaction::new(operation, arg.created_at_time, arg.memo),
+            now,
+        )?;
+        self.move_token(&arg.token_id, arg.to)?;
+        Ok(Nat::from(block_index))
+    }
+
+    /// Transfers a token on behalf of its owner. The spender needs either an
+    /// approval of the token or a collection approval of the owner.
+    pub fn transfer_from(
+        &mut self,
+        caller: Principal,
+        arg: TransferFromArg,
+        now: TimeStamp,
+    ) -> TransferFromResult {
+        let spender = Account {
+            owner: caller,
+            subaccount: arg.spender_subaccount,
+        };
+        match self.owner_of(&arg.token_id) {
+            None => return Err(TransferFromError::NonExistingTokenId),
+            Some(owner) if owner != arg.from => return Err(TransferFromError::Unauthorized),
+            Some(_) => {}
+        }
+        if !self.has_approval(&arg.from, &spender, &arg.token_id, now) {
+            return Err(TransferFromError::Unauthorized);
+        }
+        if arg.to == arg.from || arg.to == self.minting_account {
+            return Err(TransferFromError::InvalidRecipient);
+        }
+        self.check_memo(arg.memo.as_ref())?;
+
+        let block_index = self.apply(
+            Transaction::new(
+                Operation::TransferFrom {
+                    from: arg.from,
+                    to: arg.to,
+                    spender,
+                    tid: arg.token_id.clone(),
+                },
+                arg.created_at_time,
+                arg.memo,
+            ),
+            now,
+        )?;
+        self.move_token(&arg.token_id, arg.to)?;
+        Ok(Nat::from(block_index))
+    }
+
+    /// Approves a spender to transfer a token owned by the caller.
+    pub fn approve_token(
+        &mut self,
+        caller: Principal,
+        arg: ApproveTokenArg,
+        now: TimeStamp,
+    ) -> ApproveTokenResult {
+        let ApproveTokenArg {
+            token_id,
+            approval_info,
+        } = arg;
+        let from = Account {
+            owner: caller,
+            subaccount: approval_info.from_subaccount,
+        };
+        match self.owner_of(&token_id) {
+            None => return Err(ApproveTokenError::NonExistingTokenId),
+            Some(owner) if owner != from => return Err(ApproveTokenError::Unauthorized),
+            Some(_) => {}
+        }
+        if approval_info.spender.owner == caller {
+            return Err(ApproveTokenError::InvalidSpender);
+        }
+        self.check_memo(approval_info.memo.as_ref())?;
+        if let Some(approvals) = self.token_approvals.get(&token_id) {
+            check_approval_capacity(approvals, &approval_info.spender, now)?;
+        }
+
+        let block_index = self.apply(
+            Transaction::new(
+                Operation::ApproveToken {
+                    from,
+                    spender: approval_info.spender,
+                    tid: token_id.clone(),
+                    expires_at: approval_info.expires_at,
+                },
+                Some(approval_info.created_at_time),
+                approval_info.memo.clone(),
+            ),
+            now,
+        )?;
+        let approvals = self.token_approvals.entry(token_id).or_default();
+        approvals.retain(|_, approval| is_live(approval, now));
+        approvals.insert(approval_info.spender, approval_info);
+        Ok(Nat::from(block_index))
+    }
+
+    /// Approves a spender to transfer any token owned by the caller's account.
+    pub fn approve_collection(
+        &mut self,
+        caller: Principal,
+        arg: ApproveCollectionArg,
+        now: TimeStamp,
+    ) -> ApproveCollectionResult {
+        let approval_info = arg.approval_info;
+        let from = Account {
+            owner: caller,
+            subaccount: approval_info.from_subaccount,
+        };
+        if approval_info.spender.owner == caller {
+            return Err(ApproveCollectionError::InvalidSpender);
+        }
+        self.check_memo(approval_info.memo.as_ref())?;
+        if let Some(approvals) = self.collection_approvals.get(&from) {
+            check_approval_capacity(approvals, &approval_info.spender, now)?;
+        }
+
+        let block_index = self.apply(
+            Transaction::new(
+                Operation::ApproveCollection {
+                    from,
+                    spender: approval_info.spender,
+                    expires_at: approval_info.expires_at,
+                },
+                Some(approval_info.created_at_time),
+                approval_info.memo.clone(),
+            ),
+            now,
+        )?;
+        let approvals = self.collection_approvals.entry(from).or_default();
+        approvals.retain(|_, approval| is_live(approval, now));
+        approvals.insert(approval_info.spender, approval_info);
+        Ok(Nat::from(block_index))
+    }
+
+    /// Revokes the approval of a spender, or all approvals if no spender is
+    /// specified, of a token owned by the caller.
+    pub fn revoke_token_approval(
+        &mut self,
+        caller: Principal,
+        arg: RevokeTokenApprovalArg,
+        now: TimeStamp,
+    ) -> RevokeTokenApprovalResult {
+        let from = Account {
+            owner: caller,
+            subaccount: arg.from_subaccount,
+        };
+        match self.owner_of(&arg.token_id) {
+            None => return Err(RevokeTokenApprovalError::NonExistingTokenId),
+            Some(owner) if owner != from => return Err(RevokeTokenApprovalError::Unauthorized),
+            Some(_) => {}
+        }
+        let exists = self
+            .token_approvals
+            .get(&arg.token_id)
+            .is_some_and(|approvals| has_live_approval(approvals, arg.spender.as_ref(), now));
+        if !exists {
+            return Err(RevokeTokenApprovalError::ApprovalDoesNotExist);
+        }
+        self.check_memo(arg.memo.as_ref())?;
+
+        let block_index = self.apply(
+            Transaction::new(
+                Operation::RevokeToken {
+                    from,
+                    spender: arg.spender,
+                    tid: arg.token_id.clone(),
+                },
+                arg.created_at_time,
+                arg.memo,
+            ),
+            now,
+        )?;
+        remove_approvals(
+            &mut self.token_approvals,
+            &arg.token_id,
+            arg.spender.as_ref(),
+        );
+        Ok(Nat::from(block_index))
+    }
+
+    /// Revokes the collection approval of a spender, or all collection
+    /// approvals if no spender is specified, of the caller's account.
+    pub fn revoke_collection_approval(
+        &mut self,
+        caller: Principal,
+        arg: RevokeCollectionApprovalArg,
+        now: TimeStamp,
+    ) -> RevokeCollectionApprovalResult {
+        let from = Account {
+            owner: caller,
+            subaccount: arg.from_subaccount,
+        };
+        let exists = self
+            .collection_approvals
+            .get(&from)
+            .is_some_and(|approvals| has_live_approval(approvals, arg.spender.as_ref(), now));
+        if !exists {
+            return Err(RevokeCollectionApprovalError::ApprovalDoesNotExist);
+        }
+        self.check_memo(arg.memo.as_ref())?;
+
+        let block_index = self.apply(
+            Transaction::new(
+                Operation::RevokeCollection {
+                    from,
+                    spender: arg.spender,
+                },
+                arg.created_at_time,
+                arg.memo,
+            ),
+            now,
+        )?;
+        remove_approvals(&mut self.collection_approvals, &from, arg.spender.as_ref());
+        Ok(Nat::from(block_index))
+    }
+
+    fn has_approval(
+        &self,
+        owner: &Account,
+        spender: &Account,
+        token_id: &TokenId,
+        now: TimeStamp,
+    ) -> bool {
+        let approved_by = |approvals: Option<&BTreeMap<Account, ApprovalInfo>>| {
+            approvals
+                .and_then(|approvals| approvals.get(spender))
+                .is_some_and(|approval| is_live(approval, now))
+        };
+        approved_by(self.token_approvals.get(token_id))
+            || approved_by(self.collection_approvals.get(owner))
+    }
+
+    /// Changes the owner of a token after a transfer. Transfers revoke all
+    /// approvals of the token.
+    fn move_token(&mut self, token_id: &TokenId, to: Account) -> Result<(), NonExistingTokenId> {
+        if to == self.minting_account {
+            self.tokens.remove(token_id).ok_or(NonExistingTokenId)?;
+        } else {
+            self.tokens
+                .get_mut(token_id)
+                .ok_or(NonExistingTokenId)?
+                .owner = to;
+        }
+        self.token_approvals.remove(token_id);
+        Ok(())
+    }
+
+    fn check_memo(&self, memo: Option<&Memo>) -> Result<(), CommonError> {
+        match memo {
+            Some(memo) if memo.0.len() > self.max_memo_length as usize => {
+                Err(CommonError::GenericError {
+                    error_code: error_codes::MEMO_TOO_LONG,
+                    message: format!(
+                        "the memo field size of {} bytes is above the allowed limit of {} bytes",
+                        memo.0.len(),
+                        self.max_memo_length
+                    ),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn apply(&mut self, tx: Transaction, now: TimeStamp) -> Result<BlockIndex, CommonError> {
+        apply_transaction(self, tx, now, Tokens::zero())
+            .map(|(block_index, _)| block_index)
+            .map_err(|err| match err {
+                CoreTransferError::TxTooOld { .. } => CommonError::TooOld,
+                CoreTransferError::TxCreatedInFuture { ledger_time } => {
+                    CommonError::CreatedInFuture {
+                        ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+                    }
+                }
+                CoreTransferError::TxDuplicate { duplicate_of } => {
+                    CommonError::Duplicate { duplicate_of }
+                }
+                CoreTransferError::TxThrottled => CommonError::GenericError {
+                    error_code: error_codes::THROTTLED,
+                    message: "too many transactions in the transaction window".to_string(),
+                },
+                // The ownership of the token is checked before applying the
+                // transaction, so the balance book disagrees with the owners.
+                err => CommonError::GenericError {
+                    error_code: error_codes::INCONSISTENT_BALANCES,
+                    message: format!(
+                        "the token balances are inconsistent with the token owners: {:?}",
+                        err
+                    ),
+                },
+            })
+    }
+}
+
+fn is_live(approval: &ApprovalInfo, now: TimeStamp) -> bool {
+    approval.expires_at.map_or(true, |expires_at| {
+        expires_at > now.as_nanos_since_unix_epoch()
+    })
+}
+
+fn has_live_approval(
+    approvals: &BTreeMap<Account, ApprovalInfo>,
+    spender: Option<&Account>,
+    now: TimeStamp,
+) -> bool {
+    match spender {
+        Some(spender) => approvals
+            .get(spender)
+            .is_some_and(|approval| is_live(approval, now)),
+        None => approvals.values().any(|approval| is_live(approval, now)),
+    }
+}
+
+fn check_approval_capacity(
+    approvals: &BTreeMap<Account, ApprovalInfo>,
+    spender: &Account,
+    now: TimeStamp,
+) -> Result<(), CommonError> {
+    let num_live = approvals
+        .iter()
+        .filter(|(other, approval)| *other != spender && is_live(approval, now))
+        .count();
+    if num_live >= MAX_APPROVALS_PER_TOKEN_OR_COLLECTION {
+        return Err(CommonError::GenericError {
+            error_code: error_codes::TOO_MANY_APPROVALS,
+            message: format!(
+                "the number of approvals cannot exceed {}",
+                MAX_APPROVALS_PER_TOKEN_OR_COLLECTION
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn remove_approvals<K: Ord>(
+    approvals: &mut BTreeMap<K, BTreeMap<Account, ApprovalInfo>>,
+    key: &K,
+    spender: Option<&Account>,
+) {
+    match spender {
+        Some(spender) => {
+            if let Some(by_spender) = approvals.get_mut(key) {
+                by_spender.remove(spender);
+                if by_spender.is_empty() {
+                    approvals.remove(key);
+                }
+            }
+        }
+        None => {
+            approvals.remove(key);
+        }
+    }
+}
+
+fn lower_bound<K>(prev: Option<&K>) -> (Bound<&K>, Bound<&K>) {
+    match prev {
+        Some(prev) => (Bound::Excluded(prev), Bound::Unbounded),
+        None => (Bound::Unbounded, Bound::Unbounded),
+    }
+}
+
+fn effective_take(take: Option<usize>) -> usize {
+    take.unwrap_or(DEFAULT_TAKE_VALUE).min(MAX_TAKE_VALUE)
+}
+
+impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> LedgerContext for Ledger<Rt, Wasm> {
+    type AccountId = Account;
+    type AllowancesData = ic_ledger_core::approvals::HeapAllowancesData<Account, Tokens>;
+    type BalancesStore = BTreeMap<Self::AccountId, Tokens>;
+    type Tokens = Tokens;
+
+    fn balances(&self) -> &Balances<Self::BalancesStore> {
+        &self.balances
+    }
+
+    fn balances_mut(&mut self) -> &mut Balances<Self::BalancesStore> {
+        &mut self.balances
+    }
+
+    fn approvals(&self) -> &AllowanceTable<Self::AllowancesData> {
+        &self.approvals
+    }
+
+    fn approvals_mut(&mut self) -> &mut AllowanceTable<Self::AllowancesData> {
+        &mut self.approvals
+    }
+
+    fn fee_collector(&self) -> Option<&FeeCollector<Self::AccountId>> {
+        None
+    }
+}
+
+impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> LedgerData for Ledger<Rt, Wasm> {
+    type Runtime = Rt;
+    type ArchiveWasm = Wasm;
+    type Transaction = Transaction;
+    type Block = Block;
+
+    fn transaction_window(&self) -> Duration {
+        TRANSACTION_WINDOW
+    }
+
+    fn max_transactions_in_window(&self) -> usize {
+        MAX_TRANSACTIONS_IN_WINDOW
+    }
+
+    fn max_transactions_to_purge(&self) -> usize {
+        MAX_TRANSACTIONS_TO_PURGE
+    }
+
+    /// The NFT ledger never trims accounts because [`Transaction::burn`]
+    /// returns `None`. The limit only saves selecting accounts to trim.
+    fn max_number_of_accounts(&self) -> usize {
+        usize::MAX / 2
+    }
+
+    fn accounts_overflow_trim_quantity(&self) -> usize {
+        1
+    }
+
+    fn token_name(&self) -> &str {
+        &self.token_name
+    }
+
+    fn token_symbol(&self) -> &str {
+        &self.token_symbol
+    }
+
+    fn blockchain(&self) -> &Blockchain<Self::Runtime, Self::ArchiveWasm> {
+        &self.blockchain
+    }
+
+    fn blockchain_mut(&mut self) -> &mut Blockchain<Self::Runtime, Self::ArchiveWasm> {
+        &mut self.blockchain
+    }
+
+    fn transactions_by_hash(&self) -> &BTreeMap<HashOf<Self::Transaction>, BlockIndex> {
+        &self.transactions_by_hash
+    }
+
+    fn transactions_by_hash_mut(&mut self) -> &mut BTreeMap<HashOf<Self::Transaction>, BlockIndex> {
+        &mut self.transactions_by_hash
+    }
+
+    fn transactions_by_height(&self) -> &VecDeque<TransactionInfo<Self::Transaction>> {
+        &self.transactions_by_height
+    }
+
+    fn transactions_by_height_mut(&mut self) -> &mut VecDeque<TransactionInfo<Self::Transaction>> {
+        &mut self.transactions_by_height
+    }
+
+    fn on_purged_transaction(&mut self, _height: BlockIndex) {}
+
+    fn fee_collector_mut(&mut self) -> Option<&mut FeeCollector<Self::AccountId>> {
+        None
+    }
+}
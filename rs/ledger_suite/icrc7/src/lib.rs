@@ -0,0 +1,522 @@
+//! Blocks and transactions of a ledger for non-fungible tokens implementing
+//! the [ICRC-7](https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-7/ICRC-7.md)
+//! and [ICRC-37](https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-37/ICRC-37.md)
+//! standards.
+//!
+//! The ledger reuses the blockchain and archiving machinery of the fungible
+//! ledgers. Blocks are encoded as CBOR ICRC-3 values, so the ICRC-1 archive
+//! canister can store them and serve them through `icrc3_get_blocks`.
+//!
+//! The balance book of the ledger framework tracks the number of tokens owned
+//! by each account, while the [ledger::Ledger] keeps track of the owner of each
+//! token and of the approvals.
+//!
+//! This crate only provides the ledger library.
+//!
+//! TODO: The NFT ledger canister (its candid service and init and upgrade
+//! arguments), the index canister and the Rosetta integration are split out
+//! of the request that added this library and are tracked as a separate
+//! request.
+
+pub mod endpoints;
+pub mod ledger;
+mod stored;
+
+use ic_icrc1::blocks::{generic_block_to_encoded_block, try_encoded_block_to_generic_block};
+use ic_icrc1_tokens_u64::U64;
+use ic_ledger_canister_core::ledger::{LedgerContext, LedgerTransaction, TxApplyError};
+use ic_ledger_core::{
+    approvals::{AllowanceTable, HeapAllowancesData},
+    balances::Balances,
+    block::{BlockType, EncodedBlock, FeeCollector},
+    timestamp::TimeStamp,
+};
+use ic_ledger_hash_of::HashOf;
+use icrc_ledger_types::icrc::generic_value::{Map, Value};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::Memo;
+use icrc_ledger_types::icrc3::blocks::SupportedBlockType;
+use icrc_ledger_types::icrc7::metadata::TokenId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The number of tokens owned by an account.
+pub type Tokens = U64;
+
+pub type LedgerBalances = Balances<BTreeMap<Account, Tokens>>;
+pub type LedgerAllowances = AllowanceTable<HeapAllowancesData<Account, Tokens>>;
+
+const ICRC7_URL: &str = "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-7/ICRC-7.md";
+const ICRC37_URL: &str = "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-37/ICRC-37.md";
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Operation {
+    Mint {
+        to: Account,
+        tid: TokenId,
+        metadata: Map,
+    },
+    Burn {
+        from: Account,
+        tid: TokenId,
+    },
+    Transfer {
+        from: Account,
+        to: Account,
+        tid: TokenId,
+    },
+    ApproveToken {
+        from: Account,
+        spender: Account,
+        tid: TokenId,
+        expires_at: Option<u64>,
+    },
+    ApproveCollection {
+        from: Account,
+        spender: Account,
+        expires_at: Option<u64>,
+    },
+    /// Revokes the approval of `spender`, or all approvals of the token if
+    /// `spender` is not set.
+    RevokeToken {
+        from: Account,
+        spender: Option<Account>,
+        tid: TokenId,
+    },
+    /// Revokes the collection approval of `spender`, or all collection
+    /// approvals of `from` if `spender` is not set.
+    RevokeCollection {
+        from: Account,
+        spender: Option<Account>,
+    },
+    TransferFrom {
+        from: Account,
+        to: Account,
+        spender: Account,
+        tid: TokenId,
+    },
+}
+
+impl Operation {
+    /// Returns the ICRC-3 block type of blocks containing this operation.
+    pub fn btype(&self) -> &'static str {
+        match self {
+            Self::Mint { .. } => "7mint",
+            Self::Burn { .. } => "7burn",
+            Self::Transfer { .. } => "7xfer",
+            Self::ApproveToken { .. } => "37approve",
+            Self::ApproveCollection { .. } => "37approve_coll",
+            Self::RevokeToken { .. } => "37revoke",
+            Self::RevokeCollection { .. } => "37revoke_coll",
+            Self::TransferFrom { .. } => "37xfer",
+        }
+    }
+}
+
+/// Returns the ICRC-3 block types produced by the NFT ledger.
+pub fn supported_block_types() -> Vec<SupportedBlockType> {
+    [
+        ("7mint", ICRC7_URL),
+        ("7burn", ICRC7_URL),
+        ("7xfer", ICRC7_URL),
+        ("37approve", ICRC37_URL),
+        ("37approve_coll", ICRC37_URL),
+        ("37revoke", ICRC37_URL),
+        ("37revoke_coll", ICRC37_URL),
+        ("37xfer", ICRC37_URL),
+    ]
+    .into_iter()
+    .map(|(block_type, url)| SupportedBlockType {
+        block_type: block_type.to_string(),
+        url: url.to_string(),
+    })
+    .collect()
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Deserialize, Serialize)]
+#[serde(try_from = "StoredTransaction", into = "StoredTransaction")]
+pub struct Transaction {
+    pub operation: Operation,
+    pub created_at_time: Option<u64>,
+    pub memo: Option<Memo>,
+}
+
+/// The representation of a [Transaction] in the ledger state: the ICRC-3
+/// value of the transaction together with its block type.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct StoredTransaction {
+    btype: String,
+    #[serde(with = "stored::value")]
+    tx: Value,
+}
+
+impl From<Transaction> for StoredTransaction {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            btype: transaction.operation.btype().to_string(),
+            tx: transaction.to_value(),
+        }
+    }
+}
+
+impl TryFrom<StoredTransaction> for Transaction {
+    type Error = String;
+
+    fn try_from(stored: StoredTransaction) -> Result<Self, Self::Error> {
+        Self::from_value(&stored.btype, stored.tx)
+    }
+}
+
+impl Transaction {
+    pub fn new(operation: Operation, created_at_time: Option<u64>, memo: Option<Memo>) -> Self {
+        Self {
+            operation,
+            created_at_time,
+            memo,
+        }
+    }
+
+    /// Returns the ICRC-3 `tx` value of this transaction.
+    pub fn to_value(&self) -> Value {
+        let mut tx = Map::new();
+        let mut insert_account = |key: &str, account: &Account| {
+            tx.insert(key.to_string(), Value::from(*account));
+        };
+        match &self.operation {
+            Operation::Mint { to, .. } => insert_account("to", to),
+            Operation::Burn { from, .. } => insert_account("from", from),
+            Operation::Transfer { from, to, .. } => {
+                insert_account("from", from);
+                insert_account("to", to);
+            }
+            Operation::ApproveToken { from, spender, .. }
+            | Operation::ApproveCollection { from, spender, .. } => {
+                insert_account("from", from);
+                insert_account("spender", spender);
+            }
+            Operation::RevokeToken { from, spender, .. }
+            | Operation::RevokeCollection { from, spender } => {
+                insert_account("from", from);
+                if let Some(spender) = spender {
+                    insert_account("spender", spender);
+                }
+            }
+            Operation::TransferFrom {
+                from, to, spender, ..
+            } => {
+                insert_account("from", from);
+                insert_account("to", to);
+                insert_account("spender", spender);
+            }
+        }
+        match &self.operation {
+            Operation::Mint { tid, .. }
+            | Operation::Burn { tid, .. }
+            | Operation::Transfer { tid, .. }
+            | Operation::ApproveToken { tid, .. }
+            | Operation::RevokeToken { tid, .. }
+            | Operation::TransferFrom { tid, .. } => {
+                tx.insert("tid".to_string(), Value::Nat(tid.clone()));
+            }
+            Operation::ApproveCollection { .. } | Operation::RevokeCollection { .. } => {}
+        }
+        match &self.operation {
+            Operation::Mint { metadata, .. } => {
+                tx.insert("meta".to_string(), Value::Map(metadata.clone()));
+            }
+            Operation::ApproveToken { expires_at, .. }
+            | Operation::ApproveCollection { expires_at, .. } => {
+                if let Some(expires_at) = expires_at {
+                    tx.insert("exp".to_string(), Value::Nat64(*expires_at));
+                }
+            }
+            _ => {}
+        }
+        if let Some(memo) = &self.memo {
+            tx.insert("memo".to_string(), Value::Blob(memo.0.clone()));
+        }
+        if let Some(created_at_time) = self.created_at_time {
+            tx.insert("ts".to_string(), Value::Nat64(created_at_time));
+        }
+        Value::Map(tx)
+    }
+
+    /// Parses a transaction from its ICRC-3 `tx` value and the `btype` of
+    /// the enclosing block.
+    pub fn from_value(btype: &str, value: Value) -> Result<Self, String> {
+        let mut tx = value
+            .as_map()
+            .map_err(|variant| format!("expected transaction to be a Map, found {}", variant))?;
+        let mut account = |key: &str| -> Result<Option<Account>, String> {
+            tx.remove(key)
+                .map(|v| Account::try_from(v).map_err(|e| format!("invalid `{}`: {}", key, e)))
+                .transpose()
+        };
+        let from = account("from")?;
+        let to = account("to")?;
+        let spender = account("spender")?;
+        let tid = tx
+            .remove("tid")
+            .map(|v| TokenId::try_from(v).map_err(|e| format!("invalid `tid`: {}", e)))
+            .transpose()?;
+        let expires_at = tx
+            .remove("exp")
+            .map(|v| u64::try_from(v).map_err(|e| format!("invalid `exp`: {}", e)))
+            .transpose()?;
+        let metadata = tx
+            .remove("meta")
+            .map(|v| Map::try_from(v).map_err(|e| format!("invalid `meta`: {}", e)))
+            .transpose()?;
+        let memo = tx
+            .remove("memo")
+            .map(|v| {
+                v.as_blob()
+                    .map(Memo)
+                    .map_err(|variant| format!("invalid `memo`: expected Blob, found {}", variant))
+            })
+            .transpose()?;
+        let created_at_time = tx
+            .remove("ts")
+            .map(|v| u64::try_from(v).map_err(|e| format!("invalid `ts`: {}", e)))
+            .transpose()?;
+
+        let required = |field: &str| format!("`{}` field required for `{}` blocks", field, btype);
+        let operation = match btype {
+            "7mint" => Operation::Mint {
+                to: to.ok_or_else(|| required("to"))?,
+                tid: tid.ok_or_else(|| required("tid"))?,
+                metadata: metadata.unwrap_or_default(),
+            },
+            "7burn" => Operation::Burn {
+                from: from.ok_or_else(|| required("from"))?,
+                tid: tid.ok_or_else(|| required("tid"))?,
+            },
+            "7xfer" => Operation::Transfer {
+                from: from.ok_or_else(|| required("from"))?,
+                to: to.ok_or_else(|| required("to"))?,
+                tid: tid.ok_or_else(|| required("tid"))?,
+            },
+            "37approve" => Operation::ApproveToken {
+                from: from.ok_or_else(|| required("from"))?,
+                spender: spender.ok_or_else(|| required("spender"))?,
+                tid: tid.ok_or_else(|| required("tid"))?,
+                expires_at,
+            },
+            "37approve_coll" => Operation::ApproveCollection {
+                from: from.ok_or_else(|| required("from"))?,
+                spender: spender.ok_or_else(|| required("spender"))?,
+                expires_at,
+            },
+            "37revoke" => Operation::RevokeToken {
+                from: from.ok_or_else(|| required("from"))?,
+                spender,
+                tid: tid.ok_or_else(|| required("tid"))?,
+            },
+            "37revoke_coll" => Operation::RevokeCollection {
+                from: from.ok_or_else(|| required("from"))?,
+                spender,
+            },
+            "37xfer" => Operation::TransferFrom {
+                from: from.ok_or_else(|| required("from"))?,
+                to: to.ok_or_else(|| required("to"))?,
+                spender: spender.ok_or_else(|| required("spender"))?,
+                tid: tid.ok_or_else(|| required("tid"))?,
+            },
+            unknown => return Err(format!("unknown block type {}", unknown)),
+        };
+        Ok(Self {
+            operation,
+            created_at_time,
+            memo,
+        })
+    }
+}
+
+impl LedgerTransaction for Transaction {
+    type AccountId = Account;
+    type Tokens = Tokens;
+
+    /// Returns `None`, so the NFT ledger never trims accounts: burning a number
+    /// of tokens without knowing their IDs would leave the ledger in an
+    /// inconsistent state.
+    fn burn(
+        _from: Account,
+        _spender: Option<Account>,
+        _amount: Tokens,
+        _created_at_time: Option<TimeStamp>,
+        _memo: Option<u64>,
+    ) -> Option<Self> {
+        None
+    }
+
+    /// Constructs a collection approval; the amount is ignored because
+    /// approvals of non-fungible tokens carry no allowance.
+    fn approve(
+        from: Account,
+        spender: Account,
+        _amount: Tokens,
+        created_at_time: Option<TimeStamp>,
+        memo: Option<u64>,
+    ) -> Self {
+        Self {
+            operation: Operation::ApproveCollection {
+                from,
+                spender,
+                expires_at: None,
+            },
+            created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
+            memo: memo.map(Memo::from),
+        }
+    }
+
+    fn created_at_time(&self) -> Option<TimeStamp> {
+        self.created_at_time
+            .map(TimeStamp::from_nanos_since_unix_epoch)
+    }
+
+    fn hash(&self) -> HashOf<Self> {
+        HashOf::new(self.to_value().hash())
+    }
+
+    /// Updates the number of tokens owned by the accounts involved in the
+    /// transaction. The caller is responsible for checking the ownership of the
+    /// token and the approvals before applying the transaction.
+    fn apply<C>(
+        &self,
+        context: &mut C,
+        _now: TimeStamp,
+        _effective_fee: Tokens,
+    ) -> Result<(), TxApplyError<Tokens>>
+    where
+        C: LedgerContext<AccountId = Self::AccountId, Tokens = Tokens>,
+    {
+        let one = Tokens::from(1);
+        match &self.operation {
+            Operation::Mint { to, .. } => context.balances_mut().mint(to, one)?,
+            Operation::Burn { from, .. } => context.balances_mut().burn(from, one)?,
+            Operation::Transfer { from, to, .. } | Operation::TransferFrom { from, to, .. } => {
+                context
+                    .balances_mut()
+                    .transfer(from, to, one, Tokens::from(0), None)?
+            }
+            Operation::ApproveToken { .. }
+            | Operation::ApproveCollection { .. }
+            | Operation::RevokeToken { .. }
+            | Operation::RevokeCollection { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Block {
+    pub parent_hash: Option<HashOf<EncodedBlock>>,
+    pub transaction: Transaction,
+    pub timestamp: u64,
+}
+
+impl Block {
+    /// Returns the ICRC-3 value of this block.
+    pub fn to_value(&self) -> Value {
+        let mut block = Map::new();
+        block.insert(
+            "btype".to_string(),
+            Value::text(self.transaction.operation.btype()),
+        );
+        if let Some(parent_hash) = self.parent_hash {
+            block.insert(
+                "phash".to_string(),
+                Value::blob(parent_hash.as_slice().to_vec()),
+            );
+        }
+        block.insert("ts".to_string(), Value::Nat64(self.timestamp));
+        block.insert("tx".to_string(), self.transaction.to_value());
+        Value::Map(block)
+    }
+}
+
+impl TryFrom<Value> for Block {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let mut block = value
+            .as_map()
+            .map_err(|variant| format!("expected block to be a Map, found {}", variant))?;
+        let btype = block
+            .remove("btype")
+            .ok_or("`btype` field required")?
+            .as_text()
+            .map_err(|variant| format!("invalid `btype`: expected Text, found {}", variant))?;
+        let parent_hash = block
+            .remove("phash")
+            .map(|v| {
+                let bytes = v.as_blob().map_err(|variant| {
+                    format!("invalid `phash`: expected Blob, found {}", variant)
+                })?;
+                <[u8; 32]>::try_from(bytes.as_slice())
+                    .map(HashOf::new)
+                    .map_err(|_| format!("invalid `phash` length {}", bytes.len()))
+            })
+            .transpose()?;
+        let timestamp = u64::try_from(block.remove("ts").ok_or("`ts` field required")?)
+            .map_err(|e| format!("invalid `ts`: {}", e))?;
+        let transaction =
+            Transaction::from_value(&btype, block.remove("tx").ok_or("`tx` field required")?)?;
+        Ok(Self {
+            parent_hash,
+            transaction,
+            timestamp,
+        })
+    }
+}
+
+impl BlockType for Block {
+    type Transaction = Transaction;
+    type AccountId = Account;
+    type Tokens = Tokens;
+
+    fn encode(self) -> EncodedBlock {
+        generic_block_to_encoded_block(self.to_value()).expect("bug: failed to encode a block")
+    }
+
+    fn decode(encoded_block: EncodedBlock) -> Result<Self, String> {
+        Self::try_from(try_encoded_block_to_generic_block(&encoded_block)?)
+    }
+
+    fn block_hash(encoded_block: &EncodedBlock) -> HashOf<EncodedBlock> {
+        ic_icrc1::hash::hash_cbor(encoded_block.as_slice())
+            .map(HashOf::new)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "bug: encoded block {} is not hashable cbor: {}",
+                    hex::encode(encoded_block.as_slice()),
+                    err
+                )
+            })
+    }
+
+    fn parent_hash(&self) -> Option<HashOf<EncodedBlock>> {
+        self.parent_hash
+    }
+
+    fn timestamp(&self) -> TimeStamp {
+        TimeStamp::from_nanos_since_unix_epoch(self.timestamp)
+    }
+
+    fn from_transaction(
+        parent_hash: Option<HashOf<EncodedBlock>>,
+        transaction: Self::Transaction,
+        timestamp: TimeStamp,
+        _effective_fee: Tokens,
+        _fee_collector: Option<FeeCollector<Self::AccountId>>,
+    ) -> Self {
+        Self {
+            parent_hash,
+            transaction,
+            timestamp: timestamp.as_nanos_since_unix_epoch(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,62 @@
+//! Serialization helpers for the parts of the ledger state that contain
+//! candid natural numbers, which do not round-trip through CBOR.
+
+/// Serializes a [Value](icrc_ledger_types::icrc::generic_value::Value) as its
+/// CBOR encoding.
+pub mod value {
+    use ic_icrc1::blocks::{generic_block_to_encoded_block, try_encoded_block_to_generic_block};
+    use ic_ledger_core::block::EncodedBlock;
+    use icrc_ledger_types::icrc::generic_value::Value;
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+    use serde_bytes::ByteBuf;
+
+    pub fn serialize<S: Serializer>(value: &Value, serializer: S) -> Result<S::Ok, S::Error> {
+        let encoded = generic_block_to_encoded_block(value.clone()).map_err(S::Error::custom)?;
+        serializer.serialize_bytes(encoded.as_slice())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        try_encoded_block_to_generic_block(&EncodedBlock::from(bytes.into_vec()))
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Serializes a map keyed by token IDs as a sequence of entries with
+/// LEB128-encoded keys.
+pub mod token_id_map {
+    use candid::Nat;
+    use icrc_ledger_types::icrc7::metadata::TokenId;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+    use serde_bytes::ByteBuf;
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S, V>(map: &BTreeMap<TokenId, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        V: Serialize,
+    {
+        serializer.collect_seq(map.iter().map(|(token_id, value)| {
+            let mut key = vec![];
+            token_id
+                .encode(&mut key)
+                .expect("bug: failed to encode a token ID");
+            (ByteBuf::from(key), value)
+        }))
+    }
+
+    pub fn deserialize<'de, D, V>(deserializer: D) -> Result<BTreeMap<TokenId, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Deserialize<'de>,
+    {
+        Vec::<(ByteBuf, V)>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(key, value)| {
+                Nat::decode(&mut &key[..])
+                    .map(|token_id| (token_id, value))
+                    .map_err(D::Error::custom)
+            })
+            .collect()
+    }
+}
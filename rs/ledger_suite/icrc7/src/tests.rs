@@ -0,0 +1,598 @@
+use crate::endpoints::{InitArgs, MintArg, MintError};
+use crate::ledger::{error_codes, Ledger};
+use crate::{Block, Operation, Tokens, Transaction};
+use async_trait::async_trait;
+use candid::utils::{ArgumentDecoder, ArgumentEncoder};
+use candid::{Nat, Principal};
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_ledger_canister_core::archive::{ArchiveCanisterWasm, ArchiveOptions};
+use ic_ledger_canister_core::ledger::{LedgerContext, LedgerTransaction};
+use ic_ledger_canister_core::runtime::Runtime;
+use ic_ledger_core::block::BlockType;
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_ledger_hash_of::HashOf;
+use icrc_ledger_types::icrc::generic_value::{ICRC3Value, Value};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::Memo;
+use icrc_ledger_types::icrc37::approvals::{
+    ApprovalInfo, ApproveCollectionArg, ApproveTokenArg, ApproveTokenError,
+    RevokeCollectionApprovalArg, RevokeCollectionApprovalError, RevokeTokenApprovalArg,
+};
+use icrc_ledger_types::icrc37::transfer_from::{TransferFromArg, TransferFromError};
+use icrc_ledger_types::icrc7::transfer::{TransferArg, TransferError};
+use std::borrow::Cow;
+
+#[derive(Debug)]
+struct TestRuntime;
+
+#[async_trait]
+impl Runtime for TestRuntime {
+    fn id() -> CanisterId {
+        CanisterId::from_u64(1)
+    }
+
+    fn print(msg: impl AsRef<str>) {
+        println!("{}", msg.as_ref())
+    }
+
+    async fn call<In, Out>(
+        _id: CanisterId,
+        _method: &str,
+        _cycles: u64,
+        _args: In,
+    ) -> Result<Out, (i32, String)>
+    where
+        In: ArgumentEncoder + Send,
+        Out: for<'a> ArgumentDecoder<'a>,
+    {
+        unimplemented!("the tests do not make inter-canister calls")
+    }
+}
+
+#[derive(Debug)]
+struct TestArchiveWasm;
+
+impl ArchiveCanisterWasm for TestArchiveWasm {
+    fn archive_wasm() -> Cow<'static, [u8]> {
+        Cow::Borrowed(&[])
+    }
+}
+
+type TestLedger = Ledger<TestRuntime, TestArchiveWasm>;
+
+const NOW: u64 = 1_000_000_000_000;
+
+fn now() -> TimeStamp {
+    TimeStamp::from_nanos_since_unix_epoch(NOW)
+}
+
+fn principal(id: u64) -> Principal {
+    PrincipalId::new_user_test_id(id).0
+}
+
+fn account(id: u64) -> Account {
+    Account::from(principal(id))
+}
+
+fn minter() -> Principal {
+    principal(0)
+}
+
+fn new_ledger(supply_cap: Option<u64>) -> TestLedger {
+    TestLedger::from_init_args(InitArgs {
+        minting_account: Account::from(minter()),
+        token_symbol: "NFT".to_string(),
+        token_name: "Test collection".to_string(),
+        description: None,
+        logo: None,
+        supply_cap: supply_cap.map(Nat::from),
+        archive_options: ArchiveOptions {
+            trigger_threshold: 1_000,
+            num_blocks_to_archive: 100,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            more_controller_ids: None,
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+        max_memo_length: None,
+    })
+}
+
+fn mint(ledger: &mut TestLedger, to: Account, token_id: u64) -> Nat {
+    ledger
+        .mint(
+            minter(),
+            MintArg {
+                to,
+                token_id: Nat::from(token_id),
+                metadata: vec![(
+                    "icrc7:metadata:uri:image".to_string(),
+                    ICRC3Value::Text(format!("https://example.com/{}.png", token_id)),
+                )],
+                memo: None,
+                created_at_time: None,
+            },
+            now(),
+        )
+        .expect("failed to mint a token")
+}
+
+fn transfer_arg(to: Account, token_id: u64) -> TransferArg {
+    TransferArg {
+        from_subaccount: None,
+        to,
+        token_id: Nat::from(token_id),
+        memo: None,
+        created_at_time: None,
+    }
+}
+
+fn approval_info(spender: Account, expires_at: Option<u64>) -> ApprovalInfo {
+    ApprovalInfo {
+        spender,
+        from_subaccount: None,
+        expires_at,
+        memo: None,
+        created_at_time: NOW,
+    }
+}
+
+fn transfer_from_arg(from: Account, to: Account, token_id: u64) -> TransferFromArg {
+    TransferFromArg {
+        spender_subaccount: None,
+        from,
+        to,
+        token_id: Nat::from(token_id),
+        memo: None,
+        created_at_time: None,
+    }
+}
+
+#[test]
+fn block_encoding_round_trip() {
+    let operations = vec![
+        Operation::Mint {
+            to: account(1),
+            tid: Nat::from(1u64),
+            metadata: [("name".to_string(), Value::text("one"))].into(),
+        },
+        Operation::Burn {
+            from: account(1),
+            tid: Nat::from(u128::MAX),
+        },
+        Operation::Transfer {
+            from: account(1),
+            to: account(2),
+            tid: Nat::from(1u64),
+        },
+        Operation::ApproveToken {
+            from: account(1),
+            spender: account(2),
+            tid: Nat::from(1u64),
+            expires_at: Some(NOW),
+        },
+        Operation::ApproveCollection {
+            from: account(1),
+            spender: account(2),
+            expires_at: None,
+        },
+        Operation::RevokeToken {
+            from: account(1),
+            spender: None,
+            tid: Nat::from(1u64),
+        },
+        Operation::RevokeCollection {
+            from: account(1),
+            spender: Some(account(2)),
+        },
+        Operation::TransferFrom {
+            from: account(1),
+            to: account(3),
+            spender: account(2),
+            tid: Nat::from(1u64),
+        },
+    ];
+    for operation in operations {
+        let block = Block {
+            parent_hash: Some(HashOf::new([1; 32])),
+            transaction: Transaction::new(operation, Some(NOW), Some(Memo::from(42))),
+            timestamp: NOW + 1,
+        };
+        let decoded = Block::decode(block.clone().encode()).expect("failed to decode a block");
+        assert_eq!(block, decoded);
+
+        let value = block.to_value().as_map().unwrap();
+        assert_eq!(
+            value.get("btype"),
+            Some(&Value::text(block.transaction.operation.btype()))
+        );
+    }
+}
+
+#[test]
+fn transaction_serialization_round_trip() {
+    let tx = Transaction::new(
+        Operation::ApproveToken {
+            from: account(1),
+            spender: account(2),
+            tid: Nat::from(7u64),
+            expires_at: None,
+        },
+        Some(NOW),
+        None,
+    );
+    let mut bytes = vec![];
+    ciborium::ser::into_writer(&tx, &mut bytes).unwrap();
+    let decoded: Transaction = ciborium::de::from_reader(&bytes[..]).unwrap();
+    assert_eq!(tx, decoded);
+}
+
+#[test]
+fn mint_and_transfer() {
+    let mut ledger = new_ledger(None);
+    assert_eq!(mint(&mut ledger, account(1), 1), Nat::from(0u64));
+    assert_eq!(mint(&mut ledger, account(1), 2), Nat::from(1u64));
+    assert_eq!(ledger.total_supply(), 2);
+    assert_eq!(ledger.balance_of(&account(1)), Nat::from(2u64));
+    assert_eq!(ledger.owner_of(&Nat::from(1u64)), Some(account(1)));
+    assert_eq!(
+        ledger.token_metadata(&Nat::from(1u64)),
+        Some(vec![(
+            "icrc7:metadata:uri:image".to_string(),
+            ICRC3Value::Text("https://example.com/1.png".to_string())
+        )])
+    );
+
+    assert_eq!(
+        ledger.transfer(principal(2), transfer_arg(account(3), 1), now()),
+        Err(TransferError::Unauthorized)
+    );
+    assert_eq!(
+        ledger.transfer(principal(1), transfer_arg(account(3), 5), now()),
+        Err(TransferError::NonExistingTokenId)
+    );
+    assert_eq!(
+        ledger.transfer(principal(1), transfer_arg(account(1), 1), now()),
+        Err(TransferError::InvalidRecipient)
+    );
+    assert_eq!(
+        ledger.transfer(principal(1), transfer_arg(account(2), 1), now()),
+        Ok(Nat::from(2u64))
+    );
+    assert_eq!(ledger.owner_of(&Nat::from(1u64)), Some(account(2)));
+    assert_eq!(ledger.balance_of(&account(1)), Nat::from(1u64));
+    assert_eq!(ledger.balance_of(&account(2)), Nat::from(1u64));
+    assert_eq!(
+        ledger.tokens_of(&account(1), None, None),
+        vec![Nat::from(2u64)]
+    );
+    assert_eq!(
+        ledger.tokens(Some(&Nat::from(1u64)), None),
+        vec![Nat::from(2u64)]
+    );
+}
+
+#[test]
+fn mint_checks() {
+    let mut ledger = new_ledger(Some(1));
+    let arg = MintArg {
+        to: account(1),
+        token_id: Nat::from(1u64),
+        metadata: vec![],
+        memo: None,
+        created_at_time: None,
+    };
+    assert_eq!(
+        ledger.mint(principal(1), arg.clone(), now()),
+        Err(MintError::Unauthorized)
+    );
+    assert_eq!(
+        ledger.mint(
+            minter(),
+            MintArg {
+                to: Account::from(minter()),
+                ..arg.clone()
+            },
+            now()
+        ),
+        Err(MintError::InvalidRecipient)
+    );
+    assert!(ledger.mint(minter(), arg.clone(), now()).is_ok());
+    assert_eq!(
+        ledger.mint(minter(), arg.clone(), now()),
+        Err(MintError::TokenIdAlreadyExists)
+    );
+    assert_eq!(
+        ledger.mint(
+            minter(),
+            MintArg {
+                token_id: Nat::from(2u64),
+                ..arg
+            },
+            now()
+        ),
+        Err(MintError::SupplyCapReached)
+    );
+}
+
+#[test]
+fn transfer_to_minting_account_burns() {
+    let mut ledger = new_ledger(None);
+    mint(&mut ledger, account(1), 1);
+    assert!(ledger
+        .transfer(
+            principal(1),
+            transfer_arg(Account::from(minter()), 1),
+            now()
+        )
+        .is_ok());
+    assert_eq!(ledger.owner_of(&Nat::from(1u64)), None);
+    assert_eq!(ledger.total_supply(), 0);
+    assert_eq!(ledger.balance_of(&account(1)), Nat::from(0u64));
+}
+
+#[test]
+fn inconsistent_balances_are_reported_as_errors() {
+    let mut ledger = new_ledger(None);
+    mint(&mut ledger, account(1), 1);
+    // Make the balance book disagree with the owner of the token.
+    ledger
+        .balances_mut()
+        .burn(&account(1), Tokens::from(1))
+        .unwrap();
+
+    match ledger.transfer(principal(1), transfer_arg(account(2), 1), now()) {
+        Err(TransferError::GenericError { error_code, .. }) => {
+            assert_eq!(error_code, Nat::from(error_codes::INCONSISTENT_BALANCES))
+        }
+        other => panic!("expected a generic error, got {:?}", other),
+    }
+    assert_eq!(ledger.owner_of(&Nat::from(1u64)), Some(account(1)));
+}
+
+#[test]
+fn nft_transactions_cannot_burn_by_amount() {
+    assert!(<Transaction as LedgerTransaction>::burn(
+        account(1),
+        None,
+        Tokens::from(1),
+        None,
+        None
+    )
+    .is_none());
+}
+
+#[test]
+fn deduplicates_transfers() {
+    let mut ledger = new_ledger(None);
+    mint(&mut ledger, account(1), 1);
+    let arg = TransferArg {
+        created_at_time: Some(NOW),
+        ..transfer_arg(account(2), 1)
+    };
+    let block_index = ledger.transfer(principal(1), arg.clone(), now()).unwrap();
+    // Transfer the token back so that the ownership check passes.
+    ledger
+        .transfer(principal(2), transfer_arg(account(1), 1), now())
+        .unwrap();
+    assert_eq!(
+        ledger.transfer(principal(1), arg, now()),
+        Err(TransferError::Duplicate {
+            duplicate_of: block_index
+        })
+    );
+}
+
+#[test]
+fn token_approval_allows_transfer_from() {
+    let mut ledger = new_ledger(None);
+    mint(&mut ledger, account(1), 1);
+    assert_eq!(
+        ledger.transfer_from(
+            principal(2),
+            transfer_from_arg(account(1), account(3), 1),
+            now()
+        ),
+        Err(TransferFromError::Unauthorized)
+    );
+    assert_eq!(
+        ledger.approve_token(
+            principal(1),
+            ApproveTokenArg {
+                token_id: Nat::from(1u64),
+                approval_info: approval_info(account(1), None),
+            },
+            now()
+        ),
+        Err(ApproveTokenError::InvalidSpender)
+    );
+    ledger
+        .approve_token(
+            principal(1),
+            ApproveTokenArg {
+                token_id: Nat::from(1u64),
+                approval_info: approval_info(account(2), None),
+            },
+            now(),
+        )
+        .unwrap();
+    assert!(ledger.is_approved(principal(1), None, &account(2), &Nat::from(1u64), now()));
+    assert_eq!(
+        ledger
+            .token_approvals(&Nat::from(1u64), None, None, now())
+            .len(),
+        1
+    );
+
+    assert!(ledger
+        .transfer_from(
+            principal(2),
+            transfer_from_arg(account(1), account(3), 1),
+            now()
+        )
+        .is_ok());
+    assert_eq!(ledger.owner_of(&Nat::from(1u64)), Some(account(3)));
+    // Transfers revoke all token approvals.
+    assert!(!ledger.is_approved(principal(3), None, &account(2), &Nat::from(1u64), now()));
+    assert!(ledger
+        .token_approvals(&Nat::from(1u64), None, None, now())
+        .is_empty());
+}
+
+#[test]
+fn expired_approvals_are_ignored() {
+    let mut ledger = new_ledger(None);
+    mint(&mut ledger, account(1), 1);
+    ledger
+        .approve_token(
+            principal(1),
+            ApproveTokenArg {
+                token_id: Nat::from(1u64),
+                approval_info: approval_info(account(2), Some(NOW + 1)),
+            },
+            now(),
+        )
+        .unwrap();
+    let later = TimeStamp::from_nanos_since_unix_epoch(NOW + 1);
+    assert!(ledger.is_approved(principal(1), None, &account(2), &Nat::from(1u64), now()));
+    assert!(!ledger.is_approved(principal(1), None, &account(2), &Nat::from(1u64), later));
+    assert_eq!(
+        ledger.transfer_from(
+            principal(2),
+            transfer_from_arg(account(1), account(3), 1),
+            later
+        ),
+        Err(TransferFromError::Unauthorized)
+    );
+}
+
+#[test]
+fn collection_approvals() {
+    let mut ledger = new_ledger(None);
+    mint(&mut ledger, account(1), 1);
+    mint(&mut ledger, account(1), 2);
+    ledger
+        .approve_collection(
+            principal(1),
+            ApproveCollectionArg {
+                approval_info: approval_info(account(2), None),
+            },
+            now(),
+        )
+        .unwrap();
+    assert_eq!(
+        ledger
+            .collection_approvals(&account(1), None, None, now())
+            .iter()
+            .map(|approval| approval.spender)
+            .collect::<Vec<_>>(),
+        vec![account(2)]
+    );
+    assert!(ledger
+        .transfer_from(
+            principal(2),
+            transfer_from_arg(account(1), account(3), 1),
+            now()
+        )
+        .is_ok());
+
+    ledger
+        .revoke_collection_approval(
+            principal(1),
+            RevokeCollectionApprovalArg {
+                spender: Some(account(2)),
+                from_subaccount: None,
+                memo: None,
+                created_at_time: None,
+            },
+            now(),
+        )
+        .unwrap();
+    assert_eq!(
+        ledger.transfer_from(
+            principal(2),
+            transfer_from_arg(account(1), account(3), 2),
+            now()
+        ),
+        Err(TransferFromError::Unauthorized)
+    );
+    assert_eq!(
+        ledger.revoke_collection_approval(
+            principal(1),
+            RevokeCollectionApprovalArg {
+                spender: None,
+                from_subaccount: None,
+                memo: None,
+                created_at_time: None,
+            },
+            now(),
+        ),
+        Err(RevokeCollectionApprovalError::ApprovalDoesNotExist)
+    );
+}
+
+#[test]
+fn revoke_token_approvals() {
+    let mut ledger = new_ledger(None);
+    mint(&mut ledger, account(1), 1);
+    for spender in [2, 3] {
+        ledger
+            .approve_token(
+                principal(1),
+                ApproveTokenArg {
+                    token_id: Nat::from(1u64),
+                    approval_info: approval_info(account(spender), None),
+                },
+                now(),
+            )
+            .unwrap();
+    }
+    ledger
+        .revoke_token_approval(
+            principal(1),
+            RevokeTokenApprovalArg {
+                spender: None,
+                from_subaccount: None,
+                token_id: Nat::from(1u64),
+                memo: None,
+                created_at_time: None,
+            },
+            now(),
+        )
+        .unwrap();
+    assert!(ledger
+        .token_approvals(&Nat::from(1u64), None, None, now())
+        .is_empty());
+}
+
+#[test]
+fn ledger_state_serialization_round_trip() {
+    let mut ledger = new_ledger(None);
+    mint(&mut ledger, account(1), 1);
+    ledger
+        .approve_token(
+            principal(1),
+            ApproveTokenArg {
+                token_id: Nat::from(1u64),
+                approval_info: approval_info(account(2), None),
+            },
+            now(),
+        )
+        .unwrap();
+
+    let mut bytes = vec![];
+    ciborium::ser::into_writer(&ledger, &mut bytes).unwrap();
+    let decoded: TestLedger = ciborium::de::from_reader(&bytes[..]).unwrap();
+
+    assert_eq!(decoded.owner_of(&Nat::from(1u64)), Some(account(1)));
+    assert_eq!(
+        decoded.token_metadata(&Nat::from(1u64)),
+        ledger.token_metadata(&Nat::from(1u64))
+    );
+    assert_eq!(
+        decoded.token_approvals(&Nat::from(1u64), None, None, now()),
+        ledger.token_approvals(&Nat::from(1u64), None, None, now())
+    );
+}
@@ -889,12 +889,17 @@ mod checksum {
             access_kind: AccessKind,
         ) {
             self.index += 1;
-            self.value += self.index
-                * (access_addr as usize - base_addr)
-                * match access_kind {
-                    AccessKind::Read => 1,
-                    AccessKind::Write => 1 << 32,
-                };
+            // Offsets into Wasm64 memories may exceed 4 GiB, so the checksum
+            // must wrap around instead of overflowing.
+            let kind = match access_kind {
+                AccessKind::Read => 1,
+                AccessKind::Write => 1 << 32,
+            };
+            self.value = self.value.wrapping_add(
+                self.index
+                    .wrapping_mul(access_addr as usize - base_addr)
+                    .wrapping_mul(kind),
+            );
         }
     }
 
@@ -134,6 +134,10 @@ impl From<CanisterSettingsArgs> for Ic00CanisterSettingsArgs {
             log_visibility: settings.log_visibility.map(LogVisibilityV2::from),
             wasm_memory_limit: settings.wasm_memory_limit,
             wasm_memory_threshold: settings.wasm_memory_threshold,
+            log_buffer_size: None,
+            environment_variables: None,
+            wasm_trace_length: None,
+            priority_class: None,
         }
     }
 }
@@ -38,7 +38,7 @@ pub mod pocket_ic;
 pub mod state_api;
 
 use crate::state_api::state::OpOut;
-use ::pocket_ic::common::rest::{BinaryBlob, BlobId};
+use ::pocket_ic::common::rest::{BinaryBlob, BlobId, RawJournalEntry};
 use axum::async_trait;
 use pocket_ic::PocketIc;
 use serde::Deserialize;
@@ -57,6 +57,12 @@ pub trait Operation {
 
     /// Returns the unique identifier of this operation.
     fn id(&self) -> OpId;
+
+    /// Returns the journal entry recorded for this operation
+    /// if the operation changes the instance state deterministically.
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        None
+    }
 }
 
 /// Uniquely identifies an operation.
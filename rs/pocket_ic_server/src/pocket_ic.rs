@@ -18,10 +18,14 @@ use ic_btc_adapter::start_server as start_btc_server;
 use ic_config::adapters::AdaptersConfig;
 use ic_config::execution_environment::MAX_CANISTER_HTTP_REQUESTS_IN_FLIGHT;
 use ic_config::{
-    execution_environment, flag_status::FlagStatus, http_handler, logger::Config as LoggerConfig,
-    subnet_config::SubnetConfig,
+    embedders::Config as EmbeddersConfig, execution_environment, flag_status::FlagStatus,
+    http_handler, logger::Config as LoggerConfig, subnet_config::SubnetConfig,
 };
 use ic_crypto_sha2::Sha256;
+use ic_embedders::wasm_utils::{
+    decoding::decode_wasm,
+    instrumentation::{wasm_coverage, wasm_function_names},
+};
 use ic_error_types::RejectCode;
 use ic_http_endpoints_public::{
     call_v2, call_v3, metrics::HttpHandlerMetrics, CanisterReadStateServiceBuilder,
@@ -75,10 +79,11 @@ use ic_types::{NumBytes, Time};
 use ic_validator_ingress_message::StandaloneIngressSigVerifier;
 use itertools::Itertools;
 use pocket_ic::common::rest::{
-    self, BinaryBlob, BlobCompression, CanisterHttpHeader, CanisterHttpMethod, CanisterHttpRequest,
-    CanisterHttpResponse, ExtendedSubnetConfigSet, MockCanisterHttpResponse, RawAddCycles,
-    RawCanisterCall, RawCanisterId, RawEffectivePrincipal, RawMessageId, RawSetStableMemory,
-    SubnetInstructionConfig, SubnetKind, SubnetSpec, Topology,
+    self, BinaryBlob, BlobCompression, CanisterCoverage, CanisterHttpHeader, CanisterHttpMethod,
+    CanisterHttpRequest, CanisterHttpResponse, ExtendedSubnetConfigSet, MockCanisterHttpResponse,
+    RawAddCycles, RawCallRequestVersion, RawCanisterCall, RawCanisterId, RawEffectivePrincipal,
+    RawJournal, RawJournalEntry, RawMessageId, RawSetStableMemory, RawTime, RawWasmProfile,
+    RawWasmProfiles, SubnetInstructionConfig, SubnetKind, SubnetSpec, Topology,
 };
 use serde::{Deserialize, Serialize};
 use slog::Level;
@@ -417,6 +422,10 @@ pub struct PocketIc {
     log_level: Option<Level>,
     bitcoind_addr: Option<Vec<SocketAddr>>,
     _bitcoin_adapter_parts: Option<BitcoinAdapterParts>,
+    /// Records all state-changing operations if journaling is enabled for this instance.
+    journal: Option<RawJournal>,
+    wasm_coverage: bool,
+    wasm_profiling: bool,
 }
 
 impl Drop for PocketIc {
@@ -512,6 +521,8 @@ impl PocketIc {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoin_adapter_uds_path: Option<PathBuf>,
+        wasm_coverage: bool,
+        wasm_profiling: bool,
     ) -> StateMachineBuilder {
         let subnet_type = conv_type(subnet_kind);
         let subnet_size = subnet_size(subnet_kind);
@@ -545,6 +556,25 @@ impl PocketIc {
             .embedders_config
             .feature_flags
             .rate_limiting_of_debug_prints = FlagStatus::Disabled;
+        // allow canisters to set `wasm_trace_length` to debug their traps
+        hypervisor_config
+            .embedders_config
+            .feature_flags
+            .wasm_tracing = FlagStatus::Enabled;
+        // instrument canister modules with coverage counters
+        if wasm_coverage {
+            hypervisor_config
+                .embedders_config
+                .feature_flags
+                .wasm_coverage = FlagStatus::Enabled;
+        }
+        // attribute executed instructions to Wasm call stacks
+        if wasm_profiling {
+            hypervisor_config
+                .embedders_config
+                .feature_flags
+                .wasm_profiling = FlagStatus::Enabled;
+        }
         let state_machine_config = StateMachineConfig::new(subnet_config, hypervisor_config);
         let t = time
             .duration_since(SystemTime::UNIX_EPOCH)
@@ -572,14 +602,15 @@ impl PocketIc {
         nonmainnet_features: bool,
         log_level: Option<Level>,
         bitcoind_addr: Option<Vec<SocketAddr>>,
+        journal: Option<RawJournal>,
+        wasm_coverage: bool,
+        wasm_profiling: bool,
     ) -> Self {
         let mut range_gen = RangeGen::new();
-        let mut routing_table = RoutingTable::new();
-        let mut nns_subnet_id = subnet_configs.nns.as_ref().and_then(|x| {
+        let nns_subnet_id = subnet_configs.nns.as_ref().and_then(|x| {
             x.get_subnet_id()
                 .map(|y| SubnetId::new(PrincipalId(y.into())))
         });
-        let mut nns_subnet = None;
 
         let topology: Option<RawTopologyInternal> = if let Some(ref state_dir) = state_dir {
             let topology_file_path = state_dir.join("topology.json");
@@ -675,6 +706,39 @@ impl PocketIc {
             subnet_config_info
         };
 
+        Self::from_subnet_config_info(
+            runtime,
+            seed,
+            subnet_config_info,
+            range_gen,
+            nns_subnet_id,
+            state_dir,
+            nonmainnet_features,
+            log_level,
+            bitcoind_addr,
+            journal,
+            wasm_coverage,
+            wasm_profiling,
+        )
+    }
+
+    fn from_subnet_config_info(
+        runtime: Arc<Runtime>,
+        seed: u64,
+        subnet_config_info: Vec<SubnetConfigInfo>,
+        range_gen: RangeGen,
+        mut nns_subnet_id: Option<SubnetId>,
+        state_dir: Option<PathBuf>,
+        nonmainnet_features: bool,
+        log_level: Option<Level>,
+        bitcoind_addr: Option<Vec<SocketAddr>>,
+        journal: Option<RawJournal>,
+        wasm_coverage: bool,
+        wasm_profiling: bool,
+    ) -> Self {
+        let mut routing_table = RoutingTable::new();
+        let mut nns_subnet = None;
+
         let registry_data_provider = Arc::new(ProtoRegistryDataProvider::new());
         let subnets: Arc<SubnetsImpl> = Arc::new(SubnetsImpl::new());
         let mut subnet_configs = BTreeMap::new();
@@ -711,6 +775,8 @@ impl PocketIc {
                 nonmainnet_features,
                 log_level,
                 bitcoin_adapter_uds_path.clone(),
+                wasm_coverage,
+                wasm_profiling,
             );
 
             if subnet_kind == SubnetKind::NNS {
@@ -875,13 +941,86 @@ impl PocketIc {
             log_level,
             bitcoind_addr,
             _bitcoin_adapter_parts,
+            journal,
+            wasm_coverage,
+            wasm_profiling,
         }
     }
 
+    /// Creates an independent copy of this instance.
+    /// The subnets of the copy have the same topology, subnet IDs, and keys
+    /// as the subnets of this instance and their replicated states are loaded
+    /// from checkpoints of the latest states of this instance's subnets.
+    /// The immutable checkpoint files are hardlinked into the state
+    /// directories of the copy rather than copied. Writing these checkpoints
+    /// executes a round on every subnet of this instance.
+    /// The copy has no state directory and no journal.
+    /// The round writing the checkpoints changes the state of this instance,
+    /// so its state label is bumped and the round is recorded in its journal.
+    pub(crate) fn fork(&mut self, runtime: Arc<Runtime>, seed: u64) -> Self {
+        let subnet_config_info = self
+            .topology
+            .subnet_configs
+            .iter()
+            .map(|(subnet_seed, config)| {
+                let state_machine_state_dir =
+                    Self::create_state_machine_state_dir(&None, subnet_seed);
+                let subnet = self.subnets.get(config.subnet_id).unwrap();
+                subnet
+                    .state_machine
+                    .copy_checkpoint_to(&state_machine_state_dir.path());
+                SubnetConfigInfo {
+                    state_machine_state_dir,
+                    subnet_id: Some(config.subnet_id),
+                    ranges: config.ranges.clone(),
+                    alloc_range: config.alloc_range,
+                    subnet_kind: config.subnet_kind,
+                    subnet_seed: *subnet_seed,
+                    instruction_config: config.instruction_config.clone(),
+                    time: subnet.time(),
+                }
+            })
+            .collect();
+        self.bump_state_label();
+        self.record_journal_entry(RawJournalEntry::CheckpointedTick);
+        let nns_subnet_id = self
+            .topology
+            .subnet_configs
+            .values()
+            .find(|config| config.subnet_kind == SubnetKind::NNS)
+            .map(|config| config.subnet_id);
+
+        Self::from_subnet_config_info(
+            runtime,
+            seed,
+            subnet_config_info,
+            self.range_gen.clone(),
+            nns_subnet_id,
+            None,
+            self.nonmainnet_features,
+            self.log_level,
+            self.bitcoind_addr.clone(),
+            None,
+            self.wasm_coverage,
+            self.wasm_profiling,
+        )
+    }
+
     pub(crate) fn bump_state_label(&mut self) {
         self.state_label.bump();
     }
 
+    /// Appends an entry to the journal of this instance (if journaling is enabled).
+    pub(crate) fn record_journal_entry(&mut self, entry: RawJournalEntry) {
+        if let Some(ref mut journal) = self.journal {
+            journal.entries.push(entry);
+        }
+    }
+
+    pub(crate) fn journal(&self) -> Option<RawJournal> {
+        self.journal.clone()
+    }
+
     fn try_route_canister(&self, canister_id: CanisterId) -> Option<Arc<StateMachine>> {
         let subnet_id = self.routing_table.route(canister_id.into());
         subnet_id.map(|subnet_id| self.get_subnet_with_id(subnet_id).unwrap())
@@ -938,6 +1077,9 @@ impl Default for PocketIc {
             false,
             None,
             None,
+            None,
+            false,
+            false,
         )
     }
 }
@@ -1045,7 +1187,7 @@ fn get_range_config(
 }
 
 /// A stateful helper for finding available canister ranges.
-#[derive(Default)]
+#[derive(Clone, Default)]
 struct RangeGen {
     range_offset: u64,
 }
@@ -1133,6 +1275,12 @@ impl Operation for SetTime {
     fn id(&self) -> OpId {
         OpId(format!("set_time_{}", self.time))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::SetTime(RawTime {
+            nanos_since_epoch: self.time.as_nanos_since_unix_epoch(),
+        }))
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -1476,6 +1624,12 @@ impl Operation for MockCanisterHttp {
             self.mock_canister_http_response
         ))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::MockCanisterHttp(
+            self.mock_canister_http_response.clone().into(),
+        ))
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -1511,6 +1665,32 @@ impl Operation for Tick {
     fn id(&self) -> OpId {
         OpId("tick".to_string())
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::Tick)
+    }
+}
+
+/// Executes a round on every subnet that writes a checkpoint,
+/// like forking an instance does.
+#[derive(Copy, Clone, Debug)]
+pub struct CheckpointedTick;
+
+impl Operation for CheckpointedTick {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        for subnet in pic.subnets.get_all() {
+            subnet.state_machine.checkpointed_tick();
+        }
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        OpId("checkpointed_tick".to_string())
+    }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::CheckpointedTick)
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -1528,6 +1708,12 @@ impl Operation for AdvanceTimeAndTick {
     fn id(&self) -> OpId {
         OpId(format!("advance_time_and_tick({:?})", self.0))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::AdvanceTimeAndTick {
+            nanos: self.0.as_nanos() as u64,
+        })
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1567,6 +1753,10 @@ impl Operation for SubmitIngressMessage {
         let call_id = self.0.id();
         OpId(format!("submit_update_{}", call_id.0))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::SubmitIngressMessage(self.0.clone().into()))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1599,6 +1789,20 @@ impl TryFrom<RawMessageId> for MessageId {
     }
 }
 
+impl From<MessageId> for RawMessageId {
+    fn from(
+        MessageId {
+            effective_principal,
+            msg_id,
+        }: MessageId,
+    ) -> Self {
+        RawMessageId {
+            effective_principal: effective_principal.into(),
+            message_id: msg_id.as_bytes().to_vec(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AwaitIngressMessage(pub MessageId);
 
@@ -1643,6 +1847,10 @@ impl Operation for AwaitIngressMessage {
     fn id(&self) -> OpId {
         OpId(format!("await_update_{}", self.0.msg_id))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::AwaitIngressMessage(self.0.clone().into()))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1708,6 +1916,12 @@ impl Operation for ExecuteIngressMessage {
         let call_id = self.0.id();
         OpId(format!("canister_update_{}", call_id.0))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::ExecuteIngressMessage(
+            self.0.clone().into(),
+        ))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1876,6 +2090,24 @@ pub enum CallRequestVersion {
     V3,
 }
 
+impl From<RawCallRequestVersion> for CallRequestVersion {
+    fn from(version: RawCallRequestVersion) -> Self {
+        match version {
+            RawCallRequestVersion::V2 => CallRequestVersion::V2,
+            RawCallRequestVersion::V3 => CallRequestVersion::V3,
+        }
+    }
+}
+
+impl From<&CallRequestVersion> for RawCallRequestVersion {
+    fn from(version: &CallRequestVersion) -> Self {
+        match version {
+            CallRequestVersion::V2 => RawCallRequestVersion::V2,
+            CallRequestVersion::V3 => RawCallRequestVersion::V3,
+        }
+    }
+}
+
 pub struct CallRequest {
     pub effective_canister_id: CanisterId,
     pub bytes: Bytes,
@@ -2011,6 +2243,16 @@ impl Operation for CallRequest {
         let hash = Digest(hasher.finish());
         OpId(format!("call({},{})", self.effective_canister_id, hash,))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::Call {
+            effective_canister_id: RawCanisterId {
+                canister_id: self.effective_canister_id.get().to_vec(),
+            },
+            version: (&self.version).into(),
+            bytes: self.bytes.to_vec(),
+        })
+    }
 }
 
 pub struct QueryRequest {
@@ -2289,6 +2531,26 @@ impl TryFrom<RawCanisterCall> for CanisterCall {
     }
 }
 
+impl From<CanisterCall> for RawCanisterCall {
+    fn from(
+        CanisterCall {
+            effective_principal,
+            sender,
+            canister_id,
+            method,
+            payload,
+        }: CanisterCall,
+    ) -> Self {
+        RawCanisterCall {
+            sender: sender.to_vec(),
+            canister_id: canister_id.get().to_vec(),
+            effective_principal: effective_principal.into(),
+            method,
+            payload,
+        }
+    }
+}
+
 impl CanisterCall {
     fn id(&self) -> OpId {
         let mut hasher = Sha256::new();
@@ -2378,6 +2640,13 @@ impl Operation for SetStableMemory {
         let hash = Digest(hasher.finish());
         OpId(format!("set_stable_memory({}_{})", self.canister_id, hash))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::SetStableMemory {
+            canister_id: self.canister_id.get().to_vec(),
+            data: self.data.clone(),
+        })
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -2530,6 +2799,238 @@ impl Operation for AddCycles {
     fn id(&self) -> OpId {
         OpId(format!("add_cycles({},{})", self.canister_id, self.amount))
     }
+
+    fn journal_entry(&self) -> Option<RawJournalEntry> {
+        Some(RawJournalEntry::AddCycles(RawAddCycles {
+            canister_id: self.canister_id.get().to_vec(),
+            amount: self.amount,
+        }))
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct GetWasmCoverage {
+    pub canister_id: CanisterId,
+}
+
+impl Operation for GetWasmCoverage {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        if !pic.wasm_coverage {
+            return OpOut::Error(PocketIcError::WasmCoverageNotEnabled);
+        }
+        let subnet = match pic.try_route_canister(self.canister_id) {
+            Some(subnet) => subnet,
+            None => return OpOut::Error(PocketIcError::CanisterNotFound(self.canister_id)),
+        };
+        let state = subnet.get_latest_state();
+        let execution_state = match state
+            .canister_state(&self.canister_id)
+            .map(|canister| canister.execution_state.as_ref())
+        {
+            Some(Some(execution_state)) => execution_state,
+            Some(None) => return OpOut::Error(PocketIcError::CanisterIsEmpty(self.canister_id)),
+            None => return OpOut::Error(PocketIcError::CanisterNotFound(self.canister_id)),
+        };
+        let coverage = decode_wasm(
+            EmbeddersConfig::default().wasm_max_size,
+            Arc::new(execution_state.wasm_binary.binary.to_vec()),
+        )
+        .map_err(|err| err.to_string())
+        .and_then(|wasm| {
+            wasm_coverage(&wasm, &execution_state.exported_globals).map_err(|err| err.to_string())
+        });
+        match coverage {
+            Ok(functions) => OpOut::WasmCoverage(CanisterCoverage {
+                functions: functions
+                    .into_iter()
+                    .map(|f| rest::FunctionCoverage {
+                        function_index: f.function_index,
+                        name: f.name,
+                        source_location: f.source_location.map(|location| rest::SourceLocation {
+                            file: location.file,
+                            line: location.line,
+                        }),
+                        hits: f.hits,
+                    })
+                    .collect(),
+            }),
+            Err(msg) => OpOut::Error(PocketIcError::InvalidWasmCoverage(msg)),
+        }
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("get_wasm_coverage({})", self.canister_id))
+    }
+}
+
+/// Takes the instruction profiles of all messages executed
+/// since the profiles were last taken (on all subnets).
+/// Every subnet keeps a bounded number of the most recent profiles
+/// and counts the profiles it dropped.
+#[derive(Copy, Clone, Debug)]
+pub struct TakeWasmProfiles;
+
+impl Operation for TakeWasmProfiles {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        if !pic.wasm_profiling {
+            return OpOut::Error(PocketIcError::WasmProfilingNotEnabled);
+        }
+        let mut profiles = vec![];
+        let mut num_dropped = 0;
+        for subnet in pic.subnets.get_all() {
+            let (records, num_dropped_on_subnet) = subnet.state_machine.take_wasm_profiles();
+            num_dropped += num_dropped_on_subnet;
+            for record in records {
+                // Functions without a name in the name section are displayed by their index.
+                let names = decode_wasm(
+                    EmbeddersConfig::default().wasm_max_size,
+                    Arc::new(record.canister_module.as_slice().to_vec()),
+                )
+                .ok()
+                .and_then(|wasm| wasm_function_names(&wasm).ok())
+                .unwrap_or_default();
+                let folded_stacks = record
+                    .profile
+                    .iter()
+                    .map(|(stack, instructions)| {
+                        let frames = stack
+                            .iter()
+                            .map(|idx| {
+                                names
+                                    .get(idx)
+                                    .cloned()
+                                    .unwrap_or_else(|| format!("func[{}]", idx))
+                            })
+                            .join(";");
+                        format!("{} {}\n", frames, instructions)
+                    })
+                    .collect();
+                profiles.push(RawWasmProfile {
+                    canister_id: RawCanisterId {
+                        canister_id: record.canister_id.get().to_vec(),
+                    },
+                    method: record.method,
+                    folded_stacks,
+                });
+            }
+        }
+        OpOut::WasmProfiles(RawWasmProfiles {
+            profiles,
+            num_dropped,
+        })
+    }
+
+    fn id(&self) -> OpId {
+        OpId("take_wasm_profiles".into())
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct GetJournal;
+
+impl Operation for GetJournal {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        match pic.journal() {
+            Some(journal) => OpOut::Journal(journal),
+            None => OpOut::Error(PocketIcError::JournalNotEnabled),
+        }
+    }
+
+    fn id(&self) -> OpId {
+        OpId("get_journal".into())
+    }
+}
+
+/// Replays the entries of a journal on an instance.
+/// The replay is deterministic if the instance has been created
+/// from the same instance configuration as the journaled instance
+/// and the journaled instance did not process HTTPS outcalls in auto progress mode.
+pub struct Replay {
+    ops: Vec<Box<dyn Operation + Send + Sync>>,
+}
+
+fn operation_from_journal_entry(
+    entry: RawJournalEntry,
+) -> Result<Box<dyn Operation + Send + Sync>, ConversionError> {
+    let op: Box<dyn Operation + Send + Sync> = match entry {
+        RawJournalEntry::SetTime(time) => Box::new(SetTime {
+            time: Time::from_nanos_since_unix_epoch(time.nanos_since_epoch),
+        }),
+        RawJournalEntry::Tick => Box::new(Tick),
+        RawJournalEntry::CheckpointedTick => Box::new(CheckpointedTick),
+        RawJournalEntry::AdvanceTimeAndTick { nanos } => {
+            Box::new(AdvanceTimeAndTick(Duration::from_nanos(nanos)))
+        }
+        RawJournalEntry::SubmitIngressMessage(canister_call) => {
+            Box::new(SubmitIngressMessage(canister_call.try_into()?))
+        }
+        RawJournalEntry::AwaitIngressMessage(message_id) => {
+            Box::new(AwaitIngressMessage(message_id.try_into()?))
+        }
+        RawJournalEntry::ExecuteIngressMessage(canister_call) => {
+            Box::new(ExecuteIngressMessage(canister_call.try_into()?))
+        }
+        RawJournalEntry::AddCycles(add_cycles) => Box::new(AddCycles::try_from(add_cycles)?),
+        RawJournalEntry::SetStableMemory { canister_id, data } => {
+            let canister_id = CanisterId::try_from(canister_id).map_err(|_| ConversionError {
+                message: "Bad canister id".to_string(),
+            })?;
+            Box::new(SetStableMemory { canister_id, data })
+        }
+        RawJournalEntry::MockCanisterHttp(mock_canister_http_response) => {
+            Box::new(MockCanisterHttp {
+                mock_canister_http_response: mock_canister_http_response.into(),
+            })
+        }
+        RawJournalEntry::Call {
+            effective_canister_id,
+            version,
+            bytes,
+        } => {
+            let effective_canister_id = CanisterId::try_from(effective_canister_id.canister_id)
+                .map_err(|_| ConversionError {
+                    message: "Bad effective canister id".to_string(),
+                })?;
+            Box::new(CallRequest {
+                effective_canister_id,
+                bytes: bytes.into(),
+                version: version.into(),
+            })
+        }
+    };
+    Ok(op)
+}
+
+impl TryFrom<Vec<RawJournalEntry>> for Replay {
+    type Error = ConversionError;
+    fn try_from(entries: Vec<RawJournalEntry>) -> Result<Self, Self::Error> {
+        let ops = entries
+            .into_iter()
+            .map(operation_from_journal_entry)
+            .collect::<Result<_, _>>()?;
+        Ok(Replay { ops })
+    }
+}
+
+impl Operation for Replay {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        for op in &self.ops {
+            if let Some(entry) = op.journal_entry() {
+                pic.record_journal_entry(entry);
+            }
+            op.compute(pic);
+        }
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        let mut hasher = Sha256::new();
+        for op in &self.ops {
+            hasher.write(op.id().0.as_bytes());
+        }
+        let hash = Digest(hasher.finish());
+        OpId(format!("replay({},{})", self.ops.len(), hash))
+    }
 }
 
 struct Digest([u8; 32]);
@@ -2801,6 +3302,9 @@ mod tests {
                 false,
                 None,
                 None,
+                None,
+                false,
+                false,
             );
             let mut pic1 = PocketIc::new(
                 runtime.clone(),
@@ -2813,6 +3317,9 @@ mod tests {
                 false,
                 None,
                 None,
+                None,
+                false,
+                false,
             );
             assert_ne!(pic0.get_state_label(), pic1.get_state_label());
 
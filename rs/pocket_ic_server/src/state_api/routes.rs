@@ -9,9 +9,9 @@ use super::state::{ApiState, OpOut, PocketIcError, StateLabel, UpdateReply};
 use crate::pocket_ic::{
     AddCycles, AwaitIngressMessage, CallRequest, CallRequestVersion, CanisterReadStateRequest,
     DashboardRequest, ExecuteIngressMessage, GetCanisterHttp, GetControllers, GetCyclesBalance,
-    GetStableMemory, GetSubnet, GetTime, GetTopology, IngressMessageStatus, MockCanisterHttp,
-    PubKey, Query, QueryRequest, SetStableMemory, SetTime, StatusRequest, SubmitIngressMessage,
-    SubnetReadStateRequest, Tick,
+    GetJournal, GetStableMemory, GetSubnet, GetTime, GetTopology, GetWasmCoverage,
+    IngressMessageStatus, MockCanisterHttp, PubKey, Query, QueryRequest, Replay, SetStableMemory,
+    SetTime, StatusRequest, SubmitIngressMessage, SubnetReadStateRequest, TakeWasmProfiles, Tick,
 };
 use crate::{async_trait, pocket_ic::PocketIc, BlobStore, InstanceId, OpId, Operation};
 use aide::{
@@ -36,11 +36,12 @@ use hyper::header;
 use ic_http_endpoints_public::cors_layer;
 use ic_types::{CanisterId, SubnetId};
 use pocket_ic::common::rest::{
-    self, ApiResponse, AutoProgressConfig, ExtendedSubnetConfigSet, HttpGatewayConfig,
-    HttpGatewayDetails, InstanceConfig, MockCanisterHttpResponse, RawAddCycles, RawCanisterCall,
-    RawCanisterHttpRequest, RawCanisterId, RawCanisterResult, RawCycles, RawMessageId,
-    RawMockCanisterHttpResponse, RawPrincipalId, RawSetStableMemory, RawStableMemory,
-    RawSubmitIngressResult, RawSubnetId, RawTime, RawWasmResult, Topology,
+    self, ApiResponse, AutoProgressConfig, CanisterCoverage, ExtendedSubnetConfigSet,
+    HttpGatewayConfig, HttpGatewayDetails, InstanceConfig, MockCanisterHttpResponse, RawAddCycles,
+    RawCanisterCall, RawCanisterHttpRequest, RawCanisterId, RawCanisterResult, RawCycles,
+    RawJournal, RawJournalEntry, RawMessageId, RawMockCanisterHttpResponse, RawPrincipalId,
+    RawSetStableMemory, RawStableMemory, RawSubmitIngressResult, RawSubnetId, RawTime,
+    RawWasmProfiles, RawWasmResult, Topology,
 };
 use pocket_ic::WasmResult;
 use serde::Serialize;
@@ -82,6 +83,8 @@ where
         .directory_route("/get_subnet", post(handler_get_subnet))
         .directory_route("/pub_key", post(handler_pub_key))
         .directory_route("/ingress_status", post(handler_ingress_status))
+        .directory_route("/journal", get(handler_get_journal))
+        .directory_route("/get_wasm_coverage", post(handler_get_wasm_coverage))
 }
 
 pub fn instance_update_routes<S>() -> ApiRouter<S>
@@ -107,6 +110,8 @@ where
         .directory_route("/set_stable_memory", post(handler_set_stable_memory))
         .directory_route("/tick", post(handler_tick))
         .directory_route("/mock_canister_http", post(handler_mock_canister_http))
+        .directory_route("/replay", post(handler_replay))
+        .directory_route("/take_wasm_profiles", post(handler_take_wasm_profiles))
 }
 
 pub fn instance_api_v2_routes<S>() -> ApiRouter<S>
@@ -182,6 +187,10 @@ where
         // Deletes an instance.
         .directory_route("/:id", delete(delete_instance))
         //
+        // Creates a new IC instance as an independent copy of an existing instance.
+        // Returns the InstanceId of the copy.
+        .api_route("/:id/fork", post(fork_instance))
+        //
         // All the read-only endpoints
         .nest("/:id/read", instance_read_routes())
         //
@@ -346,6 +355,36 @@ impl TryFrom<OpOut> for RawTime {
     }
 }
 
+impl TryFrom<OpOut> for RawJournal {
+    type Error = OpConversionError;
+    fn try_from(value: OpOut) -> Result<Self, Self::Error> {
+        match value {
+            OpOut::Journal(journal) => Ok(journal),
+            _ => Err(OpConversionError),
+        }
+    }
+}
+
+impl TryFrom<OpOut> for CanisterCoverage {
+    type Error = OpConversionError;
+    fn try_from(value: OpOut) -> Result<Self, Self::Error> {
+        match value {
+            OpOut::WasmCoverage(coverage) => Ok(coverage),
+            _ => Err(OpConversionError),
+        }
+    }
+}
+
+impl TryFrom<OpOut> for RawWasmProfiles {
+    type Error = OpConversionError;
+    fn try_from(value: OpOut) -> Result<Self, Self::Error> {
+        match value {
+            OpOut::WasmProfiles(profiles) => Ok(profiles),
+            _ => Err(OpConversionError),
+        }
+    }
+}
+
 impl TryFrom<OpOut> for Topology {
     type Error = OpConversionError;
     fn try_from(value: OpOut) -> Result<Self, Self::Error> {
@@ -592,6 +631,17 @@ pub async fn handler_get_time(
     (code, Json(response))
 }
 
+pub async fn handler_get_journal(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+) -> (StatusCode, Json<ApiResponse<RawJournal>>) {
+    let timeout = timeout_or_default(headers);
+    let op = GetJournal;
+    let (code, response) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
 pub async fn handler_get_canister_http(
     State(AppState { api_state, .. }): State<AppState>,
     headers: HeaderMap,
@@ -665,6 +715,28 @@ pub async fn handler_get_cycles(
     }
 }
 
+pub async fn handler_get_wasm_coverage(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    extract::Json(raw_canister_id): extract::Json<RawCanisterId>,
+) -> (StatusCode, Json<ApiResponse<CanisterCoverage>>) {
+    let timeout = timeout_or_default(headers);
+    match CanisterId::try_from(raw_canister_id.canister_id) {
+        Ok(canister_id) => {
+            let get_op = GetWasmCoverage { canister_id };
+            let (code, response) = run_operation(api_state, instance_id, timeout, get_op).await;
+            (code, Json(response))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
 pub async fn handler_get_stable_memory(
     State(AppState { api_state, .. }): State<AppState>,
     Path(instance_id): Path<InstanceId>,
@@ -908,6 +980,15 @@ async fn op_out_to_response(op_out: OpOut) -> Response {
             )),
         )
             .into_response(),
+        OpOut::Journal(journal) => {
+            (StatusCode::OK, Json(ApiResponse::Success(journal))).into_response()
+        }
+        OpOut::WasmCoverage(coverage) => {
+            (StatusCode::OK, Json(ApiResponse::Success(coverage))).into_response()
+        }
+        OpOut::WasmProfiles(profiles) => {
+            (StatusCode::OK, Json(ApiResponse::Success(profiles))).into_response()
+        }
         OpOut::RawResponse(fut) => {
             let (status, headers, bytes) = fut.await;
             let code = StatusCode::from_u16(status).unwrap();
@@ -1122,6 +1203,38 @@ pub async fn handler_tick(
     (code, Json(res))
 }
 
+pub async fn handler_take_wasm_profiles(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<ApiResponse<RawWasmProfiles>>) {
+    let timeout = timeout_or_default(headers);
+    let op = TakeWasmProfiles;
+    let (code, res) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(res))
+}
+
+pub async fn handler_replay(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(instance_id): Path<InstanceId>,
+    headers: HeaderMap,
+    extract::Json(entries): extract::Json<Vec<RawJournalEntry>>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let timeout = timeout_or_default(headers);
+    match Replay::try_from(entries) {
+        Ok(replay_op) => {
+            let (code, response) = run_operation(api_state, instance_id, timeout, replay_op).await;
+            (code, Json(response))
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error {
+                message: format!("{:?}", e),
+            }),
+        ),
+    }
+}
+
 // ----------------------------------------------------------------------------------------------------------------- //
 // Other handlers
 
@@ -1158,6 +1271,10 @@ pub async fn create_instance(
     }): State<AppState>,
     extract::Json(instance_config): extract::Json<InstanceConfig>,
 ) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    let journal = instance_config.journal.then(|| RawJournal {
+        instance_config: instance_config.clone(),
+        entries: vec![],
+    });
     let subnet_configs = instance_config.subnet_config_set;
 
     let skip_validate_subnet_configs = instance_config
@@ -1211,6 +1328,9 @@ pub async fn create_instance(
                 instance_config.nonmainnet_features,
                 log_level,
                 instance_config.bitcoind_addr,
+                journal,
+                instance_config.wasm_coverage,
+                instance_config.wasm_profiling,
             )
         })
         .await;
@@ -1238,6 +1358,30 @@ pub async fn delete_instance(
     StatusCode::OK
 }
 
+pub async fn fork_instance(
+    State(AppState {
+        api_state,
+        min_alive_until: _,
+        runtime,
+        blob_store: _,
+    }): State<AppState>,
+    Path(id): Path<InstanceId>,
+) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    match api_state.fork_instance(id, runtime).await {
+        Ok((instance_id, topology)) => (
+            StatusCode::CREATED,
+            Json(rest::CreateInstanceResponse::Created {
+                instance_id,
+                topology,
+            }),
+        ),
+        Err(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(rest::CreateInstanceResponse::Error { message }),
+        ),
+    }
+}
+
 pub async fn list_http_gateways(
     State(AppState { api_state, .. }): State<AppState>,
 ) -> Json<Vec<HttpGatewayDetails>> {
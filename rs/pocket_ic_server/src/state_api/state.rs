@@ -35,8 +35,8 @@ use ic_http_gateway::{CanisterRequest, HttpGatewayClient, HttpGatewayRequestArgs
 use ic_types::{canister_http::CanisterHttpRequestId, CanisterId, PrincipalId, SubnetId};
 use itertools::Itertools;
 use pocket_ic::common::rest::{
-    CanisterHttpRequest, HttpGatewayBackend, HttpGatewayConfig, HttpGatewayDetails,
-    HttpGatewayInfo, Topology,
+    CanisterCoverage, CanisterHttpRequest, HttpGatewayBackend, HttpGatewayConfig,
+    HttpGatewayDetails, HttpGatewayInfo, RawJournal, RawWasmProfiles, Topology,
 };
 use pocket_ic::{ErrorCode, UserError, WasmResult};
 use reqwest::Url;
@@ -51,6 +51,7 @@ use std::{
     time::{Duration, SystemTime},
 };
 use tokio::{
+    runtime::Runtime,
     sync::mpsc::error::TryRecvError,
     sync::mpsc::Receiver,
     sync::{mpsc, Mutex, RwLock},
@@ -242,6 +243,9 @@ pub enum OpOut {
     MessageId((EffectivePrincipal, Vec<u8>)),
     Topology(Topology),
     CanisterHttp(Vec<CanisterHttpRequest>),
+    Journal(RawJournal),
+    WasmCoverage(CanisterCoverage),
+    WasmProfiles(RawWasmProfiles),
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Deserialize, Serialize)]
@@ -255,6 +259,10 @@ pub enum PocketIcError {
     InvalidMockCanisterHttpResponses((usize, usize)),
     InvalidRejectCode(u64),
     SettingTimeIntoPast((u64, u64)),
+    JournalNotEnabled,
+    WasmCoverageNotEnabled,
+    InvalidWasmCoverage(String),
+    WasmProfilingNotEnabled,
 }
 
 impl From<Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>> for OpOut {
@@ -328,6 +336,18 @@ impl std::fmt::Debug for OpOut {
             OpOut::Error(PocketIcError::SettingTimeIntoPast((current, set))) => {
                 write!(f, "SettingTimeIntoPast(current={},set={})", current, set)
             }
+            OpOut::Error(PocketIcError::JournalNotEnabled) => {
+                write!(f, "JournalNotEnabled")
+            }
+            OpOut::Error(PocketIcError::WasmCoverageNotEnabled) => {
+                write!(f, "WasmCoverageNotEnabled")
+            }
+            OpOut::Error(PocketIcError::InvalidWasmCoverage(msg)) => {
+                write!(f, "InvalidWasmCoverage({})", msg)
+            }
+            OpOut::Error(PocketIcError::WasmProfilingNotEnabled) => {
+                write!(f, "WasmProfilingNotEnabled")
+            }
             OpOut::Bytes(bytes) => write!(f, "Bytes({})", base64::encode(bytes)),
             OpOut::StableMemBytes(bytes) => write!(f, "StableMemory({})", base64::encode(bytes)),
             OpOut::MaybeSubnetId(Some(subnet_id)) => write!(f, "SubnetId({})", subnet_id),
@@ -355,6 +375,16 @@ impl std::fmt::Debug for OpOut {
             OpOut::CanisterHttp(canister_http_reqeusts) => {
                 write!(f, "CanisterHttp({:?})", canister_http_reqeusts)
             }
+            OpOut::Journal(journal) => write!(f, "Journal({} entries)", journal.entries.len()),
+            OpOut::WasmCoverage(coverage) => {
+                write!(f, "WasmCoverage({} functions)", coverage.functions.len())
+            }
+            OpOut::WasmProfiles(profiles) => write!(
+                f,
+                "WasmProfiles({} profiles, {} dropped)",
+                profiles.profiles.len(),
+                profiles.num_dropped
+            ),
         }
     }
 }
@@ -760,6 +790,60 @@ impl ApiState {
         }
     }
 
+    /// Creates a new instance as an independent copy of an existing instance.
+    /// The existing instance is marked busy while it is being copied.
+    pub async fn fork_instance(
+        &self,
+        instance_id: InstanceId,
+        runtime: Arc<Runtime>,
+    ) -> Result<(InstanceId, Topology), String> {
+        let mut pocket_ic = loop {
+            let instances = self.instances.read().await;
+            let Some(instance_mutex) = instances.get(instance_id) else {
+                return Err("Instance not found".to_string());
+            };
+            let mut instance = instance_mutex.lock().await;
+            match &instance.state {
+                InstanceState::Available(pocket_ic) => {
+                    let busy = InstanceState::Busy {
+                        state_label: pocket_ic.get_state_label(),
+                        op_id: OpId("fork".to_string()),
+                    };
+                    let InstanceState::Available(pocket_ic) =
+                        std::mem::replace(&mut instance.state, busy)
+                    else {
+                        unreachable!()
+                    };
+                    break pocket_ic;
+                }
+                InstanceState::Deleted => {
+                    return Err("Instance was deleted".to_string());
+                }
+                InstanceState::Busy { .. } => {}
+            }
+            drop(instance);
+            drop(instances);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        };
+        let seed = self.seed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        // fork the instance using `spawn_blocking` before acquiring a lock
+        let (pocket_ic, fork) = spawn_blocking(move || {
+            let fork = pocket_ic.fork(runtime, seed);
+            (pocket_ic, fork)
+        })
+        .await
+        .expect("Failed to fork PocketIC instance");
+        let topology = fork.topology();
+        let mut instances = self.instances.write().await;
+        instances[instance_id].get_mut().state = InstanceState::Available(pocket_ic);
+        let fork_id = instances.len();
+        instances.push(Mutex::new(Instance {
+            progress_thread: None,
+            state: InstanceState::Available(fork),
+        }));
+        Ok((fork_id, topology))
+    }
+
     pub async fn delete_all_instances(arc_self: Arc<ApiState>) {
         let mut tasks = JoinSet::new();
         let instances = arc_self.instances.read().await;
@@ -1183,6 +1267,9 @@ impl ApiState {
                                 old_state_label,
                                 op_id.0,
                             );
+                            if let Some(entry) = op.journal_entry() {
+                                pocket_ic.record_journal_entry(entry);
+                            }
                             let result = op.compute(&mut pocket_ic);
                             pocket_ic.bump_state_label();
                             let new_state_label = pocket_ic.get_state_label();
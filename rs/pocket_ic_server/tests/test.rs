@@ -104,6 +104,9 @@ fn test_creation_of_instance_extended() {
         nonmainnet_features: false,
         log_level: None,
         bitcoind_addr: None,
+        journal: false,
+        wasm_coverage: false,
+        wasm_profiling: false,
     };
     let response = client
         .post(url.join("instances").unwrap())
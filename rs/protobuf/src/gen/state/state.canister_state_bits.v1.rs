@@ -492,6 +492,8 @@ pub struct TotalQueryStats {
     pub ingress_payload_size: ::core::option::Option<Unsigned128>,
     #[prost(message, optional, tag = "4")]
     pub egress_payload_size: ::core::option::Option<Unsigned128>,
+    #[prost(message, optional, tag = "5")]
+    pub num_cache_hits: ::core::option::Option<Unsigned128>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct WasmChunkData {
@@ -539,6 +541,11 @@ pub struct CanisterLogRecord {
     pub timestamp_nanos: u64,
     #[prost(bytes = "vec", tag = "3")]
     pub content: ::prost::alloc::vec::Vec<u8>,
+    /// The level of the record (unspecified if the record has no level).
+    #[prost(enumeration = "CanisterLogLevel", tag = "4")]
+    pub level: i32,
+    #[prost(string, repeated, tag = "5")]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SnapshotId {
@@ -546,6 +553,13 @@ pub struct SnapshotId {
     pub content: ::prost::alloc::vec::Vec<u8>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnvironmentVariable {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterStateBits {
     #[prost(uint64, tag = "2")]
     pub last_full_execution_round: u64,
@@ -630,12 +644,21 @@ pub struct CanisterStateBits {
     /// The index of the next log record to be created.
     #[prost(uint64, tag = "44")]
     pub next_canister_log_record_idx: u64,
+    /// The size of the canister log buffer in bytes (the default if not set).
+    #[prost(uint64, optional, tag = "54")]
+    pub canister_log_buffer_size: ::core::option::Option<u64>,
     /// The Wasm memory limit. This is a field in developer-visible canister
     /// settings that allows the developer to limit the usage of the Wasm memory
     /// by the canister to leave some room in 4GiB for upgrade calls.
     /// See the interface specification for more information.
     #[prost(uint64, optional, tag = "45")]
     pub wasm_memory_limit: ::core::option::Option<u64>,
+    /// Environment variables of the canister, sorted by name.
+    #[prost(message, repeated, tag = "55")]
+    pub environment_variables: ::prost::alloc::vec::Vec<EnvironmentVariable>,
+    /// Number of most recent system API calls recorded for debugging traps.
+    #[prost(uint64, tag = "56")]
+    pub wasm_trace_length: u64,
     /// The next local snapshot ID.
     #[prost(uint64, tag = "46")]
     pub next_snapshot_id: u64,
@@ -646,6 +669,9 @@ pub struct CanisterStateBits {
     pub priority_credit: i64,
     #[prost(enumeration = "LongExecutionMode", tag = "49")]
     pub long_execution_mode: i32,
+    /// Scheduling priority class set in the canister settings.
+    #[prost(enumeration = "PriorityClass", tag = "57")]
+    pub priority_class: i32,
     #[prost(uint64, optional, tag = "50")]
     pub wasm_memory_threshold: ::core::option::Option<u64>,
     #[prost(enumeration = "OnLowWasmMemoryHookStatus", optional, tag = "53")]
@@ -744,6 +770,7 @@ pub enum CyclesUseCase {
     BurnedCycles = 12,
     SchnorrOutcalls = 13,
     VetKd = 14,
+    SchedulingPriority = 15,
 }
 impl CyclesUseCase {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -769,6 +796,7 @@ impl CyclesUseCase {
             Self::BurnedCycles => "CYCLES_USE_CASE_BURNED_CYCLES",
             Self::SchnorrOutcalls => "CYCLES_USE_CASE_SCHNORR_OUTCALLS",
             Self::VetKd => "CYCLES_USE_CASE_VET_KD",
+            Self::SchedulingPriority => "CYCLES_USE_CASE_SCHEDULING_PRIORITY",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -791,6 +819,42 @@ impl CyclesUseCase {
             "CYCLES_USE_CASE_BURNED_CYCLES" => Some(Self::BurnedCycles),
             "CYCLES_USE_CASE_SCHNORR_OUTCALLS" => Some(Self::SchnorrOutcalls),
             "CYCLES_USE_CASE_VET_KD" => Some(Self::VetKd),
+            "CYCLES_USE_CASE_SCHEDULING_PRIORITY" => Some(Self::SchedulingPriority),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CanisterLogLevel {
+    Unspecified = 0,
+    Debug = 1,
+    Info = 2,
+    Warning = 3,
+    Error = 4,
+}
+impl CanisterLogLevel {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "CANISTER_LOG_LEVEL_UNSPECIFIED",
+            Self::Debug => "CANISTER_LOG_LEVEL_DEBUG",
+            Self::Info => "CANISTER_LOG_LEVEL_INFO",
+            Self::Warning => "CANISTER_LOG_LEVEL_WARNING",
+            Self::Error => "CANISTER_LOG_LEVEL_ERROR",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CANISTER_LOG_LEVEL_UNSPECIFIED" => Some(Self::Unspecified),
+            "CANISTER_LOG_LEVEL_DEBUG" => Some(Self::Debug),
+            "CANISTER_LOG_LEVEL_INFO" => Some(Self::Info),
+            "CANISTER_LOG_LEVEL_WARNING" => Some(Self::Warning),
+            "CANISTER_LOG_LEVEL_ERROR" => Some(Self::Error),
             _ => None,
         }
     }
@@ -826,6 +890,35 @@ impl LongExecutionMode {
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum PriorityClass {
+    Unspecified = 0,
+    Standard = 1,
+    Burst = 2,
+}
+impl PriorityClass {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "PRIORITY_CLASS_UNSPECIFIED",
+            Self::Standard => "PRIORITY_CLASS_STANDARD",
+            Self::Burst => "PRIORITY_CLASS_BURST",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "PRIORITY_CLASS_UNSPECIFIED" => Some(Self::Unspecified),
+            "PRIORITY_CLASS_STANDARD" => Some(Self::Standard),
+            "PRIORITY_CLASS_BURST" => Some(Self::Burst),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum OnLowWasmMemoryHookStatus {
     Unspecified = 0,
     ConditionNotSatisfied = 1,
@@ -27,4 +27,6 @@ pub struct QueryStatsInner {
     pub ingress_payload_size: u64,
     #[prost(uint64, tag = "6")]
     pub egress_payload_size: u64,
+    #[prost(uint64, tag = "8")]
+    pub num_cache_hits: u64,
 }
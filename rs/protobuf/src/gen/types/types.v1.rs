@@ -1517,6 +1517,8 @@ pub struct CanisterQueryStats {
     pub ingress_payload_size: u64,
     #[prost(uint64, tag = "5")]
     pub egress_payload_size: u64,
+    #[prost(uint64, tag = "6")]
+    pub num_cache_hits: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct IngressIdOffset {
@@ -14,6 +14,7 @@ pub(crate) struct QueryStatsMetricsSet {
     num_instructions: IntGauge,
     request_bytes: IntGauge,
     response_bytes: IntGauge,
+    num_cache_hits: IntGauge,
 }
 
 impl QueryStatsMetricsSet {
@@ -35,6 +36,10 @@ impl QueryStatsMetricsSet {
                 format!("query_stats_{}_response_bytes", name),
                 "Sum of response bytes".to_string(),
             ),
+            num_cache_hits: metrics_registry.int_gauge(
+                format!("query_stats_{}_num_cache_hits", name),
+                "Sum of query cache hits".to_string(),
+            ),
         }
     }
 
@@ -46,6 +51,7 @@ impl QueryStatsMetricsSet {
             .add(query_stats.ingress_payload_size as i64);
         self.response_bytes
             .add(query_stats.egress_payload_size as i64);
+        self.num_cache_hits.add(query_stats.num_cache_hits as i64);
     }
 }
 
@@ -831,6 +831,7 @@ mod tests {
                         num_instructions: 1000,
                         ingress_payload_size: 1000,
                         egress_payload_size: 1000,
+                        num_cache_hits: 0,
                     },
                 })
                 .collect(),
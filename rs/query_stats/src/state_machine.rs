@@ -121,6 +121,7 @@ fn aggregate_query_stats(stats: Vec<&QueryStats>) -> QueryStats {
         num_instructions: get_median(&stats, |stats| stats.num_instructions),
         ingress_payload_size: get_median(&stats, |stats| stats.ingress_payload_size),
         egress_payload_size: get_median(&stats, |stats| stats.egress_payload_size),
+        num_cache_hits: get_median(&stats, |stats| stats.num_cache_hits),
     }
 }
 
@@ -145,6 +146,7 @@ fn apply_query_stats_to_canister(
             aggregated_stats.ingress_payload_size as u128 * num_nodes;
         canister_query_stats.egress_payload_size +=
             aggregated_stats.egress_payload_size as u128 * num_nodes;
+        canister_query_stats.num_cache_hits += aggregated_stats.num_cache_hits as u128 * num_nodes;
     } else {
         info!(
             logger,
@@ -571,6 +573,7 @@ mod tests {
                     num_instructions: insts,
                     ingress_payload_size: 0,
                     egress_payload_size: 0,
+                    num_cache_hits: 0,
                 },
             }],
         }
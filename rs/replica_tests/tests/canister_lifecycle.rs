@@ -5,7 +5,7 @@ use ic_config::subnet_config::CyclesAccountManagerConfig;
 use ic_config::Config;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_management_canister_types::{
-    self as ic00, CanisterChange, CanisterIdRecord, CanisterInstallMode,
+    self as ic00, CanisterChange, CanisterIdRecord, CanisterInstallMode, CanisterPriorityClass,
     CanisterSettingsArgsBuilder, CanisterStatusResultV2, CanisterStatusType, EmptyBlob,
     InstallCodeArgs, Method, Payload, UpdateSettingsArgs, IC_00,
 };
@@ -15,7 +15,10 @@ use ic_replica_tests::assert_reject;
 use ic_test_utilities::assert_utils::assert_balance_equals;
 use ic_test_utilities::universal_canister::management::CanisterUpgradeOptions;
 use ic_test_utilities::universal_canister::{call_args, management, wasm, UNIVERSAL_CANISTER_WASM};
-use ic_types::{ingress::WasmResult, CanisterId, ComputeAllocation, Cycles, NumBytes, PrincipalId};
+use ic_types::{
+    ingress::WasmResult, CanisterId, ComputeAllocation, Cycles, NumBytes, PrincipalId,
+    DEFAULT_CANISTER_LOG_BUFFER_SIZE,
+};
 use maplit::btreeset;
 use std::{collections::BTreeSet, mem::size_of, str::FromStr};
 
@@ -720,8 +723,13 @@ fn can_get_canister_information() {
                 0u128,
                 0u128,
                 0u128,
+                0u128,
                 Some(DEFAULT_WASM_MEMORY_LIMIT.get()),
                 0u64,
+                DEFAULT_CANISTER_LOG_BUFFER_SIZE as u64,
+                vec![],
+                0u64,
+                CanisterPriorityClass::Standard,
             )
         );
 
@@ -779,8 +787,13 @@ fn can_get_canister_information() {
                     0u128,
                     0u128,
                     0u128,
+                    0u128,
                     Some(DEFAULT_WASM_MEMORY_LIMIT.get()),
                     0u64,
+                    DEFAULT_CANISTER_LOG_BUFFER_SIZE as u64,
+                    vec![],
+                    0u64,
+                    CanisterPriorityClass::Standard,
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
                 2 * BALANCE_EPSILON,
@@ -1,6 +1,8 @@
 use crate::{
     canister_state::execution_state::{Global, Memory},
     canister_state::system_state::wasm_chunk_store::WasmChunkStore,
+    num_bytes_try_from,
+    page_map::{Buffer, PageAllocatorFileDescriptor},
     CanisterState, NumWasmPages, PageMap,
 };
 use ic_sys::PAGE_SIZE;
@@ -62,6 +64,27 @@ impl CanisterSnapshots {
         let canister_id = snapshot.canister_id();
         self.unflushed_changes
             .push(SnapshotOperation::Backup(canister_id, snapshot_id));
+        self.insert(snapshot_id, snapshot)
+    }
+
+    /// Adds a new snapshot whose contents are uploaded piece by piece rather
+    /// than copied from the canister.
+    ///
+    /// Additionally, adds a new item to the `unflushed_changes`
+    /// which represents the new upload accumulated since the last flush to the disk.
+    pub fn push_uploaded(
+        &mut self,
+        snapshot_id: SnapshotId,
+        snapshot: Arc<CanisterSnapshot>,
+    ) -> SnapshotId {
+        let canister_id = snapshot.canister_id();
+        self.unflushed_changes
+            .push(SnapshotOperation::Upload(canister_id, snapshot_id));
+        self.insert(snapshot_id, snapshot)
+    }
+
+    fn insert(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) -> SnapshotId {
+        let canister_id = snapshot.canister_id();
         self.memory_usage += snapshot.size();
         self.snapshots.insert(snapshot_id, snapshot);
         let snapshot_ids = self.snapshot_ids.entry(canister_id).or_default();
@@ -79,6 +102,30 @@ impl CanisterSnapshots {
         self.snapshots.get_mut(&snapshot_id)
     }
 
+    /// Inserts `chunk` into the Wasm chunk store of the snapshot identified by
+    /// `snapshot_id` and grows the snapshot size by the additional memory
+    /// taken by the chunk store.
+    ///
+    /// Returns the number of bytes the snapshot grew by.
+    pub fn insert_chunk(
+        &mut self,
+        snapshot_id: SnapshotId,
+        max_size: NumBytes,
+        chunk: &[u8],
+    ) -> Result<NumBytes, String> {
+        let snapshot = match self.snapshots.get_mut(&snapshot_id) {
+            Some(snapshot) => Arc::make_mut(snapshot),
+            None => return Err(format!("Snapshot {} not found", snapshot_id)),
+        };
+        let old_chunk_store_size = snapshot.chunk_store.memory_usage();
+        snapshot.chunk_store.insert_chunk(max_size, chunk)?;
+        let increase =
+            NumBytes::from(snapshot.chunk_store.memory_usage().get() - old_chunk_store_size.get());
+        snapshot.size += increase;
+        self.memory_usage += increase;
+        Ok(increase)
+    }
+
     /// Iterate over all snapshots.
     pub fn iter(&self) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
         self.snapshots.iter()
@@ -265,6 +312,23 @@ pub struct PageMemory {
     pub size: NumWasmPages,
 }
 
+impl PageMemory {
+    /// Reads `len` bytes of the memory starting at `offset`.
+    pub fn read(&self, offset: usize, len: usize) -> Vec<u8> {
+        let mut data = vec![0; len];
+        Buffer::new(self.page_map.clone()).read(&mut data, offset);
+        data
+    }
+
+    /// Overwrites the memory at `offset` with `data`.
+    fn write(&mut self, offset: usize, data: &[u8]) {
+        let mut buffer = Buffer::new(self.page_map.clone());
+        buffer.write(data, offset);
+        self.page_map
+            .update(&buffer.dirty_pages().collect::<Vec<_>>());
+    }
+}
+
 impl From<&Memory> for PageMemory {
     fn from(memory: &Memory) -> Self {
         Self {
@@ -340,6 +404,51 @@ impl CanisterSnapshot {
         }
     }
 
+    /// Creates a snapshot with the given metadata and zeroed contents, to be
+    /// filled in by subsequent uploads of the snapshot data.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_metadata(
+        canister_id: CanisterId,
+        taken_at_timestamp: Time,
+        canister_version: u64,
+        certified_data: Vec<u8>,
+        exported_globals: Vec<Global>,
+        wasm_module_size: u64,
+        wasm_memory_size: NumWasmPages,
+        stable_memory_size: NumWasmPages,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+    ) -> CanisterSnapshot {
+        // Mirrors `ExecutionState::memory_usage()`, which uses 8 bytes per global.
+        let size = NumBytes::from(wasm_module_size)
+            + num_bytes_try_from(wasm_memory_size)
+                .expect("could not convert from wasm memory number of pages to bytes")
+            + num_bytes_try_from(stable_memory_size)
+                .expect("could not convert from stable memory number of pages to bytes")
+            + NumBytes::from(8 * exported_globals.len() as u64)
+            + NumBytes::from(certified_data.len() as u64);
+        let execution_snapshot = ExecutionStateSnapshot {
+            wasm_binary: CanisterModule::new(vec![0; wasm_module_size as usize]),
+            exported_globals,
+            stable_memory: PageMemory {
+                page_map: PageMap::new(Arc::clone(&fd_factory)),
+                size: stable_memory_size,
+            },
+            wasm_memory: PageMemory {
+                page_map: PageMap::new(Arc::clone(&fd_factory)),
+                size: wasm_memory_size,
+            },
+        };
+        Self {
+            canister_id,
+            taken_at_timestamp,
+            canister_version,
+            certified_data,
+            chunk_store: WasmChunkStore::new(fd_factory),
+            execution_snapshot,
+            size,
+        }
+    }
+
     pub fn from_canister(
         canister: &CanisterState,
         taken_at_timestamp: Time,
@@ -420,6 +529,30 @@ impl CanisterSnapshot {
         &mut self.execution_snapshot
     }
 
+    /// Overwrites the Wasm module of the snapshot at `offset` with `data`.
+    ///
+    /// The caller is responsible for checking that the write is within the
+    /// bounds of the module.
+    pub fn write_wasm_module(&mut self, offset: usize, data: &[u8]) {
+        self.execution_snapshot.wasm_binary.write(offset, data);
+    }
+
+    /// Overwrites the Wasm memory of the snapshot at `offset` with `data`.
+    ///
+    /// The caller is responsible for checking that the write is within the
+    /// bounds of the memory.
+    pub fn write_wasm_memory(&mut self, offset: usize, data: &[u8]) {
+        self.execution_snapshot.wasm_memory.write(offset, data);
+    }
+
+    /// Overwrites the stable memory of the snapshot at `offset` with `data`.
+    ///
+    /// The caller is responsible for checking that the write is within the
+    /// bounds of the memory.
+    pub fn write_stable_memory(&mut self, offset: usize, data: &[u8]) {
+        self.execution_snapshot.stable_memory.write(offset, data);
+    }
+
     /// Returns the heap delta produced by this snapshot.
     ///
     /// The heap delta includes the delta of the wasm memory, stable memory and
@@ -452,6 +585,9 @@ pub enum SnapshotOperation {
     Delete(SnapshotId),
     Backup(CanisterId, SnapshotId),
     Restore(CanisterId, SnapshotId),
+    /// A snapshot created from uploaded metadata. Unlike `Backup`, it is not
+    /// backed by the canister's files.
+    Upload(CanisterId, SnapshotId),
 }
 
 #[cfg(test)]
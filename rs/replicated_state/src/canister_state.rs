@@ -8,7 +8,7 @@ use crate::canister_state::queues::CanisterOutputQueuesIterator;
 use crate::canister_state::system_state::{ExecutionTask, SystemState};
 use crate::{InputQueueType, StateError};
 pub use execution_state::{EmbedderCache, ExecutionState, ExportedFunctions, Global};
-use ic_management_canister_types::{CanisterStatusType, LogVisibilityV2};
+use ic_management_canister_types::{CanisterPriorityClass, CanisterStatusType, LogVisibilityV2};
 use ic_registry_subnet_type::SubnetType;
 use ic_types::batch::TotalQueryStats;
 use ic_types::methods::SystemMethod;
@@ -59,6 +59,10 @@ pub struct SchedulerState {
     /// Long execution mode: Opportunistic (default) or Prioritized
     pub long_execution_mode: LongExecutionMode,
 
+    /// Priority class set in the canister settings. Canisters in the burst
+    /// class pay a fee to be scheduled ahead of the standard class.
+    pub priority_class: CanisterPriorityClass,
+
     /// The amount of heap delta debit. The canister skips execution of update
     /// messages if this value is non-zero.
     pub heap_delta_debit: NumBytes,
@@ -93,6 +97,7 @@ impl Default for SchedulerState {
             accumulated_priority: AccumulatedPriority::default(),
             priority_credit: AccumulatedPriority::default(),
             long_execution_mode: LongExecutionMode::default(),
+            priority_class: CanisterPriorityClass::default(),
             heap_delta_debit: 0.into(),
             install_code_debit: 0.into(),
             time_of_last_allocation_charge: UNIX_EPOCH,
@@ -372,6 +377,7 @@ impl CanisterState {
         self.execution_memory_usage()
             + self.canister_history_memory_usage()
             + self.wasm_chunk_store_memory_usage()
+            + self.canister_log_memory_usage()
             + self.system_state.snapshots_memory_usage
     }
 
@@ -404,6 +410,12 @@ impl CanisterState {
         self.system_state.canister_history_memory_usage()
     }
 
+    /// Returns the memory taken by the canister log buffer beyond its default
+    /// size, in bytes.
+    pub fn canister_log_memory_usage(&self) -> NumBytes {
+        self.system_state.canister_log.memory_usage()
+    }
+
     /// Returns the memory usage of the wasm chunk store in bytes.
     pub(super) fn wasm_chunk_store_memory_usage(&self) -> NumBytes {
         self.system_state.wasm_chunk_store.memory_usage()
@@ -1,5 +1,6 @@
 use crate::hash::ic_hashtree_leaf_hash;
 use crate::{canister_state::WASM_PAGE_SIZE_IN_BYTES, num_bytes_try_from, NumWasmPages, PageMap};
+use ic_management_canister_types::SnapshotGlobal;
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
     state::canister_state_bits::v1 as pb,
@@ -107,6 +108,30 @@ impl PartialEq<Global> for Global {
 
 impl Eq for Global {}
 
+impl From<&Global> for SnapshotGlobal {
+    fn from(item: &Global) -> Self {
+        match item {
+            Global::I32(value) => SnapshotGlobal::I32(*value),
+            Global::I64(value) => SnapshotGlobal::I64(*value),
+            Global::F32(value) => SnapshotGlobal::F32(*value),
+            Global::F64(value) => SnapshotGlobal::F64(*value),
+            Global::V128(value) => SnapshotGlobal::V128(*value),
+        }
+    }
+}
+
+impl From<&SnapshotGlobal> for Global {
+    fn from(item: &SnapshotGlobal) -> Self {
+        match item {
+            SnapshotGlobal::I32(value) => Global::I32(*value),
+            SnapshotGlobal::I64(value) => Global::I64(*value),
+            SnapshotGlobal::F32(value) => Global::F32(*value),
+            SnapshotGlobal::F64(value) => Global::F64(*value),
+            SnapshotGlobal::V128(value) => Global::V128(*value),
+        }
+    }
+}
+
 impl From<&Global> for pb::Global {
     fn from(item: &Global) -> Self {
         match item {
@@ -76,6 +76,7 @@ pub enum CyclesUseCase {
     BurnedCycles = 12,
     SchnorrOutcalls = 13,
     VetKd = 14,
+    SchedulingPriority = 15,
 }
 
 impl CyclesUseCase {
@@ -97,6 +98,7 @@ impl CyclesUseCase {
             Self::BurnedCycles => "BurnedCycles",
             Self::SchnorrOutcalls => "SchnorrOutcalls",
             Self::VetKd => "VetKd",
+            Self::SchedulingPriority => "SchedulingPriority",
         }
     }
 }
@@ -120,6 +122,7 @@ impl From<CyclesUseCase> for pb::CyclesUseCase {
             CyclesUseCase::BurnedCycles => pb::CyclesUseCase::BurnedCycles,
             CyclesUseCase::SchnorrOutcalls => pb::CyclesUseCase::SchnorrOutcalls,
             CyclesUseCase::VetKd => pb::CyclesUseCase::VetKd,
+            CyclesUseCase::SchedulingPriority => pb::CyclesUseCase::SchedulingPriority,
         }
     }
 }
@@ -148,6 +151,7 @@ impl TryFrom<pb::CyclesUseCase> for CyclesUseCase {
             pb::CyclesUseCase::BurnedCycles => Ok(Self::BurnedCycles),
             pb::CyclesUseCase::SchnorrOutcalls => Ok(Self::SchnorrOutcalls),
             pb::CyclesUseCase::VetKd => Ok(Self::VetKd),
+            pb::CyclesUseCase::SchedulingPriority => Ok(Self::SchedulingPriority),
         }
     }
 }
@@ -672,6 +676,15 @@ pub struct SystemState {
     /// See the interface specification for more information.
     pub wasm_memory_limit: Option<NumBytes>,
 
+    /// Environment variables of the canister, keyed by name. Set through the
+    /// canister settings and readable by the canister via `ic0.env_var_*`.
+    pub environment_variables: BTreeMap<String, String>,
+
+    /// Number of most recent system API calls recorded while executing the
+    /// canister and written to the canister log when it traps. Zero disables
+    /// Wasm tracing for the canister.
+    pub wasm_trace_length: u64,
+
     /// Next local snapshot id.
     pub next_snapshot_id: u64,
 
@@ -1120,6 +1133,8 @@ impl SystemState {
             log_visibility: Default::default(),
             canister_log: Default::default(),
             wasm_memory_limit: None,
+            environment_variables: BTreeMap::new(),
+            wasm_trace_length: 0,
             next_snapshot_id: 0,
             snapshots_memory_usage: NumBytes::from(0),
         }
@@ -1149,6 +1164,8 @@ impl SystemState {
         log_visibility: LogVisibilityV2,
         canister_log: CanisterLog,
         wasm_memory_limit: Option<NumBytes>,
+        environment_variables: BTreeMap<String, String>,
+        wasm_trace_length: u64,
         next_snapshot_id: u64,
         snapshots_memory_usage: NumBytes,
         metrics: &dyn CheckpointLoadingMetrics,
@@ -1183,6 +1200,8 @@ impl SystemState {
             log_visibility,
             canister_log,
             wasm_memory_limit,
+            environment_variables,
+            wasm_trace_length,
             next_snapshot_id,
             snapshots_memory_usage,
         };
@@ -2165,6 +2184,7 @@ impl SystemState {
             | CyclesUseCase::ECDSAOutcalls
             | CyclesUseCase::SchnorrOutcalls
             | CyclesUseCase::VetKd
+            | CyclesUseCase::SchedulingPriority
             | CyclesUseCase::HTTPOutcalls
             | CyclesUseCase::DeletedCanisters
             | CyclesUseCase::NonConsumed
@@ -2611,6 +2631,8 @@ pub mod testing {
             log_visibility: Default::default(),
             canister_log: Default::default(),
             wasm_memory_limit: Default::default(),
+            environment_variables: Default::default(),
+            wasm_trace_length: Default::default(),
             next_snapshot_id: Default::default(),
             snapshots_memory_usage: Default::default(),
         };
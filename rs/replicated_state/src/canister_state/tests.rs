@@ -19,7 +19,7 @@ use ic_base_types::NumSeconds;
 use ic_logger::replica_logger::no_op_logger;
 use ic_management_canister_types::{
     BoundedAllowedViewers, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin,
-    CanisterLogRecord, LogVisibilityV2,
+    CanisterLogLevel, CanisterLogRecord, LogVisibilityV2,
 };
 use ic_metrics::MetricsRegistry;
 use ic_test_utilities_types::ids::{canister_test_id, message_test_id, user_test_id};
@@ -995,6 +995,8 @@ fn canister_state_canister_log_record_round_trip() {
         idx: 42,
         timestamp_nanos: 27,
         content: vec![1, 2, 3],
+        level: Some(CanisterLogLevel::Warning),
+        tags: Some(vec!["db".to_string(), "retry".to_string()]),
     };
     let encoded = pb::CanisterLogRecord::from(&initial);
     let round_trip = CanisterLogRecord::from(encoded);
@@ -80,7 +80,9 @@ pub use metadata_state::{
     IngressHistoryState, NetworkTopology, Stream, SubnetTopology, SystemMetadata,
 };
 pub use page_map::{PageIndex, PageMap};
-pub use replicated_state::{InputQueueType, InputSource, ReplicatedState, StateError};
+pub use replicated_state::{
+    InputQueueType, InputSource, MigratingCanister, ReplicatedState, StateError,
+};
 
 /// Encapsulates metrics related to errors that can occur on checkpoint loading.
 /// The intention is to pass an implementation of this trait along with the actual
@@ -473,6 +473,7 @@ impl SubnetMetrics {
                 | CyclesUseCase::CanisterCreation
                 | CyclesUseCase::SchnorrOutcalls
                 | CyclesUseCase::VetKd
+                | CyclesUseCase::SchedulingPriority
                 | CyclesUseCase::BurnedCycles => total += *cycles,
             }
         }
@@ -3,10 +3,10 @@ use super::{
     metadata_state::{IngressHistoryState, Stream, Streams, SystemMetadata},
 };
 use crate::{
-    canister_snapshots::CanisterSnapshots,
+    canister_snapshots::{CanisterSnapshot, CanisterSnapshots},
     canister_state::{
         queues::{CanisterInput, CanisterQueuesLoopDetector},
-        system_state::{push_input, CanisterOutputQueuesIterator},
+        system_state::{push_input, CanisterOutputQueuesIterator, CanisterStatus},
     },
     metadata_state::{
         subnet_call_context_manager::{IDkgDealingsContext, SignWithThresholdContext},
@@ -29,7 +29,7 @@ use ic_types::{
     ingress::IngressStatus,
     messages::{CallbackId, CanisterMessage, Ingress, MessageId, RequestOrResponse, Response},
     time::CoarseTime,
-    AccumulatedPriority, CanisterId, MemoryAllocation, NumBytes, SubnetId, Time,
+    AccumulatedPriority, CanisterId, MemoryAllocation, NumBytes, SnapshotId, SubnetId, Time,
 };
 use ic_validate_eq::ValidateEq;
 use ic_validate_eq_derive::ValidateEq;
@@ -636,7 +636,9 @@ impl ReplicatedState {
                 (
                     match canister.memory_allocation() {
                         MemoryAllocation::Reserved(bytes) => bytes,
-                        MemoryAllocation::BestEffort => canister.execution_memory_usage(),
+                        MemoryAllocation::BestEffort => {
+                            canister.execution_memory_usage() + canister.canister_log_memory_usage()
+                        }
                     },
                     canister
                         .system_state
@@ -1217,14 +1219,7 @@ impl ReplicatedState {
 
         // Adjust `CanisterQueues::(local|remote)_subnet_input_schedule` based on which
         // canisters are present in `canister_states`.
-        let local_canister_ids = canister_states.keys().cloned().collect::<Vec<_>>();
-        for canister_id in local_canister_ids.iter() {
-            let mut canister_state = canister_states.remove(canister_id).unwrap();
-            canister_state
-                .system_state
-                .split_input_schedules(canister_id, canister_states);
-            canister_states.insert(*canister_id, canister_state);
-        }
+        split_input_schedules(canister_states);
 
         // Drop in-progress management calls being executed by canisters on subnet B
         // (`own_subnet_id != split_from`). The corresponding calls will be rejected on
@@ -1244,6 +1239,177 @@ impl ReplicatedState {
 
         self.update_stream_guaranteed_responses_size_bytes();
     }
+
+    /// Removes the canister `canister_id` and its snapshots from the state, as
+    /// part of migrating the canister to another subnet.
+    ///
+    /// The canister must have been rerouted away from this subnet (i.e. the
+    /// routing table must no longer assign it to `own_subnet_id` and the
+    /// canister migrations must hold a migration trace starting at
+    /// `own_subnet_id`) and it must be stopped, with no open call contexts and
+    /// empty queues. This way, messages still in flight to the canister are
+    /// handled by Message Routing based on the canister migrations entry in the
+    /// registry; and no responses can ever be delivered to the canister on this
+    /// subnet.
+    pub fn take_canister_for_migration(
+        &mut self,
+        canister_id: &CanisterId,
+    ) -> Result<MigratingCanister, String> {
+        let own_subnet_id = self.metadata.own_subnet_id;
+        if self
+            .metadata
+            .network_topology
+            .routing_table
+            .route(canister_id.get())
+            == Some(own_subnet_id)
+        {
+            return Err(format!(
+                "Canister {} is still assigned to subnet {} by the routing table",
+                canister_id, own_subnet_id
+            ));
+        }
+        let migration_trace = self
+            .metadata
+            .network_topology
+            .canister_migrations
+            .lookup(*canister_id);
+        if migration_trace.as_ref().and_then(|trace| trace.first()) != Some(&own_subnet_id) {
+            return Err(format!(
+                "Canister {} is not being migrated away from subnet {} according to the canister migrations",
+                canister_id, own_subnet_id
+            ));
+        }
+
+        let canister_state = self
+            .canister_state(canister_id)
+            .ok_or_else(|| format!("Canister {} not found", canister_id))?;
+        let system_state = &canister_state.system_state;
+        if system_state.get_status() != &CanisterStatus::Stopped {
+            return Err(format!("Canister {} is not stopped", canister_id));
+        }
+        if system_state
+            .call_context_manager()
+            .is_some_and(|ccm| !ccm.call_contexts().is_empty())
+        {
+            return Err(format!("Canister {} has open call contexts", canister_id));
+        }
+        let queues = system_state.queues();
+        if queues.ingress_queue_message_count() > 0
+            || queues.input_queues_message_count() > 0
+            || queues.output_queues_message_count() > 0
+        {
+            return Err(format!("Canister {} has non-empty queues", canister_id));
+        }
+
+        let canister_state = self.canister_states.remove(canister_id).unwrap();
+        let snapshots = self
+            .canister_snapshots
+            .list_snapshots(*canister_id)
+            .into_iter()
+            .map(|(snapshot_id, _)| {
+                let snapshot = self.canister_snapshots.remove(snapshot_id).unwrap();
+                (snapshot_id, snapshot)
+            })
+            .collect();
+
+        // Messages from the migrated canister still in the input queues of local
+        // canisters are now remote subnet inputs.
+        split_input_schedules(&mut self.canister_states);
+
+        Ok(MigratingCanister {
+            canister_state,
+            snapshots,
+        })
+    }
+
+    /// Adds a canister removed from the state of another subnet by
+    /// [`Self::take_canister_for_migration()`] (together with its snapshots) to
+    /// this state.
+    ///
+    /// The routing table must assign the canister to `own_subnet_id` and the
+    /// canister migrations must hold a migration trace ending at
+    /// `own_subnet_id`.
+    pub fn put_migrated_canister(&mut self, canister: MigratingCanister) -> Result<(), String> {
+        let MigratingCanister {
+            canister_state,
+            snapshots,
+        } = canister;
+        let canister_id = canister_state.canister_id();
+        let own_subnet_id = self.metadata.own_subnet_id;
+        if self
+            .metadata
+            .network_topology
+            .routing_table
+            .route(canister_id.get())
+            != Some(own_subnet_id)
+        {
+            return Err(format!(
+                "Canister {} is not assigned to subnet {} by the routing table",
+                canister_id, own_subnet_id
+            ));
+        }
+        let migration_trace = self
+            .metadata
+            .network_topology
+            .canister_migrations
+            .lookup(canister_id);
+        if migration_trace.as_ref().and_then(|trace| trace.last()) != Some(&own_subnet_id) {
+            return Err(format!(
+                "Canister {} is not being migrated to subnet {} according to the canister migrations",
+                canister_id, own_subnet_id
+            ));
+        }
+        if self.canister_states.contains_key(&canister_id) {
+            return Err(format!("Canister {} already exists", canister_id));
+        }
+        for (snapshot_id, snapshot) in snapshots.iter() {
+            if snapshot.canister_id() != canister_id {
+                return Err(format!(
+                    "Snapshot {} belongs to canister {}, not {}",
+                    snapshot_id,
+                    snapshot.canister_id(),
+                    canister_id
+                ));
+            }
+            if self.canister_snapshots.contains(snapshot_id) {
+                return Err(format!("Snapshot {} already exists", snapshot_id));
+            }
+        }
+
+        self.canister_states.insert(canister_id, canister_state);
+        // The snapshots are not backed by any files of the canister on this
+        // subnet, so they are written out in full, same as uploaded snapshots.
+        for (snapshot_id, snapshot) in snapshots {
+            self.canister_snapshots.push_uploaded(snapshot_id, snapshot);
+        }
+
+        // Messages from the migrated canister already in the input queues of local
+        // canisters are now local subnet inputs.
+        split_input_schedules(&mut self.canister_states);
+        Ok(())
+    }
+}
+
+/// A canister (and its snapshots) in the process of being migrated from one
+/// subnet to another. See [`ReplicatedState::take_canister_for_migration()`]
+/// and [`ReplicatedState::put_migrated_canister()`].
+#[derive(Clone, Debug)]
+pub struct MigratingCanister {
+    pub canister_state: CanisterState,
+    pub snapshots: Vec<(SnapshotId, Arc<CanisterSnapshot>)>,
+}
+
+/// Re-partitions the local and remote input schedules of all canisters in
+/// `canister_states`, based on which canisters are present in `canister_states`.
+fn split_input_schedules(canister_states: &mut BTreeMap<CanisterId, CanisterState>) {
+    let local_canister_ids = canister_states.keys().cloned().collect::<Vec<_>>();
+    for canister_id in local_canister_ids.iter() {
+        let mut canister_state = canister_states.remove(canister_id).unwrap();
+        canister_state
+            .system_state
+            .split_input_schedules(canister_id, canister_states);
+        canister_states.insert(*canister_id, canister_state);
+    }
 }
 
 /// Converts a `CanisterInput` popped from a subnet input queue into a
@@ -1,5 +1,5 @@
 use assert_matches::assert_matches;
-use ic_base_types::{CanisterId, NumBytes, NumSeconds, PrincipalId, SubnetId};
+use ic_base_types::{CanisterId, NumBytes, NumSeconds, PrincipalId, SnapshotId, SubnetId};
 use ic_btc_interface::Network;
 use ic_btc_replica_types::{
     BitcoinAdapterResponse, BitcoinAdapterResponseWrapper, BitcoinReject,
@@ -10,8 +10,9 @@ use ic_management_canister_types::{
     BitcoinGetSuccessorsResponse, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin,
     Payload as _,
 };
-use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
+use ic_registry_routing_table::{CanisterIdRange, CanisterMigrations, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::canister_snapshots::CanisterSnapshot;
 use ic_replicated_state::canister_state::execution_state::{
     CustomSection, CustomSectionType, WasmMetadata,
 };
@@ -24,8 +25,8 @@ use ic_replicated_state::replicated_state::{
 };
 use ic_replicated_state::testing::{CanisterQueuesTesting, SystemStateTesting};
 use ic_replicated_state::{
-    CanisterState, IngressHistoryState, InputSource, ReplicatedState, SchedulerState, StateError,
-    SystemState,
+    CanisterState, CanisterStatus, IngressHistoryState, InputSource, ReplicatedState,
+    SchedulerState, StateError, SystemState,
 };
 use ic_test_utilities_state::{arb_replicated_state_with_output_queues, ExecutionStateBuilder};
 use ic_test_utilities_types::ids::{canister_test_id, message_test_id, user_test_id, SUBNET_1};
@@ -961,6 +962,136 @@ fn split() {
     assert_eq!(expected, state_b);
 }
 
+#[test]
+fn migrate_canister() {
+    const SUBNET_A: SubnetId = SUBNET_ID;
+    const SUBNET_B: SubnetId = SUBNET_1;
+
+    const CANISTER_1: CanisterId = CANISTER_ID;
+    const CANISTER_2: CanisterId = OTHER_CANISTER_ID;
+
+    let routing_table = |canister_2_subnet| {
+        Arc::new(
+            RoutingTable::try_from(btreemap! {
+                CanisterIdRange {start: CANISTER_1, end: CANISTER_1} => SUBNET_A,
+                CanisterIdRange {start: CANISTER_2, end: CANISTER_2} => canister_2_subnet,
+            })
+            .unwrap(),
+        )
+    };
+
+    let mut fixture = ReplicatedStateFixture::with_canisters(&[CANISTER_1, CANISTER_2]);
+    fixture.state.metadata.network_topology.routing_table = routing_table(SUBNET_A);
+
+    // A request from `CANISTER_2` to `CANISTER_1`, in the input queue of the latter.
+    fixture
+        .push_input(
+            RequestBuilder::default()
+                .sender(CANISTER_2)
+                .receiver(CANISTER_1)
+                .build()
+                .into(),
+        )
+        .unwrap();
+    assert_eq!(
+        &VecDeque::from(vec![CANISTER_2]),
+        fixture.local_subnet_input_schedule(&CANISTER_1)
+    );
+
+    // And a snapshot of `CANISTER_2`.
+    let snapshot_id = SnapshotId::from((CANISTER_2, 0));
+    let snapshot = CanisterSnapshot::from_canister(
+        fixture.state.canister_state(&CANISTER_2).unwrap(),
+        UNIX_EPOCH,
+    )
+    .unwrap();
+    fixture
+        .state
+        .canister_snapshots
+        .push(snapshot_id, Arc::new(snapshot));
+
+    // `CANISTER_2` cannot be migrated while still hosted by subnet A.
+    assert_matches!(
+        fixture.state.take_canister_for_migration(&CANISTER_2),
+        Err(err) if err.contains("still assigned")
+    );
+
+    // Nor without a canister migrations entry (i.e. if it was rerouted without
+    // going through the canister migration registry mutations).
+    fixture.state.metadata.network_topology.routing_table = routing_table(SUBNET_B);
+    assert_matches!(
+        fixture.state.take_canister_for_migration(&CANISTER_2),
+        Err(err) if err.contains("canister migrations")
+    );
+
+    // Nor while it is still running.
+    let canister_migrations = Arc::new(
+        CanisterMigrations::try_from(btreemap! {
+            CanisterIdRange {start: CANISTER_2, end: CANISTER_2} => vec![SUBNET_A, SUBNET_B],
+        })
+        .unwrap(),
+    );
+    fixture.state.metadata.network_topology.canister_migrations = Arc::clone(&canister_migrations);
+    assert_matches!(
+        fixture.state.take_canister_for_migration(&CANISTER_2),
+        Err(err) if err.contains("not stopped")
+    );
+
+    fixture
+        .state
+        .canister_state_mut(&CANISTER_2)
+        .unwrap()
+        .system_state
+        .set_status(CanisterStatus::Stopped);
+    let migrating_canister = fixture
+        .state
+        .take_canister_for_migration(&CANISTER_2)
+        .unwrap();
+    assert_eq!(CANISTER_2, migrating_canister.canister_state.canister_id());
+    assert_eq!(1, migrating_canister.snapshots.len());
+
+    // The canister and its snapshot are gone from subnet A.
+    assert!(fixture.state.canister_state(&CANISTER_2).is_none());
+    assert_eq!(0, fixture.state.canister_snapshots.count());
+    // And the request from `CANISTER_2` is now a remote subnet input.
+    assert!(fixture.local_subnet_input_schedule(&CANISTER_1).is_empty());
+    assert_eq!(
+        &VecDeque::from(vec![CANISTER_2]),
+        fixture.remote_subnet_input_schedule(&CANISTER_1)
+    );
+
+    // The canister cannot be put back onto subnet A.
+    assert_matches!(
+        fixture
+            .state
+            .put_migrated_canister(migrating_canister.clone()),
+        Err(err) if err.contains("not assigned")
+    );
+
+    // But it can be added to subnet B, together with its snapshot.
+    let mut state_b = ReplicatedState::new(SUBNET_B, SubnetType::Application);
+    state_b.metadata.network_topology.routing_table = routing_table(SUBNET_B);
+    assert_matches!(
+        state_b.put_migrated_canister(migrating_canister.clone()),
+        Err(err) if err.contains("canister migrations")
+    );
+    state_b.metadata.network_topology.canister_migrations = canister_migrations;
+    state_b
+        .put_migrated_canister(migrating_canister.clone())
+        .unwrap();
+    assert_eq!(
+        &migrating_canister.canister_state,
+        state_b.canister_state(&CANISTER_2).unwrap()
+    );
+    assert!(state_b.canister_snapshots.contains(&snapshot_id));
+
+    // But only once.
+    assert_matches!(
+        state_b.put_migrated_canister(migrating_canister),
+        Err(err) if err.contains("already exists")
+    );
+}
+
 #[test]
 fn input_source_roundtrip() {
     use ic_protobuf::state::queues::v1::canister_queues as pb;
@@ -32,6 +32,9 @@ impl Drop for KillOnDrop {
 pub struct RosettaOptions {
     pub ledger_id: Principal,
 
+    // Further ledgers served by the same instance.
+    pub additional_ledger_ids: Vec<Principal>,
+
     pub store_type: String,
 
     pub network_type: String,
@@ -51,6 +54,7 @@ impl Default for RosettaOptions {
     fn default() -> Self {
         RosettaOptions {
             ledger_id: Principal::anonymous(),
+            additional_ledger_ids: vec![],
             store_type: "in-memory".to_owned(),
             network_type: "testnet".to_owned(),
             network_url: None,
@@ -84,6 +88,10 @@ pub async fn start_rosetta(rosetta_bin: &Path, arguments: RosettaOptions) -> Ros
         .arg(port_file.clone())
         .stderr(std::process::Stdio::piped());
 
+    for ledger_id in arguments.additional_ledger_ids {
+        command = command.arg("--ledger-id").arg(ledger_id.to_string());
+    }
+
     if arguments.network_url.is_some() {
         command = command
             .arg("--network-url")
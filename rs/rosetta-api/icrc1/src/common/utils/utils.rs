@@ -6,7 +6,7 @@ use crate::{
         storage::storage_client::StorageClient,
         types::{ApproveMetadata, BlockMetadata, OperationType, TransactionMetadata},
     },
-    AppState,
+    AppState, MultiTokenAppState,
 };
 use anyhow::{bail, Context};
 use candid::Nat;
@@ -19,6 +19,7 @@ use rosetta_core::{
 };
 use serde_bytes::ByteBuf;
 use std::fmt::Write;
+use std::sync::Arc;
 use std::time::Duration;
 
 pub fn verify_network_id(
@@ -40,6 +41,25 @@ pub fn verify_network_id(
     Ok(())
 }
 
+/// Returns the state of the ledger exposed as the network in `network_identifier`.
+pub fn get_state_from_network_id(
+    network_identifier: &NetworkIdentifier,
+    state: &MultiTokenAppState,
+) -> anyhow::Result<Arc<AppState>> {
+    let token_state = state
+        .token_states
+        .get(&network_identifier.network)
+        .with_context(|| {
+            format!(
+                "Network {} is not served by this instance. Supported networks: {:?}",
+                network_identifier.network,
+                state.token_states.keys().collect::<Vec<_>>()
+            )
+        })?;
+    verify_network_id(network_identifier, token_state)?;
+    Ok(token_state.clone())
+}
+
 pub fn convert_timestamp_to_millis(timestamp_nanos: u64) -> anyhow::Result<u64> {
     let millis = Duration::from_nanos(timestamp_nanos).as_millis();
     u64::try_from(millis).context(format!(
@@ -685,6 +705,7 @@ pub fn rosetta_core_transaction_to_icrc1_transaction(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ic_base_types::CanisterId;
     use ic_icrc1_test_utils::account_strategy;
     use ic_icrc1_test_utils::arb_amount;
     use ic_icrc1_test_utils::blocks_strategy;
@@ -697,6 +718,53 @@ mod tests {
 
     const NUM_TEST_CASES: u32 = 100;
 
+    fn token_state(ledger_id: CanisterId, symbol: &str) -> Arc<AppState> {
+        let agent = ic_agent::Agent::builder()
+            .with_url("http://localhost:0")
+            .build()
+            .unwrap();
+        Arc::new(AppState {
+            icrc1_agent: Arc::new(icrc_ledger_agent::Icrc1Agent {
+                agent,
+                ledger_canister_id: ledger_id.into(),
+            }),
+            ledger_id,
+            synched: Arc::new(std::sync::Mutex::new(None)),
+            archive_canister_ids: Arc::new(tokio::sync::Mutex::new(vec![])),
+            storage: Arc::new(StorageClient::new_in_memory().unwrap()),
+            metadata: crate::Metadata::from_args(symbol.to_string(), 8),
+        })
+    }
+
+    #[test]
+    fn test_get_state_from_network_id() {
+        let ledger_1 = CanisterId::from_u64(1);
+        let ledger_2 = CanisterId::from_u64(2);
+        let state = MultiTokenAppState::new(vec![
+            token_state(ledger_1, "AAA"),
+            token_state(ledger_2, "BBB"),
+        ]);
+
+        for (ledger_id, symbol) in [(ledger_1, "AAA"), (ledger_2, "BBB")] {
+            let network_identifier =
+                NetworkIdentifier::new(DEFAULT_BLOCKCHAIN.to_owned(), ledger_id.to_string());
+            let token_state = get_state_from_network_id(&network_identifier, &state).unwrap();
+            assert_eq!(token_state.ledger_id, ledger_id);
+            assert_eq!(token_state.metadata.symbol, symbol);
+        }
+
+        // A ledger that is not served by this instance.
+        let network_identifier = NetworkIdentifier::new(
+            DEFAULT_BLOCKCHAIN.to_owned(),
+            CanisterId::from_u64(3).to_string(),
+        );
+        assert!(get_state_from_network_id(&network_identifier, &state).is_err());
+
+        // A served ledger on the wrong blockchain.
+        let network_identifier = NetworkIdentifier::new("Bitcoin".to_owned(), ledger_1.to_string());
+        assert!(get_state_from_network_id(&network_identifier, &state).is_err());
+    }
+
     fn test_block_conversion<T: TokensType>(block: ic_icrc1::Block<T>) {
         let currency = Currency::default();
         let rosetta_block = RosettaBlock::from_encoded_block(&block.encode(), 0).unwrap();
@@ -17,11 +17,12 @@ use ic_icrc_rosetta::{
     construction_api::endpoints::*,
     data_api::endpoints::*,
     ledger_blocks_synchronization::blocks_synchronizer::start_synching_blocks,
-    AppState, Metadata,
+    AppState, Metadata, MultiTokenAppState,
 };
 use ic_sys::fs::write_string_using_tmp_file;
 use icrc_ledger_agent::{CallMode, Icrc1Agent};
 use lazy_static::lazy_static;
+use std::collections::BTreeSet;
 use std::sync::{Arc, Mutex};
 use std::{path::PathBuf, process};
 use tokio::{net::TcpListener, sync::Mutex as AsyncMutex};
@@ -57,14 +58,19 @@ enum NetworkType {
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
-    ledger_id: CanisterId,
+    /// The id of the ICRC-1 ledger to serve.
+    /// Can be repeated to serve multiple ledgers from a single instance, each ledger
+    /// is then exposed as a separate network identified by its canister id.
+    #[arg(short, long, required = true)]
+    ledger_id: Vec<CanisterId>,
 
     /// The symbol of the ICRC-1 token.
     /// If set Rosetta will check the symbol against the ledger it connects to. If the symbol does not match, it will exit.
+    /// Can only be set if a single ledger is served.
     #[arg(long)]
     icrc1_symbol: Option<String>,
 
+    /// Can only be set if a single ledger is served.
     #[arg(long)]
     icrc1_decimals: Option<u8>,
 
@@ -83,9 +89,15 @@ struct Args {
     store_type: StoreType,
 
     /// The file to use for the store if [store_type] is file.
+    /// Only used if a single ledger is served.
     #[arg(short = 'f', long, default_value = "/data/db.sqlite")]
     store_file: PathBuf,
 
+    /// The directory to use for the stores if [store_type] is file and multiple ledgers
+    /// are served. The blocks of each ledger are stored in the file `<ledger_id>.sqlite`.
+    #[arg(long, default_value = "/data")]
+    multi_tokens_store_dir: PathBuf,
+
     /// The network type that rosetta connects to.
     #[arg(short = 'n', long, value_enum)]
     network_type: NetworkType,
@@ -141,6 +153,39 @@ impl Args {
     fn are_metadata_args_set(&self) -> bool {
         self.icrc1_symbol.is_some() && self.icrc1_decimals.is_some()
     }
+
+    fn is_multi_token(&self) -> bool {
+        self.ledger_id.len() > 1
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.ledger_id.iter().collect::<BTreeSet<_>>().len() != self.ledger_id.len() {
+            bail!("The same ledger id cannot be provided more than once.");
+        }
+        if self.is_multi_token() && (self.icrc1_symbol.is_some() || self.icrc1_decimals.is_some()) {
+            bail!(
+                "'icrc1-symbol' and 'icrc1-decimals' cannot be set if multiple ledgers are served."
+            );
+        }
+        Ok(())
+    }
+
+    /// Return the file of the store of the given ledger.
+    fn store_file(&self, ledger_id: &CanisterId) -> PathBuf {
+        if self.is_multi_token() {
+            self.multi_tokens_store_dir
+                .join(format!("{}.sqlite", ledger_id))
+        } else {
+            self.store_file.clone()
+        }
+    }
+
+    fn new_storage(&self, ledger_id: &CanisterId) -> anyhow::Result<StorageClient> {
+        match self.store_type {
+            StoreType::InMemory => StorageClient::new_in_memory(),
+            StoreType::File => StorageClient::new_persistent(&self.store_file(ledger_id)),
+        }
+    }
 }
 
 fn init_logs(log_level: Level, log_file_path: &PathBuf) -> anyhow::Result<WorkerGuard> {
@@ -285,10 +330,11 @@ async fn main() -> Result<()> {
 
     let _guard = init_logs(args.log_level, &args.log_file)?;
 
-    let storage = Arc::new(match args.store_type {
-        StoreType::InMemory => StorageClient::new_in_memory()?,
-        StoreType::File => StorageClient::new_persistent(&args.store_file)?,
-    });
+    args.validate()?;
+
+    if args.exit_on_sync && args.offline {
+        bail!("'exit-on-sync' and 'offline' parameters cannot be specified at the same time.");
+    }
 
     let network_url = args.effective_network_url();
 
@@ -313,56 +359,74 @@ async fn main() -> Result<()> {
         ic_agent.status().await?.replica_health_status
     );
 
-    let icrc1_agent = Arc::new(Icrc1Agent {
-        agent: ic_agent,
-        ledger_canister_id: args.ledger_id.into(),
-    });
+    let mut token_states = vec![];
+    for ledger_id in &args.ledger_id {
+        let storage = Arc::new(args.new_storage(ledger_id)?);
 
-    let metadata = load_metadata(&args, &icrc1_agent, &storage).await?;
-    if let Some(token_symbol) = args.icrc1_symbol.clone() {
-        if metadata.symbol != token_symbol {
-            bail!(
-                "Provided symbol does not match symbol retrieved in online mode. Expected: {}, Got: {}",
-                metadata.symbol, token_symbol
-            );
-        }
-    }
+        let icrc1_agent = Arc::new(Icrc1Agent {
+            agent: ic_agent.clone(),
+            ledger_canister_id: (*ledger_id).into(),
+        });
 
-    info!(
-        "ICRC Rosetta is connected to the ICRC-1 ledger: {}",
-        args.ledger_id
-    );
-    info!(
-        "The token symbol of the ICRC-1 ledger is: {}",
-        metadata.symbol
-    );
+        let metadata = load_metadata(&args, &icrc1_agent, &storage)
+            .await
+            .with_context(|| format!("Failed to load the metadata of ledger {}", ledger_id))?;
+        if let Some(token_symbol) = args.icrc1_symbol.clone() {
+            if metadata.symbol != token_symbol {
+                bail!(
+                    "Provided symbol does not match symbol retrieved in online mode. Expected: {}, Got: {}",
+                    metadata.symbol, token_symbol
+                );
+            }
+        }
 
-    let shared_state = Arc::new(AppState {
-        icrc1_agent: icrc1_agent.clone(),
-        ledger_id: args.ledger_id,
-        synched: Arc::new(Mutex::new(None)),
-        storage: storage.clone(),
-        archive_canister_ids: Arc::new(AsyncMutex::new(vec![])),
-        metadata,
-    });
+        info!(
+            "ICRC Rosetta is connected to the ICRC-1 ledger: {}",
+            ledger_id
+        );
+        info!(
+            "The token symbol of the ICRC-1 ledger {} is: {}",
+            ledger_id, metadata.symbol
+        );
+
+        token_states.push(Arc::new(AppState {
+            icrc1_agent,
+            ledger_id: *ledger_id,
+            synched: Arc::new(Mutex::new(None)),
+            storage,
+            archive_canister_ids: Arc::new(AsyncMutex::new(vec![])),
+            metadata,
+        }));
+    }
 
     if args.exit_on_sync {
-        if args.offline {
-            bail!("'exit-on-sync' and 'offline' parameters cannot be specified at the same time.");
-        }
-
         info!("Starting to sync blocks");
-        start_synching_blocks(
-            icrc1_agent.clone(),
-            storage.clone(),
-            *MAXIMUM_BLOCKS_PER_REQUEST,
-            Arc::new(AsyncMutex::new(vec![])),
-        )
-        .await?;
+        // The ledgers are synced concurrently, each one on its own thread.
+        let sync_handles: Vec<_> = token_states
+            .iter()
+            .map(|token_state| {
+                let token_state = token_state.clone();
+                tokio::task::spawn_blocking(move || {
+                    tokio::runtime::Handle::current().block_on(start_synching_blocks(
+                        token_state.icrc1_agent.clone(),
+                        token_state.storage.clone(),
+                        *MAXIMUM_BLOCKS_PER_REQUEST,
+                        Arc::new(AsyncMutex::new(vec![])),
+                    ))
+                })
+            })
+            .collect();
+        for (ledger_id, sync_handle) in args.ledger_id.iter().zip(sync_handles) {
+            sync_handle
+                .await?
+                .with_context(|| format!("Failed to sync the blocks of ledger {}", ledger_id))?;
+        }
 
         process::exit(0);
     }
 
+    let shared_state = Arc::new(MultiTokenAppState::new(token_states.clone()));
+
     let app = Router::new()
         .route("/ready", get(ready))
         .route("/health", get(health))
@@ -396,7 +460,7 @@ async fn main() -> Result<()> {
     let rosetta_url = format!("0.0.0.0:{}", args.get_port());
     let tcp_listener = TcpListener::bind(rosetta_url.clone()).await?;
 
-    if let Some(port_file) = args.port_file {
+    if let Some(port_file) = &args.port_file {
         write_string_using_tmp_file(
             port_file,
             tcp_listener.local_addr()?.port().to_string().as_str(),
@@ -404,38 +468,41 @@ async fn main() -> Result<()> {
     }
 
     if !args.offline {
-        tokio::task::spawn_blocking(move || {
-            let mut sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
-
+        for token_state in token_states {
             let block_sync_storage = match args.store_type {
-                StoreType::InMemory => storage.clone(),
-                StoreType::File => {
-                    Arc::new(StorageClient::new_persistent(&args.store_file).unwrap())
-                }
+                StoreType::InMemory => token_state.storage.clone(),
+                StoreType::File => Arc::new(args.new_storage(&token_state.ledger_id)?),
             };
 
-            tokio::runtime::Handle::current().block_on(async {
-                loop {
-                    if let Err(e) = start_synching_blocks(
-                        icrc1_agent.clone(),
-                        block_sync_storage.clone(),
-                        *MAXIMUM_BLOCKS_PER_REQUEST,
-                        shared_state.clone().archive_canister_ids.clone(),
-                    )
-                    .await
-                    {
-                        error!("Error while syncing blocks: {}", e);
-                        sync_wait_secs =
-                            std::cmp::min(sync_wait_secs * 2, MAX_BLOCK_SYNC_WAIT_SECS);
-                        info!("Retrying in {} seconds.", sync_wait_secs);
-                    } else {
-                        sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+            tokio::task::spawn_blocking(move || {
+                let mut sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+
+                tokio::runtime::Handle::current().block_on(async {
+                    loop {
+                        if let Err(e) = start_synching_blocks(
+                            token_state.icrc1_agent.clone(),
+                            block_sync_storage.clone(),
+                            *MAXIMUM_BLOCKS_PER_REQUEST,
+                            token_state.archive_canister_ids.clone(),
+                        )
+                        .await
+                        {
+                            error!(
+                                "Error while syncing blocks of ledger {}: {}",
+                                token_state.ledger_id, e
+                            );
+                            sync_wait_secs =
+                                std::cmp::min(sync_wait_secs * 2, MAX_BLOCK_SYNC_WAIT_SECS);
+                            info!("Retrying in {} seconds.", sync_wait_secs);
+                        } else {
+                            sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+                        }
+
+                        tokio::time::sleep(std::time::Duration::from_secs(sync_wait_secs)).await;
                     }
-
-                    tokio::time::sleep(std::time::Duration::from_secs(sync_wait_secs)).await;
-                }
+                });
             });
-        });
+        }
     }
 
     info!("Starting Rosetta server");
@@ -1,7 +1,7 @@
 use ic_base_types::{NumBytes, NumSeconds};
 use ic_config::flag_status::FlagStatus;
 use ic_logger::{error, info, warn, ReplicaLogger};
-use ic_management_canister_types::LogVisibilityV2;
+use ic_management_canister_types::{CanisterPriorityClass, LogVisibilityV2};
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -27,6 +27,7 @@ use ic_types::{
     batch::TotalQueryStats, nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId,
     CanisterLog, ComputeAllocation, Cycles, ExecutionRound, Height, LongExecutionMode,
     MemoryAllocation, NumInstructions, PrincipalId, SnapshotId, Time,
+    DEFAULT_CANISTER_LOG_BUFFER_SIZE,
 };
 use ic_utils::thread::maybe_parallel_map;
 use ic_wasm_types::{CanisterModule, WasmHash};
@@ -148,6 +149,7 @@ pub struct CanisterStateBits {
     pub accumulated_priority: AccumulatedPriority,
     pub priority_credit: AccumulatedPriority,
     pub long_execution_mode: LongExecutionMode,
+    pub priority_class: CanisterPriorityClass,
     pub execution_state_bits: Option<ExecutionStateBits>,
     pub memory_allocation: MemoryAllocation,
     pub wasm_memory_threshold: NumBytes,
@@ -177,6 +179,8 @@ pub struct CanisterStateBits {
     pub log_visibility: LogVisibilityV2,
     pub canister_log: CanisterLog,
     pub wasm_memory_limit: Option<NumBytes>,
+    pub environment_variables: BTreeMap<String, String>,
+    pub wasm_trace_length: u64,
     pub next_snapshot_id: u64,
     pub snapshots_memory_usage: NumBytes,
     pub on_low_wasm_memory_hook_status: OnLowWasmMemoryHookStatus,
@@ -2315,6 +2319,7 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
                 item.long_execution_mode,
             )
             .into(),
+            priority_class: pb_canister_state_bits::PriorityClass::from(item.priority_class).into(),
             execution_state_bits: item.execution_state_bits.as_ref().map(|v| v.into()),
             memory_allocation: item.memory_allocation.bytes().get(),
             wasm_memory_threshold: Some(item.wasm_memory_threshold.get()),
@@ -2359,7 +2364,16 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
                 .map(|record| record.into())
                 .collect(),
             next_canister_log_record_idx: item.canister_log.next_idx(),
+            canister_log_buffer_size: (item.canister_log.capacity()
+                != DEFAULT_CANISTER_LOG_BUFFER_SIZE)
+                .then(|| item.canister_log.capacity() as u64),
             wasm_memory_limit: item.wasm_memory_limit.map(|v| v.get()),
+            environment_variables: item
+                .environment_variables
+                .into_iter()
+                .map(|(name, value)| pb_canister_state_bits::EnvironmentVariable { name, value })
+                .collect(),
+            wasm_trace_length: item.wasm_trace_length,
             next_snapshot_id: item.next_snapshot_id,
             snapshots_memory_usage: item.snapshots_memory_usage.get(),
             on_low_wasm_memory_hook_status: Some(
@@ -2457,6 +2471,9 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             )
             .unwrap_or_default()
             .into(),
+            priority_class: pb_canister_state_bits::PriorityClass::try_from(value.priority_class)
+                .unwrap_or_default()
+                .into(),
             execution_state_bits,
             memory_allocation: MemoryAllocation::try_from(NumBytes::from(value.memory_allocation))
                 .map_err(|e| ProxyDecodeError::ValueOutOfRange {
@@ -2511,15 +2528,24 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 "CanisterStateBits::log_visibility_v2",
             )
             .unwrap_or_default(),
-            canister_log: CanisterLog::new(
+            canister_log: CanisterLog::new_with_capacity(
                 value.next_canister_log_record_idx,
                 value
                     .canister_log_records
                     .into_iter()
                     .map(|record| record.into())
                     .collect(),
+                value
+                    .canister_log_buffer_size
+                    .map_or(DEFAULT_CANISTER_LOG_BUFFER_SIZE, |size| size as usize),
             ),
             wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
+            environment_variables: value
+                .environment_variables
+                .into_iter()
+                .map(|variable| (variable.name, variable.value))
+                .collect(),
+            wasm_trace_length: value.wasm_trace_length,
             next_snapshot_id: value.next_snapshot_id,
             snapshots_memory_usage: NumBytes::from(value.snapshots_memory_usage),
             on_low_wasm_memory_hook_status: try_from_option_field(
@@ -29,6 +29,7 @@ fn default_canister_state_bits() -> CanisterStateBits {
         accumulated_priority: AccumulatedPriority::default(),
         priority_credit: AccumulatedPriority::default(),
         long_execution_mode: LongExecutionMode::default(),
+        priority_class: CanisterPriorityClass::default(),
         execution_state_bits: None,
         memory_allocation: MemoryAllocation::default(),
         wasm_memory_threshold: NumBytes::new(0),
@@ -58,6 +59,8 @@ fn default_canister_state_bits() -> CanisterStateBits {
         log_visibility: Default::default(),
         canister_log: Default::default(),
         wasm_memory_limit: None,
+        environment_variables: BTreeMap::new(),
+        wasm_trace_length: 0,
         next_snapshot_id: 0,
         snapshots_memory_usage: NumBytes::from(0),
         on_low_wasm_memory_hook_status: OnLowWasmMemoryHookStatus::default(),
@@ -121,6 +124,78 @@ fn test_encode_decode_non_empty_controllers() {
     assert_eq!(canister_state_bits.controllers, expected_controllers);
 }
 
+#[test]
+fn test_encode_decode_canister_log_buffer_size() {
+    for capacity in [DEFAULT_CANISTER_LOG_BUFFER_SIZE, 16 * 1024] {
+        let mut canister_log = CanisterLog::new_with_capacity(0, vec![], capacity);
+        canister_log.add_record(100, b"[info] some message".to_vec());
+        let canister_state_bits = CanisterStateBits {
+            canister_log: canister_log.clone(),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        assert_eq!(
+            pb_bits.canister_log_buffer_size.is_some(),
+            capacity != DEFAULT_CANISTER_LOG_BUFFER_SIZE
+        );
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+        assert_eq!(canister_state_bits.canister_log, canister_log);
+    }
+}
+
+#[test]
+fn test_encode_decode_environment_variables() {
+    let environment_variables = BTreeMap::from([
+        ("ENV".to_string(), "staging".to_string()),
+        ("URL".to_string(), "https://example.com".to_string()),
+    ]);
+    let canister_state_bits = CanisterStateBits {
+        environment_variables: environment_variables.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(
+        canister_state_bits.environment_variables,
+        environment_variables
+    );
+}
+
+#[test]
+fn test_encode_decode_wasm_trace_length() {
+    let canister_state_bits = CanisterStateBits {
+        wasm_trace_length: 42,
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.wasm_trace_length, 42);
+}
+
+#[test]
+fn test_encode_decode_priority_class() {
+    for priority_class in [
+        CanisterPriorityClass::Standard,
+        CanisterPriorityClass::Burst,
+    ] {
+        let canister_state_bits = CanisterStateBits {
+            priority_class,
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+        assert_eq!(canister_state_bits.priority_class, priority_class);
+    }
+}
+
 #[test]
 fn test_encode_decode_empty_history() {
     let canister_history = CanisterHistory::default();
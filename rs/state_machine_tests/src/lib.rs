@@ -21,7 +21,10 @@ use ic_crypto_tree_hash::{sparse_labeled_tree_from_paths, Label, Path as Labeled
 use ic_crypto_utils_threshold_sig_der::threshold_sig_public_key_to_der;
 use ic_cycles_account_manager::CyclesAccountManager;
 pub use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::{ExecutionServices, IngressHistoryReaderImpl};
+use ic_execution_environment::{
+    ExecutionEnvironment, ExecutionServices, IngressHistoryReaderImpl, WasmProfileRecord,
+    WasmProfiles,
+};
 use ic_http_endpoints_public::{metrics::HttpHandlerMetrics, IngressWatcher, IngressWatcherHandle};
 use ic_https_outcalls_consensus::payload_builder::CanisterHttpPayloadBuilderImpl;
 use ic_ingress_manager::{IngressManager, RandomStateKind};
@@ -51,9 +54,12 @@ use ic_management_canister_types::{
 use ic_management_canister_types::{
     CanisterHttpResponsePayload, CanisterInstallMode, CanisterSettingsArgs,
     CanisterSnapshotResponse, CanisterStatusResultV2, ClearChunkStoreArgs, EcdsaCurve, EcdsaKeyId,
-    InstallChunkedCodeArgs, LoadCanisterSnapshotArgs, SchnorrAlgorithm, SignWithECDSAReply,
-    SignWithSchnorrReply, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
-    UploadChunkReply,
+    InstallChunkedCodeArgs, LoadCanisterSnapshotArgs, ReadCanisterSnapshotDataArgs,
+    ReadCanisterSnapshotDataResponse, ReadCanisterSnapshotMetadataArgs,
+    ReadCanisterSnapshotMetadataResponse, SchnorrAlgorithm, SignWithECDSAReply,
+    SignWithSchnorrReply, TakeCanisterSnapshotArgs, UpdateSettingsArgs,
+    UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs,
+    UploadCanisterSnapshotMetadataResponse, UploadChunkArgs, UploadChunkReply,
 };
 use ic_messaging::SyncMessageRouting;
 use ic_metrics::MetricsRegistry;
@@ -148,9 +154,11 @@ use ic_types::{
     },
     crypto::threshold_sig::ThresholdSigPublicKey,
     ingress::{IngressState, IngressStatus},
-    messages::{CallbackId, MessageId},
+    messages::{CallbackId, CanisterCall, Ingress, MessageId},
+    methods::SystemMethod,
     time::Time,
-    CanisterId, CryptoHashOfState, Cycles, NumBytes, PrincipalId, SubnetId, UserId,
+    CanisterId, CryptoHashOfState, Cycles, NumBytes, NumInstructions, PrincipalId, SubnetId,
+    UserId,
 };
 use ic_xnet_payload_builder::{
     certified_slice_pool::{certified_slice_count_bytes, CertifiedSliceError},
@@ -197,6 +205,28 @@ pub enum SubmitIngressError {
     UserError(UserError),
 }
 
+/// The outcome of [`StateMachine::upgrade_canister_dry_run`].
+///
+/// The instruction counts are zero for system methods that are not exported
+/// by the corresponding canister module or that were not reached because an
+/// earlier phase of the upgrade failed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UpgradeDryRunResult {
+    /// Instructions executed by `canister_pre_upgrade` of the old module.
+    pub pre_upgrade_instructions: NumInstructions,
+    /// Instructions executed by the start function of the new module.
+    pub start_instructions: NumInstructions,
+    /// Instructions executed by `canister_post_upgrade` of the new module.
+    pub post_upgrade_instructions: NumInstructions,
+    /// Memory usage of the canister before the upgrade.
+    pub memory_usage_before: NumBytes,
+    /// Memory usage of the canister after the upgrade. It is equal to
+    /// `memory_usage_before` if the upgrade failed.
+    pub memory_usage_after: NumBytes,
+    /// The result of the upgrade, e.g., the trap of one of its phases.
+    pub result: Result<(), UserError>,
+}
+
 struct FakeVerifier;
 
 impl Verifier for FakeVerifier {
@@ -736,6 +766,7 @@ impl BatchPayloadBuilder for PocketQueryStatsPayloadBuilderImpl {
                             s.stats.num_instructions /= num_nodes as u64;
                             s.stats.ingress_payload_size /= num_nodes as u64;
                             s.stats.egress_payload_size /= num_nodes as u64;
+                            s.stats.num_cache_hits /= num_nodes as u64;
                             s
                         })
                         .collect();
@@ -830,6 +861,9 @@ pub struct StateMachine {
     pub metrics_registry: MetricsRegistry,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
     pub query_handler: Arc<Mutex<QueryExecutionService>>,
+    wasm_profiles: WasmProfiles,
+    execution_environment: Arc<ExecutionEnvironment>,
+    max_instructions_per_install_code: NumInstructions,
     pub runtime: Arc<Runtime>,
     // The atomicity is required for internal mutability and sending across threads.
     checkpoint_interval_length: AtomicU64,
@@ -1584,6 +1618,9 @@ impl StateMachine {
         // experience.
         //
         // The API state machine provides is blocking anyway.
+        let max_instructions_per_install_code = subnet_config
+            .scheduler_config
+            .max_instructions_per_install_code;
         let execution_services = runtime.block_on(async {
             ExecutionServices::setup_execution(
                 replica_logger.clone(),
@@ -1779,6 +1816,9 @@ impl StateMachine {
             message_routing,
             metrics_registry: metrics_registry.clone(),
             query_handler: Arc::new(Mutex::new(execution_services.query_execution_service)),
+            wasm_profiles: execution_services.wasm_profiles,
+            execution_environment: execution_services.execution_environment,
+            max_instructions_per_install_code,
             ingress_watcher_handle,
             _ingress_watcher_drop_guard: ingress_watcher_drop_guard,
             certified_height_tx,
@@ -2661,6 +2701,66 @@ impl StateMachine {
         self.state_manager.remove_states_below(h.increment());
     }
 
+    /// Replaces the entire replicated state in this state machine with a copy
+    /// of the given source replicated state.
+    ///
+    /// The copy is cheap: the canister memories of both states share their
+    /// `PageMap` base and overlays and diverge copy-on-write.
+    /// This is useful for forking a state machine into independent branches.
+    pub fn replace_state(&self, source_state: Arc<ReplicatedState>) {
+        self.checkpointed_tick();
+        let (h, _) = self.state_manager.take_tip();
+        let state = source_state.as_ref().clone();
+        self.state_manager
+            .commit_and_certify(state, h.increment(), CertificationScope::Full, None);
+        self.state_manager.remove_states_below(h.increment());
+    }
+
+    /// Writes a checkpoint of the latest state and shares its files with the
+    /// state directory `state_dir` of another state machine.
+    ///
+    /// A state machine created with `state_dir` starts from the latest state
+    /// of this state machine. Checkpoint files are immutable once written, so
+    /// the read-only ones are hardlinked rather than copied: both state
+    /// machines use them as `PageMap` base files and write their changes to
+    /// their own overlays. The other state machine does not depend on this
+    /// one staying alive, so this state machine can be dropped.
+    pub fn copy_checkpoint_to(&self, state_dir: &Path) {
+        self.checkpointed_tick();
+        self.await_state_hash();
+        self.state_manager.flush_tip_channel();
+
+        let state_layout = self.state_manager.state_layout();
+        let height = self.state_manager.latest_state_height();
+        let checkpoint = state_layout
+            .checkpoint_verified(height)
+            .unwrap_or_else(|e| panic!("failed to obtain checkpoint @{}: {}", height, e));
+        let relative_path = checkpoint
+            .raw_path()
+            .strip_prefix(state_layout.raw_path())
+            .expect("checkpoint is not within the state layout");
+
+        fn link_recursively(src: &Path, dst: &Path) {
+            std::fs::create_dir_all(dst).expect("failed to create directory");
+            for entry in std::fs::read_dir(src).expect("failed to read_dir") {
+                let entry = entry.expect("failed to get directory entry");
+                let dst = dst.join(entry.file_name());
+                let metadata = entry.metadata().expect("failed to get metadata");
+                if metadata.is_dir() {
+                    link_recursively(&entry.path(), &dst);
+                } else if !metadata.permissions().readonly()
+                    || std::fs::hard_link(entry.path(), &dst).is_err()
+                {
+                    // Writable files might still change, and hardlinks do not
+                    // work across file systems; copy the file in both cases.
+                    std::fs::copy(entry.path(), dst).expect("failed to copy file");
+                }
+            }
+        }
+
+        link_recursively(checkpoint.raw_path(), &state_dir.join(relative_path));
+    }
+
     /// Removes states below the latest height.
     ///
     /// This is useful for testing behaviour after old states are dropped.
@@ -2723,17 +2823,8 @@ impl StateMachine {
         wasm: Vec<u8>,
         payload: Vec<u8>,
     ) -> Result<(), UserError> {
-        let sender = self
-            .get_controllers(canister_id)
-            .map(|controllers| {
-                controllers
-                    .into_iter()
-                    .next()
-                    .unwrap_or(PrincipalId::new_anonymous())
-            })
-            .unwrap_or(PrincipalId::new_anonymous());
         self.execute_ingress_as(
-            sender,
+            self.install_code_sender(canister_id),
             ic00::IC_00,
             Method::InstallCode,
             InstallCodeArgs::new(mode, canister_id, wasm, payload, None, None).encode(),
@@ -2741,6 +2832,19 @@ impl StateMachine {
         .map(|_| ())
     }
 
+    /// Returns the sender of `install_code` messages for the canister with
+    /// the specified ID: its first controller or the anonymous principal.
+    fn install_code_sender(&self, canister_id: CanisterId) -> PrincipalId {
+        self.get_controllers(canister_id)
+            .map(|controllers| {
+                controllers
+                    .into_iter()
+                    .next()
+                    .unwrap_or(PrincipalId::new_anonymous())
+            })
+            .unwrap_or(PrincipalId::new_anonymous())
+    }
+
     /// Compiles specified WAT to Wasm and installs it for the canister using
     /// the specified ID in the provided install mode.
     fn install_wat_in_mode(
@@ -2890,6 +2994,71 @@ impl StateMachine {
         self.install_wasm_in_mode(canister_id, CanisterInstallMode::Upgrade, wasm, payload)
     }
 
+    /// Upgrades the canister with the specified ID to the specified Wasm code
+    /// and reports the outcome of every phase of the upgrade.
+    ///
+    /// The upgrade is executed on a copy of the latest replicated state that
+    /// is dropped afterwards, so the state of this state machine is never
+    /// modified. The upgrade runs without deterministic time slicing, but
+    /// with the instruction limit of `install_code` messages.
+    ///
+    /// This is useful to check whether an upgrade fits into the instruction
+    /// limit before performing it on a production canister.
+    pub fn upgrade_canister_dry_run(
+        &self,
+        canister_id: CanisterId,
+        wasm: Vec<u8>,
+        payload: Vec<u8>,
+    ) -> UpgradeDryRunResult {
+        let memory_usage = |state: &ReplicatedState| {
+            state
+                .canister_state(&canister_id)
+                .map_or(NumBytes::new(0), |canister| canister.memory_usage())
+        };
+        let state = self.get_latest_state().as_ref().clone();
+        let memory_usage_before = memory_usage(&state);
+
+        let message = CanisterCall::Ingress(Arc::new(Ingress {
+            source: UserId::from(self.install_code_sender(canister_id)),
+            receiver: CanisterId::ic_00(),
+            effective_canister_id: Some(canister_id),
+            method_name: Method::InstallCode.to_string(),
+            method_payload: InstallCodeArgs::new(
+                CanisterInstallMode::Upgrade,
+                canister_id,
+                wasm,
+                payload,
+                None,
+                None,
+            )
+            .encode(),
+            message_id: MessageId::from([0; EXPECTED_MESSAGE_ID_LENGTH]),
+            expiry_time: state.time(),
+        }));
+        let (state, dry_run) = self.execution_environment.dry_run_install_code(
+            message,
+            state,
+            self.max_instructions_per_install_code,
+            self.nodes.len(),
+        );
+
+        let instructions = |method: SystemMethod| {
+            dry_run
+                .system_method_instructions
+                .get(&method)
+                .copied()
+                .unwrap_or(NumInstructions::new(0))
+        };
+        UpgradeDryRunResult {
+            pre_upgrade_instructions: instructions(SystemMethod::CanisterPreUpgrade),
+            start_instructions: instructions(SystemMethod::CanisterStart),
+            post_upgrade_instructions: instructions(SystemMethod::CanisterPostUpgrade),
+            memory_usage_before,
+            memory_usage_after: memory_usage(&state),
+            result: dry_run.result,
+        }
+    }
+
     /// Updates the settings of the given canister.
     ///
     /// This function is synchronous.
@@ -2965,6 +3134,105 @@ impl StateMachine {
         })?
     }
 
+    /// Returns the metadata of a canister snapshot.
+    pub fn read_canister_snapshot_metadata(
+        &self,
+        args: &ReadCanisterSnapshotMetadataArgs,
+    ) -> Result<ReadCanisterSnapshotMetadataResponse, UserError> {
+        let state = self.state_manager.get_latest_state().take();
+        let sender = state
+            .canister_state(&args.get_canister_id())
+            .and_then(|s| s.controllers().iter().next().cloned())
+            .unwrap_or_else(PrincipalId::new_anonymous);
+        self.execute_ingress_as(
+            sender,
+            ic00::IC_00,
+            Method::ReadCanisterSnapshotMetadata,
+            args.encode(),
+        )
+        .map(|res| match res {
+            WasmResult::Reply(data) => ReadCanisterSnapshotMetadataResponse::decode(&data),
+            WasmResult::Reject(reason) => {
+                panic!("read_canister_snapshot_metadata call rejected: {}", reason)
+            }
+        })?
+    }
+
+    /// Returns a piece of the data of a canister snapshot.
+    pub fn read_canister_snapshot_data(
+        &self,
+        args: &ReadCanisterSnapshotDataArgs,
+    ) -> Result<ReadCanisterSnapshotDataResponse, UserError> {
+        let state = self.state_manager.get_latest_state().take();
+        let sender = state
+            .canister_state(&args.get_canister_id())
+            .and_then(|s| s.controllers().iter().next().cloned())
+            .unwrap_or_else(PrincipalId::new_anonymous);
+        self.execute_ingress_as(
+            sender,
+            ic00::IC_00,
+            Method::ReadCanisterSnapshotData,
+            args.encode(),
+        )
+        .map(|res| match res {
+            WasmResult::Reply(data) => ReadCanisterSnapshotDataResponse::decode(&data),
+            WasmResult::Reject(reason) => {
+                panic!("read_canister_snapshot_data call rejected: {}", reason)
+            }
+        })?
+    }
+
+    /// Creates a new canister snapshot from uploaded metadata.
+    pub fn upload_canister_snapshot_metadata(
+        &self,
+        args: &UploadCanisterSnapshotMetadataArgs,
+    ) -> Result<UploadCanisterSnapshotMetadataResponse, UserError> {
+        let state = self.state_manager.get_latest_state().take();
+        let sender = state
+            .canister_state(&args.get_canister_id())
+            .and_then(|s| s.controllers().iter().next().cloned())
+            .unwrap_or_else(PrincipalId::new_anonymous);
+        self.execute_ingress_as(
+            sender,
+            ic00::IC_00,
+            Method::UploadCanisterSnapshotMetadata,
+            args.encode(),
+        )
+        .map(|res| match res {
+            WasmResult::Reply(data) => UploadCanisterSnapshotMetadataResponse::decode(&data),
+            WasmResult::Reject(reason) => {
+                panic!(
+                    "upload_canister_snapshot_metadata call rejected: {}",
+                    reason
+                )
+            }
+        })?
+    }
+
+    /// Writes a piece of data into an uploaded canister snapshot.
+    pub fn upload_canister_snapshot_data(
+        &self,
+        args: &UploadCanisterSnapshotDataArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        let state = self.state_manager.get_latest_state().take();
+        let sender = state
+            .canister_state(&args.get_canister_id())
+            .and_then(|s| s.controllers().iter().next().cloned())
+            .unwrap_or_else(PrincipalId::new_anonymous);
+        self.execute_ingress_as(
+            sender,
+            ic00::IC_00,
+            Method::UploadCanisterSnapshotData,
+            args.encode(),
+        )
+        .map(|res| match res {
+            WasmResult::Reply(data) => Ok(data),
+            WasmResult::Reject(reason) => {
+                panic!("upload_canister_snapshot_data call rejected: {}", reason)
+            }
+        })?
+    }
+
     /// Upload a chunk to the wasm chunk store.
     pub fn upload_chunk(&self, args: UploadChunkArgs) -> Result<UploadChunkReply, UserError> {
         let state = self.state_manager.get_latest_state().take();
@@ -3489,6 +3757,14 @@ impl StateMachine {
         canister_state.system_state.canister_log.clone()
     }
 
+    /// Takes the Wasm profiles of all message executions since the last call
+    /// (only recorded if Wasm profiling is enabled in the hypervisor config)
+    /// and the number of profiles dropped because too many profiles were
+    /// recorded since the last call.
+    pub fn take_wasm_profiles(&self) -> (Vec<WasmProfileRecord>, u64) {
+        self.wasm_profiles.lock().unwrap().take()
+    }
+
     /// Sets the content of the stable memory for the specified canister.
     ///
     /// If the `data` is not aligned to the Wasm page boundary, this function will extend the stable
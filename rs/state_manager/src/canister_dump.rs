@@ -0,0 +1,321 @@
+//! Extracts the state of a single canister from a checkpoint into a directory
+//! and restores such a directory into the latest checkpoint under a state root.
+//!
+//! The directory has the layout of a canister directory within a checkpoint
+//! (so that it can be loaded like one), except that all memories (the Wasm
+//! heap, the stable memory and the Wasm chunk store) are stored as plain base
+//! files containing the raw memory contents. In addition, a human-readable
+//! summary of the canister state is written to [`CANISTER_SUMMARY_FILE`].
+use crate::{
+    checkpoint::load_canister_state,
+    split::{read_checkpoint, write_checkpoint},
+    CheckpointMetrics, StateManagerMetrics, NUMBER_OF_CHECKPOINT_THREADS,
+};
+
+use ic_base_types::CanisterId;
+use ic_config::state_manager::Config;
+use ic_logger::ReplicaLogger;
+use ic_metrics::MetricsRegistry;
+use ic_replicated_state::{
+    canister_state::execution_state::{Memory, WasmBinary},
+    page_map::{PageAllocatorFileDescriptor, TestPageAllocatorFileDescriptorImpl},
+    CanisterState, PageMap,
+};
+use ic_state_layout::{
+    CanisterLayout, CheckpointLayout, ReadOnly, StateLayout, CANISTER_FILE, QUEUES_FILE, WASM_FILE,
+};
+use ic_types::Height;
+use ic_wasm_types::CanisterModule;
+use scoped_threadpool::Pool;
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Name of the file containing the human-readable summary of the canister state.
+pub const CANISTER_SUMMARY_FILE: &str = "state.txt";
+
+/// Names of the base files of the Wasm heap, the stable memory and the Wasm
+/// chunk store (as they are named within a checkpoint).
+const WASM_MEMORY_FILE: &str = "vmemory_0.bin";
+const STABLE_MEMORY_FILE: &str = "stable_memory.bin";
+const WASM_CHUNK_STORE_FILE: &str = "wasm_chunk_store.bin";
+
+/// Extracts the state of `canister_id` from the checkpoint at `checkpoint_path`
+/// into `output_dir` (which must not exist or be empty).
+pub fn extract_canister(
+    checkpoint_path: PathBuf,
+    canister_id: CanisterId,
+    output_dir: PathBuf,
+    metrics_registry: &MetricsRegistry,
+    log: ReplicaLogger,
+) -> Result<(), String> {
+    let checkpoint = CheckpointLayout::<ReadOnly>::new_untracked(checkpoint_path, Height::new(0))
+        .map_err(|e| e.to_string())?;
+    if !checkpoint
+        .canister_ids()
+        .map_err(|e| e.to_string())?
+        .contains(&canister_id)
+    {
+        return Err(format!(
+            "Canister {} not found in checkpoint {}",
+            canister_id,
+            checkpoint.raw_path().display()
+        ));
+    }
+    let canister_layout = checkpoint
+        .canister(&canister_id)
+        .map_err(|e| e.to_string())?;
+
+    let metrics = CheckpointMetrics::new(metrics_registry, log);
+    let (canister_state, _) = load_canister_state(
+        &canister_layout,
+        &canister_id,
+        checkpoint.height(),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        &metrics,
+    )
+    .map_err(|e| format!("Failed to load canister {}: {}", canister_id, e))?;
+
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create directory {}: {}", output_dir.display(), e))?;
+    if std::fs::read_dir(&output_dir)
+        .map_err(|e| e.to_string())?
+        .next()
+        .is_some()
+    {
+        return Err(format!(
+            "Output directory {} is not empty",
+            output_dir.display()
+        ));
+    }
+
+    // The protobuf files and the Wasm module are copied verbatim
+    // (the queues file does not exist if the queues are empty).
+    for file in [CANISTER_FILE, QUEUES_FILE, WASM_FILE] {
+        let src = canister_layout.raw_path().join(file);
+        if src.exists() {
+            // Not using `std::fs::copy` since the files in a checkpoint are read-only.
+            let bytes = std::fs::read(&src)
+                .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+            write_file(&output_dir.join(file), &bytes)?;
+        }
+    }
+
+    if let Some(execution_state) = &canister_state.execution_state {
+        write_page_map(
+            &execution_state.wasm_memory.page_map,
+            &output_dir.join(WASM_MEMORY_FILE),
+        )?;
+        write_page_map(
+            &execution_state.stable_memory.page_map,
+            &output_dir.join(STABLE_MEMORY_FILE),
+        )?;
+    }
+    write_page_map(
+        canister_state.system_state.wasm_chunk_store.page_map(),
+        &output_dir.join(WASM_CHUNK_STORE_FILE),
+    )?;
+
+    write_file(
+        &output_dir.join(CANISTER_SUMMARY_FILE),
+        summarize(&canister_state).as_bytes(),
+    )
+}
+
+/// Loads the canister state extracted into `canister_dir` and writes it as
+/// `canister_id` into a new checkpoint based off of the latest checkpoint
+/// under `root`. An existing canister with the same ID is replaced.
+pub fn restore_canister(
+    root: PathBuf,
+    canister_id: CanisterId,
+    canister_dir: PathBuf,
+    metrics_registry: &MetricsRegistry,
+    log: ReplicaLogger,
+) -> Result<(), String> {
+    let config = Config::new(root);
+    let state_layout =
+        StateLayout::try_new(log.clone(), config.state_root.clone(), metrics_registry)
+            .map_err(|e| e.to_string())?;
+    let mut thread_pool = Pool::new(NUMBER_OF_CHECKPOINT_THREADS);
+    let fd_factory: Arc<dyn PageAllocatorFileDescriptor> =
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new());
+    let metrics = StateManagerMetrics::new(metrics_registry, log.clone());
+
+    let canister_layout =
+        CanisterLayout::<ReadOnly>::new_untracked(canister_dir).map_err(|e| e.to_string())?;
+    let (mut canister_state, _) = load_canister_state(
+        &canister_layout,
+        &canister_id,
+        Height::new(0),
+        Arc::clone(&fd_factory),
+        &metrics.checkpoint_metrics,
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to load canister from {}: {}",
+            canister_layout.raw_path().display(),
+            e
+        )
+    })?;
+    detach_from_files(&mut canister_state, &fd_factory);
+
+    let (cp, mut state) = read_checkpoint(
+        &state_layout,
+        &mut thread_pool,
+        Arc::clone(&fd_factory),
+        &metrics,
+    )?;
+    state.put_canister_state(canister_state);
+
+    write_checkpoint(
+        &mut state,
+        state_layout,
+        &cp,
+        &mut thread_pool,
+        fd_factory,
+        &config,
+        &metrics,
+        log,
+    )
+}
+
+/// Replaces the memories and the Wasm module of the given canister (which are
+/// backed by the files it was loaded from) with in-memory copies, so that they
+/// are written out in full (replacing any files of an existing canister with
+/// the same ID) when the next checkpoint is made.
+pub(crate) fn detach_from_files(
+    canister_state: &mut CanisterState,
+    fd_factory: &Arc<dyn PageAllocatorFileDescriptor>,
+) {
+    if let Some(execution_state) = canister_state.execution_state.as_mut() {
+        execution_state.wasm_memory = Memory::new(
+            copy_page_map(&execution_state.wasm_memory.page_map, fd_factory),
+            execution_state.wasm_memory.size,
+        );
+        execution_state.stable_memory = Memory::new(
+            copy_page_map(&execution_state.stable_memory.page_map, fd_factory),
+            execution_state.stable_memory.size,
+        );
+        execution_state.wasm_binary = WasmBinary::new(CanisterModule::new(
+            execution_state.wasm_binary.binary.as_slice().to_vec(),
+        ));
+    }
+    let wasm_chunk_store = canister_state.system_state.wasm_chunk_store.page_map_mut();
+    *wasm_chunk_store = copy_page_map(wasm_chunk_store, fd_factory);
+}
+
+/// Returns a new `PageMap` whose (unflushed) delta contains all pages of `page_map`.
+pub(crate) fn copy_page_map(
+    page_map: &PageMap,
+    fd_factory: &Arc<dyn PageAllocatorFileDescriptor>,
+) -> PageMap {
+    let mut copy = PageMap::new(Arc::clone(fd_factory));
+    let pages: Vec<_> = page_map.host_pages_iter().collect();
+    copy.update(&pages);
+    copy
+}
+
+/// Writes the raw contents of `page_map` into the file at `path`.
+fn write_page_map(page_map: &PageMap, path: &Path) -> Result<(), String> {
+    let map_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+    let mut writer = BufWriter::new(File::create(path).map_err(map_err)?);
+    for (_, page) in page_map.host_pages_iter() {
+        writer.write_all(page).map_err(map_err)?;
+    }
+    writer.flush().map_err(map_err)
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    std::fs::write(path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Returns a human-readable summary of the given canister state.
+fn summarize(canister_state: &CanisterState) -> String {
+    let system_state = &canister_state.system_state;
+    let mut summary = String::new();
+    // Writing into a `String` cannot fail.
+    let mut line = |key: &str, value: String| writeln!(summary, "{}: {}", key, value).unwrap();
+    line("canister_id", system_state.canister_id.to_string());
+    line("status", system_state.status_string().to_string());
+    line(
+        "controllers",
+        format!(
+            "[{}]",
+            system_state
+                .controllers
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    );
+    line("cycles_balance", system_state.balance().to_string());
+    line(
+        "reserved_cycles_balance",
+        system_state.reserved_balance().to_string(),
+    );
+    line(
+        "freeze_threshold_seconds",
+        system_state.freeze_threshold.to_string(),
+    );
+    line(
+        "memory_allocation",
+        format!("{:?}", system_state.memory_allocation),
+    );
+    line(
+        "compute_allocation",
+        canister_state
+            .scheduler_state
+            .compute_allocation
+            .to_string(),
+    );
+    line(
+        "canister_version",
+        system_state.canister_version.to_string(),
+    );
+    line(
+        "certified_data",
+        format!("0x{}", hex::encode(&system_state.certified_data)),
+    );
+    line("global_timer", format!("{:?}", system_state.global_timer));
+    match &canister_state.execution_state {
+        Some(execution_state) => {
+            line(
+                "module_hash",
+                format!(
+                    "0x{}",
+                    hex::encode(execution_state.wasm_binary.binary.module_hash())
+                ),
+            );
+            line("is_wasm64", execution_state.is_wasm64.to_string());
+            line(
+                "wasm_memory_size_wasm_pages",
+                execution_state.wasm_memory.size.get().to_string(),
+            );
+            line(
+                "stable_memory_size_wasm_pages",
+                execution_state.stable_memory.size.get().to_string(),
+            );
+            line(
+                "exported_globals",
+                format!("{:?}", execution_state.exported_globals),
+            );
+        }
+        None => line("module_hash", "none".to_string()),
+    }
+    writeln!(
+        summary,
+        "call_contexts: {:#?}",
+        system_state.call_context_manager()
+    )
+    .unwrap();
+    writeln!(summary, "queues: {:#?}", system_state.queues()).unwrap();
+    summary
+}
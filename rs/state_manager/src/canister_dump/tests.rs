@@ -0,0 +1,179 @@
+use super::*;
+use crate::{
+    checkpoint::{
+        load_checkpoint, make_checkpoint, validate_checkpoint_and_remove_unverified_marker,
+    },
+    flush_canister_snapshots_and_page_maps,
+    tip::spawn_tip_thread,
+};
+use ic_base_types::NumSeconds;
+use ic_config::state_manager::lsmt_config_default;
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{PageIndex, ReplicatedState};
+use ic_state_layout::CHECKPOINTS_DIR;
+use ic_sys::PAGE_SIZE;
+use ic_test_utilities_logger::with_test_replica_logger;
+use ic_test_utilities_state::new_canister_state_with_execution;
+use ic_test_utilities_tmpdir::tmpdir;
+use ic_test_utilities_types::ids::{subnet_test_id, user_test_id};
+use ic_types::{malicious_flags::MaliciousFlags, Cycles};
+
+const CANISTER_1: CanisterId = CanisterId::from_u64(1);
+const CANISTER_2: CanisterId = CanisterId::from_u64(2);
+const HEIGHT: Height = Height::new(42);
+
+/// Writes a checkpoint at `HEIGHT` under `root` with canisters 1 and 2, where
+/// canister 1 has some Wasm and stable memory contents.
+fn write_initial_checkpoint(root: &Path, log: ReplicaLogger) {
+    let metrics_registry = MetricsRegistry::new();
+    let layout = StateLayout::try_new(log.clone(), root.to_path_buf(), &metrics_registry).unwrap();
+    let tip_handler = layout.capture_tip_handler();
+    let state_manager_metrics = StateManagerMetrics::new(&metrics_registry, log.clone());
+    let (_tip_thread, tip_channel) = spawn_tip_thread(
+        log,
+        tip_handler,
+        layout.clone(),
+        lsmt_config_default(),
+        state_manager_metrics.clone(),
+        MaliciousFlags::default(),
+    );
+
+    let mut state = ReplicatedState::new(subnet_test_id(1), SubnetType::Application);
+    let mut canister_1 = new_canister_state_with_execution(
+        CANISTER_1,
+        user_test_id(1).get(),
+        Cycles::new(1 << 36),
+        NumSeconds::from(100_000),
+    );
+    let execution_state = canister_1.execution_state.as_mut().unwrap();
+    execution_state
+        .wasm_memory
+        .page_map
+        .update(&[(PageIndex::new(1), &[1; PAGE_SIZE])]);
+    execution_state
+        .stable_memory
+        .page_map
+        .update(&[(PageIndex::new(0), &[2; PAGE_SIZE])]);
+    canister_1.system_state.certified_data = vec![3; 32];
+    state.put_canister_state(canister_1);
+    state.put_canister_state(new_canister_state_with_execution(
+        CANISTER_2,
+        user_test_id(2).get(),
+        Cycles::new(1 << 20),
+        NumSeconds::from(200_000),
+    ));
+
+    flush_canister_snapshots_and_page_maps(
+        &mut state,
+        HEIGHT,
+        &tip_channel,
+        &state_manager_metrics.checkpoint_metrics,
+    );
+    let mut thread_pool = Pool::new(NUMBER_OF_CHECKPOINT_THREADS);
+    let (cp_layout, _state, _has_downgrade) = make_checkpoint(
+        &state,
+        HEIGHT,
+        &tip_channel,
+        &state_manager_metrics.checkpoint_metrics,
+        &mut thread_pool,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        lsmt_config_default().lsmt_status,
+    )
+    .unwrap();
+    validate_checkpoint_and_remove_unverified_marker(&cp_layout, Some(&mut thread_pool)).unwrap();
+}
+
+fn load_latest_checkpoint(root: &Path, log: ReplicaLogger) -> ReplicatedState {
+    let metrics_registry = MetricsRegistry::new();
+    let layout = StateLayout::try_new(log.clone(), root.to_path_buf(), &metrics_registry).unwrap();
+    let height = *layout.checkpoint_heights().unwrap().last().unwrap();
+    load_checkpoint(
+        &layout.checkpoint_verified(height).unwrap(),
+        SubnetType::Application,
+        &CheckpointMetrics::new(&metrics_registry, log),
+        None,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap()
+}
+
+#[test]
+fn extract_and_restore_canister() {
+    with_test_replica_logger(|log| {
+        let tmp = tmpdir("checkpoint");
+        let root = tmp.path().to_path_buf();
+        write_initial_checkpoint(&root, log.clone());
+
+        let dump = tmpdir("canister_dump");
+        let dump_dir = dump.path().join("canister_1");
+        extract_canister(
+            root.join(CHECKPOINTS_DIR)
+                .join(StateLayout::checkpoint_name(HEIGHT)),
+            CANISTER_1,
+            dump_dir.clone(),
+            &MetricsRegistry::new(),
+            log.clone(),
+        )
+        .unwrap();
+
+        // The memories are dumped as raw bytes.
+        let wasm_memory = std::fs::read(dump_dir.join(WASM_MEMORY_FILE)).unwrap();
+        assert_eq!(wasm_memory.len(), 2 * PAGE_SIZE);
+        assert!(wasm_memory[..PAGE_SIZE].iter().all(|b| *b == 0));
+        assert!(wasm_memory[PAGE_SIZE..].iter().all(|b| *b == 1));
+        let stable_memory = std::fs::read(dump_dir.join(STABLE_MEMORY_FILE)).unwrap();
+        assert_eq!(stable_memory, vec![2; PAGE_SIZE]);
+        let summary = std::fs::read_to_string(dump_dir.join(CANISTER_SUMMARY_FILE)).unwrap();
+        assert!(summary.contains(&format!("canister_id: {}", CANISTER_1)));
+        assert!(summary.contains(&format!("certified_data: 0x{}", "03".repeat(32))));
+
+        // Extracting into a non-empty directory fails.
+        assert!(extract_canister(
+            root.join(CHECKPOINTS_DIR)
+                .join(StateLayout::checkpoint_name(HEIGHT)),
+            CANISTER_1,
+            dump_dir.clone(),
+            &MetricsRegistry::new(),
+            log.clone(),
+        )
+        .is_err());
+
+        // Restore canister 1 as canister 2 (replacing the existing canister 2).
+        restore_canister(
+            root.clone(),
+            CANISTER_2,
+            dump_dir,
+            &MetricsRegistry::new(),
+            log.clone(),
+        )
+        .unwrap();
+
+        let state = load_latest_checkpoint(&root, log);
+        let canister_1 = state.canister_state(&CANISTER_1).unwrap();
+        let canister_2 = state.canister_state(&CANISTER_2).unwrap();
+        assert_eq!(
+            canister_1.system_state.controllers,
+            canister_2.system_state.controllers
+        );
+        assert_eq!(
+            canister_1.system_state.balance(),
+            canister_2.system_state.balance()
+        );
+        assert_eq!(canister_2.system_state.certified_data, vec![3; 32]);
+        let execution_state = canister_2.execution_state.as_ref().unwrap();
+        assert_eq!(
+            execution_state
+                .wasm_memory
+                .page_map
+                .get_page(PageIndex::new(1)),
+            &[1; PAGE_SIZE]
+        );
+        assert_eq!(
+            execution_state
+                .stable_memory
+                .page_map
+                .get_page(PageIndex::new(0)),
+            &[2; PAGE_SIZE]
+        );
+    })
+}
@@ -0,0 +1,155 @@
+//! Migrates canisters (together with their snapshots) between the states of
+//! two subnets, as part of an operator-driven canister migration.
+//!
+//! This module only covers the state transfer. The routing table updates are
+//! made through the existing NNS proposals and draining the XNet streams is
+//! left to Message Routing, so the operator drives every step below.
+//!
+//! TODO: A management canister call driving the migration end to end (updating
+//! the routing table and waiting for the streams to drain before moving the
+//! canister states) is out of scope here and is tracked as a separate request.
+//!
+//! The full migration flow is:
+//!  1. Stop the canisters and mark them as being migrated from the source to
+//!     the destination subnet in the registry canister migrations
+//!     (`ic-admin propose-to-prepare-canister-migration`).
+//!  2. Reroute the canisters' ID range(s) to the destination subnet in the
+//!     registry routing table (`ic-admin propose-to-reroute-canister-ranges`).
+//!     From this point on, Message Routing on both subnets reroutes (or
+//!     rejects) messages still in flight to the canisters based on the
+//!     canister migrations entry, draining the XNet streams.
+//!  3. Halt both subnets at a checkpoint height and move the canister states
+//!     from the source subnet's latest checkpoint into the destination
+//!     subnet's latest checkpoint (this module, `state_tool migrate_canisters`).
+//!     Both checkpoints must already reflect steps 1 and 2, which is
+//!     validated for every canister.
+//!  4. Unhalt the subnets and remove the canister migrations entry once the
+//!     streams no longer contain messages for the canisters
+//!     (`ic-admin propose-to-complete-canister-migration`).
+use crate::{
+    canister_dump::{copy_page_map, detach_from_files},
+    split::{read_checkpoint, write_checkpoint},
+    StateManagerMetrics, NUMBER_OF_CHECKPOINT_THREADS,
+};
+
+use ic_base_types::CanisterId;
+use ic_config::state_manager::Config;
+use ic_logger::ReplicaLogger;
+use ic_metrics::MetricsRegistry;
+use ic_replicated_state::{
+    canister_snapshots::CanisterSnapshot,
+    page_map::{PageAllocatorFileDescriptor, TestPageAllocatorFileDescriptorImpl},
+    MigratingCanister,
+};
+use ic_state_layout::StateLayout;
+use ic_wasm_types::CanisterModule;
+use scoped_threadpool::Pool;
+use std::{path::PathBuf, sync::Arc};
+
+#[cfg(test)]
+mod tests;
+
+/// Loads the latest checkpoints under `source_root` and `destination_root`;
+/// moves the given canisters and their snapshots from the former state into
+/// the latter; and writes back both states as new checkpoints, under the same
+/// roots.
+///
+/// The canisters must be stopped and assigned to the destination subnet by the
+/// routing tables and canister migrations of both states. The destination
+/// checkpoint is written first, so that the canisters are never lost.
+pub fn migrate_canisters(
+    source_root: PathBuf,
+    destination_root: PathBuf,
+    canister_ids: Vec<CanisterId>,
+    metrics_registry: &MetricsRegistry,
+    log: ReplicaLogger,
+) -> Result<(), String> {
+    let source_config = Config::new(source_root);
+    let destination_config = Config::new(destination_root);
+    let source_layout = StateLayout::try_new(
+        log.clone(),
+        source_config.state_root.clone(),
+        metrics_registry,
+    )
+    .map_err(|e| e.to_string())?;
+    let destination_layout = StateLayout::try_new(
+        log.clone(),
+        destination_config.state_root.clone(),
+        metrics_registry,
+    )
+    .map_err(|e| e.to_string())?;
+    let mut thread_pool = Pool::new(NUMBER_OF_CHECKPOINT_THREADS);
+    let fd_factory: Arc<dyn PageAllocatorFileDescriptor> =
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new());
+    let metrics = StateManagerMetrics::new(metrics_registry, log.clone());
+
+    let (source_cp, mut source_state) = read_checkpoint(
+        &source_layout,
+        &mut thread_pool,
+        Arc::clone(&fd_factory),
+        &metrics,
+    )?;
+    let (destination_cp, mut destination_state) = read_checkpoint(
+        &destination_layout,
+        &mut thread_pool,
+        Arc::clone(&fd_factory),
+        &metrics,
+    )?;
+
+    for canister_id in canister_ids {
+        let mut canister = source_state.take_canister_for_migration(&canister_id)?;
+        detach_canister_from_files(&mut canister, &fd_factory);
+        destination_state.put_migrated_canister(canister)?;
+    }
+
+    write_checkpoint(
+        &mut destination_state,
+        destination_layout,
+        &destination_cp,
+        &mut thread_pool,
+        Arc::clone(&fd_factory),
+        &destination_config,
+        &metrics,
+        log.clone(),
+    )?;
+    write_checkpoint(
+        &mut source_state,
+        source_layout,
+        &source_cp,
+        &mut thread_pool,
+        fd_factory,
+        &source_config,
+        &metrics,
+        log,
+    )
+}
+
+/// Replaces all memories and Wasm modules of the migrating canister and its
+/// snapshots (which are backed by files of the source checkpoint) with
+/// in-memory copies, so that they are written out in full under the
+/// destination state root.
+fn detach_canister_from_files(
+    canister: &mut MigratingCanister,
+    fd_factory: &Arc<dyn PageAllocatorFileDescriptor>,
+) {
+    detach_from_files(&mut canister.canister_state, fd_factory);
+    for (_, snapshot) in canister.snapshots.iter_mut() {
+        detach_snapshot_from_files(Arc::make_mut(snapshot), fd_factory);
+    }
+}
+
+fn detach_snapshot_from_files(
+    snapshot: &mut CanisterSnapshot,
+    fd_factory: &Arc<dyn PageAllocatorFileDescriptor>,
+) {
+    let chunk_store = snapshot.chunk_store_mut().page_map_mut();
+    *chunk_store = copy_page_map(chunk_store, fd_factory);
+
+    let execution_snapshot = snapshot.execution_snapshot_mut();
+    execution_snapshot.wasm_memory.page_map =
+        copy_page_map(&execution_snapshot.wasm_memory.page_map, fd_factory);
+    execution_snapshot.stable_memory.page_map =
+        copy_page_map(&execution_snapshot.stable_memory.page_map, fd_factory);
+    execution_snapshot.wasm_binary =
+        CanisterModule::new(execution_snapshot.wasm_binary.as_slice().to_vec());
+}
@@ -0,0 +1,212 @@
+use super::*;
+use crate::{
+    checkpoint::{
+        load_checkpoint, make_checkpoint, validate_checkpoint_and_remove_unverified_marker,
+    },
+    flush_canister_snapshots_and_page_maps,
+    tip::spawn_tip_thread,
+    CheckpointMetrics,
+};
+use ic_base_types::{NumSeconds, SnapshotId, SubnetId};
+use ic_config::state_manager::lsmt_config_default;
+use ic_registry_routing_table::{CanisterIdRange, CanisterMigrations, RoutingTable};
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{
+    testing::SystemStateTesting, CanisterStatus, PageIndex, ReplicatedState,
+};
+use ic_sys::PAGE_SIZE;
+use ic_test_utilities_logger::with_test_replica_logger;
+use ic_test_utilities_state::new_canister_state_with_execution;
+use ic_test_utilities_tmpdir::tmpdir;
+use ic_test_utilities_types::ids::{user_test_id, SUBNET_1, SUBNET_2};
+use ic_types::{malicious_flags::MaliciousFlags, Cycles, Height};
+use maplit::btreemap;
+use std::path::Path;
+
+const SOURCE_SUBNET: SubnetId = SUBNET_1;
+const DESTINATION_SUBNET: SubnetId = SUBNET_2;
+
+const CANISTER_1: CanisterId = CanisterId::from_u64(1);
+const CANISTER_2: CanisterId = CanisterId::from_u64(2);
+const HEIGHT: Height = Height::new(42);
+
+/// Routing table assigning `CANISTER_1` to `SOURCE_SUBNET` and `CANISTER_2` to
+/// `DESTINATION_SUBNET` (i.e. after `CANISTER_2` was rerouted).
+fn routing_table() -> Arc<RoutingTable> {
+    Arc::new(
+        RoutingTable::try_from(btreemap! {
+            CanisterIdRange {start: CANISTER_1, end: CANISTER_1} => SOURCE_SUBNET,
+            CanisterIdRange {start: CANISTER_2, end: CANISTER_2} => DESTINATION_SUBNET,
+        })
+        .unwrap(),
+    )
+}
+
+/// Canister migrations entry recording the migration of `CANISTER_2` from
+/// `SOURCE_SUBNET` to `DESTINATION_SUBNET`.
+fn canister_migrations() -> Arc<CanisterMigrations> {
+    Arc::new(
+        CanisterMigrations::try_from(btreemap! {
+            CanisterIdRange {start: CANISTER_2, end: CANISTER_2} => vec![SOURCE_SUBNET, DESTINATION_SUBNET],
+        })
+        .unwrap(),
+    )
+}
+
+/// Writes `state` as a checkpoint at `HEIGHT` under `root`.
+fn write_initial_checkpoint(root: &Path, mut state: ReplicatedState, log: ReplicaLogger) {
+    let metrics_registry = MetricsRegistry::new();
+    let layout = StateLayout::try_new(log.clone(), root.to_path_buf(), &metrics_registry).unwrap();
+    let tip_handler = layout.capture_tip_handler();
+    let state_manager_metrics = StateManagerMetrics::new(&metrics_registry, log.clone());
+    let (_tip_thread, tip_channel) = spawn_tip_thread(
+        log,
+        tip_handler,
+        layout.clone(),
+        lsmt_config_default(),
+        state_manager_metrics.clone(),
+        MaliciousFlags::default(),
+    );
+
+    flush_canister_snapshots_and_page_maps(
+        &mut state,
+        HEIGHT,
+        &tip_channel,
+        &state_manager_metrics.checkpoint_metrics,
+    );
+    let mut thread_pool = Pool::new(NUMBER_OF_CHECKPOINT_THREADS);
+    let (cp_layout, _state, _has_downgrade) = make_checkpoint(
+        &state,
+        HEIGHT,
+        &tip_channel,
+        &state_manager_metrics.checkpoint_metrics,
+        &mut thread_pool,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        lsmt_config_default().lsmt_status,
+    )
+    .unwrap();
+    validate_checkpoint_and_remove_unverified_marker(&cp_layout, Some(&mut thread_pool)).unwrap();
+}
+
+fn load_latest_checkpoint(root: &Path, log: ReplicaLogger) -> ReplicatedState {
+    let metrics_registry = MetricsRegistry::new();
+    let layout = StateLayout::try_new(log.clone(), root.to_path_buf(), &metrics_registry).unwrap();
+    let heights = layout.checkpoint_heights().unwrap();
+    assert_eq!(Some(&HEIGHT.increment()), heights.last());
+    load_checkpoint(
+        &layout.checkpoint_verified(HEIGHT.increment()).unwrap(),
+        SubnetType::Application,
+        &CheckpointMetrics::new(&metrics_registry, log),
+        None,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .unwrap()
+}
+
+#[test]
+fn migrate_canister_with_snapshot() {
+    with_test_replica_logger(|log| {
+        let source = tmpdir("source");
+        let destination = tmpdir("destination");
+
+        // Source subnet hosting `CANISTER_1` and (stopped) `CANISTER_2`, which has
+        // some heap contents and a snapshot.
+        let mut source_state = ReplicatedState::new(SOURCE_SUBNET, SubnetType::Application);
+        source_state.metadata.network_topology.routing_table = routing_table();
+        source_state.metadata.network_topology.canister_migrations = canister_migrations();
+        source_state.put_canister_state(new_canister_state_with_execution(
+            CANISTER_1,
+            user_test_id(1).get(),
+            Cycles::new(1 << 36),
+            NumSeconds::from(100_000),
+        ));
+        let mut canister_2 = new_canister_state_with_execution(
+            CANISTER_2,
+            user_test_id(2).get(),
+            Cycles::new(1 << 20),
+            NumSeconds::from(200_000),
+        );
+        canister_2
+            .execution_state
+            .as_mut()
+            .unwrap()
+            .wasm_memory
+            .page_map
+            .update(&[(PageIndex::new(1), &[7; PAGE_SIZE])]);
+        canister_2.system_state.set_status(CanisterStatus::Stopped);
+        let snapshot_id = SnapshotId::from((CANISTER_2, 0));
+        let snapshot = CanisterSnapshot::from_canister(&canister_2, source_state.time()).unwrap();
+        source_state.put_canister_state(canister_2);
+        source_state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(snapshot));
+        write_initial_checkpoint(source.path(), source_state, log.clone());
+
+        // Empty destination subnet.
+        let mut destination_state =
+            ReplicatedState::new(DESTINATION_SUBNET, SubnetType::Application);
+        destination_state.metadata.network_topology.routing_table = routing_table();
+        destination_state
+            .metadata
+            .network_topology
+            .canister_migrations = canister_migrations();
+        write_initial_checkpoint(destination.path(), destination_state, log.clone());
+
+        // `CANISTER_1` is not assigned to the destination subnet.
+        assert!(migrate_canisters(
+            source.path().to_path_buf(),
+            destination.path().to_path_buf(),
+            vec![CANISTER_1],
+            &MetricsRegistry::new(),
+            log.clone(),
+        )
+        .is_err());
+
+        migrate_canisters(
+            source.path().to_path_buf(),
+            destination.path().to_path_buf(),
+            vec![CANISTER_2],
+            &MetricsRegistry::new(),
+            log.clone(),
+        )
+        .unwrap();
+
+        let source_state = load_latest_checkpoint(source.path(), log.clone());
+        assert_eq!(
+            vec![CANISTER_1],
+            source_state
+                .canister_states
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, source_state.canister_snapshots.count());
+
+        let destination_state = load_latest_checkpoint(destination.path(), log);
+        let canister_2 = destination_state.canister_state(&CANISTER_2).unwrap();
+        assert_eq!(Cycles::new(1 << 20), canister_2.system_state.balance());
+        assert_eq!(
+            &[7; PAGE_SIZE],
+            canister_2
+                .execution_state
+                .as_ref()
+                .unwrap()
+                .wasm_memory
+                .page_map
+                .get_page(PageIndex::new(1))
+        );
+        let snapshot = destination_state
+            .canister_snapshots
+            .get(snapshot_id)
+            .unwrap();
+        assert_eq!(CANISTER_2, snapshot.canister_id());
+        assert_eq!(
+            &[7; PAGE_SIZE],
+            snapshot
+                .execution_snapshot()
+                .wasm_memory
+                .page_map
+                .get_page(PageIndex::new(1))
+        );
+    })
+}
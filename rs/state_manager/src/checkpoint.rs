@@ -498,6 +498,8 @@ pub fn load_canister_state(
         canister_state_bits.log_visibility,
         canister_state_bits.canister_log,
         canister_state_bits.wasm_memory_limit,
+        canister_state_bits.environment_variables,
+        canister_state_bits.wasm_trace_length,
         canister_state_bits.next_snapshot_id,
         canister_state_bits.snapshots_memory_usage,
         metrics,
@@ -513,6 +515,7 @@ pub fn load_canister_state(
             accumulated_priority: canister_state_bits.accumulated_priority,
             priority_credit: canister_state_bits.priority_credit,
             long_execution_mode: canister_state_bits.long_execution_mode,
+            priority_class: canister_state_bits.priority_class,
             heap_delta_debit: canister_state_bits.heap_delta_debit,
             install_code_debit: canister_state_bits.install_code_debit,
             time_of_last_allocation_charge: Time::from_nanos_since_unix_epoch(
@@ -1,5 +1,7 @@
 // Needs to be `pub` so that the benchmarking code in `state_benches`
 // can access it.
+pub mod canister_dump;
+pub mod canister_migration;
 pub mod checkpoint;
 pub mod labeled_tree_visitor;
 pub mod manifest;
@@ -2872,8 +2874,10 @@ fn flush_canister_snapshots_and_page_maps(
 
     for op in &snapshot_operations {
         // Only CanisterSnapshots that are new since the last flush will have PageMaps that need to be flushed. They will
-        // have a corresponding Backup in the snapshot operations list.
-        if let SnapshotOperation::Backup(_canister_id, snapshot_id) = op {
+        // have a corresponding Backup or Upload in the snapshot operations list.
+        if let SnapshotOperation::Backup(_canister_id, snapshot_id)
+        | SnapshotOperation::Upload(_canister_id, snapshot_id) = op
+        {
             // If we can't find the CanisterSnapshot they must have been already deleted again. Nothing to flush in this case.
             if let Some(canister_snapshot) = tip_state.canister_snapshots.get_mut(*snapshot_id) {
                 let new_snapshot = Arc::make_mut(canister_snapshot);
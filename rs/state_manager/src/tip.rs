@@ -490,6 +490,11 @@ fn flush_snapshot_changes<T>(
             SnapshotOperation::Restore(canister_id, snapshot_id) => {
                 restore(log, layout, canister_id, snapshot_id)?;
             }
+            SnapshotOperation::Upload(_canister_id, snapshot_id) => {
+                // An uploaded snapshot starts out empty; creating the layout
+                // creates its directory, all data is flushed on top of it.
+                layout.snapshot(&snapshot_id)?;
+            }
         }
     }
 
@@ -1012,6 +1017,7 @@ fn serialize_canister_to_tip(
             compute_allocation: canister_state.scheduler_state.compute_allocation,
             priority_credit: canister_state.scheduler_state.priority_credit,
             long_execution_mode: canister_state.scheduler_state.long_execution_mode,
+            priority_class: canister_state.scheduler_state.priority_class,
             accumulated_priority: canister_state.scheduler_state.accumulated_priority,
             memory_allocation: canister_state.system_state.memory_allocation,
             wasm_memory_threshold: canister_state.system_state.wasm_memory_threshold,
@@ -1069,6 +1075,8 @@ fn serialize_canister_to_tip(
             log_visibility: canister_state.system_state.log_visibility.clone(),
             canister_log: canister_state.system_state.canister_log.clone(),
             wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
+            environment_variables: canister_state.system_state.environment_variables.clone(),
+            wasm_trace_length: canister_state.system_state.wasm_trace_length,
             next_snapshot_id: canister_state.system_state.next_snapshot_id,
             snapshots_memory_usage: canister_state.system_state.snapshots_memory_usage,
             on_low_wasm_memory_hook_status: canister_state
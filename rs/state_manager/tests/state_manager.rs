@@ -778,6 +778,7 @@ fn query_stats_are_persisted() {
             num_instructions: 100000,
             ingress_payload_size: 100001,
             egress_payload_size: 100002,
+            num_cache_hits: 100003,
         };
 
         let mut inner = BTreeMap::new();
@@ -7379,6 +7380,7 @@ fn query_stats_are_collected() {
                 num_instructions: INITIAL_VALUES,
                 ingress_payload_size: INITIAL_VALUES,
                 egress_payload_size: INITIAL_VALUES,
+                num_cache_hits: INITIAL_VALUES,
             },
         );
     }
@@ -7394,6 +7396,7 @@ fn query_stats_are_collected() {
         assert!(canister_state.num_instructions == INITIAL_VALUES);
         assert!(canister_state.ingress_payload_size == INITIAL_VALUES);
         assert!(canister_state.egress_payload_size == INITIAL_VALUES);
+        assert!(canister_state.num_cache_hits == INITIAL_VALUES);
     }
 
     // Run for an entire epoch and then deliver `NUM_NODES` more batches to ensure query stats get aggregated to the canister state.
@@ -7414,6 +7417,7 @@ fn query_stats_are_collected() {
                     num_instructions: 2,
                     ingress_payload_size: 3,
                     egress_payload_size: 4,
+                    num_cache_hits: 5,
                 },
             });
 
@@ -7427,6 +7431,7 @@ fn query_stats_are_collected() {
                     num_instructions: 2,
                     ingress_payload_size: 3,
                     egress_payload_size: 4,
+                    num_cache_hits: 5,
                 },
             });
 
@@ -7439,6 +7444,7 @@ fn query_stats_are_collected() {
                         num_instructions: 2,
                         ingress_payload_size: 3,
                         egress_payload_size: 4,
+                        num_cache_hits: 5,
                     },
                 });
             } else {
@@ -7450,6 +7456,7 @@ fn query_stats_are_collected() {
                         num_instructions: 2,
                         ingress_payload_size: 3,
                         egress_payload_size: 4,
+                        num_cache_hits: 5,
                     },
                 });
             }
@@ -7480,6 +7487,7 @@ fn query_stats_are_collected() {
         assert!(canister_state.num_instructions == 2 * NUM_NODES as u128 + INITIAL_VALUES);
         assert!(canister_state.ingress_payload_size == 3 * NUM_NODES as u128 + INITIAL_VALUES);
         assert!(canister_state.egress_payload_size == 4 * NUM_NODES as u128 + INITIAL_VALUES);
+        assert!(canister_state.num_cache_hits == 5 * NUM_NODES as u128 + INITIAL_VALUES);
     }
 
     // The imbalanced canister should not have been charged, as only malicious nodes
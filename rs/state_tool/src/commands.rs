@@ -1,4 +1,6 @@
 //! Command implementations.
+pub mod canister_dump;
+pub mod canister_migration;
 pub mod cdiff;
 pub mod chash;
 pub mod convert_ids;
@@ -6,6 +8,7 @@ pub mod decode;
 pub mod import_state;
 pub mod list;
 pub mod manifest;
+pub mod sdiff;
 pub mod split;
 pub mod split_manifest;
 mod utils;
@@ -0,0 +1,34 @@
+//! Extracts the state of a single canister from a checkpoint and restores it
+//! into a replicated state.
+
+use ic_logger::replica_logger::no_op_logger;
+use ic_metrics::MetricsRegistry;
+use ic_state_manager::canister_dump::{extract_canister, restore_canister};
+use ic_types::{CanisterId, PrincipalId};
+use std::path::PathBuf;
+
+/// Dumps the system state, Wasm module, exported globals, stable memory and
+/// Wasm heap of `canister_id` in the checkpoint at `path` into `output`.
+pub fn do_extract(path: PathBuf, canister_id: PrincipalId, output: PathBuf) -> Result<(), String> {
+    extract_canister(
+        path,
+        CanisterId::unchecked_from_principal(canister_id),
+        output,
+        &MetricsRegistry::new(),
+        no_op_logger(),
+    )
+}
+
+/// Loads the latest checkpoint under the given root; adds the canister state
+/// previously extracted into `input` as `canister_id` (replacing any existing
+/// canister with that ID); and writes back the state as a new checkpoint,
+/// under the same root.
+pub fn do_restore(root: PathBuf, canister_id: PrincipalId, input: PathBuf) -> Result<(), String> {
+    restore_canister(
+        root,
+        CanisterId::unchecked_from_principal(canister_id),
+        input,
+        &MetricsRegistry::new(),
+        no_op_logger(),
+    )
+}
@@ -0,0 +1,29 @@
+//! Moves canisters between the states of two subnets, as part of a canister
+//! migration.
+
+use ic_logger::replica_logger::no_op_logger;
+use ic_metrics::MetricsRegistry;
+use ic_state_manager::canister_migration::migrate_canisters;
+use ic_types::{CanisterId, PrincipalId};
+use std::path::PathBuf;
+
+/// Loads the latest checkpoints under `source_root` and `destination_root`;
+/// moves the given (stopped) canisters and their snapshots from the former into
+/// the latter; and writes back both states as new checkpoints, under the same
+/// roots.
+pub fn do_migrate(
+    source_root: PathBuf,
+    destination_root: PathBuf,
+    canister_ids: Vec<PrincipalId>,
+) -> Result<(), String> {
+    migrate_canisters(
+        source_root,
+        destination_root,
+        canister_ids
+            .into_iter()
+            .map(CanisterId::unchecked_from_principal)
+            .collect(),
+        &MetricsRegistry::new(),
+        no_op_logger(),
+    )
+}
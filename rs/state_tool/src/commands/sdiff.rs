@@ -0,0 +1,475 @@
+//! Computes a semantic diff between checkpoints: loads both checkpoints as
+//! replicated states and reports changes to canisters, streams and subnet
+//! metadata (as opposed to `cdiff`, which diffs canonical trees).
+
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{
+    page_map::TestPageAllocatorFileDescriptorImpl, CanisterState, ReplicatedState, Stream,
+};
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::{checkpoint::load_checkpoint, CheckpointMetrics};
+use ic_types::{CountBytes, Height};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+};
+
+/// A change of a single field between the two states.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum FieldChange {
+    /// A change of a numeric quantity (cycles, bytes, message counts, etc.).
+    Quantity {
+        before: u128,
+        after: u128,
+        delta: i128,
+    },
+    /// A change of any other value, in its textual representation.
+    Value { before: String, after: String },
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldChange::Quantity {
+                before,
+                after,
+                delta,
+            } => write!(f, "{} -> {} ({:+})", before, after, delta),
+            FieldChange::Value { before, after } => write!(f, "{} -> {}", before, after),
+        }
+    }
+}
+
+/// The changed fields of a state component, by field name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct FieldChanges(pub BTreeMap<&'static str, FieldChange>);
+
+impl FieldChanges {
+    /// Records a change of the quantity `name`, if `before` and `after` differ.
+    fn quantity(&mut self, name: &'static str, before: impl Into<u128>, after: impl Into<u128>) {
+        let (before, after) = (before.into(), after.into());
+        if before != after {
+            let delta = (after as i128).saturating_sub(before as i128);
+            self.0.insert(
+                name,
+                FieldChange::Quantity {
+                    before,
+                    after,
+                    delta,
+                },
+            );
+        }
+    }
+
+    /// Records a change of the value `name`, if `before` and `after` differ.
+    fn value<T: PartialEq + fmt::Display>(&mut self, name: &'static str, before: T, after: T) {
+        if before != after {
+            self.0.insert(
+                name,
+                FieldChange::Value {
+                    before: before.to_string(),
+                    after: after.to_string(),
+                },
+            );
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for FieldChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, change) in self.0.iter() {
+            writeln!(f, "  {}: {}", name, change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Semantic differences between two replicated states. Canisters and streams
+/// are keyed by canister ID and destination subnet ID, respectively.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StateDiff {
+    pub canisters_added: Vec<String>,
+    pub canisters_removed: Vec<String>,
+    pub canisters_changed: BTreeMap<String, FieldChanges>,
+    pub streams_added: Vec<String>,
+    pub streams_removed: Vec<String>,
+    pub streams_changed: BTreeMap<String, FieldChanges>,
+    pub metadata: FieldChanges,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for canister_id in self.canisters_added.iter() {
+            writeln!(f, "+ canister {}", canister_id)?;
+        }
+        for canister_id in self.canisters_removed.iter() {
+            writeln!(f, "- canister {}", canister_id)?;
+        }
+        for (canister_id, changes) in self.canisters_changed.iter() {
+            writeln!(f, "~ canister {}", canister_id)?;
+            write!(f, "{}", changes)?;
+        }
+        for subnet_id in self.streams_added.iter() {
+            writeln!(f, "+ stream to {}", subnet_id)?;
+        }
+        for subnet_id in self.streams_removed.iter() {
+            writeln!(f, "- stream to {}", subnet_id)?;
+        }
+        for (subnet_id, changes) in self.streams_changed.iter() {
+            writeln!(f, "~ stream to {}", subnet_id)?;
+            write!(f, "{}", changes)?;
+        }
+        if !self.metadata.is_empty() {
+            writeln!(f, "~ subnet metadata")?;
+            write!(f, "{}", self.metadata)?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the semantic differences between `state_a` and `state_b`.
+pub fn diff_states(state_a: &ReplicatedState, state_b: &ReplicatedState) -> StateDiff {
+    let mut diff = StateDiff::default();
+
+    let canister_ids: BTreeSet<_> = state_a
+        .canister_states
+        .keys()
+        .chain(state_b.canister_states.keys())
+        .collect();
+    for canister_id in canister_ids {
+        match (
+            state_a.canister_state(canister_id),
+            state_b.canister_state(canister_id),
+        ) {
+            (Some(canister_a), Some(canister_b)) => {
+                let changes = diff_canisters(canister_a, canister_b);
+                if !changes.is_empty() {
+                    diff.canisters_changed
+                        .insert(canister_id.to_string(), changes);
+                }
+            }
+            (None, Some(_)) => diff.canisters_added.push(canister_id.to_string()),
+            (Some(_), None) => diff.canisters_removed.push(canister_id.to_string()),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    let (streams_a, streams_b) = (state_a.metadata.streams(), state_b.metadata.streams());
+    let subnet_ids: BTreeSet<_> = streams_a.keys().chain(streams_b.keys()).collect();
+    for subnet_id in subnet_ids {
+        match (streams_a.get(subnet_id), streams_b.get(subnet_id)) {
+            (Some(stream_a), Some(stream_b)) => {
+                let changes = diff_streams(stream_a, stream_b);
+                if !changes.is_empty() {
+                    diff.streams_changed.insert(subnet_id.to_string(), changes);
+                }
+            }
+            (None, Some(_)) => diff.streams_added.push(subnet_id.to_string()),
+            (Some(_), None) => diff.streams_removed.push(subnet_id.to_string()),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    diff.metadata = diff_metadata(state_a, state_b);
+    diff
+}
+
+fn diff_canisters(a: &CanisterState, b: &CanisterState) -> FieldChanges {
+    let mut changes = FieldChanges::default();
+    let (system_a, system_b) = (&a.system_state, &b.system_state);
+    changes.value("status", system_a.status_string(), system_b.status_string());
+    changes.quantity(
+        "cycles_balance",
+        system_a.balance().get(),
+        system_b.balance().get(),
+    );
+    changes.quantity(
+        "reserved_cycles_balance",
+        system_a.reserved_balance().get(),
+        system_b.reserved_balance().get(),
+    );
+    changes.quantity(
+        "canister_version",
+        system_a.canister_version,
+        system_b.canister_version,
+    );
+    changes.value(
+        "module_hash",
+        module_hash(a).unwrap_or_else(|| "none".into()),
+        module_hash(b).unwrap_or_else(|| "none".into()),
+    );
+    changes.quantity(
+        "memory_usage_bytes",
+        a.memory_usage().get(),
+        b.memory_usage().get(),
+    );
+    changes.quantity(
+        "wasm_memory_size_wasm_pages",
+        wasm_memory_pages(a),
+        wasm_memory_pages(b),
+    );
+    changes.quantity(
+        "stable_memory_size_wasm_pages",
+        stable_memory_pages(a),
+        stable_memory_pages(b),
+    );
+    changes.quantity(
+        "wasm_chunk_store_bytes",
+        system_a.wasm_chunk_store.memory_usage().get(),
+        system_b.wasm_chunk_store.memory_usage().get(),
+    );
+
+    let (queues_a, queues_b) = (system_a.queues(), system_b.queues());
+    changes.quantity(
+        "ingress_queue_messages",
+        queues_a.ingress_queue_message_count() as u64,
+        queues_b.ingress_queue_message_count() as u64,
+    );
+    changes.quantity(
+        "input_queues_messages",
+        queues_a.input_queues_message_count() as u64,
+        queues_b.input_queues_message_count() as u64,
+    );
+    changes.quantity(
+        "output_queues_messages",
+        queues_a.output_queues_message_count() as u64,
+        queues_b.output_queues_message_count() as u64,
+    );
+    changes
+}
+
+fn module_hash(canister: &CanisterState) -> Option<String> {
+    canister
+        .execution_state
+        .as_ref()
+        .map(|es| hex::encode(es.wasm_binary.binary.module_hash()))
+}
+
+fn wasm_memory_pages(canister: &CanisterState) -> u64 {
+    canister
+        .execution_state
+        .as_ref()
+        .map_or(0, |es| es.wasm_memory.size.get() as u64)
+}
+
+fn stable_memory_pages(canister: &CanisterState) -> u64 {
+    canister
+        .execution_state
+        .as_ref()
+        .map_or(0, |es| es.stable_memory.size.get() as u64)
+}
+
+fn diff_streams(a: &Stream, b: &Stream) -> FieldChanges {
+    let mut changes = FieldChanges::default();
+    changes.quantity(
+        "messages_begin",
+        a.messages_begin().get(),
+        b.messages_begin().get(),
+    );
+    changes.quantity(
+        "messages_end",
+        a.messages_end().get(),
+        b.messages_end().get(),
+    );
+    changes.quantity(
+        "messages",
+        a.messages().len() as u64,
+        b.messages().len() as u64,
+    );
+    changes.quantity("size_bytes", a.count_bytes() as u64, b.count_bytes() as u64);
+    changes.quantity("signals_end", a.signals_end().get(), b.signals_end().get());
+    changes.quantity(
+        "reject_signals",
+        a.reject_signals().len() as u64,
+        b.reject_signals().len() as u64,
+    );
+    changes
+}
+
+fn diff_metadata(state_a: &ReplicatedState, state_b: &ReplicatedState) -> FieldChanges {
+    let mut changes = FieldChanges::default();
+    let (a, b) = (&state_a.metadata, &state_b.metadata);
+    changes.value("batch_time", a.batch_time, b.batch_time);
+    changes.value("own_subnet_id", a.own_subnet_id, b.own_subnet_id);
+    changes.value(
+        "own_subnet_type",
+        format!("{:?}", a.own_subnet_type),
+        format!("{:?}", b.own_subnet_type),
+    );
+    changes.value(
+        "certification_version",
+        format!("{:?}", a.certification_version),
+        format!("{:?}", b.certification_version),
+    );
+    changes.value(
+        "split_from",
+        format!("{:?}", a.split_from),
+        format!("{:?}", b.split_from),
+    );
+    changes.quantity(
+        "heap_delta_estimate_bytes",
+        a.heap_delta_estimate.get(),
+        b.heap_delta_estimate.get(),
+    );
+    changes.quantity(
+        "ingress_history_statuses",
+        a.ingress_history.len() as u64,
+        b.ingress_history.len() as u64,
+    );
+    changes.quantity(
+        "subnets",
+        a.network_topology.subnets.len() as u64,
+        b.network_topology.subnets.len() as u64,
+    );
+    changes.value(
+        "routing_table",
+        format!("{:?}", a.network_topology.routing_table),
+        format!("{:?}", b.network_topology.routing_table),
+    );
+    changes.quantity(
+        "subnet_input_queues_messages",
+        state_a.subnet_queues().input_queues_message_count() as u64,
+        state_b.subnet_queues().input_queues_message_count() as u64,
+    );
+    changes.quantity(
+        "subnet_output_queues_messages",
+        state_a.subnet_queues().output_queues_message_count() as u64,
+        state_b.subnet_queues().output_queues_message_count() as u64,
+    );
+    changes
+}
+
+/// Loads the checkpoint at `path`.
+fn load_state(path: PathBuf, metrics: &CheckpointMetrics) -> Result<ReplicatedState, String> {
+    let cp_layout = CompleteCheckpointLayout::new_untracked(path.clone(), Height::new(0))
+        .map_err(|e| format!("failed to create checkpoint layout: {}", e))?;
+    load_checkpoint(
+        &cp_layout,
+        SubnetType::Application,
+        metrics,
+        None,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .map_err(|e| format!("failed to load checkpoint at {}: {}", path.display(), e))
+}
+
+/// `sdiff` command entry point. Prints the semantic diff between the
+/// checkpoints at `path_a` and `path_b`, as JSON if `json` is set.
+pub fn do_diff(path_a: PathBuf, path_b: PathBuf, json: bool) -> Result<(), String> {
+    let dummy_metrics_registry = ic_metrics::MetricsRegistry::new();
+    let dummy_metrics = CheckpointMetrics::new(&dummy_metrics_registry, crate::commands::logger());
+    let state_a = load_state(path_a, &dummy_metrics)?;
+    let state_b = load_state(path_b, &dummy_metrics)?;
+
+    let diff = diff_states(&state_a, &state_b);
+    if json {
+        let json = serde_json::to_string_pretty(&diff)
+            .map_err(|e| format!("failed to serialize diff: {}", e))?;
+        println!("{}", json);
+    } else if diff.is_empty() {
+        println!("✓ States are semantically identical");
+    } else {
+        print!("{}", diff);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_replicated_state::testing::ReplicatedStateTesting;
+    use ic_test_utilities_state::{CanisterStateBuilder, ReplicatedStateBuilder};
+    use ic_test_utilities_types::ids::{canister_test_id, subnet_test_id};
+    use ic_types::{Cycles, NumBytes};
+    use maplit::btreemap;
+
+    #[test]
+    fn diff_states_reports_semantic_changes() {
+        let state_a = ReplicatedStateBuilder::new()
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(1))
+                    .with_cycles(Cycles::new(1_000))
+                    .build(),
+            )
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(2))
+                    .build(),
+            )
+            .build();
+        assert!(diff_states(&state_a, &state_a).is_empty());
+
+        let mut state_b = state_a.clone();
+        state_b.put_canister_state(
+            CanisterStateBuilder::new()
+                .with_canister_id(canister_test_id(1))
+                .with_cycles(Cycles::new(700))
+                .build(),
+        );
+        state_b.canister_states.remove(&canister_test_id(2));
+        state_b.put_canister_state(
+            CanisterStateBuilder::new()
+                .with_canister_id(canister_test_id(3))
+                .build(),
+        );
+        state_b.modify_streams(|streams| {
+            streams.insert(subnet_test_id(7), Stream::default());
+        });
+        state_b.metadata.heap_delta_estimate = NumBytes::new(4096);
+
+        let diff = diff_states(&state_a, &state_b);
+        assert_eq!(diff.canisters_added, vec![canister_test_id(3).to_string()]);
+        assert_eq!(
+            diff.canisters_removed,
+            vec![canister_test_id(2).to_string()]
+        );
+        assert_eq!(
+            diff.canisters_changed,
+            btreemap! {
+                canister_test_id(1).to_string() => FieldChanges(btreemap! {
+                    "cycles_balance" => FieldChange::Quantity {
+                        before: 1_000,
+                        after: 700,
+                        delta: -300,
+                    },
+                }),
+            }
+        );
+        assert_eq!(diff.streams_added, vec![subnet_test_id(7).to_string()]);
+        assert!(diff.streams_removed.is_empty());
+        assert!(diff.streams_changed.is_empty());
+        assert_eq!(
+            diff.metadata,
+            FieldChanges(btreemap! {
+                "heap_delta_estimate_bytes" => FieldChange::Quantity {
+                    before: 0,
+                    after: 4096,
+                    delta: 4096,
+                },
+            })
+        );
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(
+            json["canisters_changed"][canister_test_id(1).to_string()]["cycles_balance"]["delta"],
+            -300
+        );
+    }
+}
@@ -2,7 +2,8 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, import state trees, extract, restore and migrate
+//! individual canisters).
 
 use clap::Parser;
 use ic_registry_routing_table::CanisterIdRange;
@@ -19,6 +20,17 @@ enum Opt {
     #[clap(name = "cdiff")]
     CDiff { path_a: PathBuf, path_b: PathBuf },
 
+    /// Computes semantic diff between checkpoints (canisters, streams and
+    /// subnet metadata).
+    #[clap(name = "sdiff")]
+    SDiff {
+        path_a: PathBuf,
+        path_b: PathBuf,
+        /// Outputs the diff as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+
     /// Computes partial state hash that is used for certification.
     #[clap(name = "chash")]
     CHash {
@@ -147,12 +159,66 @@ enum Opt {
         #[clap(long, required = true, num_args(1..))]
         migrated_ranges: Vec<CanisterIdRange>,
     },
+
+    /// Dumps the state of a canister (system state, Wasm module, exported
+    /// globals, stable memory and Wasm heap) from a checkpoint into a directory.
+    #[clap(name = "extract_canister")]
+    ExtractCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state", required = true)]
+        path: PathBuf,
+        /// ID of the canister to extract.
+        #[clap(long, required = true)]
+        canister_id: PrincipalId,
+        /// Directory to write the canister state to (must not exist or be empty).
+        #[clap(long, required = true)]
+        output: PathBuf,
+    },
+
+    /// Restores a canister state dumped by `extract_canister` into a new
+    /// checkpoint, replacing any existing canister with the same ID.
+    #[clap(name = "restore_canister")]
+    RestoreCanister {
+        /// Path to the state layout.
+        #[clap(long, required = true)]
+        root: PathBuf,
+        /// ID to restore the canister as.
+        #[clap(long, required = true)]
+        canister_id: PrincipalId,
+        /// Directory containing the canister state dump.
+        #[clap(long, required = true)]
+        input: PathBuf,
+    },
+
+    /// Moves stopped canisters (and their snapshots) from the latest checkpoint
+    /// of one subnet into the latest checkpoint of another, as part of a canister
+    /// migration. The canisters must already be rerouted to the destination
+    /// subnet (via the prepare canister migration and reroute canister ranges
+    /// proposals) in both states. Updating the routing table and draining the
+    /// XNet streams are not automated by this command.
+    #[clap(name = "migrate_canisters")]
+    MigrateCanisters {
+        /// Path to the state layout of the source subnet.
+        #[clap(long, required = true)]
+        source_root: PathBuf,
+        /// Path to the state layout of the destination subnet.
+        #[clap(long, required = true)]
+        destination_root: PathBuf,
+        /// IDs of the canisters to migrate.
+        #[clap(long, required = true, num_args(1..))]
+        canister_ids: Vec<PrincipalId>,
+    },
 }
 
 fn main() {
     let opt = Parser::parse();
     let result = match opt {
         Opt::CDiff { path_a, path_b } => commands::cdiff::do_diff(path_a, path_b),
+        Opt::SDiff {
+            path_a,
+            path_b,
+            json,
+        } => commands::sdiff::do_diff(path_a, path_b, json),
         Opt::CHash { path } => commands::chash::do_hash(path),
         Opt::ImportState {
             state,
@@ -200,6 +266,21 @@ fn main() {
             Time::from_nanos_since_unix_epoch(batch_time_nanos),
             migrated_ranges,
         ),
+        Opt::ExtractCanister {
+            path,
+            canister_id,
+            output,
+        } => commands::canister_dump::do_extract(path, canister_id, output),
+        Opt::RestoreCanister {
+            root,
+            canister_id,
+            input,
+        } => commands::canister_dump::do_restore(root, canister_id, input),
+        Opt::MigrateCanisters {
+            source_root,
+            destination_root,
+            canister_ids,
+        } => commands::canister_migration::do_migrate(source_root, destination_root, canister_ids),
     };
 
     if let Err(e) = result {
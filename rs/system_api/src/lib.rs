@@ -9,8 +9,13 @@ use ic_interfaces::execution_environment::{
     HypervisorResult, OutOfInstructionsHandler, PerformanceCounterType, StableGrowOutcome,
     StableMemoryApi, SubnetAvailableMemory, SystemApi, SystemApiCallCounters,
     TrapCode::{self, CyclesAmountTooBigFor64Bit},
+    WasmProfile,
 };
 use ic_logger::{error, ReplicaLogger};
+use ic_management_canister_types::{
+    CanisterLogLevel, EcdsaCurve, EcdsaKeyId, MasterPublicKeyId, SchnorrAlgorithm, SchnorrKeyId,
+    VetKdCurve, VetKdKeyId,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::WASM_PAGE_SIZE_IN_BYTES, memory_required_to_push_request, Memory, NumWasmPages,
@@ -18,6 +23,7 @@ use ic_replicated_state::{
 };
 use ic_sys::PageBytes;
 use ic_types::{
+    canister_log::parse_canister_log_tags,
     ingress::WasmResult,
     messages::{CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
     methods::{SystemMethod, WasmClosure},
@@ -31,6 +37,8 @@ use sandbox_safe_system_state::{CanisterStatusView, SandboxSafeSystemState, Syst
 use serde::{Deserialize, Serialize};
 use stable_memory::StableMemory;
 use std::{
+    cell::RefCell,
+    collections::{BTreeMap, VecDeque},
     convert::{From, TryFrom},
     rc::Rc,
 };
@@ -43,11 +51,14 @@ mod stable_memory;
 
 pub const MULTIPLIER_MAX_SIZE_LOCAL_SUBNET: u64 = 5;
 const MAX_NON_REPLICATED_QUERY_REPLY_SIZE: NumBytes = NumBytes::new(3 << 20);
-const CERTIFIED_DATA_MAX_LENGTH: usize = 32;
+pub const CERTIFIED_DATA_MAX_LENGTH: usize = 32;
 
 // Enables tracing of system calls for local debugging.
 const TRACE_SYSCALLS: bool = false;
 
+/// The maximum length of a single system API call recorded by Wasm tracing.
+const MAX_TRACED_SYSTEM_API_CALL_LENGTH: usize = 200;
+
 /// This error should be displayed if stable memory is used through the system
 /// API when Wasm-native stable memory is enabled.
 const WASM_NATIVE_STABLE_MEMORY_ERROR: &str = "Stable memory cannot be accessed through the System API when Wasm-native stable memory is enabled.";
@@ -79,25 +90,30 @@ macro_rules! trace_syscall {
                 &$result
             );
         }
+        if $self.system_api_trace.is_enabled() {
+            $self.system_api_trace.record(format!(
+                "{}: {:?} => {:?}",
+                stringify!($name),
+                ($(&$args, )*),
+                &$result
+            ));
+        }
     }}
 }
 
 // This helper is used in system calls for displaying a summary hash of a heap region.
+// It is only evaluated by `trace_syscall!` if tracing is enabled.
 #[inline]
 fn summarize(heap: &[u8], start: usize, size: usize) -> u64 {
-    if TRACE_SYSCALLS {
-        let start = start.min(heap.len());
-        let end = (start + size).min(heap.len());
-        // The actual hash function doesn't matter much as long as it is
-        // cheap to compute and maps the input to u64 reasonably well.
-        let mut sum = 0;
-        for (i, byte) in heap[start..end].iter().enumerate() {
-            sum += (i + 1) as u64 * *byte as u64
-        }
-        sum
-    } else {
-        0
-    }
+    let start = start.min(heap.len());
+    let end = start.saturating_add(size).min(heap.len());
+    // The actual hash function doesn't matter much as long as it is
+    // cheap to compute and maps the input to u64 reasonably well.
+    let mut sum: u64 = 0;
+    for (i, byte) in heap[start..end].iter().enumerate() {
+        sum = sum.wrapping_add((i + 1) as u64 * *byte as u64)
+    }
+    sum
 }
 
 /// Keeps the message instruction limit and the maximum slice instruction limit.
@@ -1062,6 +1078,78 @@ pub struct SystemApiImpl {
 
     /// How many times each tracked System API call was invoked.
     call_counters: SystemApiCallCounters,
+
+    /// Attributes executed instructions to call stacks if Wasm profiling is enabled.
+    wasm_profiler: WasmProfiler,
+
+    /// Records the most recent system API calls if Wasm tracing is enabled.
+    system_api_trace: SystemApiTrace,
+}
+
+/// Keeps the most recent system API calls of a message so that they can be
+/// written to the canister log if the message traps.
+#[derive(Default)]
+struct SystemApiTrace {
+    /// The maximum number of recorded calls. Zero disables the recording.
+    capacity: usize,
+    /// The recorded calls, oldest first. System API calls taking `&self`
+    /// are recorded too, hence the interior mutability.
+    calls: RefCell<VecDeque<String>>,
+}
+
+impl SystemApiTrace {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            calls: RefCell::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Records the given call, evicting the oldest one if the trace is full.
+    fn record(&self, mut call: String) {
+        if call.len() > MAX_TRACED_SYSTEM_API_CALL_LENGTH {
+            let mut end = MAX_TRACED_SYSTEM_API_CALL_LENGTH;
+            while !call.is_char_boundary(end) {
+                end -= 1;
+            }
+            call.truncate(end);
+            call.push_str("...");
+        }
+        let mut calls = self.calls.borrow_mut();
+        if calls.len() == self.capacity {
+            calls.pop_front();
+        }
+        calls.push_back(call);
+    }
+}
+
+/// Attributes the instructions executed by a message to the call stacks
+/// of Wasm functions based on the instructions executed by the message
+/// whenever a function is entered or exited.
+#[derive(Default)]
+struct WasmProfiler {
+    /// The indices of the functions on the current call stack.
+    stack: Vec<u32>,
+    /// The instructions executed by the message at the last function entry or exit.
+    instructions_executed: u64,
+    /// The instructions attributed to each call stack so far.
+    profile: BTreeMap<Vec<u32>, u64>,
+}
+
+impl WasmProfiler {
+    /// Attributes the instructions executed since the last function entry or exit
+    /// to the current call stack.
+    fn attribute(&mut self, instructions_executed: u64) {
+        let instructions = instructions_executed.saturating_sub(self.instructions_executed);
+        self.instructions_executed = instructions_executed;
+        if !self.stack.is_empty() && instructions > 0 {
+            *self.profile.entry(self.stack.clone()).or_default() += instructions;
+        }
+    }
 }
 
 impl SystemApiImpl {
@@ -1075,6 +1163,7 @@ impl SystemApiImpl {
         subnet_available_memory: SubnetAvailableMemory,
         wasm_native_stable_memory: FlagStatus,
         canister_backtrace: FlagStatus,
+        wasm_tracing: FlagStatus,
         max_sum_exported_function_name_lengths: usize,
         stable_memory: Memory,
         wasm_memory_size: NumWasmPages,
@@ -1108,6 +1197,12 @@ impl SystemApiImpl {
         );
         let stable_memory = StableMemory::new(stable_memory);
         let slice_limit = execution_parameters.instruction_limits.slice().get();
+        let system_api_trace = match wasm_tracing {
+            FlagStatus::Enabled => {
+                SystemApiTrace::new(sandbox_safe_system_state.wasm_trace_length() as usize)
+            }
+            FlagStatus::Disabled => SystemApiTrace::default(),
+        };
         Self {
             execution_error: None,
             api_type,
@@ -1123,6 +1218,8 @@ impl SystemApiImpl {
             current_slice_instruction_limit: i64::try_from(slice_limit).unwrap_or(i64::MAX),
             instructions_executed_before_current_slice: 0,
             call_counters: SystemApiCallCounters::default(),
+            wasm_profiler: WasmProfiler::default(),
+            system_api_trace,
         }
     }
 
@@ -1571,6 +1668,8 @@ impl SystemApiImpl {
     pub fn save_log_message(&mut self, src: usize, size: usize, heap: &[u8]) {
         self.sandbox_safe_system_state.append_canister_log(
             self.api_type.time(),
+            None,
+            vec![],
             valid_subslice("save_log_message", src, size, heap)
                 .unwrap_or(
                     // Do not trap here!
@@ -1581,6 +1680,53 @@ impl SystemApiImpl {
         );
     }
 
+    /// Appends the specified bytes on the heap as a string to the canister's logs,
+    /// as a record with the given level and tags (see `ic0.debug_print_structured`).
+    ///
+    /// Unlike `save_log_message()`, this traps if the level or the tags are invalid,
+    /// since the canister relies on them being stored as given.
+    pub fn save_structured_log_message(
+        &mut self,
+        level: i32,
+        tags_src: usize,
+        tags_size: usize,
+        src: usize,
+        size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<()> {
+        const METHOD_NAME: &str = "ic0.debug_print_structured";
+        let level = match level {
+            0 => CanisterLogLevel::Debug,
+            1 => CanisterLogLevel::Info,
+            2 => CanisterLogLevel::Warning,
+            3 => CanisterLogLevel::Error,
+            _ => {
+                return Err(UserContractViolation {
+                    error: format!("{}: invalid log level {}", METHOD_NAME, level),
+                    suggestion:
+                        "Use one of the levels 0 (debug), 1 (info), 2 (warning) or 3 (error)."
+                            .to_string(),
+                    doc_link: "".to_string(),
+                })
+            }
+        };
+        let tags = parse_canister_log_tags(valid_subslice(METHOD_NAME, tags_src, tags_size, heap)?)
+            .map_err(|err| UserContractViolation {
+                error: format!("{}: {}", METHOD_NAME, err),
+                suggestion: "Pass the tags as comma-separated UTF-8 text without whitespace."
+                    .to_string(),
+                doc_link: "".to_string(),
+            })?;
+        let content = valid_subslice(METHOD_NAME, src, size, heap)?.to_vec();
+        self.sandbox_safe_system_state.append_canister_log(
+            self.api_type.time(),
+            Some(level),
+            tags,
+            content,
+        );
+        Ok(())
+    }
+
     /// Takes collected canister log records.
     pub fn take_canister_log(&mut self) -> CanisterLog {
         self.sandbox_safe_system_state.take_canister_log()
@@ -1591,6 +1737,40 @@ impl SystemApiImpl {
         self.sandbox_safe_system_state.canister_log()
     }
 
+    /// Records that the function with the given index has been entered
+    /// (only called if Wasm profiling is enabled).
+    pub fn profile_enter(&mut self, func_index: u32, instruction_counter: i64) {
+        let instructions_executed = self.message_instructions_executed(instruction_counter);
+        self.wasm_profiler.attribute(instructions_executed.get());
+        self.wasm_profiler.stack.push(func_index);
+    }
+
+    /// Records that the innermost function on the call stack has been exited
+    /// (only called if Wasm profiling is enabled).
+    pub fn profile_exit(&mut self, instruction_counter: i64) {
+        let instructions_executed = self.message_instructions_executed(instruction_counter);
+        self.wasm_profiler.attribute(instructions_executed.get());
+        self.wasm_profiler.stack.pop();
+    }
+
+    /// Takes the Wasm profile collected so far. The instructions executed
+    /// since the last function entry or exit (e.g., if the execution trapped)
+    /// are attributed to the current call stack.
+    pub fn take_wasm_profile(&mut self, instruction_counter: i64) -> WasmProfile {
+        let instructions_executed = self.message_instructions_executed(instruction_counter);
+        self.wasm_profiler.attribute(instructions_executed.get());
+        std::mem::take(&mut self.wasm_profiler)
+            .profile
+            .into_iter()
+            .collect()
+    }
+
+    /// Takes the system API calls recorded so far, oldest first. Returns
+    /// an empty vector if Wasm tracing is disabled.
+    pub fn take_system_api_trace(&mut self) -> Vec<String> {
+        self.system_api_trace.calls.take().into()
+    }
+
     /// Checks if the current API type is an install or upgrade message.
     /// This is relevant when enforcing the stable memory dirty page limit.
     pub fn is_install_or_upgrade_message(&self) -> bool {
@@ -1627,6 +1807,107 @@ impl SystemApiImpl {
             }
         }
     }
+
+    /// Shared implementation of the `ic0.cost_sign_with_*` system calls.
+    ///
+    /// `key_id` builds the key id from the key name read from the heap and is
+    /// `None` if the curve or algorithm passed by the canister is unknown.
+    fn cost_sign_with_key<F>(
+        &self,
+        method_name: &str,
+        src: usize,
+        size: usize,
+        key_id: Option<F>,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32>
+    where
+        F: FnOnce(String) -> MasterPublicKeyId,
+    {
+        const UNKNOWN_CURVE_OR_ALGORITHM: u32 = 1;
+        const UNKNOWN_KEY: u32 = 2;
+        match self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. } => {
+                let name = valid_subslice(method_name, src, size, heap)?;
+                let Some(key_id) = key_id else {
+                    return Ok(UNKNOWN_CURVE_OR_ALGORITHM);
+                };
+                // A name that is not valid UTF-8 cannot refer to an enabled key.
+                let cost = std::str::from_utf8(name).ok().and_then(|name| {
+                    self.sandbox_safe_system_state
+                        .cost_sign_with_key(&key_id(name.to_string()))
+                });
+                match cost {
+                    Some(cost) => {
+                        copy_cycles_to_heap(cost, dst, heap, method_name)?;
+                        Ok(0)
+                    }
+                    None => Ok(UNKNOWN_KEY),
+                }
+            }
+        }
+    }
+
+    /// Returns the name and value of the environment variable at `index`,
+    /// where variables are ordered by name.
+    fn env_var_at(&self, method_name: &str, index: usize) -> HypervisorResult<(&String, &String)> {
+        let environment_variables = self.sandbox_safe_system_state.environment_variables();
+        environment_variables
+            .iter()
+            .nth(index)
+            .ok_or_else(|| UserContractViolation {
+                error: format!(
+                    "{} failed because index {} is out of range. The canister has {} \
+                    environment variables.",
+                    method_name,
+                    index,
+                    environment_variables.len()
+                ),
+                suggestion: "Use ic0.env_var_count to get the number of environment variables."
+                    .to_string(),
+                doc_link: "".to_string(),
+            })
+    }
+
+    /// Returns the value of the environment variable whose name is stored in
+    /// `heap[name_src..name_src + name_size]`.
+    fn env_var_value(
+        &self,
+        method_name: &str,
+        name_src: usize,
+        name_size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<&String> {
+        let name = valid_subslice(method_name, name_src, name_size, heap)?;
+        std::str::from_utf8(name)
+            .ok()
+            .and_then(|name| {
+                self.sandbox_safe_system_state
+                    .environment_variables()
+                    .get(name)
+            })
+            .ok_or_else(|| UserContractViolation {
+                error: format!(
+                    "{} failed because environment variable \"{}\" does not exist.",
+                    method_name,
+                    String::from_utf8_lossy(name)
+                ),
+                suggestion: "Use ic0.env_var_name_exists to check whether an environment \
+                    variable exists."
+                    .to_string(),
+                doc_link: "".to_string(),
+            })
+    }
 }
 
 impl SystemApi for SystemApiImpl {
@@ -3452,6 +3733,501 @@ impl SystemApi for SystemApiImpl {
         trace_syscall!(self, CyclesBurn128, result, amount);
         result
     }
+
+    fn ic0_cost_call(
+        &self,
+        method_name_size: u64,
+        payload_size: u64,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let method_name = "ic0_cost_call";
+        let result = match self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. } => {
+                let cost = self
+                    .sandbox_safe_system_state
+                    .cost_call(method_name_size, payload_size);
+                copy_cycles_to_heap(cost, dst, heap, method_name)
+            }
+        };
+        trace_syscall!(self, CostCall, result, method_name_size, payload_size, dst);
+        result
+    }
+
+    fn ic0_cost_create_canister(&self, dst: usize, heap: &mut [u8]) -> HypervisorResult<()> {
+        let method_name = "ic0_cost_create_canister";
+        let result = match self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. } => {
+                let cost = self.sandbox_safe_system_state.cost_create_canister();
+                copy_cycles_to_heap(cost, dst, heap, method_name)
+            }
+        };
+        trace_syscall!(self, CostCreateCanister, result, dst);
+        result
+    }
+
+    fn ic0_cost_http_request(
+        &self,
+        request_size: u64,
+        max_res_bytes: u64,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let method_name = "ic0_cost_http_request";
+        let result = match self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. } => {
+                let cost = self
+                    .sandbox_safe_system_state
+                    .cost_http_request(request_size, max_res_bytes);
+                copy_cycles_to_heap(cost, dst, heap, method_name)
+            }
+        };
+        trace_syscall!(
+            self,
+            CostHttpRequest,
+            result,
+            request_size,
+            max_res_bytes,
+            dst
+        );
+        result
+    }
+
+    fn ic0_cost_sign_with_ecdsa(
+        &self,
+        src: usize,
+        size: usize,
+        ecdsa_curve: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32> {
+        let curve = match ecdsa_curve {
+            0 => Some(EcdsaCurve::Secp256k1),
+            _ => None,
+        };
+        let result = self.cost_sign_with_key(
+            "ic0_cost_sign_with_ecdsa",
+            src,
+            size,
+            curve.map(|curve| move |name| MasterPublicKeyId::Ecdsa(EcdsaKeyId { curve, name })),
+            dst,
+            heap,
+        );
+        trace_syscall!(self, CostSignWithEcdsa, result, src, size, ecdsa_curve, dst);
+        result
+    }
+
+    fn ic0_cost_sign_with_schnorr(
+        &self,
+        src: usize,
+        size: usize,
+        algorithm: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32> {
+        let schnorr_algorithm = match algorithm {
+            0 => Some(SchnorrAlgorithm::Bip340Secp256k1),
+            1 => Some(SchnorrAlgorithm::Ed25519),
+            _ => None,
+        };
+        let result = self.cost_sign_with_key(
+            "ic0_cost_sign_with_schnorr",
+            src,
+            size,
+            schnorr_algorithm.map(|algorithm| {
+                move |name| MasterPublicKeyId::Schnorr(SchnorrKeyId { algorithm, name })
+            }),
+            dst,
+            heap,
+        );
+        trace_syscall!(self, CostSignWithSchnorr, result, src, size, algorithm, dst);
+        result
+    }
+
+    fn ic0_cost_vetkd_derive_key(
+        &self,
+        src: usize,
+        size: usize,
+        vetkd_curve: u32,
+        dst: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32> {
+        let curve = match vetkd_curve {
+            0 => Some(VetKdCurve::Bls12_381_G2),
+            _ => None,
+        };
+        let result = self.cost_sign_with_key(
+            "ic0_cost_vetkd_derive_key",
+            src,
+            size,
+            curve.map(|curve| move |name| MasterPublicKeyId::VetKd(VetKdKeyId { curve, name })),
+            dst,
+            heap,
+        );
+        trace_syscall!(
+            self,
+            CostVetkdDeriveKey,
+            result,
+            src,
+            size,
+            vetkd_curve,
+            dst
+        );
+        result
+    }
+
+    fn ic0_env_var_count(&self) -> HypervisorResult<usize> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_env_var_count")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                Ok(self.sandbox_safe_system_state.environment_variables().len())
+            }
+        };
+        trace_syscall!(self, EnvVarCount, result);
+        result
+    }
+
+    fn ic0_env_var_name_size(&self, index: usize) -> HypervisorResult<usize> {
+        let method_name = "ic0_env_var_name_size";
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => self
+                .env_var_at(method_name, index)
+                .map(|(name, _)| name.len()),
+        };
+        trace_syscall!(self, EnvVarNameSize, result, index);
+        result
+    }
+
+    fn ic0_env_var_name_copy(
+        &self,
+        index: usize,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let method_name = "ic0_env_var_name_copy";
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                valid_subslice("ic0.env_var_name_copy heap", dst, size, heap)?;
+                let (name, _) = self.env_var_at(method_name, index)?;
+                let slice =
+                    valid_subslice("ic0.env_var_name_copy name", offset, size, name.as_bytes())?;
+                deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
+                Ok(())
+            }
+        };
+        trace_syscall!(
+            self,
+            EnvVarNameCopy,
+            result,
+            index,
+            dst,
+            offset,
+            size,
+            summarize(heap, dst, size)
+        );
+        result
+    }
+
+    fn ic0_env_var_name_exists(
+        &self,
+        name_src: usize,
+        name_size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<u32> {
+        let method_name = "ic0_env_var_name_exists";
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                let name = valid_subslice(method_name, name_src, name_size, heap)?;
+                let exists = std::str::from_utf8(name)
+                    .map(|name| {
+                        self.sandbox_safe_system_state
+                            .environment_variables()
+                            .contains_key(name)
+                    })
+                    .unwrap_or(false);
+                Ok(exists as u32)
+            }
+        };
+        trace_syscall!(
+            self,
+            EnvVarNameExists,
+            result,
+            name_src,
+            name_size,
+            summarize(heap, name_src, name_size)
+        );
+        result
+    }
+
+    fn ic0_env_var_value_size(
+        &self,
+        name_src: usize,
+        name_size: usize,
+        heap: &[u8],
+    ) -> HypervisorResult<usize> {
+        let method_name = "ic0_env_var_value_size";
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => self
+                .env_var_value(method_name, name_src, name_size, heap)
+                .map(|value| value.len()),
+        };
+        trace_syscall!(
+            self,
+            EnvVarValueSize,
+            result,
+            name_src,
+            name_size,
+            summarize(heap, name_src, name_size)
+        );
+        result
+    }
+
+    fn ic0_env_var_value_copy(
+        &self,
+        name_src: usize,
+        name_size: usize,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let method_name = "ic0_env_var_value_copy";
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for(method_name)),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                valid_subslice("ic0.env_var_value_copy heap", dst, size, heap)?;
+                let value = self.env_var_value(method_name, name_src, name_size, heap)?;
+                let slice = valid_subslice(
+                    "ic0.env_var_value_copy value",
+                    offset,
+                    size,
+                    value.as_bytes(),
+                )?;
+                deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
+                Ok(())
+            }
+        };
+        trace_syscall!(
+            self,
+            EnvVarValueCopy,
+            result,
+            name_src,
+            name_size,
+            dst,
+            offset,
+            size,
+            summarize(heap, dst, size)
+        );
+        result
+    }
+
+    fn ic0_subnet_self_size(&self) -> HypervisorResult<usize> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_subnet_self_size")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => Ok(self
+                .sandbox_safe_system_state
+                .subnet_id()
+                .get_ref()
+                .as_slice()
+                .len()),
+        };
+        trace_syscall!(self, SubnetSelfSize, result);
+        result
+    }
+
+    fn ic0_subnet_self_copy(
+        &self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_subnet_self_copy")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                valid_subslice("ic0.subnet_self_copy heap", dst, size, heap)?;
+                let subnet_id = self.sandbox_safe_system_state.subnet_id();
+                let id_bytes = subnet_id.get_ref().as_slice();
+                let slice = valid_subslice("ic0.subnet_self_copy id", offset, size, id_bytes)?;
+                deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
+                Ok(())
+            }
+        };
+        trace_syscall!(
+            self,
+            SubnetSelfCopy,
+            result,
+            dst,
+            offset,
+            size,
+            summarize(heap, dst, size)
+        );
+        result
+    }
+
+    fn ic0_root_key_size(&self) -> HypervisorResult<usize> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_root_key_size")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => Ok(self.sandbox_safe_system_state.root_key().len()),
+        };
+        trace_syscall!(self, RootKeySize, result);
+        result
+    }
+
+    fn ic0_root_key_copy(
+        &self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_root_key_copy")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                valid_subslice("ic0.root_key_copy heap", dst, size, heap)?;
+                let root_key = self.sandbox_safe_system_state.root_key();
+                let slice = valid_subslice("ic0.root_key_copy key", offset, size, root_key)?;
+                deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
+                Ok(())
+            }
+        };
+        trace_syscall!(
+            self,
+            RootKeyCopy,
+            result,
+            dst,
+            offset,
+            size,
+            summarize(heap, dst, size)
+        );
+        result
+    }
 }
 
 /// The default implementation of the `OutOfInstructionHandler` trait.
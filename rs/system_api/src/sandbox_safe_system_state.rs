@@ -10,8 +10,8 @@ use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_limits::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_logger::{info, ReplicaLogger};
 use ic_management_canister_types::{
-    CanisterStatusType, CreateCanisterArgs, InstallChunkedCodeArgs, InstallCodeArgsV2,
-    LoadCanisterSnapshotArgs, Method as Ic00Method, Payload,
+    CanisterLogLevel, CanisterStatusType, CreateCanisterArgs, InstallChunkedCodeArgs,
+    InstallCodeArgsV2, LoadCanisterSnapshotArgs, MasterPublicKeyId, Method as Ic00Method, Payload,
     ProvisionalCreateCanisterWithCyclesArgs, UninstallCodeArgs, UpdateSettingsArgs, IC_00,
 };
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
@@ -24,6 +24,7 @@ use ic_types::{
     methods::Callback,
     time::CoarseTime,
     CanisterLog, CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumInstructions, Time,
+    MAX_WASM64_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::WasmEngineError;
 use serde::{Deserialize, Serialize};
@@ -270,7 +271,11 @@ impl SystemStateChanges {
             | Ok(Ic00Method::ClearChunkStore)
             | Ok(Ic00Method::TakeCanisterSnapshot)
             | Ok(Ic00Method::ListCanisterSnapshots)
-            | Ok(Ic00Method::DeleteCanisterSnapshot) => Ok(None),
+            | Ok(Ic00Method::DeleteCanisterSnapshot)
+            | Ok(Ic00Method::ReadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::ReadCanisterSnapshotData)
+            | Ok(Ic00Method::UploadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::UploadCanisterSnapshotData) => Ok(None),
             Err(_) => Err(UserError::new(
                 ErrorCode::CanisterMethodNotFound,
                 format!("Management canister has no method '{}'", msg.method_name),
@@ -585,9 +590,18 @@ pub struct SandboxSafeSystemState {
     available_request_slots: BTreeMap<CanisterId, usize>,
     ic00_available_request_slots: usize,
     ic00_aliases: BTreeSet<CanisterId>,
+    /// The threshold keys enabled on any subnet, mapped to the size of the
+    /// subnet that signing requests with the key are routed to. Signing fees
+    /// are charged by that subnet, so they are priced with its size.
+    chain_key_subnet_sizes: BTreeMap<MasterPublicKeyId, usize>,
+    /// The DER-encoded public key of the IC (the NNS subnet public key).
+    root_key: Vec<u8>,
     global_timer: CanisterTimer,
     canister_version: u64,
     controllers: BTreeSet<PrincipalId>,
+    environment_variables: BTreeMap<String, String>,
+    /// Number of most recent system API calls to record for debugging traps.
+    wasm_trace_length: u64,
     pub(super) request_metadata: RequestMetadata,
     caller: Option<PrincipalId>,
     pub is_wasm64_execution: bool,
@@ -616,14 +630,19 @@ impl SandboxSafeSystemState {
         available_request_slots: BTreeMap<CanisterId, usize>,
         ic00_available_request_slots: usize,
         ic00_aliases: BTreeSet<CanisterId>,
+        chain_key_subnet_sizes: BTreeMap<MasterPublicKeyId, usize>,
+        root_key: Vec<u8>,
         subnet_size: usize,
         dirty_page_overhead: NumInstructions,
         global_timer: CanisterTimer,
         canister_version: u64,
         controllers: BTreeSet<PrincipalId>,
+        environment_variables: BTreeMap<String, String>,
+        wasm_trace_length: u64,
         request_metadata: RequestMetadata,
         caller: Option<PrincipalId>,
         next_canister_log_record_idx: u64,
+        canister_log_buffer_size: usize,
         is_wasm64_execution: bool,
     ) -> Self {
         Self {
@@ -637,8 +656,13 @@ impl SandboxSafeSystemState {
             wasm_memory_threshold,
             compute_allocation,
             system_state_changes: SystemStateChanges {
-                // Start indexing new batch of canister log records from the given index.
-                canister_log: CanisterLog::new_with_next_index(next_canister_log_record_idx),
+                // Start indexing new batch of canister log records from the given index,
+                // bounded by the size of the canister's log buffer.
+                canister_log: CanisterLog::new_with_capacity(
+                    next_canister_log_record_idx,
+                    vec![],
+                    canister_log_buffer_size,
+                ),
                 call_context_balance_taken: call_context_id
                     .map(|call_context_id| (call_context_id, Cycles::zero())),
                 ..SystemStateChanges::default()
@@ -654,9 +678,13 @@ impl SandboxSafeSystemState {
             available_request_slots,
             ic00_available_request_slots,
             ic00_aliases,
+            chain_key_subnet_sizes,
+            root_key,
             global_timer,
             canister_version,
             controllers,
+            environment_variables,
+            wasm_trace_length,
             request_metadata,
             caller,
             is_wasm64_execution,
@@ -765,14 +793,34 @@ impl SandboxSafeSystemState {
             available_request_slots,
             ic00_available_request_slots,
             ic00_aliases,
+            network_topology
+                .chain_key_enabled_subnets
+                .iter()
+                .filter_map(|(key_id, subnet_ids)| {
+                    // Signing requests are routed to the first enabled subnet.
+                    let subnet_id = subnet_ids.first()?;
+                    let subnet_size = network_topology
+                        .get_subnet_size(subnet_id)
+                        .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
+                    Some((key_id.clone(), subnet_size))
+                })
+                .collect(),
+            network_topology
+                .subnets
+                .get(&network_topology.nns_subnet_id)
+                .map(|subnet| subnet.public_key.clone())
+                .unwrap_or_default(),
             subnet_size,
             dirty_page_overhead,
             system_state.global_timer,
             system_state.canister_version,
             system_state.controllers.clone(),
+            system_state.environment_variables.clone(),
+            system_state.wasm_trace_length,
             request_metadata,
             caller,
             system_state.canister_log.next_idx(),
+            system_state.canister_log.capacity(),
             is_wasm64_execution,
         )
     }
@@ -976,6 +1024,45 @@ impl SandboxSafeSystemState {
             .prepayment_for_response_transmission(self.subnet_size)
     }
 
+    /// Returns the cost of an inter-canister call with the given method name
+    /// and argument sizes, including the prepayments for its response.
+    pub fn cost_call(&self, method_name_size: u64, payload_size: u64) -> Cycles {
+        let payload_size = NumBytes::from(method_name_size.saturating_add(payload_size));
+        self.cycles_account_manager
+            .xnet_call_total_fee(payload_size, self.subnet_size)
+            + self.prepayment_for_response_transmission()
+            + self.prepayment_for_response_execution()
+    }
+
+    /// Returns the cost of creating a canister on this subnet.
+    pub fn cost_create_canister(&self) -> Cycles {
+        self.cycles_account_manager
+            .canister_creation_fee(self.subnet_size)
+    }
+
+    /// Returns the cost of an HTTPS outcall with the given request size and
+    /// response size limit.
+    pub fn cost_http_request(&self, request_size: u64, max_response_bytes: u64) -> Cycles {
+        self.cycles_account_manager.http_request_fee(
+            NumBytes::from(request_size),
+            Some(NumBytes::from(max_response_bytes)),
+            self.subnet_size,
+        )
+    }
+
+    /// Returns the cost of a threshold signing request with the given key, or
+    /// `None` if the key is not enabled on any subnet. The fee is charged by
+    /// the subnet holding the key, so it is priced with that subnet's size.
+    pub fn cost_sign_with_key(&self, key_id: &MasterPublicKeyId) -> Option<Cycles> {
+        let subnet_size = *self.chain_key_subnet_sizes.get(key_id)?;
+        let cam = &self.cycles_account_manager;
+        Some(match key_id {
+            MasterPublicKeyId::Ecdsa(_) => cam.ecdsa_signature_fee(subnet_size),
+            MasterPublicKeyId::Schnorr(_) => cam.schnorr_signature_fee(subnet_size),
+            MasterPublicKeyId::VetKd(_) => cam.vetkd_fee(subnet_size),
+        })
+    }
+
     pub(super) fn withdraw_cycles_for_transfer(
         &mut self,
         canister_current_memory_usage: NumBytes,
@@ -1084,6 +1171,23 @@ impl SandboxSafeSystemState {
         self.controllers.contains(principal_id)
     }
 
+    pub fn environment_variables(&self) -> &BTreeMap<String, String> {
+        &self.environment_variables
+    }
+
+    /// Returns the ID of the subnet the canister is running on.
+    pub fn subnet_id(&self) -> SubnetId {
+        self.cycles_account_manager.get_subnet_id()
+    }
+
+    pub fn root_key(&self) -> &[u8] {
+        &self.root_key
+    }
+
+    pub fn wasm_trace_length(&self) -> u64 {
+        self.wasm_trace_length
+    }
+
     /// Checks the cycles balance against the freezing threshold with the new
     /// memory usage if that's needed for the given API type.
     ///
@@ -1269,7 +1373,9 @@ impl SandboxSafeSystemState {
     /// 2. Without memory allocation
     ///     `wasm_memory_threshold >= wasm_memory_limit - wasm_memory_usage`
     ///
-    /// Note: if `wasm_memory_limit` is not set, its default value is 4 GiB.
+    /// Note: if `wasm_memory_limit` is not set, its default value is the
+    /// maximum Wasm memory size (4 GiB for Wasm32 and
+    /// `MAX_WASM64_MEMORY_IN_BYTES` for Wasm64).
     pub fn check_on_low_wasm_memory_hook_condition(
         &mut self,
         memory_allocation: Option<NumBytes>,
@@ -1277,11 +1383,16 @@ impl SandboxSafeSystemState {
         memory_usage: NumBytes,
         wasm_memory_usage: NumBytes,
     ) {
-        // If wasm memory limit is not set, the default is 4 GiB. Wasm memory
-        // limit is ignored for query methods, response callback handlers,
-        // global timers, heartbeats, and canister pre_upgrade.
-        let wasm_memory_limit =
-            wasm_memory_limit.unwrap_or_else(|| NumBytes::new(4 * 1024 * 1024 * 1024));
+        // If wasm memory limit is not set, the default is the maximum Wasm
+        // memory size. Wasm memory limit is ignored for query methods, response
+        // callback handlers, global timers, heartbeats, and canister pre_upgrade.
+        let wasm_memory_limit = wasm_memory_limit.unwrap_or_else(|| {
+            if self.is_wasm64_execution {
+                NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES)
+            } else {
+                NumBytes::new(MAX_WASM_MEMORY_IN_BYTES)
+            }
+        });
 
         debug_assert!(
             wasm_memory_usage <= memory_usage,
@@ -1350,10 +1461,16 @@ impl SandboxSafeSystemState {
     }
 
     /// Appends a log record to the system state changes.
-    pub fn append_canister_log(&mut self, time: &Time, content: Vec<u8>) {
+    pub fn append_canister_log(
+        &mut self,
+        time: &Time,
+        level: Option<CanisterLogLevel>,
+        tags: Vec<String>,
+        content: Vec<u8>,
+    ) {
         self.system_state_changes
             .canister_log
-            .add_record(time.as_nanos_since_unix_epoch(), content);
+            .add_structured_record(time.as_nanos_since_unix_epoch(), level, tags, content);
     }
 
     /// Takes collected canister log records.
@@ -1400,7 +1517,7 @@ mod tests {
         messages::{RequestMetadata, NO_DEADLINE},
         time::CoarseTime,
         CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
-        Time,
+        Time, DEFAULT_CANISTER_LOG_BUFFER_SIZE,
     };
 
     use crate::{
@@ -1497,14 +1614,19 @@ mod tests {
             BTreeMap::new(),
             0,
             BTreeSet::new(),
+            BTreeMap::new(),
+            vec![],
             SMALL_APP_SUBNET_MAX_SIZE,
             SchedulerConfig::application_subnet().dirty_page_overhead,
             CanisterTimer::Inactive,
             0,
             BTreeSet::new(),
+            BTreeMap::new(),
+            0,
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
             None,
             0,
+            DEFAULT_CANISTER_LOG_BUFFER_SIZE,
             // Wasm32 execution environment. Sufficient in testing.
             false,
         );
@@ -1547,14 +1669,19 @@ mod tests {
             BTreeMap::new(),
             0,
             BTreeSet::new(),
+            BTreeMap::new(),
+            vec![],
             SMALL_APP_SUBNET_MAX_SIZE,
             SchedulerConfig::application_subnet().dirty_page_overhead,
             CanisterTimer::Inactive,
             0,
             BTreeSet::new(),
+            BTreeMap::new(),
+            0,
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
             None,
             0,
+            DEFAULT_CANISTER_LOG_BUFFER_SIZE,
             // Wasm32 execution environment. Sufficient in testing.
             false,
         )
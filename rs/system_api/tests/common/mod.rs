@@ -181,12 +181,26 @@ pub fn get_system_api(
     api_type: ApiType,
     system_state: &SystemState,
     cycles_account_manager: CyclesAccountManager,
+) -> SystemApiImpl {
+    get_system_api_with_network_topology(
+        api_type,
+        system_state,
+        cycles_account_manager,
+        &NetworkTopology::default(),
+    )
+}
+
+pub fn get_system_api_with_network_topology(
+    api_type: ApiType,
+    system_state: &SystemState,
+    cycles_account_manager: CyclesAccountManager,
+    network_topology: &NetworkTopology,
 ) -> SystemApiImpl {
     let execution_mode = api_type.execution_mode();
     let sandbox_safe_system_state = SandboxSafeSystemState::new_for_testing(
         system_state,
         cycles_account_manager,
-        &NetworkTopology::default(),
+        network_topology,
         SchedulerConfig::application_subnet().dirty_page_overhead,
         execution_parameters(execution_mode.clone()).compute_allocation,
         execution_parameters(execution_mode.clone()).canister_guaranteed_callback_quota,
@@ -209,6 +223,7 @@ pub fn get_system_api(
             .feature_flags
             .wasm_native_stable_memory,
         EmbeddersConfig::default().feature_flags.canister_backtrace,
+        EmbeddersConfig::default().feature_flags.wasm_tracing,
         EmbeddersConfig::default().max_sum_exported_function_name_lengths,
         Memory::new_for_testing(),
         NumWasmPages::from(0),
@@ -2,7 +2,7 @@ use ic_base_types::{NumBytes, NumSeconds, PrincipalIdBlobParseError};
 use ic_config::{
     embedders::Config as EmbeddersConfig, flag_status::FlagStatus, subnet_config::SchedulerConfig,
 };
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, WasmExecutionMode};
 use ic_error_types::RejectCode;
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, ExecutionMode, HypervisorError, HypervisorResult,
@@ -11,11 +11,15 @@ use ic_interfaces::execution_environment::{
 };
 use ic_limits::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_logger::replica_logger::no_op_logger;
+use ic_management_canister_types::{
+    CanisterLogLevel, EcdsaCurve, EcdsaKeyId, MasterPublicKeyId, SchnorrAlgorithm, SchnorrKeyId,
+    VetKdCurve, VetKdKeyId,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::NumWasmPages;
 use ic_replicated_state::{
     canister_state::system_state::OnLowWasmMemoryHookStatus, testing::CanisterQueuesTesting,
-    CallOrigin, Memory, NetworkTopology, SystemState,
+    CallOrigin, Memory, NetworkTopology, SubnetTopology, SystemState,
 };
 use ic_system_api::{
     sandbox_safe_system_state::SandboxSafeSystemState, ApiType, DefaultOutOfInstructionsHandler,
@@ -24,7 +28,7 @@ use ic_system_api::{
 use ic_test_utilities::cycles_account_manager::CyclesAccountManagerBuilder;
 use ic_test_utilities_state::SystemStateBuilder;
 use ic_test_utilities_types::{
-    ids::{call_context_test_id, canister_test_id, subnet_test_id, user_test_id},
+    ids::{call_context_test_id, canister_test_id, node_test_id, subnet_test_id, user_test_id},
     messages::RequestBuilder,
 };
 use ic_types::{
@@ -33,7 +37,7 @@ use ic_types::{
     time,
     time::UNIX_EPOCH,
     CanisterTimer, CountBytes, Cycles, NumInstructions, PrincipalId, Time,
-    MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE,
+    DEFAULT_CANISTER_LOG_BUFFER_SIZE,
 };
 use maplit::btreemap;
 use more_asserts::assert_le;
@@ -258,8 +262,18 @@ fn is_supported(api_type: SystemApiCallId, context: &str) -> bool {
         SystemApiCallId::MsgCyclesAccept => vec!["U", "Rt", "Ry"],
         SystemApiCallId::MsgCyclesAccept128 => vec!["U", "Rt", "Ry"],
         SystemApiCallId::CyclesBurn128 => vec!["I", "G", "U", "Ry", "Rt", "C", "T"],
+        SystemApiCallId::CostCall => vec!["*"],
+        SystemApiCallId::CostCreateCanister => vec!["*"],
+        SystemApiCallId::CostHttpRequest => vec!["*"],
+        SystemApiCallId::CostSignWithEcdsa => vec!["*"],
+        SystemApiCallId::CostSignWithSchnorr => vec!["*"],
+        SystemApiCallId::CostVetkdDeriveKey => vec!["*"],
         SystemApiCallId::CanisterSelfSize => vec!["*"],
         SystemApiCallId::CanisterSelfCopy => vec!["*"],
+        SystemApiCallId::SubnetSelfSize => vec!["*"],
+        SystemApiCallId::SubnetSelfCopy => vec!["*"],
+        SystemApiCallId::RootKeySize => vec!["*"],
+        SystemApiCallId::RootKeyCopy => vec!["*"],
         SystemApiCallId::CanisterCycleBalance => vec!["*"],
         SystemApiCallId::CanisterCycleBalance128 => vec!["*"],
         SystemApiCallId::CanisterStatus => vec!["*"],
@@ -292,6 +306,12 @@ fn is_supported(api_type: SystemApiCallId, context: &str) -> bool {
         SystemApiCallId::IsController => vec!["*", "s"],
         SystemApiCallId::InReplicatedExecution => vec!["*", "s"],
         SystemApiCallId::DebugPrint => vec!["*", "s"],
+        SystemApiCallId::EnvVarCount => vec!["*"],
+        SystemApiCallId::EnvVarNameCopy => vec!["*"],
+        SystemApiCallId::EnvVarNameExists => vec!["*"],
+        SystemApiCallId::EnvVarNameSize => vec!["*"],
+        SystemApiCallId::EnvVarValueCopy => vec!["*"],
+        SystemApiCallId::EnvVarValueSize => vec!["*"],
         SystemApiCallId::Trap => vec!["*", "s"],
         SystemApiCallId::MintCycles => vec!["U", "Ry", "Rt", "T"],
         SystemApiCallId::MintCycles128 => vec!["U", "Ry", "Rt", "T"]
@@ -307,7 +327,11 @@ fn api_availability_test(
     api_type_enum: SystemApiCallId,
     context: &str,
 ) {
-    let system_state = get_system_state();
+    let mut system_state = get_system_state();
+    // The environment variable APIs trap if the variable does not exist.
+    system_state.environment_variables = btreemap! {
+        "NAME".to_string() => "value".to_string(),
+    };
     match api_type_enum {
         SystemApiCallId::MsgCallerSize => {
             assert_api_availability(
@@ -469,6 +493,46 @@ fn api_availability_test(
                 context,
             );
         }
+        SystemApiCallId::SubnetSelfSize => {
+            assert_api_availability(
+                |api| api.ic0_subnet_self_size(),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::SubnetSelfCopy => {
+            assert_api_availability(
+                |api| api.ic0_subnet_self_copy(0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::RootKeySize => {
+            assert_api_availability(
+                |api| api.ic0_root_key_size(),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::RootKeyCopy => {
+            assert_api_availability(
+                |api| api.ic0_root_key_copy(0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
         SystemApiCallId::DebugPrint => {
             assert_api_availability(
                 |api| api.ic0_debug_print(0, 0, &[42; 128]),
@@ -781,6 +845,134 @@ fn api_availability_test(
                 context,
             );
         }
+        SystemApiCallId::CostCall => {
+            assert_api_availability(
+                |api| api.ic0_cost_call(0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::CostCreateCanister => {
+            assert_api_availability(
+                |api| api.ic0_cost_create_canister(0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::CostHttpRequest => {
+            assert_api_availability(
+                |api| api.ic0_cost_http_request(0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::CostSignWithEcdsa => {
+            assert_api_availability(
+                |api| api.ic0_cost_sign_with_ecdsa(0, 0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::CostSignWithSchnorr => {
+            assert_api_availability(
+                |api| api.ic0_cost_sign_with_schnorr(0, 0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::CostVetkdDeriveKey => {
+            assert_api_availability(
+                |api| api.ic0_cost_vetkd_derive_key(0, 0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::EnvVarCount => {
+            assert_api_availability(
+                |api| api.ic0_env_var_count(),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::EnvVarNameSize => {
+            assert_api_availability(
+                |api| api.ic0_env_var_name_size(0),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::EnvVarNameCopy => {
+            assert_api_availability(
+                |api| api.ic0_env_var_name_copy(0, 0, 0, 4, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::EnvVarNameExists => {
+            assert_api_availability(
+                |api| api.ic0_env_var_name_exists(0, 4, &[42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::EnvVarValueSize => {
+            assert_api_availability(
+                |api| {
+                    let mut heap = [42; 128];
+                    heap[..4].copy_from_slice(b"NAME");
+                    api.ic0_env_var_value_size(0, 4, &heap)
+                },
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::EnvVarValueCopy => {
+            assert_api_availability(
+                |api| {
+                    let mut heap = [42; 128];
+                    heap[..4].copy_from_slice(b"NAME");
+                    api.ic0_env_var_value_copy(0, 4, 8, 0, 5, &mut heap)
+                },
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
         // stable API is tested separately
         SystemApiCallId::StableGrow
         | SystemApiCallId::StableRead
@@ -1359,6 +1551,7 @@ fn growing_wasm_memory_updates_subnet_available_memory() {
             .feature_flags
             .wasm_native_stable_memory,
         EmbeddersConfig::default().feature_flags.canister_backtrace,
+        EmbeddersConfig::default().feature_flags.wasm_tracing,
         EmbeddersConfig::default().max_sum_exported_function_name_lengths,
         Memory::new_for_testing(),
         NumWasmPages::from(0),
@@ -1438,6 +1631,7 @@ fn helper_test_on_low_wasm_memory(
             .feature_flags
             .wasm_native_stable_memory,
         EmbeddersConfig::default().feature_flags.canister_backtrace,
+        EmbeddersConfig::default().feature_flags.wasm_tracing,
         EmbeddersConfig::default().max_sum_exported_function_name_lengths,
         Memory::new_for_testing(),
         NumWasmPages::from(0),
@@ -1673,6 +1867,7 @@ fn push_output_request_respects_memory_limits() {
             .feature_flags
             .wasm_native_stable_memory,
         EmbeddersConfig::default().feature_flags.canister_backtrace,
+        EmbeddersConfig::default().feature_flags.wasm_tracing,
         EmbeddersConfig::default().max_sum_exported_function_name_lengths,
         Memory::new_for_testing(),
         NumWasmPages::from(0),
@@ -1787,6 +1982,7 @@ fn push_output_request_oversized_request_memory_limits() {
             .feature_flags
             .wasm_native_stable_memory,
         EmbeddersConfig::default().feature_flags.canister_backtrace,
+        EmbeddersConfig::default().feature_flags.wasm_tracing,
         EmbeddersConfig::default().max_sum_exported_function_name_lengths,
         Memory::new_for_testing(),
         NumWasmPages::from(0),
@@ -1928,6 +2124,230 @@ fn ic0_is_controller_invalid_principal_id() {
     ));
 }
 
+fn cycles_from_heap(heap: &[u8]) -> Cycles {
+    Cycles::new(u128::from_le_bytes(heap[..16].try_into().unwrap()))
+}
+
+#[test]
+fn ic0_cost_functions_match_cycles_account_manager() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &SystemStateBuilder::default().build(),
+        cycles_account_manager,
+    );
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let mut heap = [0u8; 16];
+
+    api.ic0_cost_call(10, 1_000, 0, &mut heap).unwrap();
+    assert_eq!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.xnet_call_total_fee(NumBytes::from(1_010), subnet_size)
+            + cycles_account_manager.prepayment_for_response_transmission(subnet_size)
+            + cycles_account_manager
+                .prepayment_for_response_execution(subnet_size, WasmExecutionMode::Wasm32)
+    );
+
+    api.ic0_cost_create_canister(0, &mut heap).unwrap();
+    assert_eq!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.canister_creation_fee(subnet_size)
+    );
+
+    api.ic0_cost_http_request(100, 2_000, 0, &mut heap).unwrap();
+    assert_eq!(
+        cycles_from_heap(&heap),
+        cycles_account_manager.http_request_fee(
+            NumBytes::from(100),
+            Some(NumBytes::from(2_000)),
+            subnet_size
+        )
+    );
+
+    // Writing the result out of bounds traps.
+    assert!(api.ic0_cost_create_canister(1, &mut heap).is_err());
+}
+
+#[test]
+fn ic0_env_var_functions() {
+    let mut system_state = SystemStateBuilder::default().build();
+    system_state.environment_variables = btreemap! {
+        "B_NAME".to_string() => "second".to_string(),
+        "A".to_string() => "first".to_string(),
+    };
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        CyclesAccountManagerBuilder::new().build(),
+    );
+    let mut heap = [0u8; 32];
+
+    assert_eq!(api.ic0_env_var_count().unwrap(), 2);
+
+    // Variables are ordered by name.
+    assert_eq!(api.ic0_env_var_name_size(0).unwrap(), 1);
+    assert_eq!(api.ic0_env_var_name_size(1).unwrap(), 6);
+    api.ic0_env_var_name_copy(1, 0, 2, 4, &mut heap).unwrap();
+    assert_eq!(&heap[..4], b"NAME");
+
+    heap[..6].copy_from_slice(b"B_NAME");
+    assert_eq!(api.ic0_env_var_name_exists(0, 6, &heap).unwrap(), 1);
+    assert_eq!(api.ic0_env_var_name_exists(0, 5, &heap).unwrap(), 0);
+    assert_eq!(api.ic0_env_var_value_size(0, 6, &heap).unwrap(), 6);
+    api.ic0_env_var_value_copy(0, 6, 16, 0, 6, &mut heap)
+        .unwrap();
+    assert_eq!(&heap[16..22], b"second");
+
+    // Out-of-range indices and unknown names trap.
+    assert!(api.ic0_env_var_name_size(2).is_err());
+    assert!(api.ic0_env_var_name_copy(2, 0, 0, 0, &mut heap).is_err());
+    assert!(api.ic0_env_var_value_size(0, 5, &heap).is_err());
+    // Copying past the end of the value traps.
+    assert!(api
+        .ic0_env_var_value_copy(0, 6, 16, 1, 6, &mut heap)
+        .is_err());
+}
+
+#[test]
+fn ic0_subnet_self_and_root_key() {
+    let own_subnet_id = subnet_test_id(7);
+    let nns_subnet_id = subnet_test_id(1);
+    let root_key = vec![1, 2, 3, 4, 5];
+    let network_topology = NetworkTopology {
+        nns_subnet_id,
+        subnets: btreemap! {
+            nns_subnet_id => SubnetTopology {
+                public_key: root_key.clone(),
+                ..SubnetTopology::default()
+            },
+            own_subnet_id => SubnetTopology::default(),
+        },
+        ..NetworkTopology::default()
+    };
+    let api = get_system_api_with_network_topology(
+        ApiTypeBuilder::build_update_api(),
+        &SystemStateBuilder::default().build(),
+        CyclesAccountManagerBuilder::new()
+            .with_subnet_id(own_subnet_id)
+            .build(),
+        &network_topology,
+    );
+
+    let subnet_id_bytes = own_subnet_id.get_ref().as_slice();
+    let size = api.ic0_subnet_self_size().unwrap();
+    assert_eq!(size, subnet_id_bytes.len());
+    let mut heap = vec![0; size];
+    api.ic0_subnet_self_copy(0, 0, size, &mut heap).unwrap();
+    assert_eq!(heap, subnet_id_bytes);
+
+    assert_eq!(api.ic0_root_key_size().unwrap(), root_key.len());
+    let mut heap = vec![0; 3];
+    api.ic0_root_key_copy(0, 2, 3, &mut heap).unwrap();
+    assert_eq!(heap, &root_key[2..]);
+    // Copying past the end of the key traps.
+    assert!(api.ic0_root_key_copy(0, 3, 3, &mut heap).is_err());
+}
+
+#[test]
+fn ic0_cost_sign_with_threshold_keys() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let system_state = SystemStateBuilder::default().build();
+    let api_type = ApiTypeBuilder::build_update_api();
+    // The keys are held by a subnet larger than the caller's subnet, which is
+    // not part of the topology and hence priced as a small app subnet.
+    let key_subnet_size = 34;
+    let mut network_topology = NetworkTopology {
+        subnets: btreemap! {
+            subnet_test_id(1) => SubnetTopology {
+                nodes: (0..key_subnet_size).map(node_test_id).collect(),
+                ..SubnetTopology::default()
+            },
+        },
+        ..NetworkTopology::default()
+    };
+    for key_id in [
+        MasterPublicKeyId::Ecdsa(EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: "key".to_string(),
+        }),
+        MasterPublicKeyId::Schnorr(SchnorrKeyId {
+            algorithm: SchnorrAlgorithm::Ed25519,
+            name: "key".to_string(),
+        }),
+        MasterPublicKeyId::VetKd(VetKdKeyId {
+            curve: VetKdCurve::Bls12_381_G2,
+            name: "key".to_string(),
+        }),
+    ] {
+        network_topology
+            .chain_key_enabled_subnets
+            .insert(key_id, vec![subnet_test_id(1)]);
+    }
+    let api = get_system_api_with_network_topology(
+        api_type,
+        &system_state,
+        cycles_account_manager,
+        &network_topology,
+    );
+    let subnet_size = key_subnet_size as usize;
+    assert_ne!(subnet_size, SMALL_APP_SUBNET_MAX_SIZE);
+
+    // The key name is at the start of the heap, the cost is written after it.
+    let mut heap = [0u8; 32];
+    heap[..3].copy_from_slice(b"key");
+
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(0, 3, 0, 16, &mut heap)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        cycles_from_heap(&heap[16..]),
+        cycles_account_manager.ecdsa_signature_fee(subnet_size)
+    );
+    assert_eq!(
+        api.ic0_cost_sign_with_schnorr(0, 3, 1, 16, &mut heap)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        cycles_from_heap(&heap[16..]),
+        cycles_account_manager.schnorr_signature_fee(subnet_size)
+    );
+    assert_eq!(
+        api.ic0_cost_vetkd_derive_key(0, 3, 0, 16, &mut heap)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        cycles_from_heap(&heap[16..]),
+        cycles_account_manager.vetkd_fee(subnet_size)
+    );
+
+    // Unknown curve or algorithm.
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(0, 3, 1, 16, &mut heap)
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        api.ic0_cost_sign_with_schnorr(0, 3, 2, 16, &mut heap)
+            .unwrap(),
+        1
+    );
+    // Unknown key, including a key of another algorithm with the same name.
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(0, 2, 0, 16, &mut heap)
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        api.ic0_cost_sign_with_schnorr(0, 3, 0, 16, &mut heap)
+            .unwrap(),
+        2
+    );
+}
+
 #[test]
 fn test_ic0_cycles_burn() {
     let initial_cycles = Cycles::new(5_000_000_000_000);
@@ -2033,7 +2453,7 @@ fn test_save_log_message_invalid_message_offset() {
 
 #[test]
 fn test_save_log_message_trims_long_message() {
-    let long_message_size = 2 * MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE;
+    let long_message_size = 2 * DEFAULT_CANISTER_LOG_BUFFER_SIZE;
     let mut api = get_system_api(
         ApiTypeBuilder::build_update_api(),
         &SystemStateBuilder::default().build(),
@@ -2046,13 +2466,60 @@ fn test_save_log_message_trims_long_message() {
     // Expect added log record with the content trimmed to the allowed size.
     let records = api.canister_log().records();
     assert_eq!(records.len(), initial_records_number + 1);
-    assert!(records.back().unwrap().content.len() <= MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+    assert!(records.back().unwrap().content.len() <= DEFAULT_CANISTER_LOG_BUFFER_SIZE);
+}
+
+#[test]
+fn test_save_structured_log_message_adds_level_and_tags() {
+    let heap = b"db,retrymessage";
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &SystemStateBuilder::default().build(),
+        CyclesAccountManagerBuilder::new().build(),
+    );
+    api.save_structured_log_message(2, 0, 8, 8, 7, heap)
+        .unwrap();
+    let record = api.canister_log().records().back().unwrap().clone();
+    assert_eq!(record.content, b"message".to_vec());
+    assert_eq!(record.level, Some(CanisterLogLevel::Warning));
+    assert_eq!(
+        record.tags,
+        Some(vec!["db".to_string(), "retry".to_string()])
+    );
+}
+
+#[test]
+fn test_save_structured_log_message_rejects_invalid_arguments() {
+    let heap = b"db,message";
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &SystemStateBuilder::default().build(),
+        CyclesAccountManagerBuilder::new().build(),
+    );
+    let initial_records_number = api.canister_log().records().len();
+    // Invalid level.
+    assert!(api
+        .save_structured_log_message(4, 0, 2, 3, 7, heap)
+        .is_err());
+    // Empty tag.
+    assert!(api
+        .save_structured_log_message(1, 0, 3, 3, 7, heap)
+        .is_err());
+    // Tags out of memory bounds.
+    assert!(api
+        .save_structured_log_message(1, 0, 11, 3, 7, heap)
+        .is_err());
+    // Content out of memory bounds.
+    assert!(api
+        .save_structured_log_message(1, 0, 2, 3, 8, heap)
+        .is_err());
+    assert_eq!(api.canister_log().records().len(), initial_records_number);
 }
 
 #[test]
 fn test_save_log_message_keeps_total_log_size_limited() {
     let messages_number = 10;
-    let long_message_size = 2 * MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE;
+    let long_message_size = 2 * DEFAULT_CANISTER_LOG_BUFFER_SIZE;
     let mut api = get_system_api(
         ApiTypeBuilder::build_update_api(),
         &SystemStateBuilder::default().build(),
@@ -2067,5 +2534,5 @@ fn test_save_log_message_keeps_total_log_size_limited() {
     // Expect only one log record to be kept, staying within the size limit.
     let log = api.canister_log();
     assert_eq!(log.records().len(), initial_records_number + 1);
-    assert_le!(log.used_space(), MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+    assert_le!(log.used_space(), DEFAULT_CANISTER_LOG_BUFFER_SIZE);
 }
@@ -170,6 +170,7 @@ impl WasmtimeInstanceBuilder {
             ),
             embedder.config().feature_flags.wasm_native_stable_memory,
             embedder.config().feature_flags.canister_backtrace,
+            embedder.config().feature_flags.wasm_tracing,
             embedder.config().max_sum_exported_function_name_lengths,
             Memory::new_for_testing(),
             NumWasmPages::from(0),
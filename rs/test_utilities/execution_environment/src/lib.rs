@@ -2103,8 +2103,18 @@ impl ExecutionTestBuilder {
         self
     }
 
-    pub fn with_max_wasm_memory_size(mut self, wasm_memory_size: NumBytes) -> Self {
-        self.execution_config.embedders_config.max_wasm_memory_size = wasm_memory_size;
+    pub fn with_wasm_tracing(mut self) -> Self {
+        self.execution_config
+            .embedders_config
+            .feature_flags
+            .wasm_tracing = FlagStatus::Enabled;
+        self
+    }
+
+    pub fn with_max_wasm64_memory_size(mut self, wasm_memory_size: NumBytes) -> Self {
+        self.execution_config
+            .embedders_config
+            .max_wasm64_memory_size = wasm_memory_size;
         self
     }
 
@@ -19,6 +19,7 @@ enum FnCall {
     StableRead(i32, i32),
     GlobalTimerSet(i64),
     DebugPrint(Vec<u8>),
+    DebugPrintStructured(i32, Vec<u8>, Vec<u8>),
     Trap(Vec<u8>),
     Wait(i64),
 }
@@ -63,6 +64,17 @@ impl WatFnCode {
         self
     }
 
+    /// Call the `ic0.debug_print_structured` function with the given level
+    /// code and comma-separated tags.
+    pub fn debug_print_structured(mut self, level: i32, tags: &[u8], message: &[u8]) -> Self {
+        self.calls.push(FnCall::DebugPrintStructured(
+            level,
+            tags.to_vec(),
+            message.to_vec(),
+        ));
+        self
+    }
+
     /// Call the `ic0.trap` function.
     pub fn trap_with_blob(mut self, message: &[u8]) -> Self {
         self.calls.push(FnCall::Trap(message.to_vec()));
@@ -140,6 +152,26 @@ impl WatCall {
         }
     }
 
+    fn debug_print_structured(
+        level: i32,
+        tags_offset: i32,
+        tags_size: i32,
+        offset: i32,
+        size: i32,
+    ) -> Self {
+        Self {
+            func: "ic0_debug_print_structured".to_string(),
+            params: vec![
+                WatConst::I32(level),
+                WatConst::I32(tags_offset),
+                WatConst::I32(tags_size),
+                WatConst::I32(offset),
+                WatConst::I32(size),
+            ],
+            drop_result: false,
+        }
+    }
+
     fn trap(offset: i32, size: i32) -> Self {
         Self {
             func: "ic0_trap".to_string(),
@@ -378,6 +410,7 @@ impl WatCanisterBuilder {
             (import "ic0" "global_timer_set" (func $ic0_global_timer_set (param i64) (result i64)))
             (import "ic0" "performance_counter" (func $ic0_performance_counter (param i32) (result i64)))
             (import "ic0" "debug_print" (func $ic0_debug_print (param i32) (param i32)))
+            (import "ic0" "debug_print_structured" (func $ic0_debug_print_structured (param i32 i32 i32 i32 i32)))
             (import "ic0" "trap" (func $ic0_trap (param i32) (param i32)))
 
             ;; Define functions
@@ -478,6 +511,15 @@ impl WatCanisterBuilder {
                 FnCall::DebugPrint(message) => {
                     WatCall::debug_print(self.get_memory_offset(message), message.len() as i32)
                 }
+                FnCall::DebugPrintStructured(level, tags, message) => {
+                    WatCall::debug_print_structured(
+                        *level,
+                        self.get_memory_offset(tags),
+                        tags.len() as i32,
+                        self.get_memory_offset(message),
+                        message.len() as i32,
+                    )
+                }
                 FnCall::Trap(message) => {
                     WatCall::trap(self.get_memory_offset(message), message.len() as i32)
                 }
@@ -523,6 +565,10 @@ mod tests {
                 WatCall::debug_print(0, 4),
                 "(call $ic0_debug_print (i32.const 0) (i32.const 4))",
             ),
+            (
+                WatCall::debug_print_structured(2, 0, 4, 4, 5),
+                "(call $ic0_debug_print_structured (i32.const 2) (i32.const 0) (i32.const 4) (i32.const 4) (i32.const 5))",
+            ),
             (
                 WatCall::trap(2, 4),
                 "(call $ic0_trap (i32.const 2) (i32.const 4))",
@@ -746,6 +792,7 @@ mod tests {
             (import "ic0" "global_timer_set" (func $ic0_global_timer_set (param i64) (result i64)))
             (import "ic0" "performance_counter" (func $ic0_performance_counter (param i32) (result i64)))
             (import "ic0" "debug_print" (func $ic0_debug_print (param i32) (param i32)))
+            (import "ic0" "debug_print_structured" (func $ic0_debug_print_structured (param i32 i32 i32 i32 i32)))
             (import "ic0" "trap" (func $ic0_trap (param i32) (param i32)))
 
             ;; Define functions
@@ -1011,6 +1011,7 @@ pub(crate) fn arb_cycles_use_case() -> impl Strategy<Value = CyclesUseCase> {
         Just(CyclesUseCase::HTTPOutcalls),
         Just(CyclesUseCase::DeletedCanisters),
         Just(CyclesUseCase::NonConsumed),
+        Just(CyclesUseCase::SchedulingPriority),
     ]
 }
 
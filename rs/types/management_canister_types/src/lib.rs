@@ -126,6 +126,12 @@ pub enum Method {
     LoadCanisterSnapshot,
     ListCanisterSnapshots,
     DeleteCanisterSnapshot,
+
+    // Support for downloading and uploading canister snapshots.
+    ReadCanisterSnapshotMetadata,
+    ReadCanisterSnapshotData,
+    UploadCanisterSnapshotMetadata,
+    UploadCanisterSnapshotData,
 }
 
 fn candid_error_to_user_error(err: candid::Error) -> UserError {
@@ -869,6 +875,50 @@ impl TryFrom<pb_canister_state_bits::LogVisibilityV2> for LogVisibilityV2 {
     }
 }
 
+/// Scheduling priority class of a canister.
+/// ```text
+/// variant {
+///     standard;
+///     burst;
+/// }
+/// ```
+///
+/// Canisters in the burst class pay a fee for every round in which they are
+/// scheduled with new messages, and in exchange get a bounded boost of their
+/// scheduling priority in that round. The boost does not override the
+/// guarantees of canisters with a compute allocation.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default, CandidType, Deserialize, EnumIter,
+)]
+pub enum CanisterPriorityClass {
+    #[default]
+    #[serde(rename = "standard")]
+    Standard,
+    #[serde(rename = "burst")]
+    Burst,
+}
+
+impl Payload<'_> for CanisterPriorityClass {}
+
+impl From<CanisterPriorityClass> for pb_canister_state_bits::PriorityClass {
+    fn from(item: CanisterPriorityClass) -> Self {
+        match item {
+            CanisterPriorityClass::Standard => pb_canister_state_bits::PriorityClass::Standard,
+            CanisterPriorityClass::Burst => pb_canister_state_bits::PriorityClass::Burst,
+        }
+    }
+}
+
+impl From<pb_canister_state_bits::PriorityClass> for CanisterPriorityClass {
+    fn from(item: pb_canister_state_bits::PriorityClass) -> Self {
+        match item {
+            pb_canister_state_bits::PriorityClass::Unspecified
+            | pb_canister_state_bits::PriorityClass::Standard => CanisterPriorityClass::Standard,
+            pb_canister_state_bits::PriorityClass::Burst => CanisterPriorityClass::Burst,
+        }
+    }
+}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     controller : principal;
@@ -879,6 +929,10 @@ impl TryFrom<pb_canister_state_bits::LogVisibilityV2> for LogVisibilityV2 {
 ///     log_visibility: log_visibility;
 ///     wasm_memory_limit: nat;
 ///     wasm_memory_threshold: nat;
+///     log_buffer_size: nat;
+///     environment_variables: vec environment_variable;
+///     wasm_trace_length: nat;
+///     priority_class: priority_class;
 /// })`
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct DefiniteCanisterSettingsArgs {
@@ -891,9 +945,14 @@ pub struct DefiniteCanisterSettingsArgs {
     log_visibility: LogVisibilityV2,
     wasm_memory_limit: candid::Nat,
     wasm_memory_threshold: candid::Nat,
+    log_buffer_size: candid::Nat,
+    environment_variables: Vec<EnvironmentVariable>,
+    wasm_trace_length: candid::Nat,
+    priority_class: CanisterPriorityClass,
 }
 
 impl DefiniteCanisterSettingsArgs {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         controller: PrincipalId,
         controllers: Vec<PrincipalId>,
@@ -904,6 +963,10 @@ impl DefiniteCanisterSettingsArgs {
         log_visibility: LogVisibilityV2,
         wasm_memory_limit: Option<u64>,
         wasm_memory_threshold: u64,
+        log_buffer_size: u64,
+        environment_variables: Vec<EnvironmentVariable>,
+        wasm_trace_length: u64,
+        priority_class: CanisterPriorityClass,
     ) -> Self {
         let memory_allocation = candid::Nat::from(memory_allocation.unwrap_or(0));
         let reserved_cycles_limit = candid::Nat::from(reserved_cycles_limit.unwrap_or(0));
@@ -918,6 +981,10 @@ impl DefiniteCanisterSettingsArgs {
             log_visibility,
             wasm_memory_limit,
             wasm_memory_threshold: candid::Nat::from(wasm_memory_threshold),
+            log_buffer_size: candid::Nat::from(log_buffer_size),
+            environment_variables,
+            wasm_trace_length: candid::Nat::from(wasm_trace_length),
+            priority_class,
         }
     }
 
@@ -948,6 +1015,22 @@ impl DefiniteCanisterSettingsArgs {
     pub fn freezing_threshold(&self) -> candid::Nat {
         self.freezing_threshold.clone()
     }
+
+    pub fn log_buffer_size(&self) -> candid::Nat {
+        self.log_buffer_size.clone()
+    }
+
+    pub fn environment_variables(&self) -> &[EnvironmentVariable] {
+        &self.environment_variables
+    }
+
+    pub fn wasm_trace_length(&self) -> candid::Nat {
+        self.wasm_trace_length.clone()
+    }
+
+    pub fn priority_class(&self) -> CanisterPriorityClass {
+        self.priority_class
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -1014,6 +1097,7 @@ pub struct QueryStats {
     num_instructions_total: candid::Nat,
     request_payload_bytes_total: candid::Nat,
     response_payload_bytes_total: candid::Nat,
+    num_cache_hits_total: candid::Nat,
 }
 
 /// Struct used for encoding/decoding
@@ -1032,6 +1116,7 @@ pub struct QueryStats {
 ///         num_instructions: nat;
 ///         ingress_payload_size: nat;
 ///         egress_payload_size: nat;
+///         num_cache_hits: nat;
 ///     }
 /// })`
 #[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
@@ -1070,8 +1155,13 @@ impl CanisterStatusResultV2 {
         query_num_instructions: u128,
         query_ingress_payload_size: u128,
         query_egress_payload_size: u128,
+        query_num_cache_hits: u128,
         wasm_memory_limit: Option<u64>,
         wasm_memory_threshold: u64,
+        log_buffer_size: u64,
+        environment_variables: Vec<EnvironmentVariable>,
+        wasm_trace_length: u64,
+        priority_class: CanisterPriorityClass,
     ) -> Self {
         Self {
             status,
@@ -1092,6 +1182,10 @@ impl CanisterStatusResultV2 {
                 log_visibility,
                 wasm_memory_limit,
                 wasm_memory_threshold,
+                log_buffer_size,
+                environment_variables,
+                wasm_trace_length,
+                priority_class,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
@@ -1101,6 +1195,7 @@ impl CanisterStatusResultV2 {
                 num_instructions_total: candid::Nat::from(query_num_instructions),
                 request_payload_bytes_total: candid::Nat::from(query_ingress_payload_size),
                 response_payload_bytes_total: candid::Nat::from(query_egress_payload_size),
+                num_cache_hits_total: candid::Nat::from(query_num_cache_hits),
             },
         }
     }
@@ -1145,6 +1240,10 @@ impl CanisterStatusResultV2 {
         self.idle_cycles_burned_per_day.0.to_u128().unwrap()
     }
 
+    pub fn query_num_cache_hits(&self) -> u128 {
+        self.query_stats.num_cache_hits_total.0.to_u128().unwrap()
+    }
+
     pub fn reserved_cycles(&self) -> u128 {
         self.reserved_cycles.0.to_u128().unwrap()
     }
@@ -1725,6 +1824,56 @@ pub type BoundedControllers =
 
 impl Payload<'_> for BoundedControllers {}
 
+/// Maximum number of environment variables of a canister.
+pub const MAX_ENVIRONMENT_VARIABLES_COUNT: usize = 20;
+
+/// Maximum size of the name of an environment variable in bytes.
+pub const MAX_ENVIRONMENT_VARIABLE_NAME_SIZE: usize = 128;
+
+/// Maximum size of the value of an environment variable in bytes.
+pub const MAX_ENVIRONMENT_VARIABLE_VALUE_SIZE: usize = 128;
+
+/// Maximum number of system API calls recorded by Wasm tracing.
+pub const MAX_WASM_TRACE_LENGTH: u64 = 100;
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     name: text;
+///     value: text;
+/// })`
+#[derive(
+    Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, CandidType, Deserialize, Serialize,
+)]
+pub struct EnvironmentVariable {
+    pub name: String,
+    pub value: String,
+}
+
+impl EnvironmentVariable {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+impl DataSize for EnvironmentVariable {
+    fn data_size(&self) -> usize {
+        self.name.data_size() + self.value.data_size()
+    }
+}
+
+/// Environment variables bounded by count and by the size of each variable.
+/// The individual limits on names and values are checked when the settings
+/// are validated.
+pub type BoundedEnvironmentVariables = BoundedVec<
+    MAX_ENVIRONMENT_VARIABLES_COUNT,
+    UNBOUNDED,
+    { MAX_ENVIRONMENT_VARIABLE_NAME_SIZE + MAX_ENVIRONMENT_VARIABLE_VALUE_SIZE },
+    EnvironmentVariable,
+>;
+
 impl DataSize for PrincipalId {
     fn data_size(&self) -> usize {
         self.as_slice().data_size()
@@ -1741,6 +1890,10 @@ impl DataSize for PrincipalId {
 ///     log_visibility : opt log_visibility;
 ///     wasm_memory_limit: opt nat;
 ///     wasm_memory_threshold: opt nat;
+///     log_buffer_size: opt nat;
+///     environment_variables: opt vec environment_variable;
+///     wasm_trace_length: opt nat;
+///     priority_class: opt priority_class;
 /// })`
 #[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
 pub struct CanisterSettingsArgs {
@@ -1752,6 +1905,10 @@ pub struct CanisterSettingsArgs {
     pub log_visibility: Option<LogVisibilityV2>,
     pub wasm_memory_limit: Option<candid::Nat>,
     pub wasm_memory_threshold: Option<candid::Nat>,
+    pub log_buffer_size: Option<candid::Nat>,
+    pub environment_variables: Option<BoundedEnvironmentVariables>,
+    pub wasm_trace_length: Option<candid::Nat>,
+    pub priority_class: Option<CanisterPriorityClass>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1769,6 +1926,10 @@ impl CanisterSettingsArgs {
             log_visibility: None,
             wasm_memory_limit: None,
             wasm_memory_threshold: None,
+            log_buffer_size: None,
+            environment_variables: None,
+            wasm_trace_length: None,
+            priority_class: None,
         }
     }
 }
@@ -1783,6 +1944,10 @@ pub struct CanisterSettingsArgsBuilder {
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<candid::Nat>,
     wasm_memory_threshold: Option<candid::Nat>,
+    log_buffer_size: Option<candid::Nat>,
+    environment_variables: Option<Vec<EnvironmentVariable>>,
+    wasm_trace_length: Option<candid::Nat>,
+    priority_class: Option<CanisterPriorityClass>,
 }
 
 #[allow(dead_code)]
@@ -1801,6 +1966,12 @@ impl CanisterSettingsArgsBuilder {
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
             wasm_memory_threshold: self.wasm_memory_threshold,
+            log_buffer_size: self.log_buffer_size,
+            environment_variables: self
+                .environment_variables
+                .map(BoundedEnvironmentVariables::new),
+            wasm_trace_length: self.wasm_trace_length,
+            priority_class: self.priority_class,
         }
     }
 
@@ -1885,6 +2056,42 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets the size of the canister log buffer in bytes.
+    pub fn with_log_buffer_size(self, log_buffer_size: u64) -> Self {
+        Self {
+            log_buffer_size: Some(candid::Nat::from(log_buffer_size)),
+            ..self
+        }
+    }
+
+    /// Sets the environment variables, replacing any existing ones.
+    pub fn with_environment_variables(
+        self,
+        environment_variables: Vec<EnvironmentVariable>,
+    ) -> Self {
+        Self {
+            environment_variables: Some(environment_variables),
+            ..self
+        }
+    }
+
+    /// Sets the number of most recent system API calls that are recorded
+    /// and written to the canister log if the canister traps. Zero disables
+    /// Wasm tracing.
+    pub fn with_wasm_trace_length(self, wasm_trace_length: u64) -> Self {
+        Self {
+            wasm_trace_length: Some(candid::Nat::from(wasm_trace_length)),
+            ..self
+        }
+    }
+
+    pub fn with_priority_class(self, priority_class: CanisterPriorityClass) -> Self {
+        Self {
+            priority_class: Some(priority_class),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -3006,11 +3213,15 @@ impl Payload<'_> for NodeMetricsHistoryResponse {}
 /// ```text
 /// record {
 ///     canister_id: principal;
+///     filter: opt canister_log_filter;
+///     max_records: opt nat64;
 /// }
 /// ```
 #[derive(Clone, Debug, Default, CandidType, Deserialize)]
 pub struct FetchCanisterLogsRequest {
     pub canister_id: PrincipalId,
+    pub filter: Option<CanisterLogFilter>,
+    pub max_records: Option<u64>,
 }
 
 impl Payload<'_> for FetchCanisterLogsRequest {}
@@ -3019,46 +3230,196 @@ impl FetchCanisterLogsRequest {
     pub fn new(canister_id: CanisterId) -> Self {
         Self {
             canister_id: canister_id.into(),
+            filter: None,
+            max_records: None,
         }
     }
 
+    pub fn with_filter(mut self, filter: CanisterLogFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn with_max_records(mut self, max_records: u64) -> Self {
+        self.max_records = Some(max_records);
+        self
+    }
+
     pub fn get_canister_id(&self) -> CanisterId {
         CanisterId::unchecked_from_principal(self.canister_id)
     }
 }
 
+/// A half-open range `[start, end)` of canister log record indices or timestamps.
+/// ```text
+/// record {
+///     start: nat64;
+///     end: nat64;
+/// }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct CanisterLogRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl CanisterLogRange {
+    pub fn new(start: u64, end: u64) -> Self {
+        Self { start, end }
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        self.start <= value && value < self.end
+    }
+}
+
+/// Severity of a canister log record.
+/// ```text
+/// variant {
+///     debug;
+///     info;
+///     warning;
+///     error;
+/// }
+/// ```
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, CandidType, Deserialize, Serialize, EnumIter,
+)]
+pub enum CanisterLogLevel {
+    #[serde(rename = "debug")]
+    Debug,
+    #[serde(rename = "info")]
+    Info,
+    #[serde(rename = "warning")]
+    Warning,
+    #[serde(rename = "error")]
+    Error,
+}
+
+impl From<CanisterLogLevel> for pb_canister_state_bits::CanisterLogLevel {
+    fn from(item: CanisterLogLevel) -> Self {
+        match item {
+            CanisterLogLevel::Debug => pb_canister_state_bits::CanisterLogLevel::Debug,
+            CanisterLogLevel::Info => pb_canister_state_bits::CanisterLogLevel::Info,
+            CanisterLogLevel::Warning => pb_canister_state_bits::CanisterLogLevel::Warning,
+            CanisterLogLevel::Error => pb_canister_state_bits::CanisterLogLevel::Error,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterLogLevel> for CanisterLogLevel {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::CanisterLogLevel) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::CanisterLogLevel::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "CanisterLogLevel",
+                    err: format!("Unexpected value for canister log level {:?}", item),
+                })
+            }
+            pb_canister_state_bits::CanisterLogLevel::Debug => Ok(CanisterLogLevel::Debug),
+            pb_canister_state_bits::CanisterLogLevel::Info => Ok(CanisterLogLevel::Info),
+            pb_canister_state_bits::CanisterLogLevel::Warning => Ok(CanisterLogLevel::Warning),
+            pb_canister_state_bits::CanisterLogLevel::Error => Ok(CanisterLogLevel::Error),
+        }
+    }
+}
+
+/// Filters the records returned by `fetch_canister_logs`. A record is
+/// returned only if it satisfies all of the given conditions.
+/// ```text
+/// record {
+///     idx_range: opt canister_log_range;
+///     timestamp_nanos_range: opt canister_log_range;
+///     min_level: opt canister_log_level;
+///     tag: opt text;
+/// }
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct CanisterLogFilter {
+    pub idx_range: Option<CanisterLogRange>,
+    pub timestamp_nanos_range: Option<CanisterLogRange>,
+    /// Records without a level never match if a minimum level is set.
+    pub min_level: Option<CanisterLogLevel>,
+    /// Only records carrying this tag match if set.
+    pub tag: Option<String>,
+}
+
+impl CanisterLogFilter {
+    /// Returns true if the record satisfies all conditions of the filter.
+    pub fn matches(&self, record: &CanisterLogRecord) -> bool {
+        self.idx_range.map_or(true, |r| r.contains(record.idx))
+            && self
+                .timestamp_nanos_range
+                .map_or(true, |r| r.contains(record.timestamp_nanos))
+            && self
+                .min_level
+                .map_or(true, |min| record.level.map_or(false, |l| l >= min))
+            && self
+                .tag
+                .as_ref()
+                .map_or(true, |tag| record.tags.iter().flatten().any(|t| t == tag))
+    }
+}
+
 /// `CandidType` for `CanisterLogRecord`
 /// ```text
 /// record {
 ///     idx: nat64;
 ///     timestamp_nanos: nat64;
 ///     content: blob;
+///     level: opt canister_log_level;
+///     tags: opt vec text;
 /// }
 /// ```
+///
+/// The level and the tags are set by the system, never parsed from the
+/// content: records written via `ic0.debug_print` have neither, records
+/// written via `ic0.debug_print_structured` have the level and the tags (if
+/// any) given to it, and records of traps have level `error`.
 #[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize, Serialize)]
 pub struct CanisterLogRecord {
     pub idx: u64,
     pub timestamp_nanos: u64,
     #[serde(with = "serde_bytes")]
     pub content: Vec<u8>,
+    pub level: Option<CanisterLogLevel>,
+    /// Never set to an empty list.
+    pub tags: Option<Vec<String>>,
 }
 
 impl Payload<'_> for CanisterLogRecord {}
 
 impl DataSize for CanisterLogRecord {
+    /// The level and the tags only count towards the size if they are set,
+    /// so records without them take as much space in the log buffer as
+    /// records did before levels and tags were introduced.
     fn data_size(&self) -> usize {
-        std::mem::size_of::<Self>() + self.content.as_slice().data_size()
+        self.idx.data_size()
+            + self.timestamp_nanos.data_size()
+            + self.content.data_size()
+            + self
+                .level
+                .map_or(0, |_| std::mem::size_of::<CanisterLogLevel>())
+            + self.tags.as_ref().map_or(0, |tags| tags.data_size())
     }
 }
 
 #[test]
 fn test_canister_log_record_data_size() {
-    let record = CanisterLogRecord {
+    let mut record = CanisterLogRecord {
         idx: 100,
         timestamp_nanos: 200,
         content: vec![1, 2, 3],
+        level: None,
+        tags: None,
     };
     assert_eq!(record.data_size(), 8 + 8 + 24 + 3);
+
+    record.level = Some(CanisterLogLevel::Info);
+    record.tags = Some(vec!["ab".to_string(), "c".to_string()]);
+    assert_eq!(record.data_size(), 8 + 8 + 24 + 3 + 1 + 24 + 3);
 }
 
 impl From<&CanisterLogRecord> for pb_canister_state_bits::CanisterLogRecord {
@@ -3067,6 +3428,14 @@ impl From<&CanisterLogRecord> for pb_canister_state_bits::CanisterLogRecord {
             idx: item.idx,
             timestamp_nanos: item.timestamp_nanos,
             content: item.content.clone(),
+            level: item
+                .level
+                .map_or(
+                    pb_canister_state_bits::CanisterLogLevel::Unspecified,
+                    |level| level.into(),
+                )
+                .into(),
+            tags: item.tags.clone().unwrap_or_default(),
         }
     }
 }
@@ -3077,6 +3446,11 @@ impl From<pb_canister_state_bits::CanisterLogRecord> for CanisterLogRecord {
             idx: item.idx,
             timestamp_nanos: item.timestamp_nanos,
             content: item.content,
+            // Records persisted without a (valid) level have none.
+            level: pb_canister_state_bits::CanisterLogLevel::try_from(item.level)
+                .ok()
+                .and_then(|level| CanisterLogLevel::try_from(level).ok()),
+            tags: (!item.tags.is_empty()).then_some(item.tags),
         }
     }
 }
@@ -3085,11 +3459,16 @@ impl From<pb_canister_state_bits::CanisterLogRecord> for CanisterLogRecord {
 /// ```text
 /// record {
 ///     canister_log_records: vec canister_log_record;
+///     next_page_idx: opt nat64;
 /// }
 /// ```
 #[derive(Clone, PartialEq, Debug, Default, CandidType, Deserialize)]
 pub struct FetchCanisterLogsResponse {
     pub canister_log_records: Vec<CanisterLogRecord>,
+    /// Set if more records matching the filter were left out due to
+    /// `max_records`. The next page is fetched by repeating the request with
+    /// the start of `idx_range` set to this index.
+    pub next_page_idx: Option<u64>,
 }
 
 impl Payload<'_> for FetchCanisterLogsResponse {}
@@ -3539,6 +3918,372 @@ impl ListCanisterSnapshotArgs {
 
 impl Payload<'_> for ListCanisterSnapshotArgs {}
 
+/// The maximum size in bytes of a piece of snapshot data that can be read
+/// or uploaded in a single `read_canister_snapshot_data` or
+/// `upload_canister_snapshot_data` call.
+pub const MAX_SNAPSHOT_DATA_SLICE_SIZE: u64 = 2_000_000;
+
+/// Candid type representing an exported global of a canister snapshot.
+/// `(variant {
+///     i32 : int32;
+///     i64 : int64;
+///     f32 : float32;
+///     f64 : float64;
+///     v128 : nat;
+/// })`
+#[derive(Copy, Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub enum SnapshotGlobal {
+    #[serde(rename = "i32")]
+    I32(i32),
+    #[serde(rename = "i64")]
+    I64(i64),
+    #[serde(rename = "f32")]
+    F32(f32),
+    #[serde(rename = "f64")]
+    F64(f64),
+    #[serde(rename = "v128")]
+    V128(u128),
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct ReadCanisterSnapshotMetadataArgs {
+    canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
+}
+
+impl ReadCanisterSnapshotMetadataArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: SnapshotId) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.to_vec(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+impl<'a> Payload<'a> for ReadCanisterSnapshotMetadataArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+        // Verify that snapshot ID has the correct format.
+        if let Err(err) = SnapshotId::try_from(&args.snapshot_id) {
+            return Err(UserError::new(
+                ErrorCode::InvalidManagementPayload,
+                format!("Payload deserialization error: {err:?}"),
+            ));
+        }
+        Ok(args)
+    }
+}
+
+/// Struct to be returned when reading the metadata of a canister snapshot.
+/// `(record {
+///     taken_at_timestamp: nat64;
+///     canister_version: nat64;
+///     wasm_module_size: nat64;
+///     exported_globals: vec global;
+///     wasm_memory_size: nat64;
+///     stable_memory_size: nat64;
+///     wasm_chunk_store: vec record { hash: blob };
+///     certified_data: blob;
+/// })`
+#[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ReadCanisterSnapshotMetadataResponse {
+    pub taken_at_timestamp: u64,
+    pub canister_version: u64,
+    pub wasm_module_size: u64,
+    pub exported_globals: Vec<SnapshotGlobal>,
+    pub wasm_memory_size: u64,
+    pub stable_memory_size: u64,
+    pub wasm_chunk_store: Vec<ChunkHash>,
+    #[serde(with = "serde_bytes")]
+    pub certified_data: Vec<u8>,
+}
+
+impl Payload<'_> for ReadCanisterSnapshotMetadataResponse {}
+
+/// Candid type selecting the piece of snapshot data to be read.
+/// `(variant {
+///     wasm_module : record { offset : nat64; size : nat64 };
+///     main_memory : record { offset : nat64; size : nat64 };
+///     stable_memory : record { offset : nat64; size : nat64 };
+///     wasm_chunk : record { hash : blob };
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum CanisterSnapshotDataKind {
+    #[serde(rename = "wasm_module")]
+    WasmModule { offset: u64, size: u64 },
+    #[serde(rename = "main_memory")]
+    MainMemory { offset: u64, size: u64 },
+    #[serde(rename = "stable_memory")]
+    StableMemory { offset: u64, size: u64 },
+    #[serde(rename = "wasm_chunk")]
+    WasmChunk {
+        #[serde(with = "serde_bytes")]
+        hash: Vec<u8>,
+    },
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+///     kind: variant {
+///         wasm_module : record { offset : nat64; size : nat64 };
+///         main_memory : record { offset : nat64; size : nat64 };
+///         stable_memory : record { offset : nat64; size : nat64 };
+///         wasm_chunk : record { hash : blob };
+///     };
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ReadCanisterSnapshotDataArgs {
+    canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
+    pub kind: CanisterSnapshotDataKind,
+}
+
+impl ReadCanisterSnapshotDataArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+        kind: CanisterSnapshotDataKind,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.to_vec(),
+            kind,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+impl<'a> Payload<'a> for ReadCanisterSnapshotDataArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+        // Verify that snapshot ID has the correct format.
+        if let Err(err) = SnapshotId::try_from(&args.snapshot_id) {
+            return Err(UserError::new(
+                ErrorCode::InvalidManagementPayload,
+                format!("Payload deserialization error: {err:?}"),
+            ));
+        }
+        Ok(args)
+    }
+}
+
+/// Struct to be returned when reading a piece of snapshot data.
+/// `(record {
+///     chunk: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ReadCanisterSnapshotDataResponse {
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl Payload<'_> for ReadCanisterSnapshotDataResponse {}
+
+impl ReadCanisterSnapshotDataResponse {
+    pub fn new(chunk: Vec<u8>) -> Self {
+        Self { chunk }
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     replace_snapshot: opt blob;
+///     wasm_module_size: nat64;
+///     exported_globals: vec global;
+///     wasm_memory_size: nat64;
+///     stable_memory_size: nat64;
+///     certified_data: blob;
+/// })`
+#[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub struct UploadCanisterSnapshotMetadataArgs {
+    pub canister_id: PrincipalId,
+    pub replace_snapshot: Option<serde_bytes::ByteBuf>,
+    pub wasm_module_size: u64,
+    pub exported_globals: Vec<SnapshotGlobal>,
+    pub wasm_memory_size: u64,
+    pub stable_memory_size: u64,
+    #[serde(with = "serde_bytes")]
+    pub certified_data: Vec<u8>,
+}
+
+impl UploadCanisterSnapshotMetadataArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        replace_snapshot: Option<SnapshotId>,
+        wasm_module_size: u64,
+        exported_globals: Vec<SnapshotGlobal>,
+        wasm_memory_size: u64,
+        stable_memory_size: u64,
+        certified_data: Vec<u8>,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            replace_snapshot: replace_snapshot
+                .map(|snapshot_id| ByteBuf::from(snapshot_id.to_vec())),
+            wasm_module_size,
+            exported_globals,
+            wasm_memory_size,
+            stable_memory_size,
+            certified_data,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn replace_snapshot(&self) -> Option<SnapshotId> {
+        self.replace_snapshot
+            .as_ref()
+            .map(|bytes| SnapshotId::try_from(&bytes.clone().into_vec()).unwrap())
+    }
+}
+
+impl<'a> Payload<'a> for UploadCanisterSnapshotMetadataArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+
+        if let Some(replace_snapshot) = &args.replace_snapshot {
+            // Verify that snapshot ID has the correct format.
+            if let Err(err) = SnapshotId::try_from(&replace_snapshot.clone().into_vec()) {
+                return Err(UserError::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!("Payload deserialization error: {err:?}"),
+                ));
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Struct to be returned when uploading the metadata of a canister snapshot.
+/// `(record {
+///     snapshot_id: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct UploadCanisterSnapshotMetadataResponse {
+    #[serde(with = "serde_bytes")]
+    pub snapshot_id: Vec<u8>,
+}
+
+impl Payload<'_> for UploadCanisterSnapshotMetadataResponse {}
+
+impl UploadCanisterSnapshotMetadataResponse {
+    pub fn new(snapshot_id: &SnapshotId) -> Self {
+        Self {
+            snapshot_id: snapshot_id.to_vec(),
+        }
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+/// Candid type selecting where a piece of snapshot data is written to.
+/// `(variant {
+///     wasm_module : record { offset : nat64 };
+///     main_memory : record { offset : nat64 };
+///     stable_memory : record { offset : nat64 };
+///     wasm_chunk;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum CanisterSnapshotDataOffset {
+    #[serde(rename = "wasm_module")]
+    WasmModule { offset: u64 },
+    #[serde(rename = "main_memory")]
+    MainMemory { offset: u64 },
+    #[serde(rename = "stable_memory")]
+    StableMemory { offset: u64 },
+    #[serde(rename = "wasm_chunk")]
+    WasmChunk,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+///     kind: variant {
+///         wasm_module : record { offset : nat64 };
+///         main_memory : record { offset : nat64 };
+///         stable_memory : record { offset : nat64 };
+///         wasm_chunk;
+///     };
+///     chunk: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct UploadCanisterSnapshotDataArgs {
+    canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
+    pub kind: CanisterSnapshotDataOffset,
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl UploadCanisterSnapshotDataArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+        kind: CanisterSnapshotDataOffset,
+        chunk: Vec<u8>,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.to_vec(),
+            kind,
+            chunk,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+impl<'a> Payload<'a> for UploadCanisterSnapshotDataArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+        // Verify that snapshot ID has the correct format.
+        if let Err(err) = SnapshotId::try_from(&args.snapshot_id) {
+            return Err(UserError::new(
+                ErrorCode::InvalidManagementPayload,
+                format!("Payload deserialization error: {err:?}"),
+            ));
+        }
+        Ok(args)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3869,4 +4614,90 @@ mod tests {
             );
         }
     }
+
+    fn log_record(
+        idx: u64,
+        timestamp_nanos: u64,
+        level: Option<CanisterLogLevel>,
+        tags: &[&str],
+    ) -> CanisterLogRecord {
+        CanisterLogRecord {
+            idx,
+            timestamp_nanos,
+            content: format!("record #{idx}").into_bytes(),
+            level,
+            tags: (!tags.is_empty()).then(|| tags.iter().map(|tag| tag.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn canister_log_record_pb_roundtrip() {
+        for record in [
+            log_record(0, 100, None, &[]),
+            log_record(1, 200, Some(CanisterLogLevel::Warning), &["db", "retry"]),
+        ] {
+            let pb_record = pb_canister_state_bits::CanisterLogRecord::from(&record);
+            assert_eq!(CanisterLogRecord::from(pb_record), record);
+        }
+    }
+
+    #[test]
+    fn canister_log_filter_matches() {
+        let mut forged = log_record(4, 500, None, &[]);
+        forged.content = b"[error:x] forged".to_vec();
+        let records = [
+            log_record(0, 100, Some(CanisterLogLevel::Debug), &[]),
+            log_record(1, 200, None, &[]),
+            log_record(2, 300, Some(CanisterLogLevel::Warning), &[]),
+            log_record(3, 400, Some(CanisterLogLevel::Error), &["x"]),
+            // Level and tags are never parsed from the content.
+            forged,
+        ];
+        let matching = |filter: CanisterLogFilter| -> Vec<u64> {
+            records
+                .iter()
+                .filter(|r| filter.matches(r))
+                .map(|r| r.idx)
+                .collect()
+        };
+
+        assert_eq!(matching(CanisterLogFilter::default()), vec![0, 1, 2, 3, 4]);
+        assert_eq!(
+            matching(CanisterLogFilter {
+                idx_range: Some(CanisterLogRange::new(1, 3)),
+                ..Default::default()
+            }),
+            vec![1, 2]
+        );
+        assert_eq!(
+            matching(CanisterLogFilter {
+                timestamp_nanos_range: Some(CanisterLogRange::new(200, 401)),
+                ..Default::default()
+            }),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            matching(CanisterLogFilter {
+                min_level: Some(CanisterLogLevel::Warning),
+                ..Default::default()
+            }),
+            vec![2, 3]
+        );
+        assert_eq!(
+            matching(CanisterLogFilter {
+                tag: Some("x".to_string()),
+                ..Default::default()
+            }),
+            vec![3]
+        );
+        assert_eq!(
+            matching(CanisterLogFilter {
+                idx_range: Some(CanisterLogRange::new(0, 3)),
+                timestamp_nanos_range: Some(CanisterLogRange::new(0, 1_000)),
+                min_level: Some(CanisterLogLevel::Debug),
+                tag: None,
+            }),
+            vec![0, 2]
+        );
+    }
 }
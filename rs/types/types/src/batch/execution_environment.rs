@@ -40,6 +40,8 @@ pub struct QueryStats {
     pub num_instructions: u64, // Want u128, but not supported in protobuf
     pub ingress_payload_size: u64,
     pub egress_payload_size: u64,
+    /// The number of calls served from the replica side query cache.
+    pub num_cache_hits: u64,
 }
 
 impl QueryStats {
@@ -52,6 +54,7 @@ impl QueryStats {
         self.egress_payload_size = self
             .egress_payload_size
             .saturating_add(rhs.egress_payload_size);
+        self.num_cache_hits = self.num_cache_hits.saturating_add(rhs.num_cache_hits);
     }
 }
 
@@ -71,6 +74,7 @@ pub struct TotalQueryStats {
     pub num_instructions: u128,
     pub ingress_payload_size: u128,
     pub egress_payload_size: u128,
+    pub num_cache_hits: u128,
 }
 
 fn get_u128_from_protobuf(proto: Option<Unsigned128>) -> Result<u128, ProxyDecodeError> {
@@ -101,6 +105,11 @@ impl TryFrom<TotalQueryStatsProto> for TotalQueryStats {
             num_instructions: get_u128_from_protobuf(value.num_instructions)?,
             ingress_payload_size: get_u128_from_protobuf(value.ingress_payload_size)?,
             egress_payload_size: get_u128_from_protobuf(value.egress_payload_size)?,
+            // The field is missing in the checkpoints created before it was introduced.
+            num_cache_hits: match value.num_cache_hits {
+                Some(_) => get_u128_from_protobuf(value.num_cache_hits)?,
+                None => 0,
+            },
         })
     }
 }
@@ -112,6 +121,7 @@ impl From<&TotalQueryStats> for TotalQueryStatsProto {
             num_instructions: Some(get_protobuf_for_u128(value.num_instructions)),
             ingress_payload_size: Some(get_protobuf_for_u128(value.ingress_payload_size)),
             egress_payload_size: Some(get_protobuf_for_u128(value.egress_payload_size)),
+            num_cache_hits: Some(get_protobuf_for_u128(value.num_cache_hits)),
         }
     }
 }
@@ -154,6 +164,7 @@ impl RawQueryStats {
                         num_instructions: stats.num_instructions,
                         ingress_payload_size: stats.ingress_payload_size,
                         egress_payload_size: stats.egress_payload_size,
+                        num_cache_hits: stats.num_cache_hits,
                     });
                 }
             }
@@ -196,6 +207,7 @@ impl TryFrom<QueryStatsProto> for RawQueryStats {
                             num_instructions: entry.num_instructions,
                             ingress_payload_size: entry.ingress_payload_size,
                             egress_payload_size: entry.egress_payload_size,
+                            num_cache_hits: entry.num_cache_hits,
                         },
                     );
             }
@@ -314,6 +326,7 @@ impl From<&CanisterQueryStats> for pb::CanisterQueryStats {
             num_instructions: entry.stats.num_instructions,
             ingress_payload_size: entry.stats.ingress_payload_size,
             egress_payload_size: entry.stats.egress_payload_size,
+            num_cache_hits: entry.stats.num_cache_hits,
         }
     }
 }
@@ -332,6 +345,7 @@ impl TryFrom<&pb::CanisterQueryStats> for CanisterQueryStats {
                 num_instructions: entry.num_instructions,
                 ingress_payload_size: entry.ingress_payload_size,
                 egress_payload_size: entry.egress_payload_size,
+                num_cache_hits: entry.num_cache_hits,
             },
         })
     }
@@ -426,6 +440,7 @@ mod tests {
             num_instructions: rng.gen(),
             ingress_payload_size: rng.gen(),
             egress_payload_size: rng.gen(),
+            num_cache_hits: rng.gen(),
         }
     }
 }
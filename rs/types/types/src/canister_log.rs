@@ -1,39 +1,91 @@
+use crate::NumBytes;
 use candid::Deserialize;
-use ic_management_canister_types::{CanisterLogRecord, DataSize};
+use ic_management_canister_types::{
+    CanisterLogFilter, CanisterLogLevel, CanisterLogRecord, DataSize,
+};
 use ic_validate_eq::ValidateEq;
 use ic_validate_eq_derive::ValidateEq;
 use serde::Serialize;
 use std::collections::VecDeque;
 
-/// The maximum allowed size of a canister log buffer.
-pub const MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
+/// The default size of a canister log buffer.
+pub const DEFAULT_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
 
-fn truncate_content(mut record: CanisterLogRecord) -> CanisterLogRecord {
-    let max_content_size =
-        MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE - std::mem::size_of::<CanisterLogRecord>();
+/// The minimum size of a canister log buffer that can be set via canister settings.
+pub const MIN_CANISTER_LOG_BUFFER_SIZE: usize = DEFAULT_CANISTER_LOG_BUFFER_SIZE;
+
+/// The maximum size of a canister log buffer that can be set via canister settings.
+pub const MAX_CANISTER_LOG_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The maximum total size of the tags of a canister log record, as passed to
+/// `ic0.debug_print_structured`.
+pub const MAX_CANISTER_LOG_TAGS_SIZE: usize = 256;
+
+fn truncate_content(mut record: CanisterLogRecord, capacity: usize) -> CanisterLogRecord {
+    let overhead = record.data_size() - record.content.len();
+    let max_content_size = capacity.saturating_sub(overhead);
     record.content.truncate(max_content_size);
     record
 }
 
+/// Parses the tags of a canister log record from their encoding in
+/// `ic0.debug_print_structured`: a UTF-8 string of at most
+/// `MAX_CANISTER_LOG_TAGS_SIZE` bytes holding comma-separated tags, each of
+/// them non-empty and without whitespace. An empty string holds no tags.
+pub fn parse_canister_log_tags(bytes: &[u8]) -> Result<Vec<String>, String> {
+    if bytes.len() > MAX_CANISTER_LOG_TAGS_SIZE {
+        return Err(format!(
+            "the tags take {} bytes, more than the maximum of {} bytes",
+            bytes.len(),
+            MAX_CANISTER_LOG_TAGS_SIZE
+        ));
+    }
+    if bytes.is_empty() {
+        return Ok(vec![]);
+    }
+    let tags = std::str::from_utf8(bytes).map_err(|_| "the tags are not valid UTF-8")?;
+    tags.split(',')
+        .map(|tag| {
+            if tag.is_empty() || tag.chars().any(char::is_whitespace) {
+                Err(format!("invalid tag {:?}", tag))
+            } else {
+                Ok(tag.to_string())
+            }
+        })
+        .collect()
+}
+
 // Helper struct to hold canister log records and keep track of the used space.
 // This is needed to avoid iterating over all records to calculate the used space.
-#[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize, Serialize, ValidateEq)]
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize, ValidateEq)]
 struct Records {
     #[validate_eq(Ignore)]
     records: VecDeque<CanisterLogRecord>,
     used_space: usize,
+    capacity: usize,
+}
+
+impl Default for Records {
+    fn default() -> Self {
+        Self {
+            records: Default::default(),
+            used_space: 0,
+            capacity: DEFAULT_CANISTER_LOG_BUFFER_SIZE,
+        }
+    }
 }
 
 impl Records {
-    fn from(records: Vec<CanisterLogRecord>) -> Self {
+    fn from(records: Vec<CanisterLogRecord>, capacity: usize) -> Self {
         let records: Vec<_> = records
             .into_iter()
-            .map(truncate_content) // Apply size limit to each record's content.
+            .map(|r| truncate_content(r, capacity)) // Apply size limit to each record's content.
             .collect();
         let used_space = records.iter().map(|r| r.data_size()).sum();
         let mut result = Self {
             records: records.into(),
             used_space,
+            capacity,
         };
         // Make sure the buffer is within limit.
         result.make_free_space_within_limit(0);
@@ -82,7 +134,12 @@ impl Records {
     }
 
     fn capacity(&self) -> usize {
-        MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE
+        self.capacity
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.make_free_space_within_limit(0);
     }
 
     fn make_free_space_within_limit(&mut self, new_data_size: usize) {
@@ -109,9 +166,19 @@ pub struct CanisterLog {
 impl CanisterLog {
     /// Creates a new `CanisterLog` with the given next index and records.
     pub fn new(next_idx: u64, records: Vec<CanisterLogRecord>) -> Self {
+        Self::new_with_capacity(next_idx, records, DEFAULT_CANISTER_LOG_BUFFER_SIZE)
+    }
+
+    /// Creates a new `CanisterLog` with the given next index, records and
+    /// buffer size. The oldest records are dropped if they do not fit.
+    pub fn new_with_capacity(
+        next_idx: u64,
+        records: Vec<CanisterLogRecord>,
+        capacity: usize,
+    ) -> Self {
         Self {
             next_idx,
-            records: Records::from(records),
+            records: Records::from(records, capacity),
         }
     }
 
@@ -138,11 +205,30 @@ impl CanisterLog {
         self.records.clear();
     }
 
-    /// Returns the maximum allowed size of a canister log buffer.
+    /// Returns the size of the canister log buffer.
     pub fn capacity(&self) -> usize {
         self.records.capacity()
     }
 
+    /// Sets the size of the canister log buffer, dropping the oldest records
+    /// if they no longer fit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.records.set_capacity(capacity);
+    }
+
+    /// Returns the canister memory taken by the canister log buffer, see
+    /// `memory_usage_for_capacity()`.
+    pub fn memory_usage(&self) -> NumBytes {
+        Self::memory_usage_for_capacity(self.capacity())
+    }
+
+    /// Returns the canister memory taken by a canister log buffer of the given
+    /// size: the part of the buffer exceeding the default size, which every
+    /// canister gets for free.
+    pub fn memory_usage_for_capacity(capacity: usize) -> NumBytes {
+        NumBytes::new(capacity.saturating_sub(DEFAULT_CANISTER_LOG_BUFFER_SIZE) as u64)
+    }
+
     /// Returns the used space in the canister log buffer.
     pub fn used_space(&self) -> usize {
         self.records.used_space()
@@ -154,17 +240,60 @@ impl CanisterLog {
         records.capacity().saturating_sub(records.used_space())
     }
 
-    /// Adds a new log record.
+    /// Adds a new log record without level and tags.
     pub fn add_record(&mut self, timestamp_nanos: u64, content: Vec<u8>) {
-        // Add record and update the next index.
-        self.records.push_back(truncate_content(CanisterLogRecord {
-            idx: self.next_idx,
+        self.add_structured_record(timestamp_nanos, None, vec![], content);
+    }
+
+    /// Adds a new log record for a trap, at level `Error`.
+    pub fn add_trap_record(&mut self, timestamp_nanos: u64, content: Vec<u8>) {
+        self.add_structured_record(
             timestamp_nanos,
+            Some(CanisterLogLevel::Error),
+            vec![],
             content,
-        }));
+        );
+    }
+
+    /// Adds a new log record with the given level and tags.
+    /// A record with an empty list of tags has no tags.
+    pub fn add_structured_record(
+        &mut self,
+        timestamp_nanos: u64,
+        level: Option<CanisterLogLevel>,
+        tags: Vec<String>,
+        content: Vec<u8>,
+    ) {
+        // Add record and update the next index.
+        let capacity = self.capacity();
+        self.records.push_back(truncate_content(
+            CanisterLogRecord {
+                idx: self.next_idx,
+                timestamp_nanos,
+                content,
+                level,
+                tags: (!tags.is_empty()).then_some(tags),
+            },
+            capacity,
+        ));
         self.next_idx += 1;
     }
 
+    /// Returns up to `max_records` of the oldest records matching `filter`,
+    /// along with the index of the next matching record if there are more.
+    pub fn fetch(
+        &self,
+        filter: &CanisterLogFilter,
+        max_records: Option<usize>,
+    ) -> (Vec<CanisterLogRecord>, Option<u64>) {
+        let mut matching = self.records().iter().filter(|r| filter.matches(r));
+        let records: Vec<_> = match max_records {
+            Some(max_records) => matching.by_ref().take(max_records).cloned().collect(),
+            None => matching.by_ref().cloned().collect(),
+        };
+        (records, matching.next().map(|r| r.idx))
+    }
+
     /// Moves all the logs from `other` to `self`.
     pub fn append(&mut self, other: &mut Self) {
         // Assume records sorted cronologically (with increasing idx) and
@@ -179,7 +308,7 @@ impl CanisterLog {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ic_management_canister_types::CanisterLogRecord;
+    use ic_management_canister_types::CanisterLogRange;
 
     const TEST_MAX_ALLOWED_SIZE: usize = 4 * 1024;
     const BIGGER_THAN_LIMIT_MESSAGE: &[u8] = &[b'a'; 2 * TEST_MAX_ALLOWED_SIZE];
@@ -190,6 +319,7 @@ mod tests {
                 idx,
                 timestamp_nanos,
                 content: content.to_vec(),
+                ..Default::default()
             })
             .collect()
     }
@@ -343,4 +473,129 @@ mod tests {
             ]))
         );
     }
+
+    #[test]
+    fn test_canister_log_with_capacity_keeps_more_records() {
+        let mut log = CanisterLog::new_with_capacity(0, vec![], 4 * TEST_MAX_ALLOWED_SIZE);
+        for _ in 0..10 {
+            log.add_record(100, BIGGER_THAN_LIMIT_MESSAGE.to_vec());
+        }
+        // Each record holds twice the default size, so two of them fit.
+        assert_eq!(log.records().len(), 2);
+        assert!(log.used_space() <= 4 * TEST_MAX_ALLOWED_SIZE);
+        assert_eq!(log.capacity(), 4 * TEST_MAX_ALLOWED_SIZE);
+    }
+
+    #[test]
+    fn test_canister_log_set_capacity_drops_oldest_records() {
+        let mut log = CanisterLog::new_with_capacity(0, vec![], 4 * TEST_MAX_ALLOWED_SIZE);
+        for i in 0..100 {
+            log.add_record(i, format!("record #{i}").into_bytes());
+        }
+        assert_eq!(log.records().len(), 100);
+
+        log.set_capacity(TEST_MAX_ALLOWED_SIZE);
+
+        assert_eq!(log.capacity(), TEST_MAX_ALLOWED_SIZE);
+        assert!(log.used_space() <= TEST_MAX_ALLOWED_SIZE);
+        assert!(log.records().len() < 100);
+        // The newest records are kept.
+        assert_eq!(log.records().back().unwrap().idx, 99);
+        assert_eq!(log.next_idx(), 100);
+    }
+
+    #[test]
+    fn test_canister_log_memory_usage_for_capacity() {
+        assert_eq!(CanisterLog::default().memory_usage(), NumBytes::new(0));
+        assert_eq!(
+            CanisterLog::new_with_capacity(0, vec![], MAX_CANISTER_LOG_BUFFER_SIZE).memory_usage(),
+            NumBytes::new((MAX_CANISTER_LOG_BUFFER_SIZE - DEFAULT_CANISTER_LOG_BUFFER_SIZE) as u64)
+        );
+    }
+
+    #[test]
+    fn test_parse_canister_log_tags() {
+        assert_eq!(parse_canister_log_tags(b""), Ok(vec![]));
+        assert_eq!(
+            parse_canister_log_tags(b"db,retry"),
+            Ok(vec!["db".to_string(), "retry".to_string()])
+        );
+        for invalid in [
+            &b","[..],
+            b"db,",
+            b"a b",
+            b"\xff",
+            &[b'a'; MAX_CANISTER_LOG_TAGS_SIZE + 1],
+        ] {
+            assert!(parse_canister_log_tags(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_canister_log_truncates_content_of_structured_record() {
+        let mut log = CanisterLog::default();
+        let tags = vec!["t".repeat(100)];
+        log.add_structured_record(
+            100,
+            Some(CanisterLogLevel::Error),
+            tags.clone(),
+            BIGGER_THAN_LIMIT_MESSAGE.to_vec(),
+        );
+        let record = log.records().back().unwrap();
+        assert_eq!(record.level, Some(CanisterLogLevel::Error));
+        assert_eq!(record.tags, Some(tags));
+        assert_eq!(log.used_space(), TEST_MAX_ALLOWED_SIZE);
+    }
+
+    #[test]
+    fn test_canister_log_fetch_with_filter_and_pagination() {
+        let mut log = CanisterLog::default();
+        for (level, tags) in [
+            (Some(CanisterLogLevel::Debug), vec![]),
+            (Some(CanisterLogLevel::Info), vec![]),
+            (None, vec![]),
+            (Some(CanisterLogLevel::Error), vec!["db".to_string()]),
+            (Some(CanisterLogLevel::Warning), vec![]),
+            (Some(CanisterLogLevel::Error), vec![]),
+        ] {
+            let idx = log.next_idx();
+            log.add_structured_record(100 + idx, level, tags, format!("#{idx}").into_bytes());
+        }
+        let idxs = |records: Vec<CanisterLogRecord>| -> Vec<u64> {
+            records.into_iter().map(|r| r.idx).collect()
+        };
+
+        let (records, next) = log.fetch(&CanisterLogFilter::default(), None);
+        assert_eq!(idxs(records), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(next, None);
+
+        let mut filter = CanisterLogFilter {
+            min_level: Some(CanisterLogLevel::Info),
+            ..Default::default()
+        };
+        let (records, next) = log.fetch(&filter, Some(2));
+        assert_eq!(idxs(records), vec![1, 3]);
+        assert_eq!(next, Some(4));
+
+        filter.idx_range = Some(CanisterLogRange::new(next.unwrap(), u64::MAX));
+        let (records, next) = log.fetch(&filter, Some(2));
+        assert_eq!(idxs(records), vec![4, 5]);
+        assert_eq!(next, None);
+
+        let filter = CanisterLogFilter {
+            tag: Some("db".to_string()),
+            ..Default::default()
+        };
+        let (records, next) = log.fetch(&filter, None);
+        assert_eq!(idxs(records), vec![3]);
+        assert_eq!(next, None);
+
+        let filter = CanisterLogFilter {
+            timestamp_nanos_range: Some(CanisterLogRange::new(101, 104)),
+            ..Default::default()
+        };
+        let (records, next) = log.fetch(&filter, Some(0));
+        assert!(records.is_empty());
+        assert_eq!(next, Some(1));
+    }
 }
@@ -88,7 +88,10 @@ pub mod xnet;
 #[cfg(test)]
 pub mod exhaustive;
 
-pub use crate::canister_log::{CanisterLog, MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE};
+pub use crate::canister_log::{
+    CanisterLog, DEFAULT_CANISTER_LOG_BUFFER_SIZE, MAX_CANISTER_LOG_BUFFER_SIZE,
+    MIN_CANISTER_LOG_BUFFER_SIZE,
+};
 pub use crate::replica_version::ReplicaVersion;
 pub use crate::time::Time;
 pub use funds::*;
@@ -484,14 +487,19 @@ const GIB: u64 = 1024 * 1024 * 1024;
 /// it is public and `u64` (`NumBytes` cannot be used in const expressions).
 pub const MAX_STABLE_MEMORY_IN_BYTES: u64 = 500 * GIB;
 
-/// The upper limit on the Wasm memory size.
+/// The upper limit on the Wasm memory size of Wasm32 canisters.
 /// This constant is used by other crates to define other constants, that's why
 /// it is public and `u64` (`NumBytes` cannot be used in const expressions).
 pub const MAX_WASM_MEMORY_IN_BYTES: u64 = 4 * GIB;
 
+/// The upper limit on the Wasm memory size of Wasm64 canisters.
+/// This constant is used by other crates to define other constants, that's why
+/// it is public and `u64` (`NumBytes` cannot be used in const expressions).
+pub const MAX_WASM64_MEMORY_IN_BYTES: u64 = 6 * GIB;
+
 const MIN_MEMORY_ALLOCATION: NumBytes = NumBytes::new(0);
 pub const MAX_MEMORY_ALLOCATION: NumBytes =
-    NumBytes::new(MAX_STABLE_MEMORY_IN_BYTES + MAX_WASM_MEMORY_IN_BYTES);
+    NumBytes::new(MAX_STABLE_MEMORY_IN_BYTES + MAX_WASM64_MEMORY_IN_BYTES);
 
 impl InvalidMemoryAllocationError {
     pub fn new(given: candid::Nat) -> Self {
@@ -9,11 +9,10 @@ pub use errors::{
 use ic_types::CountBytes;
 use ic_utils::byte_slice_fmt::truncate_and_format;
 use ic_validate_eq::ValidateEq;
-use ic_validate_eq_derive::ValidateEq;
 use std::{
     fmt,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, OnceLock},
 };
 
 const WASM_HASH_LENGTH: usize = 32;
@@ -52,19 +51,19 @@ impl BinaryEncodedWasm {
 ///   * Gzip-compressed Wasm modules (magic number \1f\8b\08)
 // We don't derive `Serialize` and `Deserialize` because this is a binary that is serialized by
 // writing it to a file when creating checkpoints.
-#[derive(Clone, ValidateEq)]
+#[derive(Clone)]
 pub struct CanisterModule {
     // The Wasm binary.
-    #[validate_eq(Ignore)]
     module: ModuleStorage,
-    // The Sha256 hash of the binary.
-    module_hash: [u8; WASM_HASH_LENGTH],
+    // The Sha256 hash of the binary. Computed lazily after the binary
+    // was modified by `write`.
+    module_hash: OnceLock<[u8; WASM_HASH_LENGTH]>,
 }
 
 impl CanisterModule {
     pub fn new(bytes: Vec<u8>) -> Self {
         let module = ModuleStorage::Memory(Arc::new(bytes));
-        let module_hash = ic_crypto_sha2::Sha256::hash(module.as_slice());
+        let module_hash = OnceLock::from(ic_crypto_sha2::Sha256::hash(module.as_slice()));
         Self {
             module,
             module_hash,
@@ -75,8 +74,9 @@ impl CanisterModule {
         let module = ModuleStorage::mmap_file(path)?;
         // It should only be necessary to compute the hash here when
         // loading checkpoints written by older replica versions
-        let module_hash =
-            module_hash.map_or_else(|| ic_crypto_sha2::Sha256::hash(module.as_slice()), |h| h.0);
+        let module_hash = OnceLock::from(
+            module_hash.map_or_else(|| ic_crypto_sha2::Sha256::hash(module.as_slice()), |h| h.0),
+        );
         Ok(Self {
             module,
             module_hash,
@@ -112,7 +112,37 @@ impl CanisterModule {
 
     /// Returns the Sha256 hash of this Wasm module.
     pub fn module_hash(&self) -> [u8; WASM_HASH_LENGTH] {
-        self.module_hash
+        *self
+            .module_hash
+            .get_or_init(|| ic_crypto_sha2::Sha256::hash(self.as_slice()))
+    }
+
+    /// Overwrites the module at `offset` with `data`.
+    ///
+    /// The bytes are modified in place unless they are shared with another
+    /// copy of the module or backed by a file, in which case they are copied
+    /// once. The hash is only recomputed when it is requested next, so that
+    /// writing a module in many slices takes time linear in its size.
+    ///
+    /// Panics if the write is not within the bounds of the module.
+    pub fn write(&mut self, offset: usize, data: &[u8]) {
+        if matches!(self.module, ModuleStorage::File(..)) {
+            self.module = ModuleStorage::Memory(Arc::new(self.as_slice().to_vec()));
+        }
+        let ModuleStorage::Memory(bytes) = &mut self.module else {
+            unreachable!("The module was converted to an in-memory module above.");
+        };
+        Arc::make_mut(bytes)[offset..offset + data.len()].copy_from_slice(data);
+        self.module_hash = OnceLock::new();
+    }
+}
+
+impl ValidateEq for CanisterModule {
+    fn validate_eq(&self, rhs: &Self) -> Result<(), String> {
+        if self.module_hash() != rhs.module_hash() {
+            return Err("module_hash".to_string());
+        }
+        Ok(())
     }
 }
 
@@ -202,6 +232,25 @@ fn wasmhash_display() {
     assert_eq!(expected, format!("{}", hash));
 }
 
+#[test]
+fn canister_module_write() {
+    let mut module = CanisterModule::new(vec![0; 8]);
+    let shared = module.clone();
+    module.write(2, &[1, 2, 3]);
+    module.write(6, &[4]);
+    assert_eq!(module.as_slice(), &[0, 0, 1, 2, 3, 0, 4, 0]);
+    assert_eq!(
+        module.module_hash(),
+        CanisterModule::new(vec![0, 0, 1, 2, 3, 0, 4, 0]).module_hash()
+    );
+    // Copies of the module are not affected by the writes.
+    assert_eq!(shared.as_slice(), &[0; 8]);
+    assert_eq!(
+        shared.module_hash(),
+        CanisterModule::new(vec![0; 8]).module_hash()
+    );
+}
+
 // We introduce another enum instead of making `BinaryEncodedWasm` an enum to
 // keep constructors private. We want `BinaryEncodedWasm` to be visible, but not
 // its structure.